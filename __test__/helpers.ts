@@ -0,0 +1,210 @@
+import { inflateSync } from 'node:zlib'
+
+export interface PdfPageSpec {
+  /** Raw content stream operators */
+  content: string
+  width?: number
+  height?: number
+  /** Extra entries merged into the page /Resources dictionary */
+  resources?: string
+  /** Extra entries added to the page /Font dictionary */
+  fonts?: string
+  /** Extra entries added to the page dictionary (e.g. `/Annots [...]`) */
+  extra?: string
+}
+
+export interface PdfSpec {
+  pages: Array<string | PdfPageSpec>
+  /**
+   * Additional objects, numbered from 5 in order (1 = catalog, 2 = pages,
+   * 3 = Helvetica as /F1, 4 = Helvetica-Bold as /F2).
+   */
+  objects?: Array<string | Buffer>
+  /** Extra entries added to the catalog dictionary */
+  catalog?: string
+}
+
+/** Escape a string for use inside a PDF literal string. */
+export function pdfString(text: string): string {
+  return `(${text.replace(/\\/g, '\\\\').replace(/\(/g, '\\(').replace(/\)/g, '\\)')})`
+}
+
+/** Content stream drawing one line of text per entry, top to bottom. */
+export function textLines(lines: string[], { x = 72, top = 750, size = 12, font = 'F1' } = {}): string {
+  return lines
+    .map((line, i) => `BT /${font} ${size} Tf 1 0 0 1 ${x} ${top - i * size * 1.4} Tm ${pdfString(line)} Tj ET`)
+    .join('\n')
+}
+
+/** Stream object body (dictionary + data) for use in `PdfSpec.objects`. */
+export function pdfStream(dict: string, data: string | Buffer): Buffer {
+  const bytes = typeof data === 'string' ? Buffer.from(data, 'latin1') : data
+  return Buffer.concat([
+    Buffer.from(`<< ${dict} /Length ${bytes.length} >>\nstream\n`, 'latin1'),
+    bytes,
+    Buffer.from('\nendstream', 'latin1'),
+  ])
+}
+
+/** Build a small but valid PDF document with Helvetica text pages. */
+export function buildPdf(spec: PdfSpec): Buffer {
+  const extra = spec.objects ?? []
+  const firstPageId = 5 + extra.length
+  const pages = spec.pages.map((p) => (typeof p === 'string' ? { content: p } : p))
+  const pageIds = pages.map((_, i) => firstPageId + i * 2)
+
+  const bodies: Array<string | Buffer> = [
+    `<< /Type /Catalog /Pages 2 0 R ${spec.catalog ?? ''} >>`,
+    `<< /Type /Pages /Kids [${pageIds.map((id) => `${id} 0 R`).join(' ')}] /Count ${pages.length} >>`,
+    '<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica /Encoding /WinAnsiEncoding >>',
+    '<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica-Bold /Encoding /WinAnsiEncoding >>',
+    ...extra,
+  ]
+  pages.forEach((page, i) => {
+    const contentId = pageIds[i] + 1
+    bodies.push(
+      `<< /Type /Page /Parent 2 0 R /MediaBox [0 0 ${page.width ?? 612} ${page.height ?? 792}] ` +
+        `/Resources << /Font << /F1 3 0 R /F2 4 0 R ${page.fonts ?? ''} >> ${page.resources ?? ''} >> ` +
+        `/Contents ${contentId} 0 R ${page.extra ?? ''} >>`,
+    )
+    bodies.push(pdfStream('', page.content))
+  })
+
+  const chunks: Buffer[] = [Buffer.from('%PDF-1.4\n', 'latin1')]
+  let offset = chunks[0].length
+  const offsets: number[] = []
+  bodies.forEach((body, i) => {
+    const chunk = Buffer.concat([
+      Buffer.from(`${i + 1} 0 obj\n`, 'latin1'),
+      typeof body === 'string' ? Buffer.from(body, 'latin1') : body,
+      Buffer.from('\nendobj\n', 'latin1'),
+    ])
+    offsets.push(offset)
+    offset += chunk.length
+    chunks.push(chunk)
+  })
+
+  const xref =
+    `xref\n0 ${bodies.length + 1}\n0000000000 65535 f \n` +
+    offsets.map((o) => `${String(o).padStart(10, '0')} 00000 n \n`).join('') +
+    `trailer\n<< /Size ${bodies.length + 1} /Root 1 0 R >>\nstartxref\n${offset}\n%%EOF\n`
+  chunks.push(Buffer.from(xref, 'latin1'))
+
+  return Buffer.concat(chunks)
+}
+
+export interface PdfStreamData {
+  /** Stream dictionary, without the enclosing `<<` `>>` */
+  dict: string
+  /** Stream data, inflated when Flate-encoded */
+  data: Buffer
+}
+
+/** Every stream of a PDF, for checking what a generated file contains. */
+export function pdfStreams(pdf: Buffer): PdfStreamData[] {
+  const source = pdf.toString('latin1')
+  return [...source.matchAll(/\d+ 0 obj\s*<<(.*?)>>\s*stream\r?\n/gs)].map((match) => {
+    const start = (match.index ?? 0) + match[0].length
+    const data = pdf.subarray(start, source.indexOf('endstream', start))
+    return { dict: match[1], data: match[1].includes('FlateDecode') ? inflateSync(data) : data }
+  })
+}
+
+const CRC_TABLE = Array.from({ length: 256 }, (_, n) => {
+  let c = n
+  for (let k = 0; k < 8; k++) c = c & 1 ? 0xedb88320 ^ (c >>> 1) : c >>> 1
+  return c >>> 0
+})
+
+function crc32(data: Buffer): number {
+  let crc = 0xffffffff
+  for (const byte of data) crc = CRC_TABLE[(crc ^ byte) & 0xff] ^ (crc >>> 8)
+  return (crc ^ 0xffffffff) >>> 0
+}
+
+/** Build a ZIP archive with the given (uncompressed) entries. */
+export function buildZip(entries: Record<string, string | Buffer>): Buffer {
+  const chunks: Buffer[] = []
+  const central: Buffer[] = []
+  let offset = 0
+  for (const [name, content] of Object.entries(entries)) {
+    const data = typeof content === 'string' ? Buffer.from(content, 'utf8') : content
+    const fileName = Buffer.from(name, 'utf8')
+    const crc = crc32(data)
+
+    const local = Buffer.alloc(30)
+    local.writeUInt32LE(0x04034b50, 0)
+    local.writeUInt16LE(20, 4)
+    local.writeUInt16LE(0x0800, 6)
+    local.writeUInt32LE(crc, 14)
+    local.writeUInt32LE(data.length, 18)
+    local.writeUInt32LE(data.length, 22)
+    local.writeUInt16LE(fileName.length, 26)
+
+    const header = Buffer.alloc(46)
+    header.writeUInt32LE(0x02014b50, 0)
+    header.writeUInt16LE(20, 4)
+    header.writeUInt16LE(20, 6)
+    header.writeUInt16LE(0x0800, 8)
+    header.writeUInt32LE(crc, 16)
+    header.writeUInt32LE(data.length, 20)
+    header.writeUInt32LE(data.length, 24)
+    header.writeUInt16LE(fileName.length, 28)
+    header.writeUInt32LE(offset, 42)
+    central.push(header, fileName)
+
+    chunks.push(local, fileName, data)
+    offset += local.length + fileName.length + data.length
+  }
+  const directory = Buffer.concat(central)
+  const end = Buffer.alloc(22)
+  end.writeUInt32LE(0x06054b50, 0)
+  end.writeUInt16LE(central.length / 2, 8)
+  end.writeUInt16LE(central.length / 2, 10)
+  end.writeUInt32LE(directory.length, 12)
+  end.writeUInt32LE(offset, 16)
+  return Buffer.concat([...chunks, directory, end])
+}
+
+const W_NS = 'http://schemas.openxmlformats.org/wordprocessingml/2006/main'
+const R_NS = 'http://schemas.openxmlformats.org/officeDocument/2006/relationships'
+
+export interface DocxParts {
+  /** Content of `word/styles.xml`, inside `<w:styles>` */
+  styles?: string
+  /** Content of `word/numbering.xml`, inside `<w:numbering>` */
+  numbering?: string
+  /** Media parts (`word/media/<name>`), referenced by relationship id `rId<name without extension>` */
+  media?: Record<string, Buffer>
+}
+
+/** Build a DOCX package whose body is `body` (WordprocessingML). */
+export function buildDocx(body: string, parts: DocxParts = {}): Buffer {
+  const { styles, numbering, media = {} } = parts
+  const rels = [
+    styles && '<Relationship Id="rIdStyles" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/styles" Target="styles.xml"/>',
+    numbering &&
+      '<Relationship Id="rIdNumbering" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/numbering" Target="numbering.xml"/>',
+    ...Object.keys(media).map(
+      (name) =>
+        `<Relationship Id="rId${name.replace(/\..*$/, '')}" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/image" Target="media/${name}"/>`,
+    ),
+  ].filter(Boolean)
+  const relsXml = (items: string[]) =>
+    `<?xml version="1.0" encoding="UTF-8"?><Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships">${items.join('')}</Relationships>`
+  const entries: Record<string, string | Buffer> = {
+    '[Content_Types].xml':
+      '<?xml version="1.0" encoding="UTF-8"?><Types xmlns="http://schemas.openxmlformats.org/package/2006/content-types"><Default Extension="rels" ContentType="application/vnd.openxmlformats-package.relationships+xml"/><Default Extension="xml" ContentType="application/xml"/><Override PartName="/word/document.xml" ContentType="application/vnd.openxmlformats-officedocument.wordprocessingml.document.main+xml"/></Types>',
+    '_rels/.rels': relsXml([
+      '<Relationship Id="rId1" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/officeDocument" Target="word/document.xml"/>',
+    ]),
+    'word/document.xml': `<?xml version="1.0" encoding="UTF-8"?><w:document xmlns:w="${W_NS}" xmlns:r="${R_NS}" xmlns:a="http://schemas.openxmlformats.org/drawingml/2006/main" xmlns:wp="http://schemas.openxmlformats.org/drawingml/2006/wordprocessingDrawing"><w:body>${body}</w:body></w:document>`,
+    'word/_rels/document.xml.rels': relsXml(rels as string[]),
+  }
+  if (styles) entries['word/styles.xml'] = `<?xml version="1.0" encoding="UTF-8"?><w:styles xmlns:w="${W_NS}">${styles}</w:styles>`
+  if (numbering) {
+    entries['word/numbering.xml'] = `<?xml version="1.0" encoding="UTF-8"?><w:numbering xmlns:w="${W_NS}">${numbering}</w:numbering>`
+  }
+  for (const [name, data] of Object.entries(media)) entries[`word/media/${name}`] = data
+  return buildZip(entries)
+}
@@ -2,21 +2,41 @@ import test from 'ava'
 import { existsSync, mkdirSync, readFileSync, rmSync, writeFileSync } from 'node:fs'
 import path from 'node:path'
 import { fileURLToPath } from 'node:url'
+import { deflateSync } from 'node:zlib'
 
 import {
+  analyzePdf,
+  anonymizeCv,
   base64ToBuffer,
   bufferToBase64,
   convertImagesToWebpRecursive,
+  detectPii,
+  diffCvText,
+  extractCvsFromEmail,
+  extractFormFields,
+  extractMarkdownFromPdf,
+  extractPdfImages,
+  extractPdfOutline,
+  extractText,
   extractTextFromPdf,
+  extractTextFromPdfDetailed,
   imageToWebp,
   imageToWebpFromBase64,
   imageToWebpFromFile,
+  listPdfFonts,
   normalizeCvToPdf,
+  ocrDocument,
   optimizeImage,
   optimizeImageFromBase64,
   optimizeImageFromFile,
+  redactPdf,
+  renderResumePdf,
+  scoreTextQuality,
+  setPdfOutline,
 } from '../index'
 
+import { buildDocx, buildPdf, buildZip, pdfStream, pdfStreams, pdfString, textLines } from './helpers'
+
 const __filename = fileURLToPath(import.meta.url)
 const __dirname = path.dirname(__filename)
 
@@ -125,6 +145,1596 @@ test('extractTextFromPdf throws on invalid PDF', (t) => {
   t.regex(error?.message ?? '', /Failed to extract text from PDF/)
 })
 
+test('extractTextFromPdf cleanup removes repeated headers/footers and re-joins hyphenated words', (t) => {
+  const pdf = buildPdf({
+    pages: [
+      textLines(['ACME Talent - Confidential', 'Senior soft-', 'ware   engineer', 'Page 1 of 2']),
+      textLines(['ACME Talent - Confidential', 'Rust and TypeScript', 'Page 2 of 2']),
+    ],
+  })
+
+  const raw = extractTextFromPdf(pdf)
+  t.regex(raw, /ACME Talent/)
+
+  const cleaned = extractTextFromPdf(pdf, { cleanup: {} })
+  t.notRegex(cleaned, /ACME Talent/)
+  t.notRegex(cleaned, /Page \d of 2/)
+  t.regex(cleaned, /Senior software\nengineer/)
+  t.regex(cleaned, /Rust and TypeScript/)
+})
+
+test('extractTextFromPdf cleanup steps can be disabled', (t) => {
+  const pdf = buildPdf({
+    pages: [textLines(['Header', 'Senior soft-', 'ware engineer']), textLines(['Header', 'Second page'])],
+  })
+
+  const cleaned = extractTextFromPdf(pdf, { cleanup: { dehyphenate: false, removeHeadersFooters: false } })
+  t.regex(cleaned, /Header/)
+  t.regex(cleaned, /soft-\nware/)
+})
+
+test('extractTextFromPdf layout mode reads a two-column CV column by column', (t) => {
+  const sidebar = ['CONTACT', 'jane@example.com', '+33 6 12 34 56 78', 'SKILLS', 'Rust', 'TypeScript']
+  const main = [
+    'EXPERIENCE',
+    'Backend engineer at Acme since 2019,',
+    'building document pipelines in Rust.',
+    'Led the migration of the CV parser.',
+    'EDUCATION',
+    'MSc Computer Science',
+  ]
+  // Typical template output: both columns are drawn row by row.
+  const rows = sidebar.map((left, i) => {
+    const top = 700 - i * 14
+    return [textLines([left], { x: 40, top, size: 10 }), textLines([main[i]], { x: 220, top, size: 10 })].join('\n')
+  })
+  const header = textLines(['Jane Doe - Senior Backend Engineer'], { x: 150, top: 760, size: 18, font: 'F2' })
+  const pdf = buildPdf({ pages: [[header, ...rows].join('\n')] })
+
+  const raw = extractTextFromPdf(pdf)
+  t.regex(raw, /CONTACT\s+EXPERIENCE/)
+
+  const text = extractTextFromPdf(pdf, { layout: true })
+  t.true(text.startsWith('Jane Doe - Senior Backend Engineer'))
+  t.regex(text, /CONTACT\njane@example\.com\n\+33 6 12 34 56 78\nSKILLS\nRust\nTypeScript/)
+  t.regex(text, /EXPERIENCE\nBackend engineer at Acme since 2019,\nbuilding document pipelines in Rust\./)
+  t.true(text.indexOf('TypeScript') < text.indexOf('EXPERIENCE'))
+})
+
+test('extractTextFromPdf layout mode keeps single-column text in order', (t) => {
+  const pdf = buildPdf({ pages: [textLines(['First line', 'Second line', 'Third line'])] })
+
+  const text = extractTextFromPdf(pdf, { layout: true, cleanup: {} })
+  t.is(text, 'First line\nSecond line\nThird line')
+})
+
+test('extractMarkdownFromPdf maps font size, weight, bullets and links to Markdown', (t) => {
+  const line = (font: string, size: number, x: number, y: number, text: string) =>
+    `BT /${font} ${size} Tf 1 0 0 1 ${x} ${y} Tm ${pdfString(text)} Tj ET`
+  const content = [
+    line('F2', 24, 72, 750, 'Jane Doe'),
+    line('F1', 11, 72, 720, 'Backend engineer in Lyon'),
+    line('F2', 14, 72, 690, 'Experience'),
+    // 0x95 is the bullet glyph in WinAnsiEncoding
+    line('F1', 11, 72, 670, '\x95 Built a PDF pipeline in Rust'),
+    line('F1', 11, 72, 655, '\x95 Mentored two engineers'),
+    'BT /F1 11 Tf 1 0 0 1 72 625 Tm (Senior engineer at ) Tj /F2 11 Tf (Acme) Tj ET',
+    line('F1', 11, 72, 595, 'Portfolio:'),
+    line('F1', 11, 130, 595, 'github.com/jane'),
+  ].join('\n')
+  const pdf = buildPdf({
+    pages: [
+      {
+        content,
+        extra: '/Annots [<< /Type /Annot /Subtype /Link /Rect [128 590 250 606] /A << /S /URI /URI (https://github.com/jane) >> >>]',
+      },
+    ],
+  })
+
+  t.is(
+    extractMarkdownFromPdf(pdf),
+    [
+      '# Jane Doe',
+      '',
+      'Backend engineer in Lyon',
+      '',
+      '## Experience',
+      '',
+      '- Built a PDF pipeline in Rust',
+      '- Mentored two engineers',
+      '',
+      'Senior engineer at **Acme**',
+      '',
+      'Portfolio: [github.com/jane](https://github.com/jane)',
+    ].join('\n'),
+  )
+})
+
+test('extractMarkdownFromPdf throws on invalid PDF', (t) => {
+  const error = t.throws(
+    () => {
+      extractMarkdownFromPdf(Buffer.from('Not a PDF'))
+    },
+    { instanceOf: Error },
+  )
+
+  t.is(error?.code, 'InvalidArg')
+  t.regex(error?.message ?? '', /Failed to extract text from PDF/)
+})
+
+test('analyzePdf reports a digital PDF', (t) => {
+  const pdf = buildPdf({
+    pages: [textLines(['Jane Doe', 'Backend engineer with ten years of experience in document processing'])],
+  })
+
+  const analysis = analyzePdf(pdf)
+  t.is(analysis.verdict, 'digital')
+  t.false(analysis.needsOcr)
+  t.is(analysis.pageCount, 1)
+  t.is(analysis.textCoverage, 1)
+  t.is(analysis.pages[0].charCount, 66)
+  t.is(analysis.pages[0].imageCount, 0)
+  t.false(analysis.pages[0].likelyScanned)
+})
+
+test('analyzePdf detects an image-only PDF as a scan', (t) => {
+  const jpeg = readFileSync(path.join(__dirname, 'image.jpg'))
+  const pdf = Buffer.from(normalizeCvToPdf(jpeg, 'image/jpeg'))
+
+  const analysis = analyzePdf(pdf)
+  t.is(analysis.verdict, 'scanned')
+  t.true(analysis.needsOcr)
+  t.is(analysis.charCount, 0)
+  t.is(analysis.pages[0].imageCount, 1)
+  t.is(analysis.pages[0].imageCoverage, 1)
+  t.true(analysis.pages[0].likelyScanned)
+})
+
+test('analyzePdf reports mixed documents page by page', (t) => {
+  // Second page: a full-page inline image and no text
+  const scan = 'q 612 0 0 792 0 0 cm BI /W 2 /H 2 /CS /G /BPC 8 ID \x00\xff\xff\x00 EI Q'
+  const pdf = buildPdf({ pages: [textLines(['Curriculum vitae of Jane Doe, backend engineer in Lyon']), scan] })
+
+  const analysis = analyzePdf(pdf)
+  t.is(analysis.verdict, 'mixed')
+  t.is(analysis.scannedPages, 1)
+  t.is(analysis.textCoverage, 0.5)
+  t.deepEqual(
+    analysis.pages.map((p) => [p.pageNumber, p.likelyScanned, p.imageCoverage]),
+    [
+      [1, false, 0],
+      [2, true, 1],
+    ],
+  )
+})
+
+test('extractTextFromPdf falls back to the native extractor when pdf-extract panics', (t) => {
+  // A `Tj` before any `Tf` makes pdf-extract panic
+  const pdf = buildPdf({ pages: ['BT 1 0 0 1 72 700 Tm (No font selected) Tj ET'] })
+
+  t.is(extractTextFromPdf(pdf), 'No font selected')
+  t.is(extractTextFromPdf(pdf, { layout: true }), 'No font selected')
+  t.is(extractTextFromPdfDetailed(pdf).extractor, 'native')
+})
+
+test('native extractor decodes ToUnicode CMaps and Differences encodings', (t) => {
+  const toUnicode =
+    '/CIDInit /ProcSet findresource begin 12 dict begin begincmap\n' +
+    '1 begincodespacerange <0000> <FFFF> endcodespacerange\n' +
+    '5 beginbfchar <0001> <0043> <0002> <0061> <0003> <0066> <0004> <00E9> <0005> <0020> endbfchar\n' +
+    '1 beginbfrange <0010> <0019> <0030> endbfrange\n' +
+    'endcmap end end'
+  const digital = textLines(['Digital page'])
+  const broken = {
+    // `5 Tj` (a number instead of a string) makes pdf-extract panic on this page
+    content:
+      'BT /F5 12 Tf 72 700 Td <000100020003000400050012001000110019> Tj ' +
+      '0 -16 Td /F6 12 Tf <010203040506> Tj 5 Tj ET',
+    fonts: '/F5 5 0 R /F6 8 0 R',
+  }
+  const pdf = buildPdf({
+    pages: [digital, broken],
+    objects: [
+      '<< /Type /Font /Subtype /Type0 /BaseFont /ABCDEF+Custom /Encoding /Identity-H ' +
+        '/DescendantFonts [6 0 R] /ToUnicode 7 0 R >>',
+      '<< /Type /Font /Subtype /CIDFontType2 /BaseFont /ABCDEF+Custom ' +
+        '/CIDSystemInfo << /Registry (Adobe) /Ordering (Identity) /Supplement 0 >> /DW 500 >>',
+      pdfStream('', toUnicode),
+      '<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica ' +
+        '/Encoding << /Type /Encoding /Differences [1 /R /eacute /s /u /m /eacute] >> >>',
+    ],
+  })
+
+  const result = extractTextFromPdfDetailed(pdf)
+  t.is(result.text, '\n\nDigital page\n\nCafé 2019\nRésumé')
+  t.is(result.extractor, 'mixed')
+  t.is(extractTextFromPdfDetailed(buildPdf({ pages: [digital] })).extractor, 'pdf-extract')
+  t.is(extractMarkdownFromPdf(pdf), 'Digital page\n\nCafé 2019\nRésumé')
+})
+
+test('extractTextFromPdfDetailed stops at maxPages and timeoutMs', (t) => {
+  const pdf = buildPdf({ pages: [textLines(['Page one']), textLines(['Page two']), textLines(['Page three'])] })
+
+  const full = extractTextFromPdfDetailed(pdf, { layout: true })
+  t.is(full.pageCount, 3)
+  t.is(full.pagesExtracted, 3)
+  t.false(full.truncated)
+  t.is(full.truncationReason, undefined)
+
+  const limited = extractTextFromPdfDetailed(pdf, { layout: true, maxPages: 2 })
+  t.is(limited.text, 'Page one\n\nPage two')
+  t.is(limited.pagesExtracted, 2)
+  t.true(limited.truncated)
+  t.is(limited.truncationReason, 'max_pages')
+
+  const timedOut = extractTextFromPdfDetailed(pdf, { timeoutMs: 0 })
+  t.is(timedOut.pagesExtracted, 0)
+  t.is(timedOut.truncationReason, 'timeout')
+})
+
+test('extractTextFromPdfDetailed skips streams above maxStreamBytes', (t) => {
+  // A form XObject that inflates to 4 MB of spaces
+  const bomb = pdfStream(
+    '/Type /XObject /Subtype /Form /BBox [0 0 10 10] /Filter /FlateDecode',
+    deflateSync(Buffer.alloc(4 * 1024 * 1024, ' ')),
+  )
+  const pdf = buildPdf({
+    objects: [bomb],
+    pages: [{ content: `${textLines(['Jane Doe'])}\n/Bomb Do`, resources: '/XObject << /Bomb 5 0 R >>' }],
+  })
+
+  const result = extractTextFromPdfDetailed(pdf, { maxStreamBytes: 1024 * 1024, cleanup: {} })
+  t.is(result.text, 'Jane Doe')
+  t.true(result.truncated)
+  t.is(result.truncationReason, 'max_stream_bytes')
+
+  t.is(extractTextFromPdf(pdf, { maxStreamBytes: 1024 * 1024, cleanup: {} }), 'Jane Doe')
+})
+
+test('extractTextFromPdfDetailed bounds the work spent on a single page', (t) => {
+  // Two passes of Flate over 64 MB of spaces: the outer one only inflates to ~64 KB
+  const chain = pdfStream(
+    '/Type /XObject /Subtype /Form /BBox [0 0 10 10] /Filter [/FlateDecode /FlateDecode]',
+    deflateSync(deflateSync(Buffer.alloc(64 * 1024 * 1024, ' '))),
+  )
+  const bomb = buildPdf({
+    objects: [chain],
+    pages: [{ content: `${textLines(['Jane Doe'])}\n/Bomb Do`, resources: '/XObject << /Bomb 5 0 R >>' }],
+  })
+  const stripped = extractTextFromPdfDetailed(bomb, { maxStreamBytes: 1024 * 1024, cleanup: {} })
+  t.is(stripped.text, 'Jane Doe')
+  t.is(stripped.truncationReason, 'max_stream_bytes')
+
+  // Millions of path operations on the second page: the timeout stops inside it
+  const paths = pdfStream(
+    '/Type /XObject /Subtype /Form /BBox [0 0 10 10] /Filter /FlateDecode',
+    deflateSync(Buffer.from('0 0 m 1 1 l S\n'.repeat(1_000_000), 'latin1')),
+  )
+  const heavy = buildPdf({
+    objects: [paths],
+    pages: [
+      textLines(['Jane Doe']),
+      { content: '/Paths Do', resources: '/XObject << /Paths 5 0 R >>' },
+      textLines(['Designer']),
+    ],
+  })
+  const timedOut = extractTextFromPdfDetailed(heavy, { timeoutMs: 20, cleanup: {} })
+  t.is(timedOut.text, 'Jane Doe')
+  t.is(timedOut.pagesExtracted, 1)
+  t.is(timedOut.truncationReason, 'timeout')
+
+  // A form drawing itself is read by the native extractor instead of pdf-extract
+  const loop = pdfStream(
+    '/Type /XObject /Subtype /Form /BBox [0 0 612 792] /Resources << /XObject << /Fm 5 0 R >> /Font << /F1 3 0 R >> >>',
+    `${textLines(['Jane Doe'])}\n/Fm Do`,
+  )
+  const cyclic = buildPdf({
+    objects: [loop],
+    pages: [{ content: '/Fm Do', resources: '/XObject << /Fm 5 0 R >>' }],
+  })
+  t.is(extractTextFromPdf(cyclic).trim(), 'Jane Doe')
+  t.is(extractTextFromPdfDetailed(cyclic).extractor, 'native')
+})
+
+test('scoreTextQuality tells clean text from mojibake and private-use glyphs', (t) => {
+  const clean = scoreTextQuality(
+    'Jane Doe — Senior Software Engineer, Paris\njane.doe@example.com | +33 6 12 34 56 78\n' +
+      '2019–2024: Lead developer at ACME. TypeScript, PostgreSQL, CSS3, CI/CD.',
+  )
+  t.is(clean.verdict, 'good')
+  t.true(clean.score > 0.9)
+
+  const mojibake = scoreTextQuality('Ã©tÃ© Ã  Paris â€™ dÃ©veloppeur CompÃ©tences')
+  t.is(mojibake.verdict, 'garbled')
+
+  const privateUse = scoreTextQuality('\uE001\uE002\uE003 \uE004\uE005')
+  t.is(privateUse.verdict, 'garbled')
+  t.is(privateUse.invalidCharRatio, 1)
+
+  t.is(scoreTextQuality(' \n ').verdict, 'empty')
+})
+
+test('scoreTextQuality handles scripts without spaces and letter soup', (t) => {
+  const chinese = scoreTextQuality('软件工程师，十年经验，熟悉分布式系统和云计算')
+  t.is(chinese.verdict, 'good')
+  t.is(chinese.wordRatio, 1)
+  t.is(scoreTextQuality('ソフトウェアエンジニア。東京都在住（Java、Python）').verdict, 'good')
+
+  const soup = scoreTextQuality('kjh qwx zzv bnm')
+  t.is(soup.verdict, 'garbled')
+  t.true(soup.wordRatio < 0.5)
+
+  // Consonant clusters of real names and acronyms in capitals are not letter soup
+  const names = scoreTextQuality('Krzysztof Wojciechowski, Schwarzkopf GmbH, Ljubljana. Skills: AWS, GCP, SQL')
+  t.is(names.verdict, 'good')
+  t.is(scoreTextQuality('Strengths: Schmidt and Schwarz led the migration to Kubernetes.').verdict, 'good')
+  t.is(scoreTextQuality('COMPÉTENCES TECHNIQUES · Łukasz Żółć, Dvořák, Ștefan Țurcan').verdict, 'good')
+
+  // Runs of Latin-1 capitals and rare Latin letters are mojibake, in capitals too
+  t.not(scoreTextQuality('ÊÌÏÐ ÑÒÓÔ ÕÖØÙ ÚÛÜÝ').verdict, 'good')
+  t.not(scoreTextQuality('Ǧǝ ȳȰ ȶɃ ɉɌ').verdict, 'good')
+  t.not(scoreTextQuality('XKCDQWRTZ BNMVCXZLK').verdict, 'good')
+})
+
+test('extractTextFromPdfDetailed scores the quality of the extracted text', (t) => {
+  const digital = extractTextFromPdfDetailed(buildPdf({ pages: [textLines(['Jane Doe', 'Software Engineer'])] }))
+  t.is(digital.quality.verdict, 'good')
+
+  // Subset font without ToUnicode whose glyph names carry no meaning
+  const pdf = buildPdf({
+    pages: [{ content: 'BT /F5 12 Tf 72 700 Td <0102030405060708> Tj ET', fonts: '/F5 5 0 R' }],
+    objects: [
+      '<< /Type /Font /Subtype /Type1 /BaseFont /ABCDEF+Custom ' +
+        '/Encoding << /Type /Encoding /Differences [1 /g17 /g42 /g8 /g3 /g99 /g12 /g5 /g23] >> >>',
+    ],
+  })
+  const garbled = extractTextFromPdfDetailed(pdf)
+  t.is(garbled.quality.verdict, 'garbled')
+  t.true(garbled.quality.score < 0.4)
+})
+
+/** Stub tesseract executable: writes the language and input file name as the OCR output. */
+function tesseractStub(name: string): string {
+  const dir = path.join(__dirname, name)
+  mkdirSync(dir, { recursive: true })
+  const stub = path.join(dir, 'tesseract')
+  writeFileSync(
+    stub,
+    '#!/bin/sh\n' +
+      'printf "Recognized %s from %s\\n" "$4" "$(basename "$1")" > "$2.txt"\n' +
+      'printf "<div class=\'ocr_page\'>%s</div>\\n" "$(basename "$1")" > "$2.hocr"\n',
+    { mode: 0o755 },
+  )
+  return stub
+}
+
+test('ocrDocument runs tesseract on images and image-only PDF pages', (t) => {
+  const stubDir = path.join(__dirname, 'test-ocr-stub')
+  const tesseractPath = tesseractStub('test-ocr-stub')
+  try {
+    const jpeg = readFileSync(path.join(__dirname, 'image.jpg'))
+    const image = ocrDocument(jpeg, 'image/jpeg', { tesseractPath, language: 'fra+eng' })
+    t.is(image.status, 'ok')
+    t.is(image.text, 'Recognized fra+eng from page-1.png')
+    t.is(image.pages[0].hocr.trim(), "<div class='ocr_page'>page-1.png</div>")
+
+    // JPEG scans are passed through, other images are exported as PNG
+    const scanned = ocrDocument(Buffer.from(normalizeCvToPdf(jpeg, 'image/jpeg')), 'application/pdf', { tesseractPath })
+    t.is(scanned.text, 'Recognized eng from page-1.jpeg')
+
+    const scan = 'q 612 0 0 792 0 0 cm BI /W 2 /H 2 /CS /G /BPC 8 ID \x00\xff\xff\x00 EI Q'
+    const mixed = buildPdf({ pages: [textLines(['Curriculum vitae of Jane Doe, backend engineer in Lyon']), scan] })
+    const result = ocrDocument(mixed, 'application/pdf', { tesseractPath })
+    t.deepEqual(
+      result.pages.map((p) => [p.pageNumber, p.text.trim()]),
+      [[2, 'Recognized eng from page-2.png']],
+    )
+
+    const digital = ocrDocument(buildPdf({ pages: [textLines(['Jane Doe'])] }), 'application/pdf', { tesseractPath })
+    t.is(digital.status, 'not_needed')
+    t.deepEqual(digital.pages, [])
+
+    // CCITT fax scans are wrapped in a TIFF file; JBIG2 ones are reported as skipped
+    const fax = (filter: string, parms = '') =>
+      pdfStream(
+        `/Type /XObject /Subtype /Image /Width 8 /Height 8 /BitsPerComponent 1 /Filter /${filter} ${parms}`,
+        '\xff',
+      )
+    const page = { content: 'q 612 0 0 792 0 0 cm /Im0 Do Q' }
+    const faxes = buildPdf({
+      objects: [fax('CCITTFaxDecode', '/DecodeParms << /K -1 /Columns 8 >>'), fax('JBIG2Decode')],
+      pages: [
+        { ...page, resources: '/XObject << /Im0 5 0 R >>' },
+        { ...page, resources: '/XObject << /Im0 6 0 R >>' },
+      ],
+    })
+    const partial = ocrDocument(faxes, 'application/pdf', { tesseractPath })
+    t.is(partial.status, 'ok')
+    t.deepEqual(
+      partial.pages.map((p) => [p.pageNumber, p.text.trim()]),
+      [[1, 'Recognized eng from page-1.tiff']],
+    )
+    t.regex(partial.message ?? '', /page 2 \(JBIG2Decode\)/)
+
+    const jbig2 = buildPdf({
+      objects: [fax('JBIG2Decode')],
+      pages: [{ ...page, resources: '/XObject << /Im0 5 0 R >>' }],
+    })
+    const unsupported = ocrDocument(jbig2, 'application/pdf', { tesseractPath })
+    t.is(unsupported.status, 'unsupported')
+    t.deepEqual(unsupported.pages, [])
+    t.regex(unsupported.message ?? '', /page 1 \(JBIG2Decode\)/)
+  } finally {
+    rmSync(stubDir, { recursive: true, force: true })
+  }
+})
+
+test('ocrDocument reports OCR as unavailable without a tesseract executable', (t) => {
+  const jpeg = readFileSync(path.join(__dirname, 'image.jpg'))
+  const result = ocrDocument(jpeg, 'image/jpeg', { tesseractPath: path.join(__dirname, 'missing', 'tesseract') })
+  t.is(result.status, 'unavailable')
+  t.is(result.text, '')
+  t.regex(result.message ?? '', /OCR unavailable/)
+})
+
+test('normalizeCvToPdf adds an invisible text layer from hOCR', (t) => {
+  const jpeg = readFileSync(path.join(__dirname, 'image.jpg'))
+  const hocr = `<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE html PUBLIC "-//W3C//DTD XHTML 1.0 Transitional//EN" "http://www.w3.org/TR/xhtml1/DTD/xhtml1-transitional.dtd">
+<html xmlns="http://www.w3.org/1999/xhtml"><body>
+<div class="ocr_page" title="image; bbox 0 0 1000 1000">
+  <span class="ocr_line" title="bbox 100 200 600 240; baseline 0 -5; x_size 30">
+    <span class="ocrx_word" title="bbox 100 200 300 240; x_wconf 95">Jane</span>
+    <span class="ocrx_word" title="bbox 320 200 600 240; x_wconf 93">Développeuse</span>
+  </span>
+  <span class="ocr_line" title="bbox 100 300 400 340; baseline 0 -5; x_size 30">
+    <span class="ocrx_word" title="bbox 100 300 400 340; x_wconf 90">Lyon</span>
+  </span>
+</div></body></html>`
+
+  const pdf = Buffer.from(normalizeCvToPdf(jpeg, 'image/jpeg', { ocr: hocr }))
+  const raw = pdf.toString('latin1')
+  t.regex(raw, /3 Tr/)
+
+  // OCR coordinates are scaled to the page size chosen for the image
+  const [, width, height] = raw.match(/\/MediaBox \[0 0 (\d+) (\d+)\]/) ?? []
+  const tm = raw.match(/1 0 0 1 ([\d.]+) ([\d.]+) Tm/) ?? []
+  t.is(Number(tm[1]), Math.round(Number(width) * 10) / 100)
+  t.is(Number(tm[2]), Math.round((Number(height) - (235 * Number(height)) / 1000) * 100) / 100)
+
+  const text = extractTextFromPdf(pdf)
+  t.regex(text, /Jane Développeuse/)
+  t.regex(text, /Lyon/)
+  t.is(analyzePdf(pdf).pages[0].charCount, 20)
+
+  // The text layer font is embedded, so the output passes its own font check
+  t.true(listPdfFonts(pdf).every((font) => font.embedded))
+  t.notThrows(() => normalizeCvToPdf(pdf, 'application/pdf', { requireEmbeddedFonts: true }))
+})
+
+test('normalizeCvToPdf adds an invisible text layer from ALTO', (t) => {
+  const jpeg = readFileSync(path.join(__dirname, 'image.jpg'))
+  const alto = `<?xml version="1.0" encoding="UTF-8"?>
+<alto xmlns="http://www.loc.gov/standards/alto/ns-v3#">
+  <Layout><Page WIDTH="1000" HEIGHT="1000" PHYSICAL_IMG_NR="1"><PrintSpace>
+    <TextBlock><TextLine HPOS="100" VPOS="200" WIDTH="500" HEIGHT="40" BASELINE="235">
+      <String CONTENT="Senior" HPOS="100" VPOS="200" WIDTH="200" HEIGHT="40"/>
+      <SP/>
+      <String CONTENT="engineer" HPOS="320" VPOS="200" WIDTH="280" HEIGHT="40"/>
+    </TextLine></TextBlock>
+  </PrintSpace></Page></Layout>
+</alto>`
+
+  const pdf = Buffer.from(normalizeCvToPdf(jpeg, 'image/jpeg', { ocr: alto }))
+  t.regex(extractTextFromPdf(pdf), /Senior engineer/)
+})
+
+test('normalizeCvToPdf rejects invalid OCR results', (t) => {
+  const jpeg = readFileSync(path.join(__dirname, 'image.jpg'))
+  const error = t.throws(() => normalizeCvToPdf(jpeg, 'image/jpeg', { ocr: '<svg></svg>' }))
+  t.regex(error?.message ?? '', /Failed to parse OCR results: expected hOCR/)
+  t.throws(() => normalizeCvToPdf(jpeg, 'image/jpeg', { ocr: '<html><body>' }))
+})
+
+/** Application form with a text field, a nested text field, a checkbox, a combo box and a signature. */
+function applicationForm(): Buffer {
+  const widget = (rect: string) => `/Type /Annot /Subtype /Widget /Rect [${rect}] /P 14 0 R`
+  const form = (bbox: string, content: string) =>
+    pdfStream(`/Type /XObject /Subtype /Form /BBox [${bbox}] /Resources << /Font << /F1 3 0 R >> >>`, content)
+  return buildPdf({
+    objects: [
+      // 5: text field merged with its widget, without appearance stream
+      `<< /FT /Tx /T (name) /V (Jane Doe) /DA (/Helv 0 Tf 0 g) ${widget('150 700 400 720')} >>`,
+      // 6-8: `contact.email`, with an appearance stream
+      '<< /T (contact) /Kids [7 0 R] >>',
+      `<< /FT /Tx /T (email) /V (jane@example.com) /Parent 6 0 R /AP << /N 8 0 R >> ${widget('150 660 400 680')} >>`,
+      form('0 0 250 20', 'BT /F1 10 Tf 2 6 Td (jane@example.com) Tj ET'),
+      // 9-11: checked checkbox
+      `<< /FT /Btn /T (relocate) /V /Yes /AS /Yes /AP << /N << /Yes 10 0 R /Off 11 0 R >> >> ${widget('150 620 162 632')} >>`,
+      form('0 0 12 12', 'BT /F1 10 Tf 2 2 Td (X) Tj ET'),
+      form('0 0 12 12', ''),
+      // 12: combo box
+      `<< /FT /Ch /Ff 131072 /T (contract) /V <FEFF0043004400490020002D0020004C0079006F006E> ${widget('150 580 300 600')} >>`,
+      // 13: unsigned signature field, not on a page
+      '<< /FT /Sig /T (signature) >>',
+    ],
+    pages: [
+      {
+        content: textLines(['Name:', '', '', 'E-mail:', '', '', 'Relocation:', '', '', 'Contract:'], { top: 705 }),
+        extra: '/Annots [5 0 R 7 0 R 9 0 R 12 0 R]',
+      },
+    ],
+    catalog: '/AcroForm << /Fields [5 0 R 6 0 R 9 0 R 12 0 R 13 0 R] /DA (/Helv 0 Tf 0 g) >>',
+  })
+}
+
+test('extractFormFields returns name, type, value and page of every field', (t) => {
+  const fields = extractFormFields(applicationForm())
+  t.deepEqual(
+    fields.map((f) => [f.name, f.fieldType, f.value, f.pageNumber]),
+    [
+      ['name', 'text', 'Jane Doe', 1],
+      ['contact.email', 'text', 'jane@example.com', 1],
+      ['relocate', 'checkbox', 'Yes', 1],
+      ['contract', 'choice', 'CDI - Lyon', 1],
+      ['signature', 'signature', undefined, undefined],
+    ],
+  )
+})
+
+test('extractFormFields returns an empty list for PDFs without a form', (t) => {
+  t.deepEqual(extractFormFields(buildPdf({ pages: [textLines(['No form here'])] })), [])
+  t.throws(() => extractFormFields(Buffer.from('not a pdf')))
+})
+
+test('normalizeCvToPdf flattens form fields into the page content', (t) => {
+  const input = applicationForm()
+  t.notRegex(extractTextFromPdf(input), /Jane Doe/)
+
+  const pdf = Buffer.from(normalizeCvToPdf(input, 'application/pdf', { flattenForms: true }))
+  t.deepEqual(extractFormFields(pdf), [])
+  t.notRegex(pdf.toString('latin1'), /\/AcroForm|\/Widget/)
+
+  const text = extractTextFromPdf(pdf)
+  for (const value of ['Jane Doe', 'jane@example.com', 'X', 'CDI - Lyon']) {
+    t.true(text.includes(value), value)
+  }
+})
+
+test('normalizeCvToPdf flattens any text value and checked boxes without appearance', (t) => {
+  const widget = (rect: string) => `/Type /Annot /Subtype /Widget /Rect [${rect}] /P 8 0 R`
+  const name = Buffer.from('\ufeffŁukasz Żółć', 'utf16le').swap16().toString('hex')
+  const input = buildPdf({
+    objects: [
+      `<< /FT /Tx /T (name) /V <${name}> /DA (/Helv 0 Tf 0 g) ${widget('150 700 400 720')} >>`,
+      `<< /FT /Btn /T (relocate) /V /Yes /AS /Yes ${widget('150 660 162 672')} >>`,
+      `<< /FT /Btn /Ff 32768 /T (remote) /V /Off ${widget('150 620 162 632')} >>`,
+    ],
+    pages: [{ content: textLines(['Name:', '', '', 'Relocation:', '', '', 'Remote:'], { top: 705 }), extra: '/Annots [5 0 R 6 0 R 7 0 R]' }],
+    catalog: '/AcroForm << /Fields [5 0 R 6 0 R 7 0 R] >>',
+  })
+
+  const pdf = Buffer.from(normalizeCvToPdf(input, 'application/pdf', { flattenForms: true }))
+  const text = extractTextFromPdf(pdf)
+  t.true(text.includes('Łukasz Żółć'), text)
+  t.is(text.split('✓').length - 1, 1)
+  const fonts = listPdfFonts(pdf)
+  t.deepEqual(fonts.filter((font) => !font.embedded).map((font) => font.name), ['Helvetica'])
+  t.true(fonts.some((font) => font.name === 'DejaVuSans' && font.subset))
+})
+
+test('normalizeCvToPdf flattens annotations and keeps links', (t) => {
+  const stamp = (text: string) =>
+    pdfStream(
+      '/Type /XObject /Subtype /Form /BBox [0 0 100 20] /Resources << /Font << /F1 3 0 R >> >>',
+      `BT /F1 12 Tf 2 5 Td (${text}) Tj ET`,
+    )
+  const input = buildPdf({
+    objects: [
+      // 5-6: stamp with an appearance stream, drawn half transparent
+      '<< /Type /Annot /Subtype /Stamp /Rect [300 700 400 720] /CA 0.5 /AP << /N 6 0 R >> >>',
+      stamp('APPROVED'),
+      // 7-8: highlight without appearance stream, and its popup
+      '<< /Type /Annot /Subtype /Highlight /Rect [72 745 200 765] /QuadPoints [72 765 200 765 72 745 200 745] /C [1 1 0] /Popup 8 0 R >>',
+      '<< /Type /Annot /Subtype /Popup /Rect [400 600 550 700] /Parent 7 0 R >>',
+      // 9: link
+      '<< /Type /Annot /Subtype /Link /Rect [72 728 250 746] /A << /S /URI /URI (https://example.com/jane) >> >>',
+      // 10-11: hidden note
+      '<< /Type /Annot /Subtype /FreeText /Rect [300 600 400 620] /F 2 /AP << /N 11 0 R >> >>',
+      stamp('SECRET'),
+    ],
+    pages: [
+      {
+        content: textLines(['Jane Doe', 'example.com/jane']),
+        extra: '/Annots [5 0 R 7 0 R 8 0 R 9 0 R 10 0 R]',
+      },
+    ],
+  })
+
+  const pdf = Buffer.from(normalizeCvToPdf(input, 'application/pdf', { flatten: true }))
+  const raw = pdf.toString('latin1')
+  t.notRegex(raw, /\/Stamp|\/Highlight|\/Popup|\/FreeText/)
+  t.regex(raw, /\/BM ?\/Multiply/)
+  t.regex(raw, /\/CA ?0\.5/)
+
+  const text = extractTextFromPdf(pdf)
+  t.true(text.includes('APPROVED'))
+  t.false(text.includes('SECRET'))
+  t.true(extractMarkdownFromPdf(pdf).includes('(https://example.com/jane)'))
+})
+
+/** CV page with a photo (JPEG) in the header and a two-color logo (Flate RGB). */
+function cvWithPhoto(photo = true): Buffer {
+  const jpeg = readFileSync(path.join(__dirname, 'image.jpg'))
+  const logo = Buffer.alloc(40 * 10 * 3)
+  for (let i = 0; i < 40 * 10; i++) {
+    logo.set(i % 40 < 20 ? [0, 70, 140] : [255, 255, 255], i * 3)
+  }
+  return buildPdf({
+    objects: [
+      pdfStream(
+        '/Type /XObject /Subtype /Image /Width 1080 /Height 1350 /ColorSpace /DeviceRGB /BitsPerComponent 8 /Filter /DCTDecode',
+        jpeg,
+      ),
+      pdfStream(
+        '/Type /XObject /Subtype /Image /Width 40 /Height 10 /ColorSpace /DeviceRGB /BitsPerComponent 8 /Filter /FlateDecode',
+        deflateSync(logo),
+      ),
+    ],
+    pages: [
+      {
+        content: [
+          textLines(['Jane Doe', 'Backend engineer']),
+          photo ? 'q 96 0 0 120 460 640 cm /Photo Do Q' : '',
+          'q 120 0 0 30 72 40 cm /Logo Do Q',
+        ].join('\n'),
+        resources: '/XObject << /Photo 5 0 R /Logo 6 0 R >>',
+      },
+    ],
+  })
+}
+
+test('extractPdfImages lists images with placement, size and encoded bytes', (t) => {
+  const images = extractPdfImages(cvWithPhoto())
+  t.deepEqual(
+    images.map((i) => [i.pageNumber, i.rect, i.pixelWidth, i.pixelHeight, i.format, i.likelyPortrait]),
+    [
+      [1, [460, 640, 556, 760], 1080, 1350, 'jpeg', false],
+      [1, [72, 40, 192, 70], 40, 10, 'png', false],
+    ],
+  )
+  // DCT data is passed through unchanged; other images are re-encoded as PNG
+  t.true(Buffer.from(images[0].data).equals(readFileSync(path.join(__dirname, 'image.jpg'))))
+  t.deepEqual(images[1].data.slice(0, 4), [0x89, 0x50, 0x4e, 0x47])
+})
+
+test('extractPdfImages flags the likely portrait photo', (t) => {
+  const images = extractPdfImages(cvWithPhoto(), { detectPortrait: true })
+  t.deepEqual(
+    images.map((i) => i.likelyPortrait),
+    [true, false],
+  )
+
+  const logoOnly = extractPdfImages(cvWithPhoto(false), { detectPortrait: true })
+  t.is(logoOnly.length, 1)
+  t.false(logoOnly[0].likelyPortrait)
+})
+
+test('extractPdfImages throws on invalid PDF', (t) => {
+  t.throws(() => extractPdfImages(Buffer.from('not a pdf')))
+})
+
+test('extractPdfOutline reads nested bookmarks with explicit and named destinations', (t) => {
+  const pdf = buildPdf({
+    objects: [
+      '<< /Type /Outlines /First 6 0 R /Last 8 0 R /Count 3 >>',
+      '<< /Title (Experience) /Parent 5 0 R /Next 8 0 R /First 7 0 R /Last 7 0 R /Count 1 /Dest [9 0 R /XYZ 0 792 null] >>',
+      '<< /Title (Acme Corp) /Parent 6 0 R /A << /S /GoTo /D (acme) >> >>',
+      '<< /Title <FEFF00C9006400750063006100740069006F006E> /Parent 5 0 R /Prev 6 0 R /Dest /education >>',
+    ],
+    pages: [textLines(['Experience', 'Acme Corp']), textLines(['Education'])],
+    catalog:
+      '/Outlines 5 0 R /Names << /Dests << /Names [(acme) [9 0 R /XYZ 0 500 null]] >> >> /Dests << /education [11 0 R /Fit] >>',
+  })
+
+  t.deepEqual(extractPdfOutline(pdf), [
+    {
+      title: 'Experience',
+      pageNumber: 1,
+      children: [{ title: 'Acme Corp', pageNumber: 1, children: [] }],
+    },
+    { title: 'Éducation', pageNumber: 2, children: [] },
+  ])
+  t.deepEqual(extractPdfOutline(buildPdf({ pages: [textLines(['No outline'])] })), [])
+})
+
+test('setPdfOutline writes an outline that extractPdfOutline reads back', (t) => {
+  const input = buildPdf({ pages: [textLines(['Profile']), textLines(['Skills'])] })
+  const outline = [
+    { title: 'Profil', pageNumber: 1, children: [{ title: 'Compétences', pageNumber: 2, children: [] }] },
+    { title: 'Contact', children: [] },
+  ]
+
+  const pdf = Buffer.from(setPdfOutline(input, outline))
+  const read = extractPdfOutline(pdf)
+  t.is(read.length, 2)
+  t.deepEqual(read[0], outline[0])
+  t.is(read[1].title, 'Contact')
+  t.is(read[1].pageNumber, undefined)
+  t.is(extractTextFromPdf(pdf).trim(), extractTextFromPdf(input).trim())
+
+  t.deepEqual(extractPdfOutline(Buffer.from(setPdfOutline(pdf, []))), [])
+  const error = t.throws(() => setPdfOutline(input, [{ title: 'Missing', pageNumber: 3, children: [] }]))
+  t.regex(error?.message ?? '', /page 3, but the PDF has 2 pages/)
+})
+
+const embeddedFontObjects = [
+  '<< /Type /Font /Subtype /TrueType /BaseFont /ABCDEF+Lato-Regular /FontDescriptor 6 0 R /ToUnicode 7 0 R ' +
+    '/Encoding << /BaseEncoding /WinAnsiEncoding /Differences [32 /space] >> >>',
+  '<< /Type /FontDescriptor /FontName /ABCDEF+Lato-Regular /Flags 32 /FontBBox [0 0 1000 1000] /ItalicAngle 0 ' +
+    '/Ascent 800 /Descent -200 /CapHeight 700 /StemV 80 /FontFile2 8 0 R >>',
+  pdfStream('', '/CIDInit /ProcSet findresource begin\nbegincmap\nendcmap\nend\n'),
+  pdfStream('', 'not a real font program'),
+]
+
+test('listPdfFonts reports subtype, encoding, embedding and ToUnicode per page', (t) => {
+  const pdf = buildPdf({
+    objects: embeddedFontObjects,
+    pages: [
+      { content: `${textLines(['Jane Doe'], { font: 'F3' })}\n${textLines(['Designer'], { top: 700 })}`, fonts: '/F3 5 0 R' },
+      textLines(['Experience', 'Acme'], { font: 'F2' }),
+    ],
+  })
+  t.deepEqual(listPdfFonts(pdf), [
+    {
+      pageNumber: 1,
+      name: 'Lato-Regular',
+      subtype: 'TrueType',
+      encoding: 'WinAnsiEncoding with Differences',
+      embedded: true,
+      subset: true,
+      hasToUnicode: true,
+    },
+    {
+      pageNumber: 1,
+      name: 'Helvetica',
+      subtype: 'Type1',
+      encoding: 'WinAnsiEncoding',
+      embedded: false,
+      subset: false,
+      hasToUnicode: false,
+    },
+    {
+      pageNumber: 2,
+      name: 'Helvetica-Bold',
+      subtype: 'Type1',
+      encoding: 'WinAnsiEncoding',
+      embedded: false,
+      subset: false,
+      hasToUnicode: false,
+    },
+  ])
+  t.throws(() => listPdfFonts(Buffer.from('not a pdf')), { code: 'InvalidArg' })
+})
+
+test('content walks do not follow a form XObject drawing itself', (t) => {
+  const draws = Array.from({ length: 12 }, () => 'q /Fm Do Q').join('\n')
+  const form = pdfStream(
+    '/Type /XObject /Subtype /Form /BBox [0 0 612 792] /Resources << /XObject << /Fm 5 0 R >> /Font << /F1 3 0 R >> >>',
+    `${textLines(['Jane Doe'])}\n${draws}`,
+  )
+  const pdf = buildPdf({
+    pages: [{ content: 'q /Fm Do Q', resources: '/XObject << /Fm 5 0 R >>' }],
+    objects: [form],
+  })
+
+  t.deepEqual(extractPdfImages(pdf), [])
+  t.deepEqual(listPdfFonts(pdf).map((font) => font.name), ['Helvetica'])
+  const redacted = redactPdf(pdf, { areas: [{ pageNumber: 1, rect: [0, 0, 100, 100] }] })
+  t.is(redacted.glyphsRemoved, 0)
+})
+
+test('normalizeCvToPdf can require embedded fonts', (t) => {
+  const standard = buildPdf({ pages: [textLines(['Jane Doe'], { font: 'F2' }), textLines(['Designer'])] })
+  const error = t.throws(() => normalizeCvToPdf(standard, 'application/pdf', { requireEmbeddedFonts: true }))
+  t.is(error?.message, 'PDF uses non-embedded fonts: Helvetica, Helvetica-Bold')
+  t.notThrows(() => normalizeCvToPdf(standard, 'application/pdf'))
+
+  const embedded = buildPdf({
+    objects: embeddedFontObjects,
+    pages: [{ content: textLines(['Jane Doe'], { font: 'F3' }), fonts: '/F3 5 0 R' }],
+  })
+  const output = Buffer.from(normalizeCvToPdf(embedded, 'application/pdf', { requireEmbeddedFonts: true }))
+  t.is(output.subarray(0, 5).toString(), '%PDF-')
+})
+
+test('diffCvText reports insertions, deletions, edited lines and moved sections', (t) => {
+  const oldCv = buildPdf({
+    pages: [
+      textLines([
+        'Jane Doe',
+        'Skills',
+        'Python, Rust, SQL',
+        'Experience',
+        'Acme Corp - Developer (2020-2023)',
+        'Globex - Intern',
+        'Hobbies: chess',
+      ]),
+    ],
+  })
+  const newCv = buildPdf({
+    pages: [
+      textLines([
+        'Jane Doe',
+        'Experience',
+        'Acme Corp - Senior Developer (2020-2024)',
+        'Globex - Intern',
+        'Skills',
+        'Python, Rust, SQL',
+        'Languages: French, English',
+      ]),
+    ],
+  })
+  const diff = diffCvText(oldCv, newCv)
+
+  t.deepEqual(
+    diff.lines.map((line) => [line.kind, line.text, line.moveId ?? null]),
+    [
+      ['equal', 'Jane Doe', null],
+      ['insert', 'Experience', 1],
+      ['change', 'Acme Corp - Senior Developer (2020-2024)', 1],
+      ['insert', 'Globex - Intern', 1],
+      ['equal', 'Skills', null],
+      ['equal', 'Python, Rust, SQL', null],
+      ['delete', 'Experience', 1],
+      ['delete', 'Acme Corp - Developer (2020-2023)', 1],
+      ['delete', 'Globex - Intern', 1],
+      ['delete', 'Hobbies: chess', null],
+      ['insert', 'Languages: French, English', null],
+    ],
+  )
+  t.deepEqual(diff.lines[2].words, [
+    { kind: 'equal', text: 'Acme' },
+    { kind: 'equal', text: 'Corp' },
+    { kind: 'equal', text: '-' },
+    { kind: 'insert', text: 'Senior' },
+    { kind: 'equal', text: 'Developer' },
+    { kind: 'delete', text: '(2020-2023)' },
+    { kind: 'insert', text: '(2020-2024)' },
+  ])
+  t.deepEqual(diff.moves, [{ id: 1, oldLine: 4, newLine: 2, lineCount: 3 }])
+  t.deepEqual([diff.inserted, diff.deleted, diff.changed, diff.moved], [1, 1, 1, 2])
+
+  const same = diffCvText(oldCv, oldCv)
+  t.true(same.lines.every((line) => line.kind === 'equal'))
+  t.is(same.similarity, 1)
+  t.throws(() => diffCvText(oldCv, Buffer.from('not a pdf')), { code: 'InvalidArg' })
+})
+
+test('normalizeCvToPdf renders DOCX natively', (t) => {
+  const run = (text: string, props = '') => `<w:r><w:rPr>${props}</w:rPr><w:t xml:space="preserve">${text}</w:t></w:r>`
+  const para = (content: string, props = '') => `<w:p><w:pPr>${props}</w:pPr>${content}</w:p>`
+  const cell = (text: string) => `<w:tc>${para(run(text))}</w:tc>`
+  const jpeg = readFileSync(path.join(__dirname, 'image.jpg'))
+  const docx = buildDocx(
+    [
+      para(run('Hélène Dupré'), '<w:pStyle w:val="Heading1"/>'),
+      para(
+        `<w:r><w:drawing><wp:inline><wp:extent cx="1270000" cy="1270000"/><a:graphic><a:graphicData><a:blip r:embed="rIdphoto"/></a:graphicData></a:graphic></wp:inline></w:drawing></w:r>`,
+      ),
+      para(run('Développeuse ') + run('Rust', '<w:b/>') + run(' et ') + run('TypeScript', '<w:i/>')),
+      para(run('Conception d’API'), '<w:numPr><w:ilvl w:val="0"/><w:numId w:val="1"/></w:numPr>'),
+      para(run('Revue de code'), '<w:numPr><w:ilvl w:val="0"/><w:numId w:val="1"/></w:numPr>'),
+      `<w:tbl><w:tblGrid><w:gridCol w:w="3000"/><w:gridCol w:w="6000"/></w:tblGrid><w:tr>${cell('2020–2024')}${cell('Acme, Zürich')}</w:tr></w:tbl>`,
+      para('<w:r><w:br w:type="page"/></w:r>'),
+      para(run('Références sur demande'), '<w:pStyle w:val="Heading2"/>'),
+      '<w:sectPr><w:pgSz w:w="12240" w:h="15840"/><w:pgMar w:top="1440" w:right="1440" w:bottom="1440" w:left="1440"/></w:sectPr>',
+    ].join(''),
+    {
+      styles:
+        '<w:style w:type="paragraph" w:styleId="Heading1"><w:name w:val="heading 1"/><w:rPr><w:b/><w:sz w:val="36"/></w:rPr></w:style>',
+      numbering:
+        '<w:abstractNum w:abstractNumId="0"><w:lvl w:ilvl="0"><w:numFmt w:val="decimal"/><w:lvlText w:val="%1."/><w:pPr><w:ind w:left="720" w:hanging="360"/></w:pPr></w:lvl></w:abstractNum><w:num w:numId="1"><w:abstractNumId w:val="0"/></w:num>',
+      media: { 'photo.jpg': jpeg },
+    },
+  )
+
+  const pdf = Buffer.from(
+    normalizeCvToPdf(docx, 'application/vnd.openxmlformats-officedocument.wordprocessingml.document'),
+  )
+  t.is(pdf.subarray(0, 5).toString('latin1'), '%PDF-')
+  // US Letter, from the section properties
+  t.true(pdf.toString('latin1').includes('/MediaBox [0 0 612.00 792.00]'))
+
+  const pages = extractTextFromPdf(pdf, { cleanup: {} }).split(/\n\s*\n/)
+  const text = pages.join('\n')
+  for (const expected of ['Hélène Dupré', 'Développeuse Rust et TypeScript', '1. Conception d’API', '2. Revue de code', 'Acme, Zürich']) {
+    t.true(text.includes(expected), expected)
+  }
+  t.is(extractPdfImages(pdf).length, 1)
+  t.is(extractTextFromPdfDetailed(pdf).pageCount, 2)
+  // Headings are bookmarked, nested by level
+  t.deepEqual(extractPdfOutline(pdf), [
+    {
+      title: 'Hélène Dupré',
+      pageNumber: 1,
+      children: [{ title: 'Références sur demande', pageNumber: 2, children: [] }],
+    },
+  ])
+
+  const fonts = listPdfFonts(pdf)
+  t.true(fonts.length >= 3)
+  t.true(fonts.every((font) => font.embedded && font.subset && /^DejaVuSans/.test(font.name)))
+
+  t.throws(
+    () => normalizeCvToPdf(Buffer.from('not a zip'), 'application/vnd.openxmlformats-officedocument.wordprocessingml.document'),
+    { code: 'InvalidArg' },
+  )
+})
+
+test('extractText reads DOCX, ODT, RTF, HTML and plain text', (t) => {
+  const docx = buildDocx(
+    '<w:p><w:r><w:t>Hélène Dupré</w:t></w:r></w:p>' +
+      '<w:tbl><w:tr><w:tc><w:p><w:r><w:t>2020</w:t></w:r></w:p></w:tc><w:tc><w:p><w:r><w:t>Acme</w:t></w:r></w:p></w:tc></w:tr></w:tbl>',
+  )
+  t.is(
+    extractText(docx, 'application/vnd.openxmlformats-officedocument.wordprocessingml.document'),
+    'Hélène Dupré\n2020\tAcme',
+  )
+
+  const odtContent =
+    '<?xml version="1.0"?><office:document-content xmlns:office="urn:oasis:names:tc:opendocument:xmlns:office:1.0" xmlns:text="urn:oasis:names:tc:opendocument:xmlns:text:1.0" xmlns:style="urn:oasis:names:tc:opendocument:xmlns:style:1.0">' +
+    '<office:automatic-styles><text:list-style style:name="L1"><text:list-level-style-number text:level="1" style:num-format="1" style:num-suffix="."/></text:list-style></office:automatic-styles>' +
+    '<office:body><office:text><text:h text:outline-level="1">Hélène Dupré</text:h><text:p>Rust<text:tab/>Paris</text:p>' +
+    '<text:list text:style-name="L1"><text:list-item><text:p>API</text:p></text:list-item><text:list-item><text:p>Tests</text:p></text:list-item></text:list>' +
+    '</office:text></office:body></office:document-content>'
+  const odt = buildZip({ mimetype: 'application/vnd.oasis.opendocument.text', 'content.xml': odtContent })
+  t.is(extractText(odt, 'application/vnd.oasis.opendocument.text'), 'Hélène Dupré\nRust Paris\n1. API\n2. Tests')
+
+  const rtf = Buffer.from(
+    '{\\rtf1\\ansi\\ansicpg1252{\\fonttbl{\\f0 Arial;}}{\\info{\\title CV}}\\pard\\b H\\\'e9l\\\'e8ne Dupr\\\'e9\\b0\\par\n' +
+      'D\\u233?veloppeuse {\\i Rust} \\endash  Paris\\par\n{\\pntext\\\'b7\\tab}Conception d\\rquote API\\par}',
+    'latin1',
+  )
+  t.is(extractText(rtf, 'application/rtf'), 'Hélène Dupré\nDéveloppeuse Rust – Paris\n• Conception d’API')
+
+  const html = Buffer.from(
+    '<html><head><meta charset="windows-1252"><title>CV</title><script>var p = "<p>"</script></head>' +
+      '<body><h1>H\xe9l\xe8ne Dupr&eacute;</h1><p>D&#233;veloppeuse <b>Rust</b>\n  et <i>TypeScript</i><br>Paris' +
+      '<ul><li>API<li>Tests</ul><table><tr><td>2020<td>Acme &amp; Co</table></body></html>',
+    'latin1',
+  )
+  t.is(
+    extractText(html, 'text/html'),
+    'Hélène Dupré\nDéveloppeuse Rust et TypeScript\nParis\n• API\n• Tests\n2020\tAcme & Co',
+  )
+
+  t.is(extractText(Buffer.from('Hélène\r\nDupré\r\n', 'utf16le'), 'text/plain'), 'Hélène\nDupré')
+  t.is(extractText(Buffer.from('Hélène Dupré', 'latin1'), 'text/plain'), 'Hélène Dupré')
+  t.is(extractText(Buffer.from('﻿Hélène  Dupré ', 'utf8'), 'text/plain; charset=utf-8'), 'Hélène Dupré')
+
+  t.throws(() => extractText(Buffer.from('plain'), 'application/rtf'), { code: 'InvalidArg' })
+  t.throws(() => extractText(Buffer.from('plain'), 'image/png'), { code: 'InvalidArg' })
+})
+
+test('extractText and normalizeCvToPdf read legacy .doc files', (t) => {
+  // Word 97-2003 document: a Windows-1252 piece with a hyperlink field and a
+  // table, then a UTF-16 piece
+  const doc = readFileSync(path.join(__dirname, 'cv-sample.doc'))
+  t.is(
+    extractText(doc, 'application/msword'),
+    'Hélène Dupré\nDéveloppeuse Rust\ngithub.com/helene\n2020\tAcme\n2022\tGlobex\n\nConception d’API – Paris',
+  )
+
+  const pdf = Buffer.from(normalizeCvToPdf(doc, 'application/msword'))
+  t.is(pdf.subarray(0, 5).toString('latin1'), '%PDF-')
+  // A4
+  t.true(pdf.toString('latin1').includes('/MediaBox [0 0 595.28 841.89]'))
+  const text = extractTextFromPdf(pdf, { cleanup: {} })
+  for (const expected of ['Hélène Dupré', 'github.com/helene', 'Globex', 'Conception d’API – Paris']) {
+    t.true(text.includes(expected), expected)
+  }
+  t.false(text.includes('HYPERLINK'))
+
+  t.throws(() => extractText(Buffer.from('not a doc'), 'application/msword'), { code: 'InvalidArg' })
+  t.throws(() => normalizeCvToPdf(readFileSync(path.join(__dirname, 'image.jpg')), 'application/msword'), {
+    code: 'InvalidArg',
+  })
+})
+
+test('normalizeCvToPdf renders plain text and Markdown', (t) => {
+  const markdown = [
+    'Hélène Dupré',
+    '============',
+    '',
+    'Développeuse **Rust** et *TypeScript* — [GitHub](https://github.com/helene)',
+    '',
+    '## Expérience',
+    '',
+    '1. Conception d’API',
+    '1. Revue de ~~code~~',
+    '   - Sous-tâche',
+    '',
+    '| Année | Poste |',
+    '|-------|------:|',
+    '| 2020 | Acme, Zürich |',
+  ].join('\n')
+  t.is(
+    extractText(Buffer.from(markdown), 'text/markdown'),
+    'Hélène Dupré\nDéveloppeuse Rust et TypeScript — GitHub\nExpérience\n1. Conception d’API\n2. Revue de code\n◦ Sous-tâche\nAnnée\tPoste\n2020\tAcme, Zürich',
+  )
+
+  const pdf = Buffer.from(normalizeCvToPdf(Buffer.from(markdown), 'text/markdown'))
+  t.is(pdf.subarray(0, 5).toString('latin1'), '%PDF-')
+  // A4
+  t.true(pdf.toString('latin1').includes('/MediaBox [0 0 595.28 841.89]'))
+  const text = extractTextFromPdf(pdf, { cleanup: {} })
+  for (const expected of ['Hélène Dupré', 'Rust et TypeScript', '2. Revue de code', '◦ Sous-tâche', 'Acme, Zürich']) {
+    t.true(text.includes(expected), expected)
+  }
+  t.false(text.includes('**'))
+  t.deepEqual(extractPdfOutline(pdf), [
+    { title: 'Hélène Dupré', pageNumber: 1, children: [{ title: 'Expérience', pageNumber: 1, children: [] }] },
+  ])
+  const fonts = listPdfFonts(pdf)
+  t.true(fonts.length > 0 && fonts.every((font) => font.embedded && /^DejaVuSans/.test(font.name)))
+
+  // Long plain text in Windows-1252 wraps and flows onto several pages
+  const plain = Buffer.from('Hélène Dupré\n\n- Rust\n' + 'Expérience en développement. '.repeat(500), 'latin1')
+  const plainPdf = Buffer.from(normalizeCvToPdf(plain, 'text/plain'))
+  t.true(analyzePdf(plainPdf).pageCount > 1)
+  const plainText = extractTextFromPdf(plainPdf, { cleanup: {} })
+  t.true(plainText.startsWith('Hélène Dupré'))
+  t.true(plainText.includes('• Rust'))
+})
+
+test('normalizeCvToPdf renders HTML with a CSS subset and images', (t) => {
+  const jpeg = readFileSync(path.join(__dirname, 'image.jpg')).toString('base64')
+  const html = `<!doctype html><html><head><meta charset="utf-8"><style>
+    @page { size: letter; margin: 1in }
+    body { font-size: 10pt; color: #333 }
+    h1 { font-size: 24px; color: rgb(0, 51, 102); text-transform: uppercase }
+    .skills { list-style: none; padding-left: 0 }
+    .draft { display: none }
+    td.year { font-weight: bold; background-color: #eee }
+  </style></head><body>
+    <h1>Hélène Dupré</h1>
+    <img src="data:image/jpeg;base64,${jpeg}" width="120">
+    <p>Développeuse <b>Rust</b></p>
+    <ul class="skills"><li>Rust</li><li>TypeScript</li></ul>
+    <ol type="a"><li>Conception d’API</li><li>Tests</li></ol>
+    <p class="draft">Brouillon</p>
+    <table style="border: 1px solid #999"><tr><td class="year">2020</td><td>Acme, Zürich</td></tr></table>
+  </body></html>`
+
+  const pdf = Buffer.from(normalizeCvToPdf(Buffer.from(html), 'text/html'))
+  t.is(pdf.subarray(0, 5).toString('latin1'), '%PDF-')
+  // US Letter, from the @page rule
+  t.true(pdf.toString('latin1').includes('/MediaBox [0 0 612.00 792.00]'))
+  t.is(extractPdfImages(pdf).length, 1)
+  t.deepEqual(
+    extractTextFromPdf(pdf, { cleanup: {} })
+      .split('\n')
+      .filter((line) => line.trim()),
+    ['HÉLÈNE DUPRÉ', 'Développeuse Rust', 'Rust', 'TypeScript', 'a. Conception d’API', 'b. Tests', '2020 Acme, Zürich'],
+  )
+  t.deepEqual(listPdfFonts(pdf).map((font) => font.name).sort(), ['DejaVuSans', 'DejaVuSans-Bold'])
+})
+
+test('renderResumePdf lays out a JSON Resume with a template', (t) => {
+  const work = Array.from({ length: 12 }, (_, i) => ({
+    name: `Société ${i + 1}`,
+    position: 'Développeuse Rust',
+    startDate: `${2010 + i}-03`,
+    endDate: i === 11 ? undefined : `${2011 + i}-01`,
+    summary: 'Conception et maintenance de services de traitement de documents. '.repeat(3),
+    highlights: ['Migration vers Rust', 'Latence réduite de 40 %'],
+  }))
+  const resume = {
+    basics: {
+      name: 'Hélène Dupré',
+      label: 'Développeuse Rust',
+      email: 'helene@example.com',
+      location: { city: 'Paris', countryCode: 'FR' },
+      profiles: [{ network: 'GitHub', url: 'github.com/helene' }],
+    },
+    work,
+    skills: [{ name: 'Rust', level: 'Expert', keywords: ['tokio', 'napi'] }],
+    languages: [{ language: 'Anglais', fluency: 'Courant' }],
+  }
+
+  const pdf = Buffer.from(
+    renderResumePdf(resume, {
+      language: 'fr',
+      sectionOrder: ['skills', 'work', 'languages'],
+      sectionTitles: { skills: 'Savoir-faire' },
+      primaryColor: '#004080',
+      logo: readFileSync(path.join(__dirname, 'image.jpg')),
+      logoWidth: 60,
+    }),
+  )
+  t.is(pdf.subarray(0, 5).toString('latin1'), '%PDF-')
+  t.true(analyzePdf(pdf).pageCount > 1)
+  t.is(extractPdfImages(pdf).length, 1)
+  const lines = extractTextFromPdf(pdf, { cleanup: {} })
+    .split('\n')
+    .filter((line) => line.trim())
+  t.deepEqual(lines.slice(0, 6), [
+    'Hélène Dupré',
+    'Développeuse Rust',
+    'helene@example.com · Paris, FR · github.com/helene',
+    'SAVOIR-FAIRE',
+    'Rust — Expert · tokio, napi',
+    'EXPÉRIENCE PROFESSIONNELLE',
+  ])
+  t.true(lines.includes('mars 2021 – aujourd’hui'))
+  t.true(lines.indexOf('LANGUES') > lines.indexOf('Développeuse Rust — Société 12'))
+  t.deepEqual(
+    [...new Set(listPdfFonts(pdf).map((font) => font.name))].sort(),
+    ['DejaVuSans', 'DejaVuSans-Bold', 'DejaVuSans-Oblique'],
+  )
+
+  const anonymous = extractTextFromPdf(Buffer.from(renderResumePdf(resume, { anonymize: true })))
+  t.false(anonymous.includes('Hélène'))
+  t.false(anonymous.includes('helene@example.com'))
+  t.true(anonymous.includes('EXPERIENCE'))
+
+  const error = t.throws(() => renderResumePdf(resume, { sectionOrder: ['hobbies'] }), { instanceOf: Error })
+  t.is(error?.message, 'Unknown resume section: hobbies')
+})
+
+test('anonymizeCv removes personal data and images and lists the redactions', (t) => {
+  const pdf = buildPdf({
+    objects: [
+      pdfStream(
+        '/Type /XObject /Subtype /Image /Width 1080 /Height 1350 /ColorSpace /DeviceRGB /BitsPerComponent 8 /Filter /DCTDecode',
+        readFileSync(path.join(__dirname, 'image.jpg')),
+      ),
+    ],
+    pages: [
+      {
+        content: [
+          textLines([
+            'Hélène Dupré',
+            'Développeuse Rust',
+            'helene.dupre@example.com · +33 6 12 34 56 78 · 12 rue des Lilas, 75011 Paris',
+            'Née le 12/03/1990 · Nationalité : française',
+            'linkedin.com/in/helene-dupre',
+            'Expérience',
+            '2019 - 2024 Acme : 10 ans de Rust, API pour Hélène et son équipe',
+            'Anglais courant, 32 ans',
+          ]),
+          'q 96 0 0 120 460 640 cm /Photo Do Q',
+        ].join('\n'),
+        resources: '/XObject << /Photo 5 0 R >>',
+      },
+    ],
+  })
+
+  const result = anonymizeCv(pdf)
+  t.is(
+    result.text,
+    'Développeuse Rust\nExpérience\n2019 - 2024 Acme : 10 ans de Rust, API pour […] et son équipe\nAnglais courant, […]',
+  )
+  t.deepEqual(
+    result.redactions.map((r) => [r.category, r.text ?? null, r.line ?? null, r.pageNumber ?? null]),
+    [
+      ['name', 'Hélène Dupré', 1, null],
+      ['email', 'helene.dupre@example.com', 3, null],
+      ['phone', '+33 6 12 34 56 78', 3, null],
+      ['address', '12 rue des Lilas, 75011 Paris', 3, null],
+      ['date_of_birth', 'Née le 12/03/1990', 4, null],
+      ['nationality', 'Nationalité : française', 4, null],
+      ['url', 'linkedin.com/in/helene-dupre', 5, null],
+      ['name', 'Hélène', 7, null],
+      ['age', '32 ans', 8, null],
+      ['photo', null, null, 1],
+    ],
+  )
+
+  const anonymized = Buffer.from(result.pdf)
+  t.is(extractPdfImages(anonymized).length, 0)
+  const text = extractTextFromPdf(anonymized)
+  t.true(text.includes('Développeuse Rust'))
+  t.false(text.includes('Dupré'))
+  t.false(text.includes('example.com'))
+
+  const named = anonymizeCv(pdf, { name: 'Helene Dupre', placeholder: 'X' })
+  t.true(named.text.endsWith('API pour X et son équipe\nAnglais courant, X'))
+})
+
+test('redactPdf removes text and image pixels under the redacted regions', (t) => {
+  const pdf = buildPdf({
+    objects: [
+      pdfStream(
+        '/Type /XObject /Subtype /Image /Width 10 /Height 10 /ColorSpace /DeviceRGB /BitsPerComponent 8',
+        Buffer.alloc(10 * 10 * 3, 200),
+      ),
+    ],
+    pages: [
+      {
+        content: [
+          textLines(['Jane Doe', 'jane.doe@example.com · Paris', 'Experience']),
+          'BT /F1 12 Tf 72 600 Td [(Call ) -200 (0612345678) ( now)] TJ ET',
+          'q 100 0 0 100 300 300 cm /Im1 Do Q',
+        ].join('\n'),
+        resources: '/XObject << /Im1 5 0 R >>',
+        extra: '/Annots [<< /Type /Annot /Subtype /Link /Rect [310 310 320 320] >>]',
+      },
+    ],
+  })
+
+  const result = redactPdf(pdf, {
+    texts: ['jane doe', 'jane.doe@example.com', '06 12 34 56 78'],
+    areas: [{ pageNumber: 1, rect: [300, 300, 350, 350] }],
+  })
+  t.is(result.areas.length, 4)
+  t.is(result.glyphsRemoved, 38)
+  t.is(result.imagesRedacted, 1)
+
+  const redacted = Buffer.from(result.pdf)
+  const text = extractTextFromPdf(redacted)
+  t.false(text.includes('Jane'))
+  t.false(text.includes('example.com'))
+  t.false(text.includes('0612345678'))
+  t.true(text.includes('Paris'))
+  t.true(text.includes('Experience'))
+  t.true(text.includes('now'))
+  t.false(redacted.includes('/Annots'))
+
+  const [image] = extractPdfImages(redacted)
+  t.deepEqual(image.rect, [300, 300, 400, 400])
+  t.is(image.format, 'png')
+
+  t.throws(() => redactPdf(pdf, { areas: [{ pageNumber: 2, rect: [0, 0, 10, 10] }] }), {
+    message: 'Page 2 does not exist (the PDF has 1 pages)',
+  })
+  t.throws(() => redactPdf(pdf, { fillColor: 'transparent-ish' }), { message: 'Invalid fill color: transparent-ish' })
+})
+
+test('redactPdf leaves no trace of the removed text and pixels', (t) => {
+  const pdf = buildPdf({
+    objects: [
+      pdfStream(
+        '/Type /XObject /Subtype /Image /Width 10 /Height 10 /ColorSpace /DeviceRGB /BitsPerComponent 8 /SMask 6 0 R',
+        Buffer.alloc(10 * 10 * 3, 200),
+      ),
+      pdfStream(
+        '/Type /XObject /Subtype /Image /Width 10 /Height 10 /ColorSpace /DeviceGray /BitsPerComponent 8',
+        Buffer.alloc(10 * 10, 7),
+      ),
+    ],
+    pages: [
+      {
+        content: [
+          '/Span << /ActualText (Jane Doe) /Lang (en) >> BDC BT /F1 12 Tf 72 700 Td (Jane Doe) Tj ET EMC',
+          '/P /MC0 BDC BT /F1 12 Tf 72 650 Td [(Code ) -100 (X42) (!)] TJ ET EMC',
+          '/Span << /ActualText (Designer) >> BDC BT /F1 12 Tf 72 600 Td (Designer) Tj ET EMC',
+          'q 100 0 0 100 300 300 cm /Im1 Do Q',
+        ].join('\n'),
+        resources: '/XObject << /Im1 5 0 R >> /Properties << /MC0 << /Alt (Code X42) /MCID 0 >> >>',
+      },
+    ],
+  })
+
+  const result = redactPdf(pdf, {
+    texts: ['Jane Doe', 'X42'],
+    areas: [{ pageNumber: 1, rect: [300, 300, 350, 400] }],
+  })
+  const redacted = Buffer.from(result.pdf)
+  const streams = pdfStreams(redacted)
+  const everything = [redacted.toString('latin1'), ...streams.map((s) => s.data.toString('latin1'))].join('\n')
+  // Removed glyphs leave a single gap as wide as the whole run, original kerning included
+  t.true(everything.includes('[-4280] TJ'))
+  t.true(everything.includes('[<436F646520> -1879<21>] TJ'))
+  // Replacement texts of the spans that lost content are gone, the others are kept
+  t.false(everything.includes('Jane Doe'))
+  t.false(everything.includes('X42'))
+  t.true(everything.includes('/ActualText(Designer)'))
+  t.true(everything.includes('/Lang(en)'))
+
+  // The original image and soft mask are dropped; the mask is opaque where redacted
+  t.false(redacted.includes(Buffer.alloc(10 * 10 * 3, 200)))
+  t.false(redacted.includes(Buffer.alloc(10 * 10, 7)))
+  const mask = streams.find((s) => s.dict.includes('/DeviceGray'))
+  const row = [...Array(5).fill(255), ...Array(5).fill(7)]
+  t.deepEqual([...(mask?.data ?? [])], Array.from({ length: 10 }, () => row).flat())
+})
+
+test('redactPdf removes the form fields it redacts, values included', (t) => {
+  const form = (values: [string, string, string]) => {
+    const [name, email, comment] = values
+    const widget = (rect: string) => `/Type /Annot /Subtype /Widget /Rect [${rect}] /P 10 0 R`
+    return buildPdf({
+      objects: [
+        `<< /FT /Tx /T (name) /V (${name}) /AP << /N 6 0 R >> ${widget('150 680 300 700')} >>`,
+        pdfStream('/Type /XObject /Subtype /Form /BBox [0 0 150 20] /Resources << /Font << /F1 3 0 R >> >>', `BT /F1 10 Tf 2 6 Td (${name}) Tj ET`),
+        '<< /T (contact) /Kids [8 0 R] /V (x) >>',
+        `<< /FT /Tx /T (email) /V (${email}) /Parent 7 0 R ${widget('150 640 300 660')} >>`,
+        `<< /Type /Annot /Subtype /Text /Rect [400 400 420 420] /Contents (${comment}) >>`,
+      ],
+      pages: [{ content: textLines(['Name:', '', '', 'E-mail:']), extra: '/Annots [5 0 R 8 0 R 9 0 R]' }],
+      catalog: '/AcroForm << /Fields [5 0 R 7 0 R] >>',
+    })
+  }
+
+  const byArea = Buffer.from(
+    redactPdf(form(['Jane Doe', 'jane@example.com', 'Note']), {
+      areas: [{ pageNumber: 1, rect: [140, 670, 310, 710] }],
+    }).pdf,
+  )
+  t.deepEqual(
+    extractFormFields(byArea).map((f) => [f.name, f.value]),
+    [['contact.email', 'jane@example.com']],
+  )
+  t.false(byArea.includes('Jane Doe'))
+
+  // Text matches are searched in field values, appearances and annotation contents
+  const byText = redactPdf(form(['Jane Doe', 'jane@example.com', 'Call Jane Doe']), {
+    texts: ['jane doe', 'JANE@example.com'],
+  })
+  const redacted = Buffer.from(byText.pdf)
+  t.deepEqual(extractFormFields(redacted), [])
+  t.is(byText.areas.length, 3)
+  const everything = [redacted.toString('latin1'), ...pdfStreams(redacted).map((s) => s.data.toString('latin1'))]
+  t.false(everything.some((text) => /jane/i.test(text)))
+})
+
+test('detectPii reports sensitive categories with locations and confidence', (t) => {
+  const pdf = buildPdf({
+    objects: [
+      pdfStream(
+        '/Type /XObject /Subtype /Image /Width 1080 /Height 1350 /ColorSpace /DeviceRGB /BitsPerComponent 8 /Filter /DCTDecode',
+        readFileSync(path.join(__dirname, 'image.jpg')),
+      ),
+    ],
+    pages: [
+      {
+        content: [
+          textLines([
+            'Hélène Dupré',
+            'Née le 12/03/1990 · 34 ans · Nationalité : française',
+            'Mariée, 2 enfants · 12 rue des Lilas, 75011 Paris',
+            'Reconnaissance RQTH',
+            'Marie Curie Lab - 2019',
+          ]),
+          'q 96 0 0 120 460 640 cm /Photo Do Q',
+        ].join('\n'),
+        resources: '/XObject << /Photo 5 0 R >>',
+      },
+      textLines([
+        'John Smith',
+        'Marital status: single',
+        'British citizen',
+        '2 90 03 75 112 345 90',
+        'Single Sign-On with Keycloak',
+      ]),
+    ],
+  })
+
+  const report = detectPii(pdf)
+  t.is(report.pageCount, 2)
+  t.deepEqual(report.categories, [
+    'date_of_birth',
+    'age',
+    'marital_status',
+    'nationality',
+    'photo',
+    'social_security_number',
+    'address',
+    'health',
+  ])
+  t.deepEqual(
+    report.findings.map((f) => [f.pageNumber, f.line ?? null, f.category, f.text ?? null, f.confidence]),
+    [
+      [1, 2, 'date_of_birth', 'Née le 12/03/1990', 0.95],
+      [1, 2, 'age', '34 ans', 0.85],
+      [1, 2, 'nationality', 'Nationalité : française', 0.95],
+      [1, 3, 'marital_status', 'Mariée, 2 enfants', 0.8],
+      [1, 3, 'address', '12 rue des Lilas, 75011 Paris', 0.85],
+      [1, 4, 'health', 'Reconnaissance RQTH', 0.75],
+      [1, null, 'photo', null, report.findings[6].confidence],
+      [2, 2, 'marital_status', 'Marital status: single', 0.95],
+      [2, 3, 'nationality', 'British citizen', 0.7],
+      [2, 4, 'social_security_number', '2 90 03 75 112 345 90', 0.95],
+    ],
+  )
+  t.deepEqual(report.findings[6].rect, [460, 640, 556, 760])
+  t.true(report.findings[6].confidence > 0.5)
+
+  const confident = detectPii(pdf, { minConfidence: 0.9 })
+  t.false(confident.categories.includes('age'))
+  t.true(confident.findings.every((f) => f.confidence >= 0.9))
+})
+
+test('detectPii finds every personal detail of a comma-separated list', (t) => {
+  const pdf = buildPdf({
+    pages: [
+      textLines([
+        'Jane Doe',
+        'Born 3 May 1990, French, married',
+        'Date of birth: May 3, 1990; Italian nationality',
+        'Née le 03/05/1990, 34 ans, célibataire',
+        'Pacsée, 1 enfant, de nationalité française',
+      ]),
+    ],
+  })
+
+  const report = detectPii(pdf)
+  t.deepEqual(
+    report.findings.map((f) => [f.line ?? null, f.category, f.text ?? null]),
+    [
+      [2, 'date_of_birth', 'Born 3 May 1990'],
+      [2, 'nationality', 'French'],
+      [2, 'marital_status', 'married'],
+      [3, 'date_of_birth', 'Date of birth: May 3, 1990'],
+      [3, 'nationality', 'Italian nationality'],
+      [4, 'date_of_birth', 'Née le 03/05/1990'],
+      [4, 'age', '34 ans'],
+      [4, 'marital_status', 'célibataire'],
+      [5, 'marital_status', 'Pacsée, 1 enfant'],
+      [5, 'nationality', 'nationalité française'],
+    ],
+  )
+})
+
+test('extractCvsFromEmail normalizes the CVs attached to a forwarded email', (t) => {
+  const base64 = (data: Buffer) => data.toString('base64').replace(/.{76}/g, '$&\r\n')
+  const pdf = buildPdf({ pages: [textLines(['Hélène Dupré', 'Développeuse Rust'])] })
+  const docx = buildDocx('<w:p><w:r><w:t>Lettre de motivation</w:t></w:r></w:p>')
+  const eml = [
+    'From: =?UTF-8?Q?Recrutement_=C3=89quipe?= <jobs@example.com>',
+    'Subject: =?UTF-8?B?RndkOiBDYW5kaWRhdHVyZQ==?=',
+    ' =?UTF-8?Q?_d=C3=A9veloppeuse?=',
+    'Date: Mon, 12 Oct 2026 09:30:00 +0200',
+    'MIME-Version: 1.0',
+    'Content-Type: multipart/mixed; boundary="outer"',
+    '',
+    '--outer',
+    'Content-Type: multipart/alternative; boundary=alt',
+    '',
+    '--alt',
+    'Content-Type: text/plain; charset=iso-8859-1',
+    'Content-Transfer-Encoding: quoted-printable',
+    '',
+    'Candidature transf=',
+    '=E9r=E9e par l=E9quipe.',
+    '--alt',
+    'Content-Type: text/html; charset=utf-8',
+    '',
+    '<p>HTML version</p>',
+    '--alt--',
+    '--outer',
+    'Content-Type: image/png',
+    'Content-ID: <logo@example.com>',
+    'Content-Disposition: inline; filename="logo.png"',
+    'Content-Transfer-Encoding: base64',
+    '',
+    'iVBORw0KGgo=',
+    '--outer',
+    'Content-Type: message/rfc822',
+    '',
+    'From: "Dupré, Hélène" <helene@example.com>',
+    'Content-Type: multipart/mixed; boundary=inner',
+    '',
+    '--inner',
+    'Content-Type: text/plain; charset=utf-8',
+    '',
+    'Bonjour, veuillez trouver mon CV ci-joint.',
+    '--inner',
+    'Content-Type: application/octet-stream',
+    "Content-Disposition: attachment; filename*=UTF-8''CV%20H%C3%A9l%C3%A8ne.pdf",
+    'Content-Transfer-Encoding: base64',
+    '',
+    base64(pdf),
+    '--inner',
+    'Content-Type: application/octet-stream; name="=?UTF-8?B?TGV0dHJlIGRlIG1vdGl2YXRpb24uZG9jeA==?="',
+    'Content-Transfer-Encoding: base64',
+    '',
+    base64(docx),
+    '--inner',
+    'Content-Type: application/vnd.oasis.opendocument.text; name=cv.odt',
+    '',
+    'PK',
+    '--inner',
+    'Content-Type: text/calendar; name=entretien.ics',
+    '',
+    'BEGIN:VCALENDAR',
+    '--inner--',
+    '--outer--',
+    '',
+  ].join('\r\n')
+
+  const email = extractCvsFromEmail(Buffer.from(eml))
+  t.is(email.senderName, 'Recrutement Équipe')
+  t.is(email.senderEmail, 'jobs@example.com')
+  t.is(email.subject, 'Fwd: Candidature développeuse')
+  t.is(email.date, 'Mon, 12 Oct 2026 09:30:00 +0200')
+  t.is(email.bodyText, 'Candidature transférée par léquipe.\n\nBonjour, veuillez trouver mon CV ci-joint.')
+  t.deepEqual(
+    email.cvs.map((cv) => [cv.filename, cv.mime, cv.error ?? null]),
+    [
+      ['CV Hélène.pdf', 'application/pdf', null],
+      ['Lettre de motivation.docx', 'application/vnd.openxmlformats-officedocument.wordprocessingml.document', null],
+    ],
+  )
+  t.true(extractTextFromPdf(Buffer.from(email.cvs[0].pdf ?? [])).includes('Développeuse Rust'))
+  t.true(extractTextFromPdf(Buffer.from(email.cvs[1].pdf ?? [])).includes('Lettre de motivation'))
+
+  t.throws(() => extractCvsFromEmail(pdf), { message: 'Failed to read email: no message headers' })
+})
+
+test('extractCvsFromEmail keeps the body parts of email clients out of the CVs', (t) => {
+  const pdf = buildPdf({ pages: [textLines(['John Smith', 'Data engineer'])] })
+  const eml = [
+    'From: John Smith <john@example.com>',
+    'Subject: Application',
+    'Content-Type: multipart/mixed; boundary=mixed',
+    '',
+    '--mixed',
+    'Content-Type: multipart/alternative; boundary=alt',
+    '',
+    '--alt',
+    'Content-Type: text/plain; name=body.txt',
+    '',
+    'Please find my CV attached.',
+    '--alt',
+    'Content-Type: text/html; name=body.htm',
+    '',
+    '<p>Please find my CV attached.</p>',
+    '--alt--',
+    '--mixed',
+    'Content-Type: application/pdf; name=cv.pdf',
+    'Content-Disposition: attachment; filename=cv.pdf',
+    'Content-Transfer-Encoding: base64',
+    '',
+    pdf.toString('base64'),
+    '--mixed',
+    'Content-Type: text/html; charset=us-ascii; name="ATT00001.htm"',
+    'Content-Disposition: attachment; filename="ATT00001.htm"',
+    '',
+    '<p>Sent from Outlook</p>',
+    '--mixed',
+    'Content-Type: text/plain; name=signature.txt',
+    'Content-Disposition: inline',
+    '',
+    'John Smith, +44 20 7946 0000',
+    '--mixed',
+    'Content-Type: text/plain; name=cv.txt',
+    'Content-Disposition: attachment; filename=cv.txt',
+    '',
+    'John Smith - Data engineer',
+    '--mixed--',
+    '',
+  ].join('\r\n')
+
+  const email = extractCvsFromEmail(Buffer.from(eml))
+  t.deepEqual(
+    email.cvs.map((cv) => [cv.filename, cv.mime, cv.error ?? null]),
+    [
+      ['cv.pdf', 'application/pdf', null],
+      ['cv.txt', 'text/plain', null],
+    ],
+  )
+  t.is(email.bodyText, 'Please find my CV attached.\n\nSent from Outlook\n\nJohn Smith, +44 20 7946 0000')
+})
+
+test('extractTextFromPdf layout ignores text positioned far outside the page', (t) => {
+  const columns = Array.from(
+    { length: 8 },
+    (_, i) =>
+      `BT /F1 12 Tf 1 0 0 1 72 ${700 - i * 20} Tm (Left ${i}) Tj ET ` +
+      `BT /F1 12 Tf 1 0 0 1 350 ${700 - i * 20} Tm (Right ${i}) Tj ET`,
+  )
+  const pdf = buildPdf({
+    pages: [[...columns, 'BT /F1 12 Tf 1 0 0 1 100000000000 700 Tm (Far) Tj ET'].join('\n')],
+  })
+
+  const text = extractTextFromPdf(pdf, { layout: true })
+  t.true(text.startsWith('Left 0\nLeft 1'))
+  t.true(text.includes('Far'))
+})
+
 test('optimizeImage resizes image when maxWidth is provided', (t) => {
   const imagePath = path.join(__dirname, 'image.jpg')
   const imageBuffer = readFileSync(imagePath)
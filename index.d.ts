@@ -1,5 +1,54 @@
 /* auto-generated by NAPI-RS */
 /* eslint-disable */
+/**
+ * Analyze a PDF to tell digital documents from scans.
+ *
+ * For each page, counts the characters that can be extracted and measures the area
+ * covered by images. A page with (almost) no text whose images cover a significant
+ * part of it is reported as a likely scan, so callers can route it to OCR.
+ */
+export declare function analyzePdf(bytes: Uint8Array): PdfAnalysis
+
+/**
+ * Produce a blind version of a PDF CV, without the personal data that must not
+ * influence a hiring decision.
+ *
+ * The text is extracted in reading order, as with `extract_text_from_pdf` and
+ * `layout: true`. The candidate's name, email addresses, phone numbers, profile
+ * URLs, home address, date of birth, age, nationality, marital status, social
+ * security number and health mentions are detected (in French and English) and
+ * removed, then the remaining text is laid out into a new PDF. Images, including
+ * the photo, are never carried over. Detection is heuristic: the returned
+ * `redactions` should be reviewed.
+ */
+export declare function anonymizeCv(bytes: Uint8Array, options?: AnonymizeOptions | undefined | null): AnonymizedCv
+
+/**
+ * Result of `anonymize_cv`.
+ *
+ * - `pdf`: The anonymized CV, as a new A4 PDF
+ * - `text`: Its text
+ * - `redactions`: What was removed, in page and reading order, for audit
+ */
+export interface AnonymizedCv {
+  pdf: Array<number>
+  text: string
+  redactions: Array<Redaction>
+}
+
+/**
+ * Options for CV anonymization.
+ *
+ * - `name`: The candidate's name, when known (by default, a name-like line among
+ *   the first lines of the CV is taken as the name)
+ * - `placeholder`: Text replacing personal data inside a line (default: "[…]");
+ *   lines or parts of lines left with nothing else are removed
+ */
+export interface AnonymizeOptions {
+  name?: string
+  placeholder?: string
+}
+
 /** Convert a Base64 string to a buffer. */
 export declare function base64ToBuffer(base64: string): Array<number>
 
@@ -31,13 +80,270 @@ export interface ConversionStats {
  */
 export declare function convertImagesToWebpRecursive(dirPath: string): ConversionStats
 
+/**
+ * A line of the diff between two versions of a CV.
+ *
+ * - `kind`: "equal", "insert" (only in the new version), "delete" (only in the old
+ *   version) or "change" (edited line, see `words`)
+ * - `old_line`: 1-based line number in the old text (absent for insertions)
+ * - `new_line`: 1-based line number in the new text (absent for deletions)
+ * - `text`: The line, from the new version except for deletions
+ * - `words`: Word-level diff of a changed line
+ * - `move_id`: For lines of a moved block, the `id` of the move: the block shows up
+ *   as deletions at its old position and as insertions (or changes, for lines
+ *   edited on the way) at its new one
+ */
+export interface CvDiffLine {
+  kind: string
+  oldLine?: number
+  newLine?: number
+  text: string
+  words?: Array<CvDiffWord>
+  moveId?: number
+}
+
+/**
+ * A block of lines found at another position of the new version (e.g. a section
+ * moved up), possibly with some of its lines edited.
+ *
+ * - `id`: Identifier referenced by `CvDiffLine.move_id`
+ * - `old_line`: 1-based first line of the block in the old text
+ * - `new_line`: 1-based first line of the block in the new text
+ * - `line_count`: Number of lines of the block
+ */
+export interface CvDiffMove {
+  id: number
+  oldLine: number
+  newLine: number
+  lineCount: number
+}
+
+/**
+ * Options for `diff_cv_text`.
+ *
+ * - `layout`: Extract text in natural reading order, so that a sidebar moving from
+ *   one side to the other does not show up as a change (default: true)
+ * - `ignore_case`: Compare lines case-insensitively (default: false)
+ */
+export interface CvDiffOptions {
+  layout?: boolean
+  ignoreCase?: boolean
+}
+
+/**
+ * A word of a changed line.
+ *
+ * - `kind`: "equal", "insert" or "delete"
+ * - `text`: The word
+ */
+export interface CvDiffWord {
+  kind: string
+  text: string
+}
+
+/**
+ * Differences between two versions of a CV.
+ *
+ * - `lines`: Both texts merged line by line, in the order of the new version
+ *   (deleted lines appear where they were removed)
+ * - `moves`: Blocks of lines that moved
+ * - `inserted`: Number of inserted lines, moved lines excluded
+ * - `deleted`: Number of deleted lines, moved lines excluded
+ * - `changed`: Number of changed lines
+ * - `moved`: Number of lines moved unchanged
+ * - `similarity`: Share of lines common to both versions (moved lines included),
+ *   from 0 to 1
+ */
+export interface CvTextDiff {
+  lines: Array<CvDiffLine>
+  moves: Array<CvDiffMove>
+  inserted: number
+  deleted: number
+  changed: number
+  moved: number
+  similarity: number
+}
+
+/**
+ * Report which sensitive categories of personal data appear in a PDF CV, e.g.
+ * to check a CV before storing it under the GDPR.
+ *
+ * The text of each page is extracted in reading order and cleaned up, as with
+ * `extract_text_from_pdf` and `layout: true`, and searched in French and English
+ * for a date of birth, age, marital status, nationality, social security number,
+ * home address and mentions of health or disability. The candidate's photo is
+ * detected as with `extract_pdf_images` and `detect_portrait`. Detection is
+ * heuristic: findings come with a confidence.
+ */
+export declare function detectPii(bytes: Uint8Array, options?: PiiReportOptions | undefined | null): PiiReport
+
+/**
+ * Compare the text of two versions of a PDF CV.
+ *
+ * Both documents go through `extract_text_from_pdf` with the default cleanup
+ * (repeated headers/footers, hyphenation, ligatures and whitespace); lines are then
+ * compared ignoring blank lines and bullet glyphs. Blocks of lines found unchanged
+ * at another position are reported as moves rather than as deletions and
+ * insertions, and edited lines come with a word-level diff.
+ */
+export declare function diffCvText(oldBytes: Uint8Array, newBytes: Uint8Array, options?: CvDiffOptions | undefined | null): CvTextDiff
+
+/**
+ * A CV attached to an email.
+ *
+ * - `filename`: Name of the attachment, when given
+ * - `mime`: Mime type of the attachment (from its extension when sent as
+ *   `application/octet-stream`)
+ * - `pdf`: The attachment normalized to PDF by `normalize_cv_to_pdf`
+ * - `error`: Why the attachment could not be normalized (then `pdf` is absent)
+ */
+export interface EmailCv {
+  filename?: string
+  mime: string
+  pdf?: Array<number>
+  error?: string
+}
+
+/**
+ * An application email with its normalized CVs.
+ *
+ * - `sender_name`: Display name of the sender (`From`), when given
+ * - `sender_email`: Email address of the sender
+ * - `subject`: Subject of the email
+ * - `date`: `Date` header, as written in the email
+ * - `body_text`: Text of the body (the HTML part converted to text when there is
+ *   no plain text part), including the text of forwarded messages
+ * - `cvs`: Attachments with a CV-like type, in message order
+ */
+export interface EmailCvs {
+  senderName?: string
+  senderEmail?: string
+  subject?: string
+  date?: string
+  bodyText: string
+  cvs: Array<EmailCv>
+}
+
+/**
+ * Extract the CVs attached to an email (`.eml`, RFC 822/MIME) and normalize them
+ * to PDF.
+ *
+ * The message is parsed with its multipart bodies, base64 and quoted-printable
+ * transfer encodings, charsets, RFC 2047 encoded headers and RFC 2047/2231 encoded
+ * filenames. Forwarded messages attached as `message/rfc822` are searched too.
+ *
+ * Attachments with a type `normalize_cv_to_pdf` converts (PDF, DOCX, DOC, PNG, JPEG,
+ * and plain text, Markdown or HTML files) are run through it with `options`; images
+ * embedded in an HTML body (with a `Content-ID`) are skipped, and text or HTML parts
+ * are body text unless attached as files other than Outlook's body parts
+ * (`ATT00001.htm`). An attachment that
+ * fails to normalize is returned with its `error` instead of failing the whole email.
+ */
+export declare function extractCvsFromEmail(bytes: Uint8Array, options?: NormalizeOptions | undefined | null): EmailCvs
+
+/**
+ * Extract the form fields of a PDF (filled-in application forms).
+ *
+ * Field values live in the form dictionary and its widget annotations rather than in
+ * the page content, so `extract_text_from_pdf` does not see them. Fields are returned
+ * in document order; push buttons are included with no value.
+ */
+export declare function extractFormFields(bytes: Uint8Array): Array<FormField>
+
+/**
+ * Convert a PDF document (typically a CV) to Markdown.
+ *
+ * Text is read in natural reading order, as with `layout: true`. Lines set larger
+ * than the body text, or short bold lines, become headings (`#` to `###`), lines
+ * starting with a bullet glyph become list items, bold runs are wrapped in `**`
+ * and text under URI link annotations becomes `[text](uri)`.
+ */
+export declare function extractMarkdownFromPdf(bytes: Uint8Array): string
+
+/**
+ * Extract the images drawn on the pages of a PDF, e.g. to retrieve the candidate's
+ * profile photo.
+ *
+ * Every placement of an image XObject (including those drawn inside form XObjects)
+ * and every inline image is listed, in page and drawing order. JPEG data is passed
+ * through unchanged; other images are decoded and re-encoded as PNG. Images in codecs
+ * that cannot be decoded (JPEG 2000, CCITT, JBIG2) are skipped.
+ *
+ * With `detect_portrait`, one image is flagged as the likely portrait: a photograph
+ * (rich tones, ideally skin tones) of portrait or square shape, displayed small and
+ * preferably near the top of the first page.
+ */
+export declare function extractPdfImages(bytes: Uint8Array, options?: PdfImageOptions | undefined | null): Array<PdfImage>
+
+/**
+ * Read the outline (bookmarks) of a PDF as a tree of titles and target pages.
+ *
+ * Destinations may be explicit, named (`/Dests` dictionary or name tree) or given
+ * through a GoTo action. An empty list is returned when the PDF has no outline.
+ */
+export declare function extractPdfOutline(bytes: Uint8Array): Array<PdfOutlineItem>
+
+/**
+ * Extract the text of a CV in any of the supported formats.
+ *
+ * - PDF (`application/pdf`): text in reading order, as with `extract_text_from_pdf`
+ *   and `layout: true`
+ * - DOCX: paragraphs of `word/document.xml`, with list numbers and bullets, tables
+ *   and text boxes
+ * - DOC (`application/msword`, Word 97-2003): text of the main document, read
+ *   through the piece table of its `WordDocument` stream
+ * - ODT (`application/vnd.oasis.opendocument.text`): paragraphs of `content.xml`
+ * - RTF (`application/rtf`, `text/rtf`): text of the document body, decoded from
+ *   its code page and `\u` escapes
+ * - HTML (`text/html`, `application/xhtml+xml`): text of the body, a line per block
+ *   element; the charset comes from `<meta charset>` when declared
+ * - Plain text (`text/plain`): decoded from UTF-8, UTF-16 or Windows-1252, as
+ *   detected
+ * - Markdown (`text/markdown`): the text without its markup, list markers kept
+ *
+ * Table cells are separated by tabs. The text is returned as clean UTF-8: NFC
+ * normalized, without control characters, repeated blank lines or trailing spaces.
+ * Other mime types fail with `InvalidArg`.
+ */
+export declare function extractText(bytes: Uint8Array, mime: string): string
+
 /**
  * Extract text content from a PDF document.
  *
  * This replaces pdf-parse (JS) with a native Rust implementation using pdf-extract.
  * Returns the extracted text as a single string, with pages separated by newlines.
+ * Pages pdf-extract cannot read (errors or panics) are decoded by a simpler native
+ * extractor instead.
+ * Pass `options.layout` for multi-column layouts and `options.cleanup` to
+ * post-process the text for search and indexing.
+ */
+export declare function extractTextFromPdf(bytes: Uint8Array, options?: PdfTextOptions | undefined | null): string
+
+/**
+ * Extract text content from a PDF document, reporting how much of it was read.
+ *
+ * Takes the same options as `extract_text_from_pdf`. When `max_pages`,
+ * `max_stream_bytes` or `timeout_ms` is hit, the partial text is returned with
+ * `truncated` set instead of failing. `extractor` tells whether the native fallback
+ * extractor had to be used.
+ */
+export declare function extractTextFromPdfDetailed(bytes: Uint8Array, options?: PdfTextOptions | undefined | null): PdfTextResult
+
+/**
+ * A form field of a PDF (AcroForm).
+ *
+ * - `name`: Fully qualified field name (partial names joined with `.`)
+ * - `field_type`: "text", "checkbox", "radio", "button", "choice" or "signature"
+ * - `value`: Field value: the text, the selected option(s) joined with ", ", or the
+ *   state of a checkbox or radio group ("Off" when unchecked); absent when empty
+ * - `page_number`: 1-based page of the field's first widget, when it is on a page
  */
-export declare function extractTextFromPdf(bytes: Uint8Array): string
+export interface FormField {
+  name: string
+  fieldType: string
+  value?: string
+  pageNumber?: number
+}
 
 /**
  * Optimize an image: resize and/or compress.
@@ -79,19 +385,142 @@ export declare function imageToWebpFromBase64(base64: string): Array<number>
  */
 export declare function imageToWebpFromFile(path: string): Array<number>
 
+/**
+ * A CV in the JSON Resume format (https://jsonresume.org/schema), as parsed with
+ * `JSON.parse`. Every field is optional; unknown fields are ignored.
+ */
+export interface JsonResume {
+  basics?: ResumeBasics
+  work?: Array<ResumeWork>
+  volunteer?: Array<ResumeWork>
+  education?: Array<ResumeEducation>
+  awards?: Array<ResumeAward>
+  certificates?: Array<ResumeCertificate>
+  publications?: Array<ResumePublication>
+  skills?: Array<ResumeSkill>
+  languages?: Array<ResumeLanguage>
+  interests?: Array<ResumeSkill>
+  references?: Array<ResumeReference>
+  projects?: Array<ResumeProject>
+}
+
+/**
+ * List the fonts used on each page of a PDF.
+ *
+ * Only fonts selected by the page content (including form XObjects) are reported,
+ * once per page. Non-embedded fonts are rendered with substitutes by viewers and
+ * rejected by most printers; fonts without ToUnicode map explain many bad extractions.
+ */
+export declare function listPdfFonts(bytes: Uint8Array): Array<PdfFont>
+
 /**
  * Normalize a CV file to PDF and optionally compress it.
  *
  * V1 behavior:
  * - If the mime type is a supported image (`image/png`, `image/jpeg`, `image/jpg`),
  *   the image is decoded, optionally downscaled, recompressed as JPEG,
- *   and wrapped into a single-page PDF.
- * - If the mime type is `application/pdf`, the input is currently validated
- *   (must start with `%PDF-`) then returned unchanged. This is the hook where
- *   a real PDF optimization pipeline can be implemented later.
+ *   and wrapped into a single-page PDF. With `options.ocr`, an invisible text layer
+ *   is placed over the image, scaled to the page size chosen here.
+ * - If the mime type is `application/pdf`, the input is validated (must start
+ *   with `%PDF-`). With `options.require_embedded_fonts`, it is rejected when its
+ *   pages use a font that is not embedded. With `options.flatten` or
+ *   `options.flatten_forms`, annotations or form fields are then flattened into
+ *   the page content, natively. The result is optimized with Ghostscript when it
+ *   is installed, and returned as is otherwise.
+ * - If the mime type is DOCX, the document is rendered to PDF natively:
+ *   paragraphs, headings, lists, tables, bold/italic, colors and embedded
+ *   images are laid out on the document's page size, with an embedded Unicode
+ *   font. Headers, footers and floating positions are not reproduced.
+ * - If the mime type is `application/msword` (Word 97-2003), the text of the
+ *   document is read from its piece table and laid out on A4 pages, keeping
+ *   paragraphs and tables but not the formatting.
+ * - If the mime type is `text/plain` or `text/markdown`, the text (UTF-8,
+ *   UTF-16 or Windows-1252) is laid out on A4 pages with an embedded Unicode
+ *   font: lines and bullets for plain text; headings, lists, emphasis, links,
+ *   quotes, code and tables for Markdown.
+ * - If the mime type is `text/html`, the document is laid out natively:
+ *   headings, paragraphs, lists, tables and embedded (`data:`) images, with a
+ *   CSS subset (`<style>` rules and `style` attributes) for font sizes, weights,
+ *   colors, alignment, margins, borders and the `@page` size and margins.
  * - For any other mime type, the input bytes are returned unchanged.
  */
-export declare function normalizeCvToPdf(bytes: Uint8Array, mime: string): Array<number>
+export declare function normalizeCvToPdf(bytes: Uint8Array, mime: string, options?: NormalizeOptions | undefined | null): Array<number>
+
+/**
+ * Options for CV normalization.
+ *
+ * - `ocr`: OCR results for an image input, as hOCR or ALTO XML (detected from the
+ *   content). The recognized words are added over the image as an invisible text
+ *   layer, which makes the PDF searchable and selectable. Ignored for PDF input.
+ * - `flatten_forms`: For PDF input, draw the form field values into the page content
+ *   and remove the interactive form (default: false)
+ * - `flatten`: For PDF input, draw all annotations (comments, highlights, stamps, form
+ *   fields) into the page content and remove them; links are kept (default: false)
+ * - `require_embedded_fonts`: For PDF input, fail with `InvalidArg` when the input
+ *   uses fonts that are not embedded, as printers reject them (default: false)
+ */
+export interface NormalizeOptions {
+  ocr?: string
+  flattenForms?: boolean
+  flatten?: boolean
+  requireEmbeddedFonts?: boolean
+}
+
+/**
+ * Run OCR with a local Tesseract installation.
+ *
+ * - Images (`image/png`, `image/jpeg`) are OCRed once EXIF orientation is applied.
+ * - For PDFs, only the pages `analyze_pdf` reports as likely scans are OCRed, using
+ *   the largest image drawn on each of them. JPEG, JPEG 2000, CCITT fax and raw
+ *   images are read; pages whose image is in another format (JBIG2) are skipped and
+ *   listed in `message`.
+ *
+ * Like `normalize_cv_to_pdf` with Ghostscript, the external tool is optional: when
+ * the executable cannot be found, `status` is "unavailable" instead of an error.
+ */
+export declare function ocrDocument(bytes: Uint8Array, mime: string, options?: OcrOptions | undefined | null): OcrResult
+
+/**
+ * Options for OCR.
+ *
+ * - `language`: Tesseract language(s), e.g. "eng" or "fra+eng" (default: "eng")
+ * - `tesseract_path`: Path of the tesseract executable (default: `tesseract` from the PATH)
+ */
+export interface OcrOptions {
+  language?: string
+  tesseractPath?: string
+}
+
+/**
+ * OCR output for one page.
+ *
+ * - `page_number`: 1-based page number (1 for image inputs)
+ * - `text`: Recognized text
+ * - `hocr`: hOCR document (HTML with word boxes and confidences)
+ */
+export interface OcrPage {
+  pageNumber: number
+  text: string
+  hocr: string
+}
+
+/**
+ * Result of an OCR run.
+ *
+ * - `status`: "ok", "unavailable" (no tesseract executable), "not_needed" (a PDF
+ *   without image-only pages), or "unsupported" (a PDF whose scans are all in an
+ *   image format OCR cannot read, such as JBIG2)
+ * - `text`: Recognized text of all pages, separated by blank lines
+ * - `pages`: Per-page text and hOCR
+ * - `message`: Why OCR did not run, when `status` is not "ok"; with "ok", the scanned
+ *   pages that were skipped, if any
+ */
+export interface OcrResult {
+  status: string
+  text: string
+  pages: Array<OcrPage>
+  message?: string
+}
 
 /**
  * Optimize an image from a buffer.
@@ -113,3 +542,517 @@ export declare function optimizeImageFromBase64(base64: string, options?: ImageO
  * **Input:** File path (String) - Path to the image file on disk
  */
 export declare function optimizeImageFromFile(path: string, options?: ImageOptimizeOptions | undefined | null): Array<number>
+
+/**
+ * Document-level analysis of a PDF.
+ *
+ * - `page_count`: Number of pages
+ * - `char_count`: Total number of extracted characters
+ * - `text_coverage`: Share of the pages with extractable text (0 to 1)
+ * - `scanned_pages`: Number of pages that are likely scans
+ * - `verdict`: "digital" (text on every page with content), "scanned" (every page
+ *   with content is a scan), "mixed", or "empty" (no text and no images)
+ * - `needs_ocr`: At least one page is likely a scan
+ */
+export interface PdfAnalysis {
+  pageCount: number
+  charCount: number
+  textCoverage: number
+  scannedPages: number
+  verdict: string
+  needsOcr: boolean
+  pages: Array<PdfPageAnalysis>
+}
+
+/**
+ * A font used on a PDF page.
+ *
+ * - `page_number`: 1-based page number
+ * - `name`: PostScript name (`BaseFont`) without the subset prefix, or the resource
+ *   name for fonts without one (Type 3)
+ * - `subtype`: "Type1", "TrueType", "Type0", "Type3", "MMType1", …
+ * - `encoding`: Encoding name (`WinAnsiEncoding`, `Identity-H`, …); "built-in" when
+ *   the font uses its own, "embedded CMap" for embedded CMaps; custom differences
+ *   are noted as "with Differences"
+ * - `embedded`: Whether the font program is embedded (Type 3 glyphs always are)
+ * - `subset`: Whether the embedded font is a subset (`ABCDEF+` name prefix)
+ * - `has_to_unicode`: Whether a ToUnicode map lets text be extracted reliably
+ */
+export interface PdfFont {
+  pageNumber: number
+  name: string
+  subtype: string
+  encoding: string
+  embedded: boolean
+  subset: boolean
+  hasToUnicode: boolean
+}
+
+/**
+ * An image drawn on a PDF page.
+ *
+ * - `page_number`: 1-based page number
+ * - `rect`: Placement on the page in points, `[x0, y0, x1, y1]` from the bottom left corner
+ * - `pixel_width`, `pixel_height`: Dimensions of the image in pixels
+ * - `format`: "jpeg" (DCT data passed through unchanged) or "png" (decoded samples)
+ * - `data`: Encoded image bytes
+ * - `likely_portrait`: Whether this is the likely portrait photo (only with
+ *   `detect_portrait`; at most one image is flagged)
+ */
+export interface PdfImage {
+  pageNumber: number
+  rect: Array<number>
+  pixelWidth: number
+  pixelHeight: number
+  format: string
+  data: Array<number>
+  likelyPortrait: boolean
+}
+
+/**
+ * Options for PDF image extraction.
+ *
+ * - `detect_portrait`: Flag the image most likely to be the candidate's photo
+ *   (default: false)
+ */
+export interface PdfImageOptions {
+  detectPortrait?: boolean
+}
+
+/**
+ * An outline (bookmark) entry.
+ *
+ * - `title`: Title shown in the viewer's bookmark panel
+ * - `page_number`: 1-based target page; absent for entries without a destination
+ *   (or pointing to another file)
+ * - `children`: Nested entries
+ */
+export interface PdfOutlineItem {
+  title: string
+  pageNumber?: number
+  children: Array<PdfOutlineItem>
+}
+
+/**
+ * Analysis of a single PDF page.
+ *
+ * - `page_number`: 1-based page number
+ * - `width` / `height`: Page size in points (media box)
+ * - `char_count`: Number of non-whitespace characters extracted from the page
+ * - `image_count`: Number of images drawn on the page
+ * - `image_area`: Area of the page covered by images, in square points
+ * - `image_coverage`: Share of the page covered by images (0 to 1)
+ * - `likely_scanned`: The page is mostly an image with no usable text layer
+ */
+export interface PdfPageAnalysis {
+  pageNumber: number
+  width: number
+  height: number
+  charCount: number
+  imageCount: number
+  imageArea: number
+  imageCoverage: number
+  likelyScanned: boolean
+}
+
+/**
+ * A region of a PDF page.
+ *
+ * - `page_number`: 1-based page number
+ * - `rect`: Region in points, `[x0, y0, x1, y1]` from the bottom left corner of
+ *   the page (as `PdfImage.rect`)
+ */
+export interface PdfRedactionArea {
+  pageNumber: number
+  rect: Array<number>
+}
+
+/**
+ * Options for PDF text extraction.
+ *
+ * - `cleanup`: Run the text cleanup stage (ligatures, de-hyphenation, Unicode
+ *   normalization, whitespace, repeated headers/footers). Omit to get the raw
+ *   pdf-extract output.
+ * - `layout`: Rebuild the natural reading order from glyph positions: columns
+ *   (e.g. a CV sidebar) are read one after the other instead of line by line,
+ *   and text blocks are separated by blank lines (default: false)
+ * - `max_pages`: Only extract the first N pages
+ * - `max_stream_bytes`: Skip streams whose decompressed size exceeds this many bytes
+ * - `timeout_ms`: Stop extracting once this time has elapsed (checked while reading
+ *   each page; the page being read is left out)
+ *
+ * When a limit is hit, the text extracted so far is returned; use
+ * `extractTextFromPdfDetailed` to know whether it was truncated.
+ */
+export interface PdfTextOptions {
+  cleanup?: TextCleanupOptions
+  layout?: boolean
+  maxPages?: number
+  maxStreamBytes?: number
+  timeoutMs?: number
+}
+
+/**
+ * Result of a detailed PDF text extraction.
+ *
+ * - `text`: Extracted text (pages separated by blank lines)
+ * - `page_count`: Number of pages in the document
+ * - `pages_extracted`: Number of pages the text was extracted from
+ * - `truncated`: Whether a limit was hit and the text is partial
+ * - `truncation_reason`: "max_pages", "max_stream_bytes" or "timeout"
+ * - `extractor`: Extractor that produced the text: "pdf-extract", "native" (the
+ *   fallback decoder, used on every page pdf-extract fails on or cannot be trusted
+ *   with) or "mixed"
+ * - `quality`: Quality score of the text, to detect garbled output (see
+ *   `score_text_quality`)
+ */
+export interface PdfTextResult {
+  text: string
+  pageCount: number
+  pagesExtracted: number
+  truncated: boolean
+  truncationReason?: string
+  extractor: string
+  quality: TextQuality
+}
+
+/**
+ * Sensitive data found in a CV.
+ *
+ * - `category`: "date_of_birth", "age", "marital_status", "nationality", "photo",
+ *   "social_security_number", "address" or "health"
+ * - `text`: The text found (none for the photo)
+ * - `page_number`: 1-based page number
+ * - `line`: 1-based line of the text in the page, as extracted with `layout: true`
+ * - `rect`: Placement of the photo on the page in points, `[x0, y0, x1, y1]` from the
+ *   bottom left corner
+ * - `confidence`: How sure the detection is, from 0 to 1: labelled values
+ *   ("Nationalité : française") and checked formats (a social security number
+ *   with a valid key) score higher than values recognized from their shape alone
+ */
+export interface PiiFinding {
+  category: string
+  text?: string
+  pageNumber: number
+  line?: number
+  rect?: Array<number>
+  confidence: number
+}
+
+/**
+ * Report of the sensitive data in a CV.
+ *
+ * - `categories`: Categories found, without duplicates
+ * - `findings`: Each finding, in page and reading order (the photo after the text
+ *   of its page)
+ * - `page_count`: Number of pages in the document
+ */
+export interface PiiReport {
+  categories: Array<string>
+  findings: Array<PiiFinding>
+  pageCount: number
+}
+
+/**
+ * Options for the PII report.
+ *
+ * - `min_confidence`: Leave out findings below this confidence, from 0 to 1
+ *   (default: 0, everything is reported)
+ */
+export interface PiiReportOptions {
+  minConfidence?: number
+}
+
+/**
+ * An item removed from a CV.
+ *
+ * - `category`: "name", "email", "phone", "url", "address", "date_of_birth", "age",
+ *   "nationality", "marital_status", "social_security_number", "health", "photo"
+ *   (the likely portrait) or "image"
+ * - `text`: The removed text (none for images)
+ * - `line`: 1-based line of the removed text in the text of the CV
+ * - `page_number`: 1-based page of a removed image
+ */
+export interface Redaction {
+  category: string
+  text?: string
+  line?: number
+  pageNumber?: number
+}
+
+/**
+ * Redact regions of a PDF for good: the content inside them is deleted, not just
+ * covered.
+ *
+ * Glyphs overlapping a region are removed from the content streams (the other
+ * glyphs keep their position, and each run of removed glyphs leaves a single gap),
+ * along with the replacement texts of the marked-content spans around them. Image
+ * pixels inside it are painted over in the image data itself, soft mask included,
+ * and annotations over it (links, comments, form fields) are deleted; the fields
+ * of deleted widgets leave the form with their values. Images that cannot be
+ * decoded (JPEG 2000, CCITT, JBIG2, stencil masks) are removed entirely when they
+ * overlap a region. Form XObjects are rewritten as
+ * needed. An opaque box is then drawn over each region, and objects no longer
+ * used are dropped from the file, so that the redacted content cannot be
+ * extracted with `extract_text_from_pdf` or `extract_pdf_images`.
+ *
+ * Regions are given as `areas`, or found from `texts` (a box per matched run of
+ * glyphs on a line, and the box of every matching annotation). Vector graphics inside the regions are only covered.
+ */
+export declare function redactPdf(bytes: Uint8Array, options: RedactPdfOptions): RedactPdfResult
+
+/**
+ * What to redact in a PDF.
+ *
+ * - `areas`: Regions to redact
+ * - `texts`: Text to find and redact on every page, e.g. a name or an email
+ *   address; whitespace is ignored when matching. Annotations whose contents,
+ *   form field value or appearance match are redacted whole
+ * - `match_case`: Match `texts` case-sensitively (default: false)
+ * - `fill_color`: Color of the boxes drawn over the redacted regions, as `#rrggbb`,
+ *   `rgb(…)` or a color name (default: "black")
+ */
+export interface RedactPdfOptions {
+  areas?: Array<PdfRedactionArea>
+  texts?: Array<string>
+  matchCase?: boolean
+  fillColor?: string
+}
+
+/**
+ * Result of a PDF redaction.
+ *
+ * - `pdf`: The redacted PDF
+ * - `areas`: Regions redacted, including the boxes of the text matches
+ * - `glyphs_removed`: Number of glyphs removed from the content streams
+ * - `images_redacted`: Number of image placements blanked out in the regions (or
+ *   removed, for images that cannot be decoded)
+ */
+export interface RedactPdfResult {
+  pdf: Array<number>
+  areas: Array<PdfRedactionArea>
+  glyphsRemoved: number
+  imagesRedacted: number
+}
+
+/**
+ * Render a JSON Resume to a PDF in the look described by `template`.
+ *
+ * The name and title come first, then the contact details and the sections in
+ * the template's order; empty sections are left out. Text is laid out on as many
+ * pages as needed, with the fonts embedded as subsets. Fails with `InvalidArg`
+ * for an invalid color, font, logo, language, page size or section name.
+ */
+export declare function renderResumePdf(resume: JsonResume, template?: ResumeTemplate | undefined | null): Array<number>
+
+export interface ResumeAward {
+  title?: string
+  date?: string
+  awarder?: string
+  summary?: string
+}
+
+/** Contact details and summary of the candidate (`basics` of JSON Resume). */
+export interface ResumeBasics {
+  name?: string
+  /** Job title, e.g. "Développeuse Rust" */
+  label?: string
+  email?: string
+  phone?: string
+  url?: string
+  summary?: string
+  location?: ResumeLocation
+  profiles?: Array<ResumeProfile>
+}
+
+export interface ResumeCertificate {
+  name?: string
+  date?: string
+  issuer?: string
+  url?: string
+}
+
+export interface ResumeEducation {
+  institution?: string
+  url?: string
+  area?: string
+  studyType?: string
+  startDate?: string
+  endDate?: string
+  score?: string
+  courses?: Array<string>
+}
+
+export interface ResumeLanguage {
+  language?: string
+  fluency?: string
+}
+
+export interface ResumeLocation {
+  address?: string
+  postalCode?: string
+  city?: string
+  countryCode?: string
+  region?: string
+}
+
+/** A social network profile (`basics.profiles`). */
+export interface ResumeProfile {
+  network?: string
+  username?: string
+  url?: string
+}
+
+export interface ResumeProject {
+  name?: string
+  description?: string
+  highlights?: Array<string>
+  keywords?: Array<string>
+  startDate?: string
+  endDate?: string
+  url?: string
+  roles?: Array<string>
+  entity?: string
+}
+
+export interface ResumePublication {
+  name?: string
+  publisher?: string
+  releaseDate?: string
+  url?: string
+  summary?: string
+}
+
+export interface ResumeReference {
+  name?: string
+  reference?: string
+}
+
+/** A skill (`skills`) or an interest (`interests`). */
+export interface ResumeSkill {
+  name?: string
+  level?: string
+  keywords?: Array<string>
+}
+
+/**
+ * Look of a PDF generated by `render_resume_pdf`.
+ *
+ * - `font`, `bold_font`, `italic_font`, `bold_italic_font`: TrueType fonts
+ *   (`.ttf`) to embed; missing styles fall back to the regular font (default:
+ *   DejaVu Sans)
+ * - `font_size`: Body text size in points (default: 10); the name and headings
+ *   are sized from it
+ * - `primary_color`: Color of the name, the section headings and their rules, as
+ *   `#rrggbb`, `rgb(…)` or a color name (default: "#1f3864")
+ * - `text_color`: Body text color (default: "#222222")
+ * - `muted_color`: Color of dates, places and contact details (default: "#666666")
+ * - `section_order`: Sections to show, in order, among "summary", "work",
+ *   "projects", "education", "skills", "languages", "certificates", "awards",
+ *   "publications", "volunteer", "interests" and "references" (default: all, in
+ *   this order)
+ * - `section_titles`: Titles replacing the default ones, by section name
+ * - `language`: Language of the default titles and dates, "en" or "fr" (default: "en")
+ * - `logo`: Image (PNG, JPEG, WebP) shown at the top right of the first page
+ * - `logo_width`: Width of the logo in points (default: 90)
+ * - `page_size`: "A4" or "Letter" (default: "A4")
+ * - `anonymize`: Leave out the name, contact details, location, profiles and
+ *   references (default: false)
+ */
+export interface ResumeTemplate {
+  font?: Uint8Array
+  boldFont?: Uint8Array
+  italicFont?: Uint8Array
+  boldItalicFont?: Uint8Array
+  fontSize?: number
+  primaryColor?: string
+  textColor?: string
+  mutedColor?: string
+  sectionOrder?: Array<string>
+  sectionTitles?: Record<string, string>
+  language?: string
+  logo?: Uint8Array
+  logoWidth?: number
+  pageSize?: string
+  anonymize?: boolean
+}
+
+/**
+ * A job (`work`) or a volunteering position (`volunteer`, where the
+ * organization is in `organization` rather than `name`).
+ */
+export interface ResumeWork {
+  name?: string
+  organization?: string
+  position?: string
+  location?: string
+  url?: string
+  startDate?: string
+  endDate?: string
+  summary?: string
+  highlights?: Array<string>
+}
+
+/**
+ * Score the quality of extracted text, to tell real text from mojibake.
+ *
+ * PDFs whose fonts lack a ToUnicode map often extract to private-use glyphs or to
+ * letter soup that looks like text. The score combines the share of word-like
+ * tokens (including whether their letter pairs are plausible in a natural
+ * language), the share of invalid characters and the character class entropy; use
+ * `verdict` (or a threshold on `score`) to decide whether to OCR or reject a file.
+ */
+export declare function scoreTextQuality(text: string): TextQuality
+
+/**
+ * Replace the outline (bookmarks) of a PDF and return the updated PDF.
+ *
+ * Every item pointing to a page becomes a bookmark to the top of that page; the
+ * document opens with the bookmark panel shown. An empty list removes the outline.
+ * PDFs rendered from DOCX, text, Markdown, HTML or JSON Resume already have a
+ * bookmark for every heading.
+ */
+export declare function setPdfOutline(bytes: Uint8Array, outline: Array<PdfOutlineItem>): Array<number>
+
+/**
+ * Options for the text cleanup stage applied to extracted text.
+ *
+ * Every step is enabled by default; set a flag to `false` to skip it.
+ * - `expand_ligatures`: Expand typographic ligatures (`ﬁ`, `ﬂ`, `ﬀ`, …) into plain letters
+ * - `dehyphenate`: Drop soft hyphens and re-join words split across lines with a hyphen
+ * - `normalization`: Unicode normalization form: "nfc", "nfkc" or "none" (default: "nfc")
+ * - `collapse_whitespace`: Collapse repeated spaces and blank lines, trim line ends
+ * - `remove_control_chars`: Remove control characters (newlines and tabs are kept)
+ * - `remove_headers_footers`: Remove lines repeated at the top or bottom of most pages
+ */
+export interface TextCleanupOptions {
+  expandLigatures?: boolean
+  dehyphenate?: boolean
+  normalization?: string
+  collapseWhitespace?: boolean
+  removeControlChars?: boolean
+  removeHeadersFooters?: boolean
+}
+
+/**
+ * Quality estimate of extracted text.
+ *
+ * - `score`: Overall quality, from 0 (garbage) to 1 (clean text)
+ * - `verdict`: "good", "suspect", "garbled", or "empty" (no characters)
+ * - `word_ratio`: Share of the token characters that belong to word-like tokens
+ *   (words with plausible letter pairs, numbers, dates, e-mails, URLs). Scripts
+ *   written without spaces (Chinese, Japanese) are scored character by character
+ * - `invalid_char_ratio`: Share of the characters that are private-use, replacement
+ *   (U+FFFD) or control characters
+ * - `class_entropy`: Entropy of the character class distribution (letters, digits,
+ *   punctuation, symbols, …), normalized to 0–1; mojibake mixes classes
+ * - `char_count`: Number of non-whitespace characters scored
+ */
+export interface TextQuality {
+  score: number
+  verdict: string
+  wordRatio: number
+  invalidCharRatio: number
+  classEntropy: number
+  charCount: number
+}
@@ -572,14 +572,31 @@ if (!nativeBinding) {
 }
 
 module.exports = nativeBinding
+module.exports.analyzePdf = nativeBinding.analyzePdf
+module.exports.anonymizeCv = nativeBinding.anonymizeCv
 module.exports.base64ToBuffer = nativeBinding.base64ToBuffer
 module.exports.bufferToBase64 = nativeBinding.bufferToBase64
 module.exports.convertImagesToWebpRecursive = nativeBinding.convertImagesToWebpRecursive
+module.exports.detectPii = nativeBinding.detectPii
+module.exports.diffCvText = nativeBinding.diffCvText
+module.exports.extractCvsFromEmail = nativeBinding.extractCvsFromEmail
+module.exports.extractFormFields = nativeBinding.extractFormFields
+module.exports.extractMarkdownFromPdf = nativeBinding.extractMarkdownFromPdf
+module.exports.extractPdfImages = nativeBinding.extractPdfImages
+module.exports.extractPdfOutline = nativeBinding.extractPdfOutline
+module.exports.extractText = nativeBinding.extractText
 module.exports.extractTextFromPdf = nativeBinding.extractTextFromPdf
+module.exports.extractTextFromPdfDetailed = nativeBinding.extractTextFromPdfDetailed
 module.exports.imageToWebp = nativeBinding.imageToWebp
 module.exports.imageToWebpFromBase64 = nativeBinding.imageToWebpFromBase64
 module.exports.imageToWebpFromFile = nativeBinding.imageToWebpFromFile
+module.exports.listPdfFonts = nativeBinding.listPdfFonts
 module.exports.normalizeCvToPdf = nativeBinding.normalizeCvToPdf
+module.exports.ocrDocument = nativeBinding.ocrDocument
 module.exports.optimizeImage = nativeBinding.optimizeImage
 module.exports.optimizeImageFromBase64 = nativeBinding.optimizeImageFromBase64
 module.exports.optimizeImageFromFile = nativeBinding.optimizeImageFromFile
+module.exports.redactPdf = nativeBinding.redactPdf
+module.exports.renderResumePdf = nativeBinding.renderResumePdf
+module.exports.scoreTextQuality = nativeBinding.scoreTextQuality
+module.exports.setPdfOutline = nativeBinding.setPdfOutline
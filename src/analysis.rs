@@ -0,0 +1,144 @@
+use napi::bindgen_prelude::Uint8Array;
+use napi::{Error, Status};
+use napi_derive::napi;
+use pdf_extract::Document;
+
+use crate::pdf::{catch_panic, load_document, page_glyphs};
+use crate::pdf_content::{page_images, union_area};
+use crate::pdf_objects::page_box;
+
+/// Pages with fewer extracted characters than this have no usable text layer.
+const MIN_TEXT_CHARS: u32 = 50;
+/// Minimum share of the page covered by images for a text-less page to be a scan.
+const MIN_SCAN_IMAGE_COVERAGE: f64 = 0.25;
+
+/// Analysis of a single PDF page.
+///
+/// - `page_number`: 1-based page number
+/// - `width` / `height`: Page size in points (media box)
+/// - `char_count`: Number of non-whitespace characters extracted from the page
+/// - `image_count`: Number of images drawn on the page
+/// - `image_area`: Area of the page covered by images, in square points
+/// - `image_coverage`: Share of the page covered by images (0 to 1)
+/// - `likely_scanned`: The page is mostly an image with no usable text layer
+#[napi(object)]
+pub struct PdfPageAnalysis {
+  pub page_number: u32,
+  pub width: f64,
+  pub height: f64,
+  pub char_count: u32,
+  pub image_count: u32,
+  pub image_area: f64,
+  pub image_coverage: f64,
+  pub likely_scanned: bool,
+}
+
+/// Document-level analysis of a PDF.
+///
+/// - `page_count`: Number of pages
+/// - `char_count`: Total number of extracted characters
+/// - `text_coverage`: Share of the pages with extractable text (0 to 1)
+/// - `scanned_pages`: Number of pages that are likely scans
+/// - `verdict`: "digital" (text on every page with content), "scanned" (every page
+///   with content is a scan), "mixed", or "empty" (no text and no images)
+/// - `needs_ocr`: At least one page is likely a scan
+#[napi(object)]
+pub struct PdfAnalysis {
+  pub page_count: u32,
+  pub char_count: u32,
+  pub text_coverage: f64,
+  pub scanned_pages: u32,
+  pub verdict: String,
+  pub needs_ocr: bool,
+  pub pages: Vec<PdfPageAnalysis>,
+}
+
+/// Analyze a PDF to tell digital documents from scans.
+///
+/// For each page, counts the characters that can be extracted and measures the area
+/// covered by images. A page with (almost) no text whose images cover a significant
+/// part of it is reported as a likely scan, so callers can route it to OCR.
+#[napi]
+pub fn analyze_pdf(bytes: Uint8Array) -> napi::Result<PdfAnalysis> {
+  let input = bytes.to_vec();
+  catch_panic(|| analyze(&input))
+}
+
+fn analyze(input: &[u8]) -> napi::Result<PdfAnalysis> {
+  let doc = load_document(input)
+    .map_err(|e| Error::new(Status::InvalidArg, format!("Failed to analyze PDF: {e}")))?;
+  Ok(analyze_document(&doc))
+}
+
+/// Analysis of a loaded document (see `analyze_pdf`).
+pub(crate) fn analyze_document(doc: &Document) -> PdfAnalysis {
+  let pages: Vec<PdfPageAnalysis> = doc
+    .get_pages()
+    .into_iter()
+    .map(|(page_num, page_id)| {
+      // Undecodable glyphs (U+FFFD) do not make a usable text layer.
+      let char_count = page_glyphs(doc, page_num, page_id)
+        .glyphs
+        .iter()
+        .flat_map(|g| g.text.chars())
+        .filter(|c| !c.is_whitespace() && *c != '\u{FFFD}')
+        .count() as u32;
+
+      let page = page_box(doc, page_id);
+      let (width, height) = (page[2] - page[0], page[3] - page[1]);
+      let images = page_images(doc, page_id);
+      let rects: Vec<[f64; 4]> = images.iter().map(|i| i.rect).collect();
+      let image_area = union_area(&rects, page);
+      let page_area = width * height;
+      let image_coverage = if page_area > 0.0 {
+        (image_area / page_area).min(1.0)
+      } else {
+        0.0
+      };
+
+      PdfPageAnalysis {
+        page_number: page_num,
+        width,
+        height,
+        char_count,
+        image_count: images.len() as u32,
+        image_area,
+        image_coverage,
+        likely_scanned: char_count < MIN_TEXT_CHARS && image_coverage >= MIN_SCAN_IMAGE_COVERAGE,
+      }
+    })
+    .collect();
+
+  let page_count = pages.len() as u32;
+  let char_count = pages.iter().map(|p| p.char_count).sum();
+  let text_pages = pages.iter().filter(|p| p.char_count > 0).count();
+  let scanned_pages = pages.iter().filter(|p| p.likely_scanned).count() as u32;
+  let content_pages = pages
+    .iter()
+    .filter(|p| p.char_count > 0 || p.image_count > 0)
+    .count() as u32;
+
+  let verdict = if content_pages == 0 {
+    "empty"
+  } else if scanned_pages == 0 {
+    "digital"
+  } else if scanned_pages == content_pages {
+    "scanned"
+  } else {
+    "mixed"
+  };
+
+  PdfAnalysis {
+    page_count,
+    char_count,
+    text_coverage: if page_count > 0 {
+      text_pages as f64 / page_count as f64
+    } else {
+      0.0
+    },
+    scanned_pages,
+    verdict: verdict.to_string(),
+    needs_ocr: scanned_pages > 0,
+    pages,
+  }
+}
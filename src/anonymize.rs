@@ -0,0 +1,194 @@
+use napi::bindgen_prelude::Uint8Array;
+use napi::{Error, Status};
+use napi_derive::napi;
+
+use crate::embedded_fonts::FontFamily;
+use crate::pdf::{catch_panic, extract_text, load_document, PdfTextOptions};
+use crate::pdf_images::pdf_images;
+use crate::pii::{find_pii, segments, PiiMatch};
+use crate::text::TextCleanupOptions;
+use crate::text_input::text_blocks;
+use crate::typeset::{render_pdf, PageSetup};
+
+/// Options for CV anonymization.
+///
+/// - `name`: The candidate's name, when known (by default, a name-like line among
+///   the first lines of the CV is taken as the name)
+/// - `placeholder`: Text replacing personal data inside a line (default: "[…]");
+///   lines or parts of lines left with nothing else are removed
+#[napi(object)]
+#[derive(Default)]
+pub struct AnonymizeOptions {
+  pub name: Option<String>,
+  pub placeholder: Option<String>,
+}
+
+/// An item removed from a CV.
+///
+/// - `category`: "name", "email", "phone", "url", "address", "date_of_birth", "age",
+///   "nationality", "marital_status", "social_security_number", "health", "photo"
+///   (the likely portrait) or "image"
+/// - `text`: The removed text (none for images)
+/// - `line`: 1-based line of the removed text in the text of the CV
+/// - `page_number`: 1-based page of a removed image
+#[napi(object)]
+pub struct Redaction {
+  pub category: String,
+  pub text: Option<String>,
+  pub line: Option<u32>,
+  pub page_number: Option<u32>,
+}
+
+/// Result of `anonymize_cv`.
+///
+/// - `pdf`: The anonymized CV, as a new A4 PDF
+/// - `text`: Its text
+/// - `redactions`: What was removed, in page and reading order, for audit
+#[napi(object)]
+pub struct AnonymizedCv {
+  pub pdf: Vec<u8>,
+  pub text: String,
+  pub redactions: Vec<Redaction>,
+}
+
+/// Produce a blind version of a PDF CV, without the personal data that must not
+/// influence a hiring decision.
+///
+/// The text is extracted in reading order, as with `extract_text_from_pdf` and
+/// `layout: true`. The candidate's name, email addresses, phone numbers, profile
+/// URLs, home address, date of birth, age, nationality, marital status, social
+/// security number and health mentions are detected (in French and English) and
+/// removed, then the remaining text is laid out into a new PDF. Images, including
+/// the photo, are never carried over. Detection is heuristic: the returned
+/// `redactions` should be reviewed.
+#[napi]
+pub fn anonymize_cv(
+  bytes: Uint8Array,
+  options: Option<AnonymizeOptions>,
+) -> napi::Result<AnonymizedCv> {
+  let input = bytes.to_vec();
+  let options = options.unwrap_or_default();
+  let extracted = extract_text(
+    &input,
+    &PdfTextOptions {
+      cleanup: Some(TextCleanupOptions::default()),
+      layout: Some(true),
+      ..Default::default()
+    },
+  )?;
+
+  let name = options
+    .name
+    .as_deref()
+    .map(str::trim)
+    .filter(|n| !n.is_empty());
+  let placeholder = options.placeholder.as_deref().unwrap_or("[…]");
+  let matches = find_pii(&extracted.text, name);
+  let text = redact_text(&extracted.text, &matches, placeholder);
+
+  let mut redactions: Vec<Redaction> = matches
+    .iter()
+    .map(|m| Redaction {
+      category: m.category.to_string(),
+      text: extracted
+        .text
+        .lines()
+        .nth(m.line)
+        .map(|line| line[m.range.clone()].to_string()),
+      line: Some(m.line as u32 + 1),
+      page_number: None,
+    })
+    .collect();
+  let images = catch_panic(|| {
+    let doc = load_document(&input)
+      .map_err(|e| Error::new(Status::InvalidArg, format!("Failed to read PDF: {e}")))?;
+    Ok(pdf_images(&doc, true))
+  })?;
+  redactions.extend(images.iter().map(|image| {
+    Redaction {
+      category: if image.likely_portrait {
+        "photo"
+      } else {
+        "image"
+      }
+      .to_string(),
+      text: None,
+      line: None,
+      page_number: Some(image.page_number),
+    }
+  }));
+
+  let pdf = catch_panic(|| {
+    Ok(render_pdf(
+      &text_blocks(&text),
+      &PageSetup::default(),
+      &FontFamily::dejavu_sans(),
+    ))
+  })?;
+  Ok(AnonymizedCv {
+    pdf,
+    text,
+    redactions,
+  })
+}
+
+/// `text` with the `matches` replaced by `placeholder`. Parts of lines (between
+/// separators such as " · ") and lines left without any letter or digit are
+/// removed.
+fn redact_text(text: &str, matches: &[PiiMatch], placeholder: &str) -> String {
+  let mut lines = Vec::new();
+  for (index, line) in text.lines().enumerate() {
+    let line_matches: Vec<&PiiMatch> = matches.iter().filter(|m| m.line == index).collect();
+    if line_matches.is_empty() {
+      lines.push(line.to_string());
+      continue;
+    }
+
+    let mut redacted = String::new();
+    let mut previous_end = 0;
+    for segment in segments(line) {
+      let separator = &line[previous_end..segment.start];
+      previous_end = segment.end;
+      let mut part = String::new();
+      let mut removed = false;
+      let mut left = false;
+      let mut position = segment.start;
+      for m in &line_matches {
+        if m.range.end <= position || m.range.start >= segment.end {
+          continue;
+        }
+        let start = m.range.start.max(position);
+        left |= line[position..start].chars().any(char::is_alphanumeric);
+        part.push_str(&line[position..start]);
+        part.push_str(placeholder);
+        removed = true;
+        position = m.range.end.min(segment.end);
+      }
+      left |= line[position..segment.end]
+        .chars()
+        .any(char::is_alphanumeric);
+      part.push_str(&line[position..segment.end]);
+      let kept = !removed || left;
+      if kept {
+        if !redacted.is_empty() {
+          redacted.push_str(separator);
+        }
+        redacted.push_str(&part);
+      }
+    }
+    if !redacted.trim().is_empty() {
+      lines.push(redacted);
+    }
+  }
+
+  // Blank lines left around removed lines are merged.
+  let mut text = String::new();
+  for line in lines {
+    if line.trim().is_empty() && (text.is_empty() || text.ends_with("\n\n")) {
+      continue;
+    }
+    text.push_str(&line);
+    text.push('\n');
+  }
+  text.trim_end().to_string()
+}
@@ -0,0 +1,62 @@
+use encoding_rs::{Encoding, UTF_16BE, UTF_16LE, UTF_8, WINDOWS_1252};
+
+/// Guess the encoding of a text file: a byte order mark first, then UTF-16
+/// without BOM (from the zero bytes of ASCII characters), then UTF-8 when the
+/// bytes are valid UTF-8, and Windows-1252 (a superset of Latin-1) otherwise.
+pub(crate) fn detect_encoding(bytes: &[u8]) -> &'static Encoding {
+  if let Some((encoding, _)) = Encoding::for_bom(bytes) {
+    return encoding;
+  }
+  if let Some(encoding) = detect_utf16(bytes) {
+    return encoding;
+  }
+  if std::str::from_utf8(bytes).is_ok() {
+    UTF_8
+  } else {
+    WINDOWS_1252
+  }
+}
+
+/// UTF-16 text without BOM: mostly ASCII, so one byte of most pairs is zero.
+fn detect_utf16(bytes: &[u8]) -> Option<&'static Encoding> {
+  let sample = &bytes[..bytes.len().min(4096) & !1];
+  if sample.len() < 4 {
+    return None;
+  }
+  let pairs = sample.len() / 2;
+  let zeros_at = |offset: usize| {
+    sample
+      .iter()
+      .skip(offset)
+      .step_by(2)
+      .filter(|b| **b == 0)
+      .count()
+  };
+  let (even, odd) = (zeros_at(0), zeros_at(1));
+  // Text in other encodings has no zero bytes at all.
+  if odd * 10 >= pairs * 6 && even * 10 < pairs {
+    Some(UTF_16LE)
+  } else if even * 10 >= pairs * 6 && odd * 10 < pairs {
+    Some(UTF_16BE)
+  } else {
+    None
+  }
+}
+
+/// Decode text in the detected encoding (see `detect_encoding`); the BOM is dropped.
+pub(crate) fn decode_text(bytes: &[u8]) -> String {
+  decode_with(bytes, detect_encoding(bytes))
+}
+
+/// Decode text in `encoding`, unless a byte order mark says otherwise.
+pub(crate) fn decode_with(bytes: &[u8], encoding: &'static Encoding) -> String {
+  let (text, _, _) = encoding.decode(bytes);
+  text.into_owned()
+}
+
+/// Encoding for a charset label (`utf-8`, `iso-8859-1`, `windows-1252`, …).
+///
+/// As in browsers, Latin-1 and ASCII labels map to Windows-1252.
+pub(crate) fn encoding_for_label(label: &str) -> Option<&'static Encoding> {
+  Encoding::for_label(label.trim().as_bytes())
+}
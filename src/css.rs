@@ -0,0 +1,398 @@
+use crate::html::Element;
+use crate::typeset::PageSetup;
+
+/// Default font size of a document, in points; `medium` and `1rem`.
+pub(crate) const BASE_FONT_SIZE: f64 = 11.0;
+
+/// Points per CSS pixel (96 px to the inch).
+const PX: f64 = 0.75;
+
+/// A property and its value, as written.
+#[derive(Clone, Debug)]
+pub(crate) struct Declaration {
+  /// Lowercase property name
+  pub property: String,
+  pub value: String,
+  pub important: bool,
+}
+
+/// Simple selectors that must all match one element (`p.lead#intro`).
+#[derive(Debug, Default)]
+struct Compound {
+  tag: Option<String>,
+  id: Option<String>,
+  classes: Vec<String>,
+}
+
+impl Compound {
+  fn matches(&self, element: &Element) -> bool {
+    self.tag.as_ref().is_none_or(|tag| *tag == element.name)
+      && self
+        .id
+        .as_ref()
+        .is_none_or(|id| element.attr("id") == Some(id.as_str()))
+      && self.classes.iter().all(|class| {
+        element
+          .attr("class")
+          .is_some_and(|classes| classes.split_whitespace().any(|c| c == class))
+      })
+  }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Combinator {
+  Descendant,
+  Child,
+}
+
+/// A complex selector: compounds from the outermost to the subject, each with
+/// the combinator that links it to the next one.
+#[derive(Debug)]
+struct Selector {
+  parts: Vec<(Compound, Combinator)>,
+  /// (ids, classes, tags)
+  specificity: (usize, usize, usize),
+}
+
+impl Selector {
+  /// Parse a selector of type, class and id selectors with descendant and child
+  /// combinators; anything else (attributes, pseudo-classes) is not supported.
+  fn parse(text: &str) -> Option<Selector> {
+    let mut parts: Vec<(Compound, Combinator)> = Vec::new();
+    let mut specificity = (0, 0, 0);
+    let mut combinator = Combinator::Descendant;
+    let spaced = text.replace('>', " > ");
+    for token in spaced.split_whitespace() {
+      if token == ">" {
+        if parts.is_empty() {
+          return None;
+        }
+        combinator = Combinator::Child;
+        continue;
+      }
+      if let Some(last) = parts.last_mut() {
+        last.1 = combinator;
+      }
+      combinator = Combinator::Descendant;
+
+      let mut compound = Compound::default();
+      let mut rest = token;
+      let name_end = rest.find(['.', '#']).unwrap_or(rest.len());
+      let tag = &rest[..name_end];
+      if !tag.is_empty() && tag != "*" {
+        if !is_identifier(tag) {
+          return None;
+        }
+        compound.tag = Some(tag.to_ascii_lowercase());
+        specificity.2 += 1;
+      }
+      rest = &rest[name_end..];
+      while let Some(kind) = rest.chars().next() {
+        let end = rest[1..].find(['.', '#']).map_or(rest.len(), |p| p + 1);
+        let name = &rest[1..end];
+        if !is_identifier(name) {
+          return None;
+        }
+        if kind == '#' {
+          compound.id = Some(name.to_string());
+          specificity.0 += 1;
+        } else {
+          compound.classes.push(name.to_string());
+          specificity.1 += 1;
+        }
+        rest = &rest[end..];
+      }
+      parts.push((compound, Combinator::Descendant));
+    }
+    (!parts.is_empty() && combinator == Combinator::Descendant)
+      .then_some(Selector { parts, specificity })
+  }
+
+  /// Whether the selector matches the last element of `chain` (the element
+  /// and its ancestors, from the root).
+  fn matches(&self, chain: &[&Element]) -> bool {
+    let Some(((subject, _), outer)) = self.parts.split_last() else {
+      return false;
+    };
+    let Some((element, ancestors)) = chain.split_last() else {
+      return false;
+    };
+    subject.matches(element) && Self::matches_outer(outer, ancestors)
+  }
+
+  fn matches_outer(parts: &[(Compound, Combinator)], ancestors: &[&Element]) -> bool {
+    let Some(((compound, combinator), outer)) = parts.split_last() else {
+      return true;
+    };
+    match combinator {
+      Combinator::Child => ancestors
+        .split_last()
+        .is_some_and(|(parent, rest)| compound.matches(parent) && Self::matches_outer(outer, rest)),
+      Combinator::Descendant => (0..ancestors.len())
+        .rev()
+        .any(|i| compound.matches(ancestors[i]) && Self::matches_outer(outer, &ancestors[..i])),
+    }
+  }
+}
+
+fn is_identifier(name: &str) -> bool {
+  !name.is_empty()
+    && name
+      .chars()
+      .all(|c| c.is_alphanumeric() || c == '-' || c == '_')
+}
+
+struct Rule {
+  selector: Selector,
+  declarations: Vec<Declaration>,
+}
+
+/// Style rules of a document's `<style>` elements.
+///
+/// Only the selectors and properties needed to lay out simple documents are
+/// supported; `@media` and other at-rules are skipped, except `@page`.
+#[derive(Default)]
+pub(crate) struct Stylesheet {
+  rules: Vec<Rule>,
+  page: Vec<Declaration>,
+}
+
+impl Stylesheet {
+  pub fn is_empty(&self) -> bool {
+    self.rules.is_empty()
+  }
+
+  pub fn parse(css: &str) -> Stylesheet {
+    let css = strip_comments(css);
+    let mut sheet = Stylesheet::default();
+    let mut rest = css.as_str();
+    while let Some(open) = rest.find('{') {
+      let prelude = rest[..open].trim();
+      let body_start = open + 1;
+      let Some(close) = block_end(&rest[body_start..]) else {
+        break;
+      };
+      let body = &rest[body_start..body_start + close];
+      rest = &rest[body_start + close + 1..];
+
+      if let Some(at_rule) = prelude.strip_prefix('@') {
+        if at_rule.to_ascii_lowercase().starts_with("page") {
+          sheet.page.extend(parse_declarations(body));
+        }
+        continue;
+      }
+      let declarations = parse_declarations(body);
+      for selector in prelude.split(',').filter_map(Selector::parse) {
+        sheet.rules.push(Rule {
+          selector,
+          declarations: declarations.clone(),
+        });
+      }
+    }
+    sheet
+  }
+
+  /// Declarations that apply to the last element of `chain`, in cascade
+  /// order: by specificity then source order, the `style` attribute after the
+  /// style sheet, and `!important` declarations last.
+  pub fn cascade(&self, chain: &[&Element]) -> Vec<Declaration> {
+    let Some(element) = chain.last() else {
+      return Vec::new();
+    };
+    let mut matched: Vec<(&Selector, usize)> = self
+      .rules
+      .iter()
+      .enumerate()
+      .filter(|(_, rule)| rule.selector.matches(chain))
+      .map(|(i, rule)| (&rule.selector, i))
+      .collect();
+    matched.sort_by_key(|(selector, i)| (selector.specificity, *i));
+
+    let mut declarations: Vec<Declaration> = matched
+      .iter()
+      .flat_map(|(_, i)| self.rules[*i].declarations.iter().cloned())
+      .collect();
+    if let Some(style) = element.attr("style") {
+      declarations.extend(parse_declarations(style));
+    }
+    declarations.sort_by_key(|d| d.important);
+    declarations
+  }
+
+  /// Page size and margins from the `@page` rule, A4 with 2 cm margins otherwise.
+  pub fn page_setup(&self) -> PageSetup {
+    let mut page = PageSetup::default();
+    for declaration in &self.page {
+      let value = declaration.value.to_ascii_lowercase();
+      match declaration.property.as_str() {
+        "size" => {
+          let words: Vec<&str> = value.split_whitespace().collect();
+          let (mut width, mut height) = (page.width, page.height);
+          let lengths: Vec<f64> = words
+            .iter()
+            .filter_map(|w| length(w, BASE_FONT_SIZE))
+            .collect();
+          match lengths.as_slice() {
+            [side] => (width, height) = (*side, *side),
+            [w, h, ..] => (width, height) = (*w, *h),
+            _ => {}
+          }
+          if words.contains(&"letter") {
+            (width, height) = (612.0, 792.0);
+          } else if words.contains(&"legal") {
+            (width, height) = (612.0, 1008.0);
+          } else if words.contains(&"a4") {
+            (width, height) = (595.28, 841.89);
+          } else if words.contains(&"a5") {
+            (width, height) = (419.53, 595.28);
+          }
+          if words.contains(&"landscape") {
+            (width, height) = (width.max(height), width.min(height));
+          }
+          if width >= 72.0 && height >= 72.0 {
+            (page.width, page.height) = (width, height);
+          }
+        }
+        "margin" => {
+          if let Some([top, right, bottom, left]) = box_lengths(&value, BASE_FONT_SIZE) {
+            page.margin_top = top;
+            page.margin_right = right;
+            page.margin_bottom = bottom;
+            page.margin_left = left;
+          }
+        }
+        "margin-top" => page.margin_top = length(&value, BASE_FONT_SIZE).unwrap_or(page.margin_top),
+        "margin-right" => {
+          page.margin_right = length(&value, BASE_FONT_SIZE).unwrap_or(page.margin_right)
+        }
+        "margin-bottom" => {
+          page.margin_bottom = length(&value, BASE_FONT_SIZE).unwrap_or(page.margin_bottom)
+        }
+        "margin-left" => {
+          page.margin_left = length(&value, BASE_FONT_SIZE).unwrap_or(page.margin_left)
+        }
+        _ => {}
+      }
+    }
+    page
+  }
+}
+
+fn strip_comments(css: &str) -> String {
+  let mut out = String::with_capacity(css.len());
+  let mut rest = css;
+  while let Some(start) = rest.find("/*") {
+    out.push_str(&rest[..start]);
+    rest = rest[start + 2..]
+      .find("*/")
+      .map_or("", |end| &rest[start + 2 + end + 2..]);
+  }
+  out.push_str(rest);
+  out
+}
+
+/// Position of the `}` that closes a block, skipping nested blocks.
+fn block_end(body: &str) -> Option<usize> {
+  let mut depth = 0;
+  for (i, c) in body.char_indices() {
+    match c {
+      '{' => depth += 1,
+      '}' if depth == 0 => return Some(i),
+      '}' => depth -= 1,
+      _ => {}
+    }
+  }
+  None
+}
+
+/// Declarations of a rule body or `style` attribute (`color: red; margin: 0`).
+pub(crate) fn parse_declarations(body: &str) -> Vec<Declaration> {
+  body
+    .split(';')
+    .filter_map(|declaration| {
+      let (property, value) = declaration.split_once(':')?;
+      let property = property.trim().to_ascii_lowercase();
+      let value = value.trim();
+      let (value, important) = match value.to_ascii_lowercase().rfind("!important") {
+        Some(position) => (value[..position].trim(), true),
+        None => (value, false),
+      };
+      (!property.is_empty() && !value.is_empty()).then(|| Declaration {
+        property,
+        value: value.to_string(),
+        important,
+      })
+    })
+    .collect()
+}
+
+/// A length in points: `px`, `pt`, `pc`, `in`, `cm`, `mm`, `em` and `rem`
+/// (relative to `font_size`, the element's font size), or a unitless zero.
+pub(crate) fn length(value: &str, font_size: f64) -> Option<f64> {
+  let value = value.trim().to_ascii_lowercase();
+  let number_end = value
+    .find(|c: char| !(c.is_ascii_digit() || c == '.' || c == '-' || c == '+'))
+    .unwrap_or(value.len());
+  let number: f64 = value[..number_end].parse().ok()?;
+  let points = match &value[number_end..] {
+    "px" => number * PX,
+    "pt" => number,
+    "pc" => number * 12.0,
+    "in" => number * 72.0,
+    "cm" => number * 72.0 / 2.54,
+    "mm" => number * 72.0 / 25.4,
+    "em" => number * font_size,
+    "rem" => number * BASE_FONT_SIZE,
+    "" if number == 0.0 => 0.0,
+    _ => return None,
+  };
+  points.is_finite().then_some(points)
+}
+
+/// Font size in points for a `font-size` value, given the parent's size.
+pub(crate) fn font_size(value: &str, parent: f64) -> Option<f64> {
+  let value = value.trim().to_ascii_lowercase();
+  let scale = match value.as_str() {
+    "xx-small" => Some(0.6),
+    "x-small" => Some(0.75),
+    "small" => Some(0.89),
+    "medium" => Some(1.0),
+    "large" => Some(1.2),
+    "x-large" => Some(1.5),
+    "xx-large" => Some(2.0),
+    "xxx-large" => Some(3.0),
+    _ => None,
+  };
+  let size = match (scale, value.as_str()) {
+    (Some(scale), _) => BASE_FONT_SIZE * scale,
+    (None, "smaller") => parent / 1.2,
+    (None, "larger") => parent * 1.2,
+    (None, _) => match value.strip_suffix('%') {
+      Some(percent) => parent * percent.trim().parse::<f64>().ok()? / 100.0,
+      None => length(&value, parent)?,
+    },
+  };
+  (size > 0.0).then_some(size.min(200.0))
+}
+
+/// The four sides of a `margin` or `padding` shorthand: top, right, bottom, left.
+pub(crate) fn box_lengths(value: &str, font_size: f64) -> Option<[f64; 4]> {
+  // `auto` centers blocks; laid out from the left here.
+  let lengths: Vec<f64> = value
+    .split_whitespace()
+    .map(|v| {
+      if v.eq_ignore_ascii_case("auto") {
+        Some(0.0)
+      } else {
+        length(v, font_size)
+      }
+    })
+    .collect::<Option<_>>()?;
+  match lengths.as_slice() {
+    [all] => Some([*all; 4]),
+    [vertical, horizontal] => Some([*vertical, *horizontal, *vertical, *horizontal]),
+    [top, horizontal, bottom] => Some([*top, *horizontal, *bottom, *horizontal]),
+    [top, right, bottom, left] => Some([*top, *right, *bottom, *left]),
+    _ => None,
+  }
+}
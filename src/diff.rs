@@ -0,0 +1,410 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use napi::bindgen_prelude::Uint8Array;
+use napi_derive::napi;
+use similar::{capture_diff_slices, Algorithm, DiffOp};
+
+use crate::pdf::{extract_text, PdfTextOptions};
+use crate::text::TextCleanupOptions;
+
+/// Share of common words above which a replaced line is reported as changed rather
+/// than as a deletion followed by an insertion.
+const MIN_CHANGED_LINE_SIMILARITY: f64 = 0.4;
+
+/// Characters starting list items; they are ignored when comparing lines.
+const BULLETS: &[char] = &[
+  '•', '◦', '▪', '▫', '‣', '●', '○', '■', '□', '·', '-', '–', '—', '*',
+];
+
+/// Options for `diff_cv_text`.
+///
+/// - `layout`: Extract text in natural reading order, so that a sidebar moving from
+///   one side to the other does not show up as a change (default: true)
+/// - `ignore_case`: Compare lines case-insensitively (default: false)
+#[napi(object)]
+#[derive(Default)]
+pub struct CvDiffOptions {
+  pub layout: Option<bool>,
+  pub ignore_case: Option<bool>,
+}
+
+/// A line of the diff between two versions of a CV.
+///
+/// - `kind`: "equal", "insert" (only in the new version), "delete" (only in the old
+///   version) or "change" (edited line, see `words`)
+/// - `old_line`: 1-based line number in the old text (absent for insertions)
+/// - `new_line`: 1-based line number in the new text (absent for deletions)
+/// - `text`: The line, from the new version except for deletions
+/// - `words`: Word-level diff of a changed line
+/// - `move_id`: For lines of a moved block, the `id` of the move: the block shows up
+///   as deletions at its old position and as insertions (or changes, for lines
+///   edited on the way) at its new one
+#[napi(object)]
+pub struct CvDiffLine {
+  pub kind: String,
+  pub old_line: Option<u32>,
+  pub new_line: Option<u32>,
+  pub text: String,
+  pub words: Option<Vec<CvDiffWord>>,
+  pub move_id: Option<u32>,
+}
+
+/// A word of a changed line.
+///
+/// - `kind`: "equal", "insert" or "delete"
+/// - `text`: The word
+#[napi(object)]
+pub struct CvDiffWord {
+  pub kind: String,
+  pub text: String,
+}
+
+/// A block of lines found at another position of the new version (e.g. a section
+/// moved up), possibly with some of its lines edited.
+///
+/// - `id`: Identifier referenced by `CvDiffLine.move_id`
+/// - `old_line`: 1-based first line of the block in the old text
+/// - `new_line`: 1-based first line of the block in the new text
+/// - `line_count`: Number of lines of the block
+#[napi(object)]
+pub struct CvDiffMove {
+  pub id: u32,
+  pub old_line: u32,
+  pub new_line: u32,
+  pub line_count: u32,
+}
+
+/// Differences between two versions of a CV.
+///
+/// - `lines`: Both texts merged line by line, in the order of the new version
+///   (deleted lines appear where they were removed)
+/// - `moves`: Blocks of lines that moved
+/// - `inserted`: Number of inserted lines, moved lines excluded
+/// - `deleted`: Number of deleted lines, moved lines excluded
+/// - `changed`: Number of changed lines
+/// - `moved`: Number of lines moved unchanged
+/// - `similarity`: Share of lines common to both versions (moved lines included),
+///   from 0 to 1
+#[napi(object)]
+pub struct CvTextDiff {
+  pub lines: Vec<CvDiffLine>,
+  pub moves: Vec<CvDiffMove>,
+  pub inserted: u32,
+  pub deleted: u32,
+  pub changed: u32,
+  pub moved: u32,
+  pub similarity: f64,
+}
+
+/// Compare the text of two versions of a PDF CV.
+///
+/// Both documents go through `extract_text_from_pdf` with the default cleanup
+/// (repeated headers/footers, hyphenation, ligatures and whitespace); lines are then
+/// compared ignoring blank lines and bullet glyphs. Blocks of lines found unchanged
+/// at another position are reported as moves rather than as deletions and
+/// insertions, and edited lines come with a word-level diff.
+#[napi]
+pub fn diff_cv_text(
+  old_bytes: Uint8Array,
+  new_bytes: Uint8Array,
+  options: Option<CvDiffOptions>,
+) -> napi::Result<CvTextDiff> {
+  let options = options.unwrap_or_default();
+  let text_options = PdfTextOptions {
+    cleanup: Some(TextCleanupOptions::default()),
+    layout: Some(options.layout.unwrap_or(true)),
+    ..Default::default()
+  };
+  let old_text = extract_text(&old_bytes, &text_options)?.text;
+  let new_text = extract_text(&new_bytes, &text_options)?.text;
+  Ok(diff_text(
+    &old_text,
+    &new_text,
+    options.ignore_case.unwrap_or(false),
+  ))
+}
+
+/// A non-blank line and its comparison key.
+struct Line<'a> {
+  number: u32,
+  text: &'a str,
+  key: String,
+}
+
+fn lines(text: &str, ignore_case: bool) -> Vec<Line<'_>> {
+  text
+    .lines()
+    .enumerate()
+    .filter_map(|(i, line)| {
+      let text = line.trim();
+      let key = text
+        .trim_start_matches(|c: char| BULLETS.contains(&c) || c.is_whitespace())
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ");
+      if key.is_empty() {
+        return None;
+      }
+      Some(Line {
+        number: i as u32 + 1,
+        text,
+        key: if ignore_case { key.to_lowercase() } else { key },
+      })
+    })
+    .collect()
+}
+
+fn diff_text(old_text: &str, new_text: &str, ignore_case: bool) -> CvTextDiff {
+  let old = lines(old_text, ignore_case);
+  let new = lines(new_text, ignore_case);
+  let old_keys: Vec<&str> = old.iter().map(|l| l.key.as_str()).collect();
+  let new_keys: Vec<&str> = new.iter().map(|l| l.key.as_str()).collect();
+  let ops = capture_diff_slices(Algorithm::Myers, &old_keys, &new_keys);
+
+  let mut moves = find_moves(&ops, &old, &new, ignore_case);
+  let mut diff = CvTextDiff {
+    lines: Vec::new(),
+    moves: std::mem::take(&mut moves.blocks),
+    inserted: 0,
+    deleted: 0,
+    changed: 0,
+    moved: 0,
+    similarity: 0.0,
+  };
+  let mut equal = 0;
+
+  for op in &ops {
+    let (old_range, new_range) = (op.old_range(), op.new_range());
+    if let DiffOp::Equal { .. } = op {
+      for (o, n) in old_range.zip(new_range) {
+        diff
+          .lines
+          .push(line("equal", Some(&old[o]), Some(&new[n]), None, None));
+        equal += 1;
+      }
+      continue;
+    }
+
+    let removed: Vec<usize> = old_range.filter(|o| !moves.old.contains_key(o)).collect();
+    let added: Vec<usize> = new_range.filter(|n| !moves.new.contains_key(n)).collect();
+    // Replaced lines are paired in order; similar pairs are edits of the same line.
+    let mut changes = HashMap::new();
+    for (&o, &n) in removed.iter().zip(&added) {
+      let words = diff_words(old[o].text, new[n].text, ignore_case);
+      if word_similarity(&words) >= MIN_CHANGED_LINE_SIMILARITY {
+        changes.insert(n, (o, words));
+      }
+    }
+    let changed_old: HashSet<usize> = changes.values().map(|c| c.0).collect();
+
+    for o in op.old_range() {
+      if changed_old.contains(&o) {
+        continue;
+      }
+      let move_id = moves.old.get(&o).copied();
+      diff.deleted += u32::from(move_id.is_none());
+      diff
+        .lines
+        .push(line("delete", Some(&old[o]), None, None, move_id));
+    }
+    for n in op.new_range() {
+      let move_id = moves.new.get(&n).copied();
+      let change = changes.remove(&n).or_else(|| moves.edits.remove(&n));
+      if let Some((o, words)) = change {
+        diff.changed += 1;
+        diff.lines.push(line(
+          "change",
+          Some(&old[o]),
+          Some(&new[n]),
+          Some(words),
+          move_id,
+        ));
+      } else if move_id.is_some() {
+        diff.moved += 1;
+        diff
+          .lines
+          .push(line("insert", None, Some(&new[n]), None, move_id));
+      } else {
+        diff.inserted += 1;
+        diff
+          .lines
+          .push(line("insert", None, Some(&new[n]), None, None));
+      }
+    }
+  }
+
+  let total = old.len() + new.len();
+  diff.similarity = if total == 0 {
+    1.0
+  } else {
+    2.0 * (equal + diff.moved as usize) as f64 / total as f64
+  };
+  diff
+}
+
+fn line(
+  kind: &str,
+  old: Option<&Line>,
+  new: Option<&Line>,
+  words: Option<Vec<CvDiffWord>>,
+  move_id: Option<u32>,
+) -> CvDiffLine {
+  CvDiffLine {
+    kind: kind.to_string(),
+    old_line: old.map(|l| l.number),
+    new_line: new.map(|l| l.number),
+    text: new.or(old).map(|l| l.text.to_string()).unwrap_or_default(),
+    words,
+    move_id,
+  }
+}
+
+/// Moved blocks, with the old and new line indexes they cover.
+struct Moves {
+  blocks: Vec<CvDiffMove>,
+  /// Move id of moved old lines
+  old: HashMap<usize, u32>,
+  /// Move id of moved new lines
+  new: HashMap<usize, u32>,
+  /// Lines edited inside a moved block: new line index → old line index, word diff
+  edits: HashMap<usize, (usize, Vec<CvDiffWord>)>,
+}
+
+/// Find moved blocks: deleted lines that were inserted unchanged elsewhere.
+///
+/// A block goes on over an edited line when the lines around it moved together.
+fn find_moves(ops: &[DiffOp], old: &[Line], new: &[Line], ignore_case: bool) -> Moves {
+  let mut inserted: HashMap<&str, VecDeque<usize>> = HashMap::new();
+  for n in ops
+    .iter()
+    .filter(|op| !matches!(op, DiffOp::Equal { .. }))
+    .flat_map(|op| op.new_range())
+  {
+    // Lines without letters or digits (separators) are too common to tell a move.
+    if new[n].key.chars().any(char::is_alphanumeric) {
+      inserted
+        .entry(new[n].key.as_str())
+        .or_default()
+        .push_back(n);
+    }
+  }
+  let removed: Vec<usize> = ops
+    .iter()
+    .filter(|op| !matches!(op, DiffOp::Equal { .. }))
+    .flat_map(|op| op.old_range())
+    .collect();
+
+  let mut moves = Moves {
+    blocks: Vec::new(),
+    old: HashMap::new(),
+    new: HashMap::new(),
+    edits: HashMap::new(),
+  };
+  // Last moved (old, new) line pair, to extend its block
+  let mut last: Option<(usize, usize)> = None;
+  for (i, &o) in removed.iter().enumerate() {
+    let next_new = last.filter(|&(lo, _)| lo + 1 == o).map(|(_, ln)| ln + 1);
+    let candidates = inserted.get_mut(old[o].key.as_str());
+
+    // Prefer the line right after the previous moved line, to keep blocks together.
+    let exact = candidates.and_then(|candidates| {
+      let following = next_new.and_then(|nn| candidates.iter().position(|&n| n == nn));
+      candidates
+        .remove(following.unwrap_or(0))
+        .map(|n| (n, following.is_some()))
+    });
+    let (n, following) = match exact {
+      Some(found) => found,
+      None => {
+        // An edited line continues the block if the next old line moved right after it.
+        let edited = next_new.filter(|&nn| {
+          nn < new.len()
+            && !moves.new.contains_key(&nn)
+            && inserted
+              .get(new[nn].key.as_str())
+              .is_some_and(|c| c.contains(&nn))
+            && removed.get(i + 1) == Some(&(o + 1))
+            && new
+              .get(nn + 1)
+              .is_some_and(|next| next.key == old[o + 1].key)
+        });
+        let Some(nn) = edited else {
+          last = None;
+          continue;
+        };
+        let words = diff_words(old[o].text, new[nn].text, ignore_case);
+        if word_similarity(&words) < MIN_CHANGED_LINE_SIMILARITY {
+          last = None;
+          continue;
+        }
+        if let Some(candidates) = inserted.get_mut(new[nn].key.as_str()) {
+          candidates.retain(|&n| n != nn);
+        }
+        moves.edits.insert(nn, (o, words));
+        (nn, true)
+      }
+    };
+
+    match moves.blocks.last_mut() {
+      Some(block) if following => block.line_count += 1,
+      _ => moves.blocks.push(CvDiffMove {
+        id: moves.blocks.len() as u32 + 1,
+        old_line: old[o].number,
+        new_line: new[n].number,
+        line_count: 1,
+      }),
+    }
+    let id = moves.blocks.len() as u32;
+    moves.old.insert(o, id);
+    moves.new.insert(n, id);
+    last = Some((o, n));
+  }
+  moves
+}
+
+fn diff_words(old: &str, new: &str, ignore_case: bool) -> Vec<CvDiffWord> {
+  let old_words: Vec<&str> = old.split_whitespace().collect();
+  let new_words: Vec<&str> = new.split_whitespace().collect();
+  let fold = |words: &[&str]| -> Vec<String> {
+    words
+      .iter()
+      .map(|w| {
+        if ignore_case {
+          w.to_lowercase()
+        } else {
+          w.to_string()
+        }
+      })
+      .collect()
+  };
+  let ops = capture_diff_slices(Algorithm::Myers, &fold(&old_words), &fold(&new_words));
+
+  let mut words = Vec::new();
+  let mut push = |kind: &str, text: &[&str]| {
+    words.extend(text.iter().map(|w| CvDiffWord {
+      kind: kind.to_string(),
+      text: w.to_string(),
+    }))
+  };
+  for op in ops {
+    match op {
+      DiffOp::Equal { .. } => push("equal", &new_words[op.new_range()]),
+      DiffOp::Delete { .. } => push("delete", &old_words[op.old_range()]),
+      DiffOp::Insert { .. } => push("insert", &new_words[op.new_range()]),
+      DiffOp::Replace { .. } => {
+        push("delete", &old_words[op.old_range()]);
+        push("insert", &new_words[op.new_range()]);
+      }
+    }
+  }
+  words
+}
+
+/// Share of the words of both lines that are common.
+fn word_similarity(words: &[CvDiffWord]) -> f64 {
+  let equal = words.iter().filter(|w| w.kind == "equal").count();
+  if words.is_empty() {
+    return 1.0;
+  }
+  2.0 * equal as f64 / (words.len() + equal) as f64
+}
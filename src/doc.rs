@@ -0,0 +1,306 @@
+use std::io::{Cursor, Read};
+
+use encoding_rs::WINDOWS_1252;
+
+use crate::embedded_fonts::FontFamily;
+use crate::typeset::{
+  render_pdf, trim_runs, Block, PageSetup, Paragraph, Run, Table, TableCell, TextStyle,
+};
+
+/// Largest stream read from a compound file, against corrupted sizes.
+const MAX_STREAM_SIZE: u64 = 64 * 1024 * 1024;
+
+/// Magic number of the File Information Block of Word 97 and later.
+const WORD_IDENT: u16 = 0xA5EC;
+
+/// Offset of `fcClx` in `FibRgFcLcb97` (the piece table location).
+const FC_CLX_INDEX: usize = 33;
+
+/// Render a Word 97-2003 document to PDF, on A4 pages.
+pub(crate) fn doc_to_pdf(bytes: &[u8]) -> Result<Vec<u8>, String> {
+  let blocks = read_doc(bytes)?;
+  Ok(render_pdf(
+    &blocks,
+    &PageSetup::default(),
+    &FontFamily::dejavu_sans(),
+  ))
+}
+
+/// Content of a Word 97-2003 binary document (`.doc`).
+///
+/// The text of the main document is read through the piece table of the
+/// `WordDocument` stream, inside the OLE compound file; field instructions are
+/// dropped and their results kept. Paragraphs and table rows are kept, but not
+/// the character formatting, headers, footers, footnotes or pictures.
+pub(crate) fn read_doc(bytes: &[u8]) -> Result<Vec<Block>, String> {
+  Ok(text_blocks(&read_doc_text(bytes)?))
+}
+
+/// Text of the main document, with Word's control characters (paragraph and
+/// cell marks) left in place.
+fn read_doc_text(bytes: &[u8]) -> Result<String, String> {
+  let mut file = cfb::CompoundFile::open(Cursor::new(bytes)).map_err(|e| e.to_string())?;
+  let word = read_stream(&mut file, "/WordDocument")?;
+  let fib = Fib::parse(&word)?;
+  if fib.encrypted {
+    return Err("document is encrypted".to_string());
+  }
+  let table_name = if fib.table_1 { "/1Table" } else { "/0Table" };
+  let table = read_stream(&mut file, table_name).unwrap_or_default();
+
+  let mut text = match fib
+    .clx
+    .and_then(|(fc, lcb)| table.get(fc..fc.checked_add(lcb)?))
+  {
+    Some(clx) => piece_table_text(&word, clx, fib.text_length)?,
+    // Word 95 and older, or documents saved without a piece table: the text
+    // is stored contiguously, one byte per character.
+    None => {
+      let text = word
+        .get(fib.fc_min..fib.fc_mac)
+        .ok_or("text out of range")?;
+      WINDOWS_1252
+        .decode_without_bom_handling(text)
+        .0
+        .into_owned()
+    }
+  };
+  if let Some(end) = text.char_indices().nth(fib.text_length).map(|(i, _)| i) {
+    text.truncate(end);
+  }
+  Ok(text)
+}
+
+fn read_stream(file: &mut cfb::CompoundFile<Cursor<&[u8]>>, name: &str) -> Result<Vec<u8>, String> {
+  let stream = file
+    .open_stream(name)
+    .map_err(|_| format!("missing {} stream", name.trim_start_matches('/')))?;
+  let mut data = Vec::new();
+  stream
+    .take(MAX_STREAM_SIZE)
+    .read_to_end(&mut data)
+    .map_err(|e| e.to_string())?;
+  Ok(data)
+}
+
+/// The parts of the File Information Block needed to find the text.
+struct Fib {
+  encrypted: bool,
+  /// Whether the table stream is `1Table` (else `0Table`)
+  table_1: bool,
+  /// Number of characters of the main document (`ccpText`)
+  text_length: usize,
+  /// Offset and size of the piece table (`Clx`) in the table stream
+  clx: Option<(usize, usize)>,
+  /// Start and end of the text in the WordDocument stream (older formats)
+  fc_min: usize,
+  fc_mac: usize,
+}
+
+impl Fib {
+  fn parse(word: &[u8]) -> Result<Fib, String> {
+    let u16_at = |offset: usize| -> Option<u16> {
+      Some(u16::from_le_bytes(
+        word.get(offset..offset + 2)?.try_into().ok()?,
+      ))
+    };
+    let u32_at = |offset: usize| -> Option<usize> {
+      Some(u32::from_le_bytes(word.get(offset..offset + 4)?.try_into().ok()?) as usize)
+    };
+    if u16_at(0) != Some(WORD_IDENT) {
+      return Err("not a Word document".to_string());
+    }
+    let flags = u16_at(0x0A).ok_or("truncated FIB")?;
+    let fc_min = u32_at(0x18).ok_or("truncated FIB")?;
+    let fc_mac = u32_at(0x1C).ok_or("truncated FIB")?;
+
+    // Variable-size parts of the FIB: FibRgW, FibRgLw, then FibRgFcLcb.
+    let mut offset = 32;
+    let csw = u16_at(offset).unwrap_or(0) as usize;
+    offset += 2 + csw * 2;
+    let cslw = u16_at(offset).unwrap_or(0) as usize;
+    let rg_lw = offset + 2;
+    offset = rg_lw + cslw * 4;
+    let cb_rg_fc_lcb = u16_at(offset).unwrap_or(0) as usize;
+    let rg_fc_lcb = offset + 2;
+
+    // ccpText is the fourth field of FibRgLw97.
+    let text_length = (cslw > 3)
+      .then(|| u32_at(rg_lw + 12))
+      .flatten()
+      .unwrap_or(fc_mac.saturating_sub(fc_min));
+    let clx = (cb_rg_fc_lcb > FC_CLX_INDEX)
+      .then(|| {
+        let fc = u32_at(rg_fc_lcb + FC_CLX_INDEX * 8)?;
+        let lcb = u32_at(rg_fc_lcb + FC_CLX_INDEX * 8 + 4)?;
+        (lcb > 0).then_some((fc, lcb))
+      })
+      .flatten();
+
+    Ok(Fib {
+      encrypted: flags & 0x0100 != 0,
+      table_1: flags & 0x0200 != 0,
+      text_length,
+      clx,
+      fc_min,
+      fc_mac,
+    })
+  }
+}
+
+/// Text of the pieces of the piece table in `clx`, up to `text_length` characters.
+fn piece_table_text(word: &[u8], clx: &[u8], text_length: usize) -> Result<String, String> {
+  // Skip the property modifiers (Prc) before the piece table (Pcdt).
+  let mut offset = 0;
+  while clx.get(offset) == Some(&0x01) {
+    let size = clx
+      .get(offset + 1..offset + 3)
+      .map(|b| i16::from_le_bytes([b[0], b[1]]).max(0) as usize)
+      .ok_or("truncated piece table")?;
+    offset += 3 + size;
+  }
+  if clx.get(offset) != Some(&0x02) {
+    return Err("missing piece table".to_string());
+  }
+  let size = clx
+    .get(offset + 1..offset + 5)
+    .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]) as usize)
+    .ok_or("truncated piece table")?;
+  let plc = clx
+    .get(offset + 5..offset + 5 + size)
+    .ok_or("truncated piece table")?;
+
+  // PlcPcd: n + 1 character positions, then n 8-byte piece descriptors.
+  let count = size.saturating_sub(4) / 12;
+  let cp = |i: usize| u32::from_le_bytes(plc[i * 4..i * 4 + 4].try_into().unwrap()) as usize;
+  let mut text = String::new();
+  for i in 0..count {
+    let (start, end) = (cp(i), cp(i + 1));
+    if start >= text_length {
+      break;
+    }
+    let length = end.saturating_sub(start).min(text_length - start);
+    let pcd = &plc[(count + 1) * 4 + i * 8..(count + 1) * 4 + i * 8 + 8];
+    let fc = u32::from_le_bytes(pcd[2..6].try_into().unwrap());
+    let compressed = fc & 0x4000_0000 != 0;
+    let fc = (fc & 0x3FFF_FFFF) as usize;
+    if compressed {
+      // One byte per character, in Windows-1252, at half the offset
+      let start = fc / 2;
+      if let Some(bytes) = word.get(start..start + length) {
+        text.push_str(&WINDOWS_1252.decode_without_bom_handling(bytes).0);
+      }
+    } else if let Some(bytes) = word.get(fc..fc + length * 2) {
+      let units: Vec<u16> = bytes
+        .chunks_exact(2)
+        .map(|b| u16::from_le_bytes([b[0], b[1]]))
+        .collect();
+      text.push_str(&String::from_utf16_lossy(&units));
+    }
+  }
+  Ok(text)
+}
+
+/// Blocks of Word text: `\r` ends a paragraph, `\x07` a table cell (a second one
+/// in a row ends the row), `\x0C` is a page or section break, `\x0B` a line
+/// break, and fields keep their result only.
+fn text_blocks(text: &str) -> Vec<Block> {
+  let style = TextStyle::default();
+  let mut blocks = Vec::new();
+  let mut line = String::new();
+  let mut cell_blocks = Vec::new();
+  let mut row = Vec::new();
+  let mut rows: Vec<Vec<TableCell>> = Vec::new();
+  // Field nesting: whether each open field is still in its instructions
+  let mut fields: Vec<bool> = Vec::new();
+  let mut previous = '\0';
+
+  let paragraph = |line: &mut String| {
+    let mut runs = vec![Run {
+      text: std::mem::take(line),
+      style: style.clone(),
+    }];
+    trim_runs(&mut runs);
+    if runs.is_empty() {
+      runs.push(Run {
+        text: String::new(),
+        style: style.clone(),
+      });
+    }
+    Block::Paragraph(Paragraph {
+      runs,
+      space_after: 4.0,
+      ..Paragraph::default()
+    })
+  };
+  let flush_table = |rows: &mut Vec<Vec<TableCell>>, blocks: &mut Vec<Block>| {
+    if !rows.is_empty() {
+      blocks.push(Block::Table(Table {
+        columns: Vec::new(),
+        rows: std::mem::take(rows),
+        borders: None,
+        indent_left: 0.0,
+      }));
+    }
+  };
+
+  for c in text.chars() {
+    match c {
+      '\u{13}' => fields.push(true),
+      '\u{14}' => {
+        if let Some(instructions) = fields.last_mut() {
+          *instructions = false;
+        }
+      }
+      '\u{15}' => {
+        fields.pop();
+      }
+      _ if fields.last() == Some(&true) => {}
+      '\r' => {
+        let block = paragraph(&mut line);
+        if row.is_empty() && cell_blocks.is_empty() {
+          flush_table(&mut rows, &mut blocks);
+          blocks.push(block);
+        } else {
+          cell_blocks.push(block);
+        }
+      }
+      '\u{07}' => {
+        if previous == '\u{07}' && line.is_empty() && cell_blocks.is_empty() {
+          rows.push(std::mem::take(&mut row));
+        } else {
+          if !line.is_empty() {
+            cell_blocks.push(paragraph(&mut line));
+          }
+          row.push(TableCell {
+            blocks: std::mem::take(&mut cell_blocks),
+            span: 1,
+            background: None,
+          });
+        }
+      }
+      '\u{0C}' => {
+        if !line.is_empty() {
+          blocks.push(paragraph(&mut line));
+        }
+        flush_table(&mut rows, &mut blocks);
+        blocks.push(Block::PageBreak);
+      }
+      '\u{0B}' => line.push('\n'),
+      '\t' => line.push_str("    "),
+      '\u{1E}' => line.push('-'),
+      '\u{1F}' => {}
+      c if c.is_control() => {}
+      c => line.push(c),
+    }
+    previous = c;
+  }
+  if !row.is_empty() {
+    rows.push(row);
+  }
+  flush_table(&mut rows, &mut blocks);
+  if !line.is_empty() {
+    blocks.push(paragraph(&mut line));
+  }
+  blocks
+}
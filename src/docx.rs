@@ -0,0 +1,1091 @@
+use std::collections::HashMap;
+
+use roxmltree::{Document, Node};
+
+use crate::embedded_fonts::FontFamily;
+use crate::package::{resolve_part_path, Package};
+use crate::typeset::{
+  render_pdf, Align, Block, PageSetup, Paragraph, Picture, Rgb, Run, Table, TableCell, TextStyle,
+  BLACK,
+};
+use crate::utils::{format_number, hex_color};
+
+const W_NS: &str = "http://schemas.openxmlformats.org/wordprocessingml/2006/main";
+const R_NS: &str = "http://schemas.openxmlformats.org/officeDocument/2006/relationships";
+
+/// Maximum depth of style inheritance and nested tables, against cycles.
+const MAX_DEPTH: usize = 16;
+
+/// Default font size when the document declares none (Word's own default).
+const DEFAULT_FONT_SIZE: f64 = 10.0;
+
+/// Render a DOCX (Office Open XML) document as a PDF.
+///
+/// Paragraphs, headings, numbered and bulleted lists, tables, text boxes, bold,
+/// italic, underline, colors and images are laid out on the page size and margins
+/// of the document, in DejaVu Sans (embedded). Headers, footers, footnotes and
+/// floating positions are not reproduced.
+pub(crate) fn docx_to_pdf(bytes: &[u8]) -> Result<Vec<u8>, String> {
+  let package = Package::open(bytes)?;
+  let (blocks, page) = read_docx(&package)?;
+  Ok(render_pdf(&blocks, &page, &FontFamily::dejavu_sans()))
+}
+
+/// Content and page setup of a DOCX package.
+pub(crate) fn read_docx(package: &Package) -> Result<(Vec<Block>, PageSetup), String> {
+  let document_path = main_document_path(package);
+  let xml = package
+    .read_string(&document_path)
+    .ok_or_else(|| format!("missing {document_path}"))?;
+  let doc = parse_xml(&xml)?;
+  let body = doc
+    .root_element()
+    .children()
+    .find(|n| is_w(n, "body"))
+    .ok_or("missing document body")?;
+
+  let (dir, name) = document_path
+    .rsplit_once('/')
+    .unwrap_or(("", document_path.as_str()));
+  let rels = relationships(package, dir, &format!("{dir}/_rels/{name}.rels"));
+  let styles = part_target(&rels, "/styles")
+    .and_then(|path| package.read_string(path))
+    .map(|xml| Styles::parse(&xml))
+    .unwrap_or_default();
+  let numbering = part_target(&rels, "/numbering")
+    .and_then(|path| package.read_string(path))
+    .map(|xml| Numbering::parse(&xml))
+    .unwrap_or_default();
+
+  let mut reader = DocxReader {
+    package,
+    rels,
+    styles,
+    numbering,
+    counters: HashMap::new(),
+  };
+  let blocks = reader.blocks(body, 0);
+  let page = body
+    .children()
+    .rev()
+    .find(|n| is_w(n, "sectPr"))
+    .map(page_setup)
+    .unwrap_or_default();
+  Ok((blocks, page))
+}
+
+fn parse_xml(xml: &str) -> Result<Document<'_>, String> {
+  Document::parse_with_options(
+    xml,
+    roxmltree::ParsingOptions {
+      allow_dtd: true,
+      ..Default::default()
+    },
+  )
+  .map_err(|e| e.to_string())
+}
+
+/// Path of the main document part, from the package relationships.
+fn main_document_path(package: &Package) -> String {
+  let rels = relationships(package, "", "_rels/.rels");
+  part_target(&rels, "/officeDocument")
+    .cloned()
+    .unwrap_or_else(|| "word/document.xml".to_string())
+}
+
+/// A relationship of a part: its type and resolved target path.
+struct Relationship {
+  kind: String,
+  target: String,
+}
+
+/// Relationships of a part by id; external targets are left out.
+fn relationships(package: &Package, base_dir: &str, path: &str) -> HashMap<String, Relationship> {
+  let Some(xml) = package.read_string(path) else {
+    return HashMap::new();
+  };
+  let Ok(doc) = parse_xml(&xml) else {
+    return HashMap::new();
+  };
+  doc
+    .descendants()
+    .filter(|n| n.tag_name().name() == "Relationship")
+    .filter(|n| n.attribute("TargetMode") != Some("External"))
+    .filter_map(|n| {
+      Some((
+        n.attribute("Id")?.to_string(),
+        Relationship {
+          kind: n.attribute("Type")?.to_string(),
+          target: resolve_part_path(base_dir, n.attribute("Target")?),
+        },
+      ))
+    })
+    .collect()
+}
+
+fn part_target<'r>(
+  rels: &'r HashMap<String, Relationship>,
+  kind_suffix: &str,
+) -> Option<&'r String> {
+  rels
+    .values()
+    .find(|rel| rel.kind.ends_with(kind_suffix))
+    .map(|rel| &rel.target)
+}
+
+fn is_w(node: &Node, name: &str) -> bool {
+  node.is_element() && node.tag_name().name() == name && node.tag_name().namespace() == Some(W_NS)
+}
+
+fn w_child<'a, 'i>(node: Node<'a, 'i>, name: &str) -> Option<Node<'a, 'i>> {
+  node.children().find(|n| is_w(n, name))
+}
+
+fn w_attr<'a>(node: Node<'a, '_>, name: &str) -> Option<&'a str> {
+  node.attribute((W_NS, name))
+}
+
+fn w_val<'a>(node: Node<'a, '_>) -> Option<&'a str> {
+  w_attr(node, "val")
+}
+
+/// Numeric attribute of a child element (twips, half-points, …).
+fn w_number(node: Node, child: &str, attr: &str) -> Option<f64> {
+  w_child(node, child)
+    .and_then(|c| w_attr(c, attr))
+    .and_then(|v| v.parse().ok())
+}
+
+/// On/off property (`<w:b/>`, `<w:b w:val="0"/>`).
+fn toggle(node: Node, name: &str) -> Option<bool> {
+  w_child(node, name).map(|n| !matches!(w_val(n), Some("0" | "false" | "off" | "none")))
+}
+
+fn twips(value: f64) -> f64 {
+  value / 20.0
+}
+
+/// Character properties; `None` is inherited.
+#[derive(Clone, Debug, Default)]
+struct RunProps {
+  bold: Option<bool>,
+  italic: Option<bool>,
+  underline: Option<bool>,
+  strike: Option<bool>,
+  caps: Option<bool>,
+  hidden: Option<bool>,
+  size: Option<f64>,
+  color: Option<Rgb>,
+}
+
+impl RunProps {
+  fn parse(rpr: Node) -> RunProps {
+    RunProps {
+      bold: toggle(rpr, "b"),
+      italic: toggle(rpr, "i"),
+      underline: w_child(rpr, "u").map(|u| !matches!(w_val(u), Some("none" | "0"))),
+      strike: toggle(rpr, "strike").or(toggle(rpr, "dstrike")),
+      caps: toggle(rpr, "caps"),
+      hidden: toggle(rpr, "vanish"),
+      // Half-points
+      size: w_number(rpr, "sz", "val").map(|v| v / 2.0),
+      color: w_child(rpr, "color").and_then(w_val).and_then(hex_color),
+    }
+  }
+
+  fn merge(&mut self, other: &RunProps) {
+    self.bold = other.bold.or(self.bold);
+    self.italic = other.italic.or(self.italic);
+    self.underline = other.underline.or(self.underline);
+    self.strike = other.strike.or(self.strike);
+    self.caps = other.caps.or(self.caps);
+    self.hidden = other.hidden.or(self.hidden);
+    self.size = other.size.or(self.size);
+    self.color = other.color.or(self.color);
+  }
+
+  fn text_style(&self) -> TextStyle {
+    TextStyle {
+      bold: self.bold.unwrap_or(false),
+      italic: self.italic.unwrap_or(false),
+      underline: self.underline.unwrap_or(false),
+      strike: self.strike.unwrap_or(false),
+      size: self.size.unwrap_or(DEFAULT_FONT_SIZE).max(1.0),
+      color: self.color.unwrap_or(BLACK),
+    }
+  }
+}
+
+/// Line spacing of a paragraph.
+#[derive(Clone, Copy, Debug)]
+enum LineSpacing {
+  /// Multiple of single spacing
+  Auto(f64),
+  /// Fixed (or minimum) line height in points
+  Exact(f64),
+  AtLeast(f64),
+}
+
+/// Paragraph properties; `None` is inherited.
+#[derive(Clone, Debug, Default)]
+struct ParaProps {
+  align: Option<Align>,
+  space_before: Option<f64>,
+  space_after: Option<f64>,
+  line_spacing: Option<LineSpacing>,
+  contextual_spacing: Option<bool>,
+  indent_left: Option<f64>,
+  indent_right: Option<f64>,
+  first_line_indent: Option<f64>,
+  keep_with_next: Option<bool>,
+  page_break_before: Option<bool>,
+  num_id: Option<String>,
+  level: Option<usize>,
+  outline_level: Option<usize>,
+  border_bottom: Option<Option<Rgb>>,
+}
+
+impl ParaProps {
+  fn parse(ppr: Node) -> ParaProps {
+    let spacing = w_child(ppr, "spacing");
+    let number = |node: Option<Node>, attr: &str| {
+      node
+        .and_then(|n| w_attr(n, attr))
+        .and_then(|v| v.parse::<f64>().ok())
+    };
+    let line_spacing =
+      number(spacing, "line").map(|line| match spacing.and_then(|s| w_attr(s, "lineRule")) {
+        Some("exact") => LineSpacing::Exact(twips(line)),
+        Some("atLeast") => LineSpacing::AtLeast(twips(line)),
+        _ => LineSpacing::Auto(line / 240.0),
+      });
+    let ind = w_child(ppr, "ind");
+    let indent = |names: &[&str]| names.iter().find_map(|name| number(ind, name)).map(twips);
+    let first_line_indent = indent(&["hanging"])
+      .map(|hanging| -hanging)
+      .or_else(|| indent(&["firstLine"]));
+    let num_pr = w_child(ppr, "numPr");
+    let border_bottom = w_child(ppr, "pBdr")
+      .and_then(|b| w_child(b, "bottom"))
+      .map(|bottom| match w_val(bottom) {
+        Some("none" | "nil") => None,
+        _ => Some(w_attr(bottom, "color").and_then(hex_color).unwrap_or(BLACK)),
+      });
+
+    ParaProps {
+      align: w_child(ppr, "jc").and_then(w_val).map(|jc| match jc {
+        "center" => Align::Center,
+        "right" | "end" => Align::Right,
+        "both" | "distribute" => Align::Justify,
+        _ => Align::Left,
+      }),
+      space_before: number(spacing, "before").map(twips),
+      space_after: number(spacing, "after").map(twips),
+      line_spacing,
+      contextual_spacing: toggle(ppr, "contextualSpacing"),
+      indent_left: indent(&["left", "start"]),
+      indent_right: indent(&["right", "end"]),
+      first_line_indent,
+      keep_with_next: toggle(ppr, "keepNext"),
+      page_break_before: toggle(ppr, "pageBreakBefore"),
+      num_id: num_pr
+        .and_then(|n| w_child(n, "numId"))
+        .and_then(w_val)
+        .map(str::to_string),
+      level: num_pr
+        .and_then(|n| w_child(n, "ilvl"))
+        .and_then(w_val)
+        .and_then(|v| v.parse().ok()),
+      outline_level: w_child(ppr, "outlineLvl")
+        .and_then(w_val)
+        .and_then(|v| v.parse().ok()),
+      border_bottom,
+    }
+  }
+
+  fn merge(&mut self, other: &ParaProps) {
+    self.align = other.align.or(self.align);
+    self.space_before = other.space_before.or(self.space_before);
+    self.space_after = other.space_after.or(self.space_after);
+    self.line_spacing = other.line_spacing.or(self.line_spacing);
+    self.contextual_spacing = other.contextual_spacing.or(self.contextual_spacing);
+    self.indent_left = other.indent_left.or(self.indent_left);
+    self.indent_right = other.indent_right.or(self.indent_right);
+    self.first_line_indent = other.first_line_indent.or(self.first_line_indent);
+    self.keep_with_next = other.keep_with_next.or(self.keep_with_next);
+    self.page_break_before = other.page_break_before.or(self.page_break_before);
+    self.num_id = other.num_id.clone().or(self.num_id.take());
+    self.level = other.level.or(self.level);
+    self.outline_level = other.outline_level.or(self.outline_level);
+    self.border_bottom = other.border_bottom.or(self.border_bottom);
+  }
+}
+
+struct StyleDef {
+  based_on: Option<String>,
+  paragraph: ParaProps,
+  run: RunProps,
+  /// Whether the (table) style draws cell borders
+  table_borders: Option<bool>,
+}
+
+/// Styles of `word/styles.xml`.
+#[derive(Default)]
+struct Styles {
+  defs: HashMap<String, StyleDef>,
+  default_paragraph: ParaProps,
+  default_run: RunProps,
+  /// Style of paragraphs without `pStyle` ("Normal")
+  normal: Option<String>,
+  /// Style of tables without `tblStyle`
+  normal_table: Option<String>,
+}
+
+impl Styles {
+  fn parse(xml: &str) -> Styles {
+    let mut styles = Styles::default();
+    let Ok(doc) = parse_xml(xml) else {
+      return styles;
+    };
+    let root = doc.root_element();
+    if let Some(defaults) = w_child(root, "docDefaults") {
+      if let Some(rpr) = w_child(defaults, "rPrDefault").and_then(|d| w_child(d, "rPr")) {
+        styles.default_run = RunProps::parse(rpr);
+      }
+      if let Some(ppr) = w_child(defaults, "pPrDefault").and_then(|d| w_child(d, "pPr")) {
+        styles.default_paragraph = ParaProps::parse(ppr);
+      }
+    }
+    for style in root.children().filter(|n| is_w(n, "style")) {
+      let Some(id) = w_attr(style, "styleId") else {
+        continue;
+      };
+      let kind = w_attr(style, "type").unwrap_or("paragraph");
+      if w_attr(style, "default").is_some_and(|d| d == "1" || d == "true") {
+        match kind {
+          "paragraph" => styles.normal = Some(id.to_string()),
+          "table" => styles.normal_table = Some(id.to_string()),
+          _ => {}
+        }
+      }
+      let mut paragraph = w_child(style, "pPr")
+        .map(ParaProps::parse)
+        .unwrap_or_default();
+      // Heading styles without an outline level, from their name ("heading 2").
+      let name = w_child(style, "name")
+        .and_then(w_val)
+        .unwrap_or(id)
+        .to_lowercase();
+      if paragraph.outline_level.is_none() {
+        paragraph.outline_level = heading_level(&name);
+      }
+      styles.defs.insert(
+        id.to_string(),
+        StyleDef {
+          based_on: w_child(style, "basedOn")
+            .and_then(w_val)
+            .map(str::to_string),
+          paragraph,
+          run: w_child(style, "rPr")
+            .map(RunProps::parse)
+            .unwrap_or_default(),
+          table_borders: w_child(style, "tblPr")
+            .and_then(|t| w_child(t, "tblBorders"))
+            .map(has_borders),
+        },
+      );
+    }
+    styles
+  }
+
+  /// A style followed by the styles it is based on.
+  fn chain(&self, id: Option<&str>) -> Vec<&StyleDef> {
+    let mut chain = Vec::new();
+    let mut next = id;
+    while let Some(def) = next.and_then(|id| self.defs.get(id)) {
+      if chain.len() >= MAX_DEPTH {
+        break;
+      }
+      chain.push(def);
+      next = def.based_on.as_deref();
+    }
+    chain
+  }
+
+  /// Character properties set by a character style, without the document defaults.
+  fn run_style(&self, id: &str) -> RunProps {
+    let mut run = RunProps::default();
+    for def in self.chain(Some(id)).iter().rev() {
+      run.merge(&def.run);
+    }
+    run
+  }
+
+  /// Properties of a style, including the styles it is based on.
+  fn resolve(&self, id: Option<&str>) -> (ParaProps, RunProps, Option<bool>) {
+    let chain = self.chain(id);
+    let mut paragraph = self.default_paragraph.clone();
+    let mut run = self.default_run.clone();
+    let mut borders = None;
+    for def in chain.iter().rev() {
+      paragraph.merge(&def.paragraph);
+      run.merge(&def.run);
+      borders = def.table_borders.or(borders);
+    }
+    if chain.is_empty() {
+      // Documents without styles.xml still use the built-in heading style ids.
+      if let Some(level) = id.and_then(|id| heading_level(&id.to_lowercase())) {
+        paragraph.outline_level = Some(level);
+        paragraph.keep_with_next = Some(true);
+        paragraph.space_before = Some(12.0);
+        run.bold = Some(true);
+        run.size = Some([20.0, 16.0, 13.0, 12.0][level.min(3)]);
+      }
+    }
+    (paragraph, run, borders)
+  }
+}
+
+/// Outline level (0 for the first level) of a heading style name or id.
+fn heading_level(name: &str) -> Option<usize> {
+  if name == "title" || name == "titre" {
+    return Some(0);
+  }
+  let rest = ["heading", "titre", "überschrift", "titolo", "título", "kop"]
+    .iter()
+    .find_map(|prefix| name.strip_prefix(prefix))?;
+  let level: usize = rest.trim().parse().ok()?;
+  (1..=9).contains(&level).then(|| level - 1)
+}
+
+fn has_borders(borders: Node) -> bool {
+  borders
+    .children()
+    .filter(|n| n.is_element())
+    .any(|b| !matches!(w_val(b), Some("none" | "nil")))
+}
+
+/// A level of a list definition.
+#[derive(Clone, Debug, Default)]
+struct ListLevel {
+  /// `decimal`, `bullet`, `lowerLetter`, …
+  format: String,
+  /// Marker template: `%1.` for numbers, the bullet character for bullets
+  text: String,
+  start: u32,
+  paragraph: ParaProps,
+  run: RunProps,
+}
+
+/// List definitions of `word/numbering.xml`.
+#[derive(Default)]
+struct Numbering {
+  /// numId → abstract list levels
+  lists: HashMap<String, Vec<ListLevel>>,
+}
+
+impl Numbering {
+  fn parse(xml: &str) -> Numbering {
+    let mut numbering = Numbering::default();
+    let Ok(doc) = parse_xml(xml) else {
+      return numbering;
+    };
+    let root = doc.root_element();
+    let parse_levels = |node: Node| -> Vec<ListLevel> {
+      let mut levels = vec![ListLevel::default(); 9];
+      for lvl in node.children().filter(|n| is_w(n, "lvl")) {
+        let Some(index) = w_attr(lvl, "ilvl").and_then(|i| i.parse::<usize>().ok()) else {
+          continue;
+        };
+        if index >= levels.len() {
+          continue;
+        }
+        levels[index] = ListLevel {
+          format: w_child(lvl, "numFmt")
+            .and_then(w_val)
+            .unwrap_or("decimal")
+            .to_string(),
+          text: w_child(lvl, "lvlText")
+            .and_then(w_val)
+            .unwrap_or("")
+            .to_string(),
+          start: w_child(lvl, "start")
+            .and_then(w_val)
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(1),
+          paragraph: w_child(lvl, "pPr")
+            .map(ParaProps::parse)
+            .unwrap_or_default(),
+          run: w_child(lvl, "rPr").map(RunProps::parse).unwrap_or_default(),
+        };
+      }
+      levels
+    };
+
+    let abstracts: HashMap<&str, Vec<ListLevel>> = root
+      .children()
+      .filter(|n| is_w(n, "abstractNum"))
+      .filter_map(|n| Some((w_attr(n, "abstractNumId")?, parse_levels(n))))
+      .collect();
+    for num in root.children().filter(|n| is_w(n, "num")) {
+      let (Some(id), Some(abstract_id)) = (
+        w_attr(num, "numId"),
+        w_child(num, "abstractNumId").and_then(w_val),
+      ) else {
+        continue;
+      };
+      let Some(mut levels) = abstracts.get(abstract_id).cloned() else {
+        continue;
+      };
+      for over in num.children().filter(|n| is_w(n, "lvlOverride")) {
+        let Some(index) = w_attr(over, "ilvl").and_then(|i| i.parse::<usize>().ok()) else {
+          continue;
+        };
+        if let Some(level) = levels.get_mut(index) {
+          if let Some(start) = w_child(over, "startOverride")
+            .and_then(w_val)
+            .and_then(|v| v.parse().ok())
+          {
+            level.start = start;
+          }
+        }
+      }
+      numbering.lists.insert(id.to_string(), levels);
+    }
+    numbering
+  }
+}
+
+/// Marker of a list item: the level's template with the counters of the levels.
+fn list_marker(level: &ListLevel, levels: &[ListLevel], counters: &[u32]) -> String {
+  if level.format == "bullet" {
+    return bullet_char(&level.text);
+  }
+  if level.format == "none" {
+    return String::new();
+  }
+  let mut marker = level.text.clone();
+  for (i, counter) in counters.iter().enumerate().rev() {
+    let placeholder = format!("%{}", i + 1);
+    if marker.contains(&placeholder) {
+      let format = levels
+        .get(i)
+        .map(|l| l.format.as_str())
+        .unwrap_or("decimal");
+      marker = marker.replace(&placeholder, &format_number(*counter, format));
+    }
+  }
+  marker
+}
+
+/// Bullets are often symbol font characters (private use area) that DejaVu lacks.
+fn bullet_char(text: &str) -> String {
+  let mapped: String = text
+    .chars()
+    .map(|c| match c {
+      '\u{F0A7}' | '\u{F0A8}' | '\u{F06E}' | '§' => '▪',
+      '\u{F0FC}' => '✓',
+      '\u{F0D8}' | '\u{F0E0}' => '➢',
+      'o' => '◦',
+      '\u{E000}'..='\u{F8FF}' => '•',
+      c => c,
+    })
+    .collect();
+  if mapped.trim().is_empty() {
+    "•".to_string()
+  } else {
+    mapped
+  }
+}
+
+/// Page size and margins of a section.
+fn page_setup(sect_pr: Node) -> PageSetup {
+  let default = PageSetup::default();
+  let number = |child: &str, attr: &str| {
+    w_number(sect_pr, child, attr)
+      .map(|v| twips(v.abs()))
+      .filter(|v| v.is_finite())
+  };
+  let (width, height) = match (number("pgSz", "w"), number("pgSz", "h")) {
+    (Some(w), Some(h)) if w >= 144.0 && h >= 144.0 => (w, h),
+    _ => (default.width, default.height),
+  };
+  // Margins larger than a third of the page are ignored.
+  let margin = |attr: &str, extent: f64, fallback: f64| {
+    number("pgMar", attr)
+      .filter(|m| *m <= extent / 3.0)
+      .unwrap_or(fallback)
+  };
+  PageSetup {
+    width,
+    height,
+    margin_top: margin("top", height, default.margin_top),
+    margin_right: margin("right", width, default.margin_right),
+    margin_bottom: margin("bottom", height, default.margin_bottom),
+    margin_left: margin("left", width, default.margin_left),
+  }
+}
+
+struct DocxReader<'p, 'a> {
+  package: &'p Package<'a>,
+  rels: HashMap<String, Relationship>,
+  styles: Styles,
+  numbering: Numbering,
+  /// Current number of every list level, by numId
+  counters: HashMap<String, [u32; 9]>,
+}
+
+/// Content of a paragraph, split where pictures and page breaks interrupt the text.
+#[derive(Default)]
+struct ParagraphContent {
+  parts: Vec<Part>,
+  /// Blocks of text boxes anchored in the paragraph
+  text_boxes: Vec<Block>,
+}
+
+enum Part {
+  Runs(Vec<Run>),
+  Picture(Picture),
+  PageBreak,
+}
+
+impl ParagraphContent {
+  fn push_run(&mut self, run: Run) {
+    match self.parts.last_mut() {
+      Some(Part::Runs(runs)) => runs.push(run),
+      _ => self.parts.push(Part::Runs(vec![run])),
+    }
+  }
+}
+
+impl<'p, 'a> DocxReader<'p, 'a> {
+  /// Block-level content of the body, a table cell or a text box.
+  fn blocks(&mut self, parent: Node, depth: usize) -> Vec<Block> {
+    let mut blocks = Vec::new();
+    if depth > MAX_DEPTH {
+      return blocks;
+    }
+    // Style and contextual spacing of the previous paragraph
+    let mut previous: Option<(Option<String>, bool)> = None;
+    for child in parent.children().filter(|n| n.is_element()) {
+      if child.tag_name().namespace() != Some(W_NS) {
+        continue;
+      }
+      match child.tag_name().name() {
+        "p" => {
+          let style_id = w_child(child, "pPr")
+            .and_then(|ppr| w_child(ppr, "pStyle"))
+            .and_then(w_val)
+            .map(str::to_string)
+            .or_else(|| self.styles.normal.clone());
+          let start = blocks.len();
+          let contextual = self.paragraph(child, style_id.as_deref(), &mut blocks, depth);
+          // Contextual spacing: no space between paragraphs of the same style.
+          if let Some((previous_style, previous_contextual)) = previous.take() {
+            if previous_style == style_id && (contextual || previous_contextual) {
+              if let Some(Block::Paragraph(p)) = blocks.get_mut(start) {
+                p.space_before = 0.0;
+              }
+              if let Some(Block::Paragraph(p)) =
+                start.checked_sub(1).and_then(|i| blocks.get_mut(i))
+              {
+                p.space_after = 0.0;
+              }
+            }
+          }
+          previous = Some((style_id, contextual));
+        }
+        "tbl" => {
+          blocks.extend(self.table(child, depth));
+          previous = None;
+        }
+        "sdt" => {
+          if let Some(content) = w_child(child, "sdtContent") {
+            blocks.extend(self.blocks(content, depth + 1));
+          }
+          previous = None;
+        }
+        "customXml" | "ins" | "moveTo" | "sdtContent" => {
+          blocks.extend(self.blocks(child, depth + 1));
+          previous = None;
+        }
+        _ => {}
+      }
+    }
+    blocks
+  }
+
+  /// Add the blocks of a paragraph; returns whether it uses contextual spacing.
+  fn paragraph(
+    &mut self,
+    node: Node,
+    style_id: Option<&str>,
+    blocks: &mut Vec<Block>,
+    depth: usize,
+  ) -> bool {
+    let ppr = w_child(node, "pPr");
+    let (mut props, style_run, _) = self.styles.resolve(style_id);
+    let direct = ppr.map(ParaProps::parse).unwrap_or_default();
+
+    // Numbering: the level's indentation sits between the style and direct formatting.
+    let num_id = direct.num_id.clone().or(props.num_id.clone());
+    let level_index = direct.level.or(props.level).unwrap_or(0).min(8);
+    let list_level = num_id
+      .as_deref()
+      .filter(|id| *id != "0")
+      .and_then(|id| self.numbering.lists.get(id).map(|levels| (id, levels)));
+    let mut marker = None;
+    if let Some((id, levels)) = list_level {
+      let level = &levels[level_index];
+      props.merge(&level.paragraph);
+      let counters = self
+        .counters
+        .entry(id.to_string())
+        .or_insert_with(|| [0; 9]);
+      counters[level_index] = if counters[level_index] == 0 {
+        level.start
+      } else {
+        counters[level_index] + 1
+      };
+      // Restart deeper levels, and start skipped shallower ones.
+      for (i, counter) in counters.iter_mut().enumerate() {
+        if i > level_index {
+          *counter = 0;
+        } else if *counter == 0 {
+          *counter = levels[i].start;
+        }
+      }
+      let text = list_marker(level, levels, &counters[..=level_index]);
+      if !text.is_empty() {
+        let mut run = style_run.clone();
+        run.merge(&level.run);
+        marker = Some(Run {
+          text,
+          style: run.text_style(),
+        });
+      }
+    }
+    props.merge(&direct);
+
+    // Paragraph mark formatting gives the height of empty paragraphs.
+    let mut mark = style_run.clone();
+    if let Some(rpr) = ppr.and_then(|p| w_child(p, "rPr")) {
+      mark.merge(&RunProps::parse(rpr));
+    }
+
+    let mut content = ParagraphContent::default();
+    self.inline(node, &style_run, &mut content, depth);
+
+    let style = mark.text_style();
+    let line_spacing = match props.line_spacing {
+      Some(LineSpacing::Auto(factor)) => factor,
+      Some(LineSpacing::Exact(height)) => height / (style.size * 1.164),
+      Some(LineSpacing::AtLeast(height)) => (height / (style.size * 1.164)).max(1.0),
+      None => 1.0,
+    }
+    .clamp(0.5, 4.0);
+    let is_heading = props.outline_level.is_some();
+    let template = Paragraph {
+      runs: Vec::new(),
+      align: props.align.unwrap_or_default(),
+      indent_left: props.indent_left.unwrap_or(0.0).max(0.0),
+      indent_right: props.indent_right.unwrap_or(0.0).max(0.0),
+      first_line_indent: props.first_line_indent.unwrap_or(0.0),
+      marker: None,
+      space_before: props.space_before.unwrap_or(0.0).max(0.0),
+      space_after: props.space_after.unwrap_or(0.0).max(0.0),
+      line_spacing,
+      keep_with_next: props.keep_with_next.unwrap_or(is_heading),
+      border_bottom: props.border_bottom.flatten(),
+      // Level 9 is body text
+      outline_level: props.outline_level.filter(|level| *level < 9),
+    };
+
+    if props.page_break_before.unwrap_or(false) {
+      blocks.push(Block::PageBreak);
+    }
+    let has_text = content.parts.iter().any(|p| matches!(p, Part::Runs(_)));
+    if !has_text {
+      // An empty paragraph still takes a line.
+      let mut paragraph = template.clone();
+      paragraph.marker = marker.take();
+      paragraph.runs.push(Run {
+        text: String::new(),
+        style: style.clone(),
+      });
+      // Pictures alone need no line, unless it carries a list marker.
+      if content.parts.is_empty() || paragraph.marker.is_some() {
+        blocks.push(Block::Paragraph(paragraph));
+      }
+    }
+    for part in content.parts {
+      match part {
+        Part::Runs(runs) => {
+          let mut paragraph = template.clone();
+          paragraph.marker = marker.take();
+          paragraph.runs = runs;
+          blocks.push(Block::Paragraph(paragraph));
+        }
+        Part::Picture(mut picture) => {
+          if picture.align == Align::Left {
+            picture.align = template.align;
+          }
+          blocks.push(Block::Picture(picture));
+        }
+        Part::PageBreak => blocks.push(Block::PageBreak),
+      }
+    }
+    blocks.extend(content.text_boxes);
+    props.contextual_spacing.unwrap_or(false)
+  }
+
+  /// Runs of a paragraph, including those of hyperlinks, fields and insertions.
+  fn inline(&mut self, node: Node, base: &RunProps, content: &mut ParagraphContent, depth: usize) {
+    if depth > MAX_DEPTH {
+      return;
+    }
+    for child in node.children().filter(|n| n.is_element()) {
+      let name = child.tag_name().name();
+      if child.tag_name().namespace() == Some(W_NS) {
+        match name {
+          "r" => self.run(child, base, content, depth),
+          "hyperlink" | "smartTag" | "customXml" | "ins" | "moveTo" | "fldSimple"
+          | "sdtContent" | "bdo" | "dir" => self.inline(child, base, content, depth + 1),
+          "sdt" => {
+            if let Some(inner) = w_child(child, "sdtContent") {
+              self.inline(inner, base, content, depth + 1);
+            }
+          }
+          _ => {}
+        }
+      } else if name == "oMath" || name == "oMathPara" {
+        // Equations: their text only
+        let text: String = child
+          .descendants()
+          .filter(|n| n.tag_name().name() == "t")
+          .filter_map(|n| n.text())
+          .collect();
+        content.push_run(Run {
+          text,
+          style: base.text_style(),
+        });
+      }
+    }
+  }
+
+  fn run(&mut self, node: Node, base: &RunProps, content: &mut ParagraphContent, depth: usize) {
+    let mut props = base.clone();
+    if let Some(rpr) = w_child(node, "rPr") {
+      if let Some(style) = w_child(rpr, "rStyle").and_then(w_val) {
+        props.merge(&self.styles.run_style(style));
+      }
+      props.merge(&RunProps::parse(rpr));
+    }
+    if props.hidden.unwrap_or(false) {
+      return;
+    }
+    let style = props.text_style();
+    let caps = props.caps.unwrap_or(false);
+    let mut text = String::new();
+
+    let flush = |text: &mut String, content: &mut ParagraphContent| {
+      if !text.is_empty() {
+        content.push_run(Run {
+          text: std::mem::take(text),
+          style: style.clone(),
+        });
+      }
+    };
+
+    for child in node.children().filter(|n| n.is_element()) {
+      let name = child.tag_name().name();
+      match name {
+        "t" if child.tag_name().namespace() == Some(W_NS) => {
+          let t = child.text().unwrap_or("");
+          if caps {
+            text.push_str(&t.to_uppercase());
+          } else {
+            text.push_str(t);
+          }
+        }
+        "tab" | "ptab" => text.push_str("    "),
+        "br" => {
+          if w_attr(child, "type") == Some("page") {
+            flush(&mut text, content);
+            content.parts.push(Part::PageBreak);
+          } else {
+            text.push('\n');
+          }
+        }
+        "cr" => text.push('\n'),
+        "noBreakHyphen" => text.push('-'),
+        "sym" => {
+          let c = w_attr(child, "char")
+            .and_then(|c| u32::from_str_radix(c, 16).ok())
+            .and_then(char::from_u32)
+            .map(|c| bullet_char(&c.to_string()));
+          text.push_str(&c.unwrap_or_default());
+        }
+        "drawing" | "pict" | "object" => {
+          flush(&mut text, content);
+          self.drawing(child, content, depth);
+        }
+        "AlternateContent" => {
+          // The first choice is the modern markup (DrawingML), the fallback VML.
+          let choice = child
+            .children()
+            .find(|n| n.tag_name().name() == "Choice")
+            .or_else(|| child.children().find(|n| n.tag_name().name() == "Fallback"));
+          if let Some(choice) = choice {
+            flush(&mut text, content);
+            self.drawing(choice, content, depth);
+          }
+        }
+        _ => {}
+      }
+    }
+    flush(&mut text, content);
+  }
+
+  /// Text boxes and pictures of a drawing (DrawingML or VML).
+  fn drawing(&mut self, node: Node, content: &mut ParagraphContent, depth: usize) {
+    let text_boxes: Vec<Node> = node
+      .descendants()
+      .filter(|n| is_w(n, "txbxContent"))
+      .filter(|n| !n.ancestors().skip(1).any(|a| is_w(&a, "txbxContent")))
+      .collect();
+    if !text_boxes.is_empty() {
+      for text_box in text_boxes {
+        let blocks = self.blocks(text_box, depth + 1);
+        content.text_boxes.extend(blocks);
+      }
+      return;
+    }
+
+    // DrawingML picture (a:blip r:embed) or VML image (v:imagedata r:id)
+    let image_id = node.descendants().find_map(|n| match n.tag_name().name() {
+      "blip" => n.attribute((R_NS, "embed")),
+      "imagedata" => n.attribute((R_NS, "id")),
+      _ => None,
+    });
+    let Some(data) = image_id
+      .and_then(|id| self.rels.get(id))
+      .and_then(|rel| self.package.read(&rel.target))
+    else {
+      return;
+    };
+
+    // Size in EMUs (12700 per point)
+    let extent = node.descendants().find(|n| n.tag_name().name() == "extent");
+    let size = |attr: &str| {
+      extent
+        .and_then(|e| e.attribute(attr))
+        .and_then(|v| v.parse::<f64>().ok())
+        .map(|emu| emu / 12700.0)
+        .filter(|v| *v > 0.0)
+    };
+    let align = node
+      .descendants()
+      .find(|n| n.tag_name().name() == "positionH")
+      .and_then(|p| p.children().find(|n| n.tag_name().name() == "align"))
+      .and_then(|a| a.text())
+      .map(|a| match a {
+        "center" => Align::Center,
+        "right" | "outside" => Align::Right,
+        _ => Align::Left,
+      })
+      .unwrap_or_default();
+    content.parts.push(Part::Picture(Picture {
+      data,
+      width: size("cx"),
+      height: size("cy"),
+      align,
+    }));
+  }
+
+  fn table(&mut self, node: Node, depth: usize) -> Option<Block> {
+    if depth > MAX_DEPTH {
+      return None;
+    }
+    let tbl_pr = w_child(node, "tblPr");
+    let style_id = tbl_pr
+      .and_then(|t| w_child(t, "tblStyle"))
+      .and_then(w_val)
+      .map(str::to_string)
+      .or_else(|| self.styles.normal_table.clone());
+    let (_, _, style_borders) = self.styles.resolve(style_id.as_deref());
+    let borders = tbl_pr
+      .and_then(|t| w_child(t, "tblBorders"))
+      .map(has_borders)
+      .or(style_borders)
+      .unwrap_or(false);
+    let indent = tbl_pr
+      .and_then(|t| w_number(t, "tblInd", "w"))
+      .map(twips)
+      .unwrap_or(0.0)
+      .max(0.0);
+    let columns: Vec<f64> = w_child(node, "tblGrid")
+      .map(|grid| {
+        grid
+          .children()
+          .filter(|n| is_w(n, "gridCol"))
+          .map(|col| w_attr(col, "w").and_then(|w| w.parse().ok()).unwrap_or(0.0))
+          .collect()
+      })
+      .unwrap_or_default();
+
+    let mut rows = Vec::new();
+    let row_nodes = node.children().flat_map(|child| {
+      // Rows may be wrapped in content controls.
+      if is_w(&child, "sdt") {
+        w_child(child, "sdtContent")
+          .map(|c| c.children().filter(|n| is_w(n, "tr")).collect())
+          .unwrap_or_default()
+      } else if is_w(&child, "tr") {
+        vec![child]
+      } else {
+        Vec::new()
+      }
+    });
+    for tr in row_nodes {
+      let mut cells = Vec::new();
+      for tc in tr.children().filter(|n| is_w(n, "tc")) {
+        let tc_pr = w_child(tc, "tcPr");
+        let span = tc_pr
+          .and_then(|p| w_child(p, "gridSpan"))
+          .and_then(w_val)
+          .and_then(|v| v.parse().ok())
+          .unwrap_or(1usize);
+        // Cells merged with the cell above keep their space but not their content.
+        let merged = tc_pr
+          .and_then(|p| w_child(p, "vMerge"))
+          .is_some_and(|m| w_val(m) != Some("restart"));
+        let background = tc_pr
+          .and_then(|p| w_child(p, "shd"))
+          .and_then(|shd| w_attr(shd, "fill"))
+          .and_then(hex_color);
+        cells.push(TableCell {
+          blocks: if merged {
+            Vec::new()
+          } else {
+            self.blocks(tc, depth + 1)
+          },
+          span,
+          background,
+        });
+      }
+      if !cells.is_empty() {
+        rows.push(cells);
+      }
+    }
+    if rows.is_empty() {
+      return None;
+    }
+    Some(Block::Table(Table {
+      columns,
+      rows,
+      borders: borders.then_some(BLACK),
+      indent_left: indent,
+    }))
+  }
+}
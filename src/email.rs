@@ -0,0 +1,678 @@
+use base64::alphabet::STANDARD;
+use base64::engine::{DecodePaddingMode, GeneralPurpose, GeneralPurposeConfig};
+use base64::Engine as _;
+use encoding_rs::{UTF_8, WINDOWS_1252};
+use napi::bindgen_prelude::Uint8Array;
+use napi::{Error, Status};
+use napi_derive::napi;
+
+use crate::charset::{decode_with, encoding_for_label};
+use crate::html::{html_blocks, parse_html};
+use crate::normalize::{normalize_cv_to_pdf, NormalizeOptions};
+use crate::pdf::catch_unwind_silently;
+use crate::typeset::plain_text;
+use crate::utils::{
+  is_doc_mime, is_docx_mime, is_html_mime, is_markdown_mime, is_pdf_mime, is_plain_text_mime,
+  is_supported_image_mime,
+};
+
+/// Maximum nesting of multipart bodies and attached messages, against pathological
+/// input.
+const MAX_MIME_DEPTH: usize = 16;
+
+/// Base64 as found in emails: padding optional, trailing bits ignored.
+const LENIENT_BASE64: GeneralPurpose = GeneralPurpose::new(
+  &STANDARD,
+  GeneralPurposeConfig::new()
+    .with_decode_padding_mode(DecodePaddingMode::Indifferent)
+    .with_decode_allow_trailing_bits(true),
+);
+
+/// Mime types of attachments by file extension, for attachments sent as
+/// `application/octet-stream`.
+const EXTENSION_MIMES: &[(&str, &str)] = &[
+  ("pdf", "application/pdf"),
+  (
+    "docx",
+    "application/vnd.openxmlformats-officedocument.wordprocessingml.document",
+  ),
+  ("doc", "application/msword"),
+  ("png", "image/png"),
+  ("jpg", "image/jpeg"),
+  ("jpeg", "image/jpeg"),
+  ("txt", "text/plain"),
+  ("md", "text/markdown"),
+  ("markdown", "text/markdown"),
+  ("html", "text/html"),
+  ("htm", "text/html"),
+];
+
+/// A CV attached to an email.
+///
+/// - `filename`: Name of the attachment, when given
+/// - `mime`: Mime type of the attachment (from its extension when sent as
+///   `application/octet-stream`)
+/// - `pdf`: The attachment normalized to PDF by `normalize_cv_to_pdf`
+/// - `error`: Why the attachment could not be normalized (then `pdf` is absent)
+#[napi(object)]
+pub struct EmailCv {
+  pub filename: Option<String>,
+  pub mime: String,
+  pub pdf: Option<Vec<u8>>,
+  pub error: Option<String>,
+}
+
+/// An application email with its normalized CVs.
+///
+/// - `sender_name`: Display name of the sender (`From`), when given
+/// - `sender_email`: Email address of the sender
+/// - `subject`: Subject of the email
+/// - `date`: `Date` header, as written in the email
+/// - `body_text`: Text of the body (the HTML part converted to text when there is
+///   no plain text part), including the text of forwarded messages
+/// - `cvs`: Attachments with a CV-like type, in message order
+#[napi(object)]
+pub struct EmailCvs {
+  pub sender_name: Option<String>,
+  pub sender_email: Option<String>,
+  pub subject: Option<String>,
+  pub date: Option<String>,
+  pub body_text: String,
+  pub cvs: Vec<EmailCv>,
+}
+
+/// Extract the CVs attached to an email (`.eml`, RFC 822/MIME) and normalize them
+/// to PDF.
+///
+/// The message is parsed with its multipart bodies, base64 and quoted-printable
+/// transfer encodings, charsets, RFC 2047 encoded headers and RFC 2047/2231 encoded
+/// filenames. Forwarded messages attached as `message/rfc822` are searched too.
+///
+/// Attachments with a type `normalize_cv_to_pdf` converts (PDF, DOCX, DOC, PNG, JPEG,
+/// and plain text, Markdown or HTML files) are run through it with `options`; images
+/// embedded in an HTML body (with a `Content-ID`) are skipped, and text or HTML parts
+/// are body text unless attached as files other than Outlook's body parts
+/// (`ATT00001.htm`). An attachment that
+/// fails to normalize is returned with its `error` instead of failing the whole email.
+#[napi]
+pub fn extract_cvs_from_email(
+  bytes: Uint8Array,
+  options: Option<NormalizeOptions>,
+) -> napi::Result<EmailCvs> {
+  let input = bytes.to_vec();
+  let entity = Entity::parse(&input);
+  if entity.headers.is_empty() {
+    return Err(Error::new(
+      Status::InvalidArg,
+      "Failed to read email: no message headers",
+    ));
+  }
+  let mut message = Message::default();
+  catch_unwind_silently(|| walk(&entity, 0, &mut message)).map_err(|e| {
+    Error::new(
+      Status::GenericFailure,
+      format!("Failed to process email: {e}"),
+    )
+  })?;
+
+  let (sender_name, sender_email) = entity
+    .header("From")
+    .map(|from| parse_address(&decode_words(from)))
+    .unwrap_or_default();
+  let cvs = message
+    .attachments
+    .into_iter()
+    .map(|attachment| {
+      let normalized = normalize_cv_to_pdf(
+        Uint8Array::new(attachment.data),
+        attachment.mime.clone(),
+        options.clone(),
+      )
+      .map_err(|e| e.reason.clone());
+      let (pdf, error) = match normalized {
+        Ok(pdf) => (Some(pdf), None),
+        Err(error) => (None, Some(error)),
+      };
+      EmailCv {
+        filename: attachment.filename,
+        mime: attachment.mime,
+        pdf,
+        error,
+      }
+    })
+    .collect();
+
+  Ok(EmailCvs {
+    sender_name,
+    sender_email,
+    subject: entity.header("Subject").map(decode_words),
+    date: entity.header("Date").map(|date| date.trim().to_string()),
+    body_text: message.body.join("\n\n").trim().to_string(),
+    cvs,
+  })
+}
+
+/// A MIME entity: its headers, unfolded and decoded as text, and its raw body.
+struct Entity<'a> {
+  headers: Vec<(String, String)>,
+  body: &'a [u8],
+}
+
+impl<'a> Entity<'a> {
+  fn parse(bytes: &'a [u8]) -> Entity<'a> {
+    let mut headers: Vec<(String, String)> = Vec::new();
+    let mut position = 0;
+    while position < bytes.len() {
+      let end = (bytes[position..].iter())
+        .position(|&b| b == b'\n')
+        .map_or(bytes.len(), |i| position + i + 1);
+      let line = decode_header_bytes(&bytes[position..end]);
+      let line = line.trim_end_matches(['\r', '\n']);
+      if line.is_empty() {
+        position = end;
+        break;
+      }
+      if line.starts_with([' ', '\t']) && !headers.is_empty() {
+        if let Some((_, value)) = headers.last_mut() {
+          value.push_str(line);
+        }
+      } else if let Some((name, value)) = line
+        .split_once(':')
+        .filter(|(name, _)| !name.is_empty() && !name.contains(char::is_whitespace))
+      {
+        headers.push((name.to_string(), value.trim_start().to_string()));
+      } else if !(position == 0 && line.starts_with("From ")) {
+        // Not a header (the mbox `From ` line aside): the body starts here.
+        break;
+      }
+      position = end;
+    }
+    Entity {
+      headers,
+      body: &bytes[position.min(bytes.len())..],
+    }
+  }
+
+  fn header(&self, name: &str) -> Option<&str> {
+    (self.headers.iter())
+      .find(|(header, _)| header.eq_ignore_ascii_case(name))
+      .map(|(_, value)| value.as_str())
+  }
+
+  /// Value and parameters of a structured header such as `Content-Type`.
+  fn structured(&self, name: &str) -> (String, Vec<(String, String)>) {
+    let value = self.header(name).unwrap_or("");
+    let mut parts = split_unquoted(value, ';').into_iter();
+    let main = parts.next().unwrap_or_default().trim().to_ascii_lowercase();
+    let params = parts
+      .filter_map(|part| {
+        let (name, value) = part.split_once('=')?;
+        Some((name.trim().to_ascii_lowercase(), unquote(value.trim())))
+      })
+      .collect();
+    (main, params)
+  }
+
+  /// Body with its `Content-Transfer-Encoding` decoded.
+  fn decoded_body(&self) -> Vec<u8> {
+    let encoding = self.header("Content-Transfer-Encoding").unwrap_or("");
+    match encoding.trim().to_ascii_lowercase().as_str() {
+      "base64" => decode_base64(self.body),
+      "quoted-printable" => decode_quoted_printable(self.body),
+      _ => self.body.to_vec(),
+    }
+  }
+}
+
+/// An attachment found in a message.
+struct Attachment {
+  filename: Option<String>,
+  mime: String,
+  data: Vec<u8>,
+}
+
+/// Body text and CV-like attachments of a message.
+#[derive(Default)]
+struct Message {
+  body: Vec<String>,
+  attachments: Vec<Attachment>,
+}
+
+/// Collect the body text and CV-like attachments of an entity and its parts.
+fn walk(entity: &Entity, depth: usize, message: &mut Message) {
+  let (mime, params) = entity.structured("Content-Type");
+  let mime = if mime.is_empty() {
+    "text/plain".to_string()
+  } else {
+    mime
+  };
+
+  if mime.starts_with("multipart/") {
+    let Some(boundary) = param(&params, "boundary") else {
+      return;
+    };
+    if depth >= MAX_MIME_DEPTH {
+      return;
+    }
+    let parts: Vec<Entity> = (split_multipart(entity.body, &boundary).into_iter())
+      .map(Entity::parse)
+      .collect();
+    if mime == "multipart/alternative" {
+      // The plain text version, else the richest one (the last).
+      let plain = parts
+        .iter()
+        .find(|part| part.structured("Content-Type").0 == "text/plain");
+      match plain.or(parts.last()) {
+        // A version of the body, even when it is named
+        Some(part) if is_text_part(part) => push_body_text(part, message),
+        Some(part) => walk(part, depth + 1, message),
+        None => {}
+      }
+    } else {
+      for part in &parts {
+        walk(part, depth + 1, message);
+      }
+    }
+    return;
+  }
+
+  let (disposition, disposition_params) = entity.structured("Content-Disposition");
+  if mime == "message/rfc822" {
+    if depth < MAX_MIME_DEPTH {
+      let data = entity.decoded_body();
+      walk(&Entity::parse(&data), depth + 1, message);
+    }
+    return;
+  }
+
+  let filename = param(&disposition_params, "filename").or_else(|| param(&params, "name"));
+  // Text and HTML parts are body text unless attached as files. Outlook attaches
+  // the rest of the body after an attachment as "ATT00001.txt" or "ATT00002.htm".
+  let body_file = filename.as_deref().is_some_and(is_client_body_file);
+  if is_text_part(entity) && (disposition != "attachment" || body_file) {
+    push_body_text(entity, message);
+    return;
+  }
+  if body_file {
+    return;
+  }
+  let is_attachment = disposition == "attachment" || filename.is_some();
+
+  // Images of an HTML body, such as a signature logo
+  let embedded = entity.header("Content-ID").is_some() && disposition != "attachment";
+  if !is_attachment || embedded {
+    return;
+  }
+  let mime = match (mime.as_str(), &filename) {
+    ("application/octet-stream" | "binary/octet-stream" | "text/plain", Some(filename)) => {
+      mime_from_filename(filename).unwrap_or(mime)
+    }
+    _ => mime,
+  };
+  if is_cv_mime(&mime) {
+    message.attachments.push(Attachment {
+      filename,
+      mime,
+      data: entity.decoded_body(),
+    });
+  }
+}
+
+/// Whether a part is plain text or HTML (plain text when untyped).
+fn is_text_part(entity: &Entity) -> bool {
+  let mime = entity.structured("Content-Type").0;
+  mime.is_empty() || is_plain_text_mime(&mime) || is_html_mime(&mime)
+}
+
+/// Add the text of a plain text or HTML part to the body.
+fn push_body_text(entity: &Entity, message: &mut Message) {
+  let (mime, params) = entity.structured("Content-Type");
+  let text = decode_body_text(&entity.decoded_body(), param(&params, "charset"));
+  let text = if is_html_mime(&mime) {
+    plain_text(&html_blocks(&parse_html(&text)))
+  } else {
+    text.replace("\r\n", "\n")
+  };
+  if !text.trim().is_empty() {
+    message.body.push(text.trim_end().to_string());
+  }
+}
+
+/// Whether a file name is one Outlook gives to body parts ("ATT00001.htm").
+fn is_client_body_file(filename: &str) -> bool {
+  let Some((stem, extension)) = filename.rsplit_once('.') else {
+    return false;
+  };
+  let number = stem
+    .get(..3)
+    .is_some_and(|att| att.eq_ignore_ascii_case("att"))
+    && stem.len() > 3
+    && stem[3..].bytes().all(|b| b.is_ascii_digit());
+  number
+    && matches!(
+      extension.to_ascii_lowercase().as_str(),
+      "txt" | "htm" | "html"
+    )
+}
+
+/// Whether attachments of this type can hold a CV that converts to PDF.
+fn is_cv_mime(mime: &str) -> bool {
+  is_pdf_mime(mime)
+    || is_docx_mime(mime)
+    || is_doc_mime(mime)
+    || is_supported_image_mime(mime)
+    || is_plain_text_mime(mime)
+    || is_markdown_mime(mime)
+    || is_html_mime(mime)
+}
+
+fn mime_from_filename(filename: &str) -> Option<String> {
+  let (_, extension) = filename.rsplit_once('.')?;
+  let extension = extension.to_ascii_lowercase();
+  (EXTENSION_MIMES.iter())
+    .find(|(known, _)| *known == extension)
+    .map(|(_, mime)| mime.to_string())
+}
+
+/// The parts of a multipart body, between its `--boundary` delimiter lines.
+fn split_multipart<'a>(body: &'a [u8], boundary: &str) -> Vec<&'a [u8]> {
+  let delimiter = format!("--{boundary}");
+  let mut parts = Vec::new();
+  // Start of the current part, after a delimiter line.
+  let mut start: Option<usize> = None;
+  let mut position = 0;
+  while position < body.len() {
+    let end = (body[position..].iter())
+      .position(|&b| b == b'\n')
+      .map_or(body.len(), |i| position + i + 1);
+    let line = &body[position..end];
+    if let Some(rest) = line.strip_prefix(delimiter.as_bytes()) {
+      let rest = rest.trim_ascii();
+      if rest.is_empty() || rest == b"--" {
+        if let Some(start) = start {
+          // The line break before a delimiter belongs to it.
+          let mut part_end = position;
+          if body[..part_end].ends_with(b"\n") {
+            part_end -= 1;
+          }
+          if body[..part_end].ends_with(b"\r") {
+            part_end -= 1;
+          }
+          parts.push(&body[start..part_end.max(start)]);
+        }
+        if rest == b"--" {
+          return parts;
+        }
+        start = Some(end);
+      }
+    }
+    position = end;
+  }
+  // A missing close delimiter ends the last part at the end of the body.
+  if let Some(start) = start {
+    parts.push(&body[start..]);
+  }
+  parts
+}
+
+/// A parameter of a structured header, with RFC 2231 encoding (`filename*=UTF-8''…`)
+/// and continuations (`filename*0*=`, `filename*1*=`) or RFC 2047 encoded words
+/// (`filename="=?UTF-8?B?…?="`, as many mail clients write them).
+fn param(params: &[(String, String)], name: &str) -> Option<String> {
+  if let Some((_, value)) = params.iter().find(|(key, _)| *key == format!("{name}*")) {
+    return Some(decode_extended(value));
+  }
+
+  let mut sections: Vec<(u32, bool, &str)> = params
+    .iter()
+    .filter_map(|(key, value)| {
+      let section = key.strip_prefix(name)?.strip_prefix('*')?;
+      let (index, extended) = match section.strip_suffix('*') {
+        Some(index) => (index, true),
+        None => (section, false),
+      };
+      Some((index.parse().ok()?, extended, value.as_str()))
+    })
+    .collect();
+  if !sections.is_empty() {
+    sections.sort_by_key(|(index, _, _)| *index);
+    let (charset, mut bytes) = match sections[0] {
+      (_, true, first) => {
+        let (charset, value) = split_extended(first);
+        (charset, percent_decode(value))
+      }
+      (_, false, first) => (None, first.as_bytes().to_vec()),
+    };
+    for (_, extended, value) in &sections[1..] {
+      if *extended {
+        bytes.extend(percent_decode(value));
+      } else {
+        bytes.extend(value.as_bytes());
+      }
+    }
+    return Some(decode_charset(&bytes, charset));
+  }
+
+  (params.iter())
+    .find(|(key, _)| key == name)
+    .map(|(_, value)| decode_words(value))
+}
+
+/// An RFC 2231 extended value: `charset'language'percent-encoded text`.
+fn decode_extended(value: &str) -> String {
+  let (charset, value) = split_extended(value);
+  decode_charset(&percent_decode(value), charset)
+}
+
+fn split_extended(value: &str) -> (Option<&str>, &str) {
+  let mut parts = value.splitn(3, '\'');
+  match (parts.next(), parts.next(), parts.next()) {
+    (Some(charset), Some(_), Some(text)) => (Some(charset).filter(|c| !c.is_empty()), text),
+    _ => (None, value),
+  }
+}
+
+fn percent_decode(value: &str) -> Vec<u8> {
+  let bytes = value.as_bytes();
+  let mut out = Vec::with_capacity(bytes.len());
+  let mut i = 0;
+  while i < bytes.len() {
+    let escaped = (bytes[i] == b'%')
+      .then(|| bytes.get(i + 1..i + 3))
+      .flatten()
+      .and_then(|hex| u8::from_str_radix(std::str::from_utf8(hex).ok()?, 16).ok());
+    match escaped {
+      Some(byte) => {
+        out.push(byte);
+        i += 3;
+      }
+      None => {
+        out.push(bytes[i]);
+        i += 1;
+      }
+    }
+  }
+  out
+}
+
+/// Decode the RFC 2047 encoded words of a header (`=?UTF-8?Q?Cand=C3=A9dature?=`);
+/// whitespace between two encoded words is dropped.
+fn decode_words(value: &str) -> String {
+  let mut out = String::new();
+  let mut rest = value;
+  let mut after_word = false;
+  while let Some(start) = rest.find("=?") {
+    match encoded_word(&rest[start + 2..]) {
+      Some((text, length)) => {
+        let between = &rest[..start];
+        if !(after_word && between.trim().is_empty()) {
+          out.push_str(between);
+        }
+        out.push_str(&text);
+        rest = &rest[start + 2 + length..];
+        after_word = true;
+      }
+      None => {
+        out.push_str(&rest[..start + 2]);
+        rest = &rest[start + 2..];
+        after_word = false;
+      }
+    }
+  }
+  out.push_str(rest);
+  out
+}
+
+/// Text of an encoded word after its `=?` (`charset?encoding?text?=`), and the
+/// length of the word.
+fn encoded_word(word: &str) -> Option<(String, usize)> {
+  let (charset, rest) = word.split_once('?')?;
+  let (encoding, rest) = rest.split_once('?')?;
+  let text = &rest[..rest.find("?=")?];
+  if charset.is_empty()
+    || [charset, encoding, text]
+      .iter()
+      .any(|s| s.contains(char::is_whitespace))
+  {
+    return None;
+  }
+  let bytes = match encoding {
+    "B" | "b" => decode_base64(text.as_bytes()),
+    "Q" | "q" => decode_quoted_printable(text.replace('_', " ").as_bytes()),
+    _ => return None,
+  };
+  // The language after `*` (RFC 2231) is not needed.
+  let label = charset.split('*').next().unwrap_or(charset);
+  let length = charset.len() + encoding.len() + text.len() + 4;
+  Some((decode_charset(&bytes, Some(label)), length))
+}
+
+/// Display name and address of a mailbox: `"Doe, Jane" <jane@example.com>`,
+/// `Jane Doe <jane@example.com>` or `jane@example.com (Jane Doe)`.
+fn parse_address(value: &str) -> (Option<String>, Option<String>) {
+  let value = value.trim();
+  let clean = |name: &str| {
+    let name = unquote(name.trim());
+    Some(name.trim().to_string()).filter(|name| !name.is_empty())
+  };
+  if let (Some(open), Some(close)) = (value.rfind('<'), value.rfind('>')) {
+    if open < close {
+      return (clean(&value[..open]), clean(&value[open + 1..close]));
+    }
+  }
+  match value.split_once('(') {
+    Some((address, comment)) => (clean(comment.trim_end_matches(')')), clean(address)),
+    None => (None, clean(value)),
+  }
+}
+
+/// Header bytes as text: UTF-8 when valid (RFC 6532), else Windows-1252.
+fn decode_header_bytes(bytes: &[u8]) -> String {
+  match std::str::from_utf8(bytes) {
+    Ok(text) => text.to_string(),
+    Err(_) => decode_with(bytes, WINDOWS_1252),
+  }
+}
+
+/// Text of a body part in its declared charset; UTF-8 when none is declared and
+/// the bytes are valid UTF-8, else Windows-1252.
+fn decode_body_text(bytes: &[u8], charset: Option<String>) -> String {
+  match charset {
+    Some(charset) => decode_charset(bytes, Some(&charset)),
+    None => decode_header_bytes(bytes),
+  }
+}
+
+fn decode_charset(bytes: &[u8], charset: Option<&str>) -> String {
+  let encoding = charset.and_then(encoding_for_label).unwrap_or(UTF_8);
+  decode_with(bytes, encoding)
+}
+
+fn decode_base64(data: &[u8]) -> Vec<u8> {
+  let mut clean: Vec<u8> = (data.iter())
+    .copied()
+    .filter(|b| b.is_ascii_alphanumeric() || *b == b'+' || *b == b'/')
+    .collect();
+  // A truncated last group cannot be decoded.
+  if clean.len() % 4 == 1 {
+    clean.pop();
+  }
+  LENIENT_BASE64.decode(&clean).unwrap_or_default()
+}
+
+fn decode_quoted_printable(data: &[u8]) -> Vec<u8> {
+  let mut out = Vec::with_capacity(data.len());
+  let mut i = 0;
+  while i < data.len() {
+    if data[i] != b'=' {
+      out.push(data[i]);
+      i += 1;
+      continue;
+    }
+    let hex = data
+      .get(i + 1..i + 3)
+      .and_then(|hex| u8::from_str_radix(std::str::from_utf8(hex).ok()?, 16).ok());
+    if let Some(byte) = hex {
+      out.push(byte);
+      i += 3;
+      continue;
+    }
+    // Soft line break: `=` at the end of a line, possibly followed by spaces.
+    let spaces = data[i + 1..]
+      .iter()
+      .take_while(|b| **b == b' ' || **b == b'\t')
+      .count();
+    let after = i + 1 + spaces;
+    if data[after..].starts_with(b"\r\n") {
+      i = after + 2;
+    } else if data[after..].starts_with(b"\n") || after == data.len() {
+      i = after + 1;
+    } else {
+      out.push(b'=');
+      i += 1;
+    }
+  }
+  out
+}
+
+/// Split on `separator` outside double quotes.
+fn split_unquoted(value: &str, separator: char) -> Vec<String> {
+  let mut parts = vec![String::new()];
+  let mut quoted = false;
+  let mut escaped = false;
+  for c in value.chars() {
+    if escaped {
+      escaped = false;
+    } else if c == '\\' && quoted {
+      escaped = true;
+    } else if c == '"' {
+      quoted = !quoted;
+    } else if c == separator && !quoted {
+      parts.push(String::new());
+      continue;
+    }
+    if let Some(part) = parts.last_mut() {
+      part.push(c);
+    }
+  }
+  parts
+}
+
+/// Value of a quoted string, without its quotes and backslash escapes.
+fn unquote(value: &str) -> String {
+  match value.strip_prefix('"').and_then(|v| v.strip_suffix('"')) {
+    Some(inner) => {
+      let mut out = String::with_capacity(inner.len());
+      let mut chars = inner.chars();
+      while let Some(c) = chars.next() {
+        out.push(if c == '\\' {
+          chars.next().unwrap_or(c)
+        } else {
+          c
+        });
+      }
+      out
+    }
+    None => value.to_string(),
+  }
+}
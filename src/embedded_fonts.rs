@@ -0,0 +1,272 @@
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::fmt::Write as FmtWrite;
+
+use ttf_parser::{name_id, Face, GlyphId};
+
+use crate::pdf_writer::PdfWriter;
+
+static DEJAVU_SANS: &[u8] = include_bytes!("../assets/fonts/DejaVuSans.ttf");
+static DEJAVU_SANS_BOLD: &[u8] = include_bytes!("../assets/fonts/DejaVuSans-Bold.ttf");
+static DEJAVU_SANS_OBLIQUE: &[u8] = include_bytes!("../assets/fonts/DejaVuSans-Oblique.ttf");
+static DEJAVU_SANS_BOLD_OBLIQUE: &[u8] =
+  include_bytes!("../assets/fonts/DejaVuSans-BoldOblique.ttf");
+
+/// Weight and slant of a font of a family.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub(crate) struct FontStyle {
+  pub bold: bool,
+  pub italic: bool,
+}
+
+/// A TrueType font embedded in generated PDFs, as a subset of the glyphs used.
+///
+/// Text is encoded with Identity-H: every glyph is shown by its two-byte glyph id,
+/// so any character the font covers can be written, and a ToUnicode map keeps the
+/// text extractable.
+pub(crate) struct EmbeddedFont<'a> {
+  data: &'a [u8],
+  face: Face<'a>,
+  /// PostScript name, without characters that are not allowed in a PDF name
+  name: String,
+  /// Glyphs shown so far, with the text they stand for
+  used: RefCell<BTreeMap<u16, String>>,
+}
+
+impl<'a> EmbeddedFont<'a> {
+  pub fn parse(data: &'a [u8]) -> Result<Self, String> {
+    let face = Face::parse(data, 0).map_err(|e| e.to_string())?;
+    let name = face
+      .names()
+      .into_iter()
+      .filter(|n| n.name_id == name_id::POST_SCRIPT_NAME)
+      // Macintosh names are single-byte; PostScript names are ASCII.
+      .find_map(|n| {
+        n.to_string().or_else(|| {
+          n.name
+            .is_ascii()
+            .then(|| String::from_utf8_lossy(n.name).into_owned())
+        })
+      })
+      .unwrap_or_else(|| "Font".to_string())
+      .chars()
+      .filter(|c| c.is_ascii_alphanumeric() || *c == '-')
+      .collect();
+    Ok(EmbeddedFont {
+      data,
+      face,
+      name,
+      used: RefCell::new(BTreeMap::new()),
+    })
+  }
+
+  fn glyph(&self, c: char) -> GlyphId {
+    self
+      .face
+      .glyph_index(c)
+      .or_else(|| self.face.glyph_index('?'))
+      .unwrap_or(GlyphId(0))
+  }
+
+  fn units(&self, value: f64) -> f64 {
+    value / self.face.units_per_em() as f64
+  }
+
+  /// Width of `text` set at `size` points.
+  pub fn width(&self, text: &str, size: f64) -> f64 {
+    let units: f64 = text
+      .chars()
+      .map(|c| {
+        let glyph = self.glyph(c);
+        self.face.glyph_hor_advance(glyph).unwrap_or(0) as f64
+      })
+      .sum();
+    self.units(units) * size
+  }
+
+  /// Height above the baseline, as a fraction of the font size.
+  pub fn ascent(&self) -> f64 {
+    self.units(self.face.ascender() as f64)
+  }
+
+  /// Depth below the baseline (positive), as a fraction of the font size.
+  pub fn descent(&self) -> f64 {
+    self.units(-(self.face.descender() as f64))
+  }
+
+  /// Glyph ids of `text` as a hex string for `Tj`; the glyphs are kept for the subset.
+  pub fn encode(&self, text: &str) -> String {
+    let mut used = self.used.borrow_mut();
+    let mut hex = String::with_capacity(text.len() * 4);
+    for c in text.chars() {
+      let glyph = self.glyph(c);
+      if self.face.glyph_index(c).is_some() {
+        used.entry(glyph.0).or_insert_with(|| c.to_string());
+      } else {
+        used.entry(glyph.0).or_default();
+      }
+      let _ = write!(hex, "{:04X}", glyph.0);
+    }
+    hex
+  }
+
+  /// Write the font as object `id` (a Type0 font) and its descendant objects.
+  pub fn write(&self, pdf: &mut PdfWriter, id: u32) {
+    let used = self.used.borrow();
+    let mut glyphs: Vec<u16> = used.keys().copied().collect();
+    if !glyphs.contains(&0) {
+      glyphs.insert(0, 0);
+    }
+    let program = subsetter::subset(self.data, 0, subsetter::Profile::pdf(&glyphs))
+      .unwrap_or_else(|_| self.data.to_vec());
+    let base_font = format!("{}+{}", subset_tag(&glyphs), self.name);
+
+    let cid_font = pdf.reserve();
+    let descriptor = pdf.reserve();
+    let font_file = pdf.reserve();
+    let to_unicode = pdf.reserve();
+
+    pdf.object(
+      id,
+      &format!(
+        "<< /Type /Font /Subtype /Type0 /BaseFont /{base_font} /Encoding /Identity-H /DescendantFonts [{cid_font} 0 R] /ToUnicode {to_unicode} 0 R >>"
+      ),
+    );
+
+    let to_pdf_units = |value: f64| (self.units(value) * 1000.0).round();
+    let mut widths = String::new();
+    for glyph in &glyphs {
+      let advance = self.face.glyph_hor_advance(GlyphId(*glyph)).unwrap_or(0);
+      let _ = write!(widths, "{glyph} [{}] ", to_pdf_units(advance as f64));
+    }
+    pdf.object(
+      cid_font,
+      &format!(
+        "<< /Type /Font /Subtype /CIDFontType2 /BaseFont /{base_font} /CIDSystemInfo << /Registry (Adobe) /Ordering (Identity) /Supplement 0 >> /FontDescriptor {descriptor} 0 R /CIDToGIDMap /Identity /W [{}] >>",
+        widths.trim_end()
+      ),
+    );
+
+    let bbox = self.face.global_bounding_box();
+    // Nonsymbolic (32), italic (64)
+    let flags = 32 | if self.face.is_italic() { 64 } else { 0 };
+    let cap_height = self.face.capital_height().unwrap_or(self.face.ascender());
+    pdf.object(
+      descriptor,
+      &format!(
+        "<< /Type /FontDescriptor /FontName /{base_font} /Flags {flags} /FontBBox [{} {} {} {}] /ItalicAngle {} /Ascent {} /Descent {} /CapHeight {} /StemV 80 /FontFile2 {font_file} 0 R >>",
+        to_pdf_units(bbox.x_min as f64),
+        to_pdf_units(bbox.y_min as f64),
+        to_pdf_units(bbox.x_max as f64),
+        to_pdf_units(bbox.y_max as f64),
+        self.face.italic_angle(),
+        to_pdf_units(self.face.ascender() as f64),
+        to_pdf_units(self.face.descender() as f64),
+        to_pdf_units(cap_height as f64),
+      ),
+    );
+    pdf.compressed_stream(font_file, &format!("/Length1 {}", program.len()), &program);
+    pdf.compressed_stream(to_unicode, "", to_unicode_cmap(&used).as_bytes());
+  }
+}
+
+/// Subset tag (six capital letters) derived from the glyphs kept.
+fn subset_tag(glyphs: &[u16]) -> String {
+  // FNV-1a
+  let mut hash: u32 = 0x811c_9dc5;
+  for glyph in glyphs {
+    for byte in glyph.to_be_bytes() {
+      hash = (hash ^ byte as u32).wrapping_mul(0x0100_0193);
+    }
+  }
+  (0..6)
+    .map(|_| {
+      let letter = (b'A' + (hash % 26) as u8) as char;
+      hash /= 26;
+      letter
+    })
+    .collect()
+}
+
+/// ToUnicode CMap mapping glyph ids to the text they were used for.
+fn to_unicode_cmap(used: &BTreeMap<u16, String>) -> String {
+  let mapped: Vec<(&u16, &String)> = used.iter().filter(|(_, text)| !text.is_empty()).collect();
+  let mut entries = String::new();
+  // At most 100 entries per bfchar block.
+  for block in mapped.chunks(100) {
+    let _ = writeln!(entries, "{} beginbfchar", block.len());
+    for (glyph, text) in block {
+      let unicode: String = text
+        .encode_utf16()
+        .map(|unit| format!("{unit:04X}"))
+        .collect();
+      let _ = writeln!(entries, "<{glyph:04X}> <{unicode}>");
+    }
+    entries.push_str("endbfchar\n");
+  }
+  format!(
+    "/CIDInit /ProcSet findresource begin\n12 dict begin\nbegincmap\n\
+     /CIDSystemInfo << /Registry (Adobe) /Ordering (UCS) /Supplement 0 >> def\n\
+     /CMapName /Adobe-Identity-UCS def\n/CMapType 2 def\n\
+     1 begincodespacerange\n<0000> <FFFF>\nendcodespacerange\n{entries}\
+     endcmap\nCMapName currentdict /CMap defineresource pop\nend\nend\n"
+  )
+}
+
+/// The four styles of a font family.
+pub(crate) struct FontFamily<'a> {
+  regular: EmbeddedFont<'a>,
+  bold: EmbeddedFont<'a>,
+  italic: EmbeddedFont<'a>,
+  bold_italic: EmbeddedFont<'a>,
+}
+
+impl FontFamily<'static> {
+  /// DejaVu Sans, bundled with the crate: it covers Latin (all European accents),
+  /// Greek and Cyrillic.
+  pub fn dejavu_sans() -> Self {
+    let font = |data| EmbeddedFont::parse(data).expect("bundled font is valid");
+    FontFamily {
+      regular: font(DEJAVU_SANS),
+      bold: font(DEJAVU_SANS_BOLD),
+      italic: font(DEJAVU_SANS_OBLIQUE),
+      bold_italic: font(DEJAVU_SANS_BOLD_OBLIQUE),
+    }
+  }
+}
+
+impl<'a> FontFamily<'a> {
+  /// A family of TrueType fonts; a missing bold or italic style falls back to
+  /// the regular font, and bold italic to bold.
+  pub fn from_fonts(
+    regular: &'a [u8],
+    bold: Option<&'a [u8]>,
+    italic: Option<&'a [u8]>,
+    bold_italic: Option<&'a [u8]>,
+  ) -> Result<Self, String> {
+    let font = |data: &'a [u8]| {
+      let font = EmbeddedFont::parse(data)?;
+      // Fonts are embedded as FontFile2, which holds TrueType outlines only.
+      if font.face.tables().glyf.is_none() {
+        return Err("only TrueType fonts (glyf outlines) are supported".to_string());
+      }
+      Ok(font)
+    };
+    let bold_data = bold.unwrap_or(regular);
+    Ok(FontFamily {
+      regular: font(regular)?,
+      bold: font(bold_data)?,
+      italic: font(italic.unwrap_or(regular))?,
+      bold_italic: font(bold_italic.unwrap_or(bold_data))?,
+    })
+  }
+
+  pub fn get(&self, style: FontStyle) -> &EmbeddedFont<'a> {
+    match (style.bold, style.italic) {
+      (false, false) => &self.regular,
+      (true, false) => &self.bold,
+      (false, true) => &self.italic,
+      (true, true) => &self.bold_italic,
+    }
+  }
+}
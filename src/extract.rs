@@ -0,0 +1,144 @@
+use napi::bindgen_prelude::Uint8Array;
+use napi::{Error, Status};
+use napi_derive::napi;
+
+use crate::charset::decode_text;
+use crate::doc::read_doc;
+use crate::docx::read_docx;
+use crate::html::{decode_html, html_blocks, parse_html};
+use crate::odt::read_odt;
+use crate::package::Package;
+use crate::pdf::{catch_unwind_silently, extract_text as extract_pdf_text, PdfTextOptions};
+use crate::rtf::read_rtf;
+use crate::text::{clean_pages, TextCleanupOptions};
+use crate::text_input::markdown_blocks;
+use crate::typeset::plain_text;
+use crate::utils::{
+  is_doc_mime, is_docx_mime, is_html_mime, is_markdown_mime, is_odt_mime, is_pdf_mime,
+  is_plain_text_mime, is_rtf_mime,
+};
+
+/// Reader of the text of a document format.
+type TextReader = fn(&[u8]) -> Result<String, String>;
+
+/// Extract the text of a CV in any of the supported formats.
+///
+/// - PDF (`application/pdf`): text in reading order, as with `extract_text_from_pdf`
+///   and `layout: true`
+/// - DOCX: paragraphs of `word/document.xml`, with list numbers and bullets, tables
+///   and text boxes
+/// - DOC (`application/msword`, Word 97-2003): text of the main document, read
+///   through the piece table of its `WordDocument` stream
+/// - ODT (`application/vnd.oasis.opendocument.text`): paragraphs of `content.xml`
+/// - RTF (`application/rtf`, `text/rtf`): text of the document body, decoded from
+///   its code page and `\u` escapes
+/// - HTML (`text/html`, `application/xhtml+xml`): text of the body, a line per block
+///   element; the charset comes from `<meta charset>` when declared
+/// - Plain text (`text/plain`): decoded from UTF-8, UTF-16 or Windows-1252, as
+///   detected
+/// - Markdown (`text/markdown`): the text without its markup, list markers kept
+///
+/// Table cells are separated by tabs. The text is returned as clean UTF-8: NFC
+/// normalized, without control characters, repeated blank lines or trailing spaces.
+/// Other mime types fail with `InvalidArg`.
+#[napi]
+pub fn extract_text(bytes: Uint8Array, mime: String) -> napi::Result<String> {
+  let mime_lc = mime.to_ascii_lowercase();
+  let mime_lc = mime_lc.split(';').next().unwrap_or("").trim();
+  let input = bytes.to_vec();
+
+  if is_pdf_mime(mime_lc) {
+    let options = PdfTextOptions {
+      cleanup: Some(TextCleanupOptions::default()),
+      layout: Some(true),
+      ..Default::default()
+    };
+    return extract_pdf_text(&input, &options).map(|result| result.text);
+  }
+
+  let (format, read): (&str, TextReader) = if is_docx_mime(mime_lc) {
+    ("DOCX", docx_text)
+  } else if is_doc_mime(mime_lc) {
+    ("DOC", |bytes| {
+      read_doc(bytes).map(|blocks| plain_text(&blocks))
+    })
+  } else if is_odt_mime(mime_lc) {
+    ("ODT", odt_text)
+  } else if is_rtf_mime(mime_lc) {
+    ("RTF", |bytes| {
+      read_rtf(bytes).map(|blocks| plain_text(&blocks))
+    })
+  } else if is_html_mime(mime_lc) {
+    ("HTML", |bytes| {
+      Ok(plain_text(&html_blocks(&parse_html(&decode_html(bytes)))))
+    })
+  } else if is_plain_text_mime(mime_lc) {
+    ("text", |bytes| Ok(decode_text(bytes)))
+  } else if is_markdown_mime(mime_lc) {
+    ("Markdown", |bytes| {
+      Ok(plain_text(&markdown_blocks(&decode_text(bytes))))
+    })
+  } else {
+    return Err(Error::new(
+      Status::InvalidArg,
+      format!("Unsupported mime type for text extraction: {mime}"),
+    ));
+  };
+
+  // A document that cannot be read is an invalid argument; a panic is an internal failure.
+  match catch_unwind_silently(|| read(&input)) {
+    Ok(Ok(text)) => Ok(clean_text(&text)),
+    Ok(Err(e)) => Err(Error::new(
+      Status::InvalidArg,
+      format!("Failed to read {format}: {e}"),
+    )),
+    Err(e) => Err(Error::new(
+      Status::GenericFailure,
+      format!("Failed to extract {format} text: {e}"),
+    )),
+  }
+}
+
+fn docx_text(bytes: &[u8]) -> Result<String, String> {
+  let package = Package::open(bytes)?;
+  let (blocks, _) = read_docx(&package)?;
+  Ok(plain_text(&blocks))
+}
+
+fn odt_text(bytes: &[u8]) -> Result<String, String> {
+  let package = Package::open(bytes)?;
+  Ok(plain_text(&read_odt(&package)?))
+}
+
+/// Character and whitespace cleanup; hyphens are kept, as these formats do not
+/// break words across lines, and so are the tabs between table cells.
+fn clean_text(text: &str) -> String {
+  let options = TextCleanupOptions {
+    dehyphenate: Some(false),
+    collapse_whitespace: Some(false),
+    remove_headers_footers: Some(false),
+    ..Default::default()
+  };
+  let text = clean_pages(&[text.to_string()], &options);
+
+  let mut out = String::with_capacity(text.len());
+  let mut blank_lines = 0;
+  for line in text.lines() {
+    let cells: Vec<String> = line
+      .split('\t')
+      .map(|cell| cell.split_whitespace().collect::<Vec<_>>().join(" "))
+      .collect();
+    if cells.iter().all(String::is_empty) {
+      blank_lines += 1;
+      if blank_lines > 1 || out.is_empty() {
+        continue;
+      }
+      out.push('\n');
+    } else {
+      blank_lines = 0;
+      out.push_str(&cells.join("\t"));
+      out.push('\n');
+    }
+  }
+  out.trim_end().to_string()
+}
@@ -0,0 +1,670 @@
+use std::collections::HashMap;
+use std::fmt::Write as FmtWrite;
+
+use lopdf::{Dictionary, Document, Object, ObjectId, Stream};
+use napi::{Error, Status};
+
+use crate::embedded_fonts::{EmbeddedFont, FontFamily, FontStyle};
+use crate::forms::{form_fields, needs_appearances, remove_form, Field};
+use crate::pdf::{catch_panic, load_document};
+use crate::pdf_content::{parse_content, Matrix};
+use crate::pdf_objects::{as_number, as_rect, dict_get, dict_get_dict, page_resources, resolve};
+use crate::pdf_writer::PdfWriter;
+
+/// Annotation flags (/F) of annotations that are not displayed.
+const HIDDEN: i64 = 1 << 1;
+const NO_VIEW: i64 = 1 << 5;
+
+/// Padding between a text field border and its text.
+const FIELD_PADDING: f64 = 2.0;
+/// Font size of auto-sized (`0 Tf`) field text that fits its box.
+const MAX_AUTO_FONT_SIZE: f64 = 12.0;
+/// Resource name of the font of generated field appearances.
+const FIELD_FONT: &str = "DejaVu";
+/// Mark drawn in checked checkboxes and radio buttons without an appearance.
+const CHECK_MARK: &str = "\u{2713}";
+
+/// Draw the form field values into the page content and remove the interactive form.
+///
+/// Each widget is drawn with its appearance stream, the way a viewer shows it. Text
+/// and choice fields without one, or whose form asks for new appearances
+/// (`/NeedAppearances`), get an appearance built from their value; checked
+/// checkboxes and radio buttons without one get a check mark.
+pub(crate) fn flatten_forms(doc: &mut Document) {
+  let generated = generate_field_appearances(doc);
+  flatten_annotations_where(doc, &generated, |subtype| match subtype {
+    b"Widget" => Action::Draw,
+    _ => Action::Keep,
+  });
+  remove_form(doc);
+}
+
+/// Draw all annotations (comments, highlights, stamps, form fields, …) into the page
+/// content and remove them, together with the interactive form.
+///
+/// Link annotations are kept: they have no appearance and carry the CV's URLs. Popups
+/// are removed without being drawn, as viewers only show them on demand. Text markup
+/// annotations without an appearance stream get one built from their quadrilaterals.
+pub(crate) fn flatten_annotations(doc: &mut Document) {
+  let mut generated = generate_field_appearances(doc);
+  generated.extend(generate_markup_appearances(doc));
+  flatten_annotations_where(doc, &generated, |subtype| match subtype {
+    b"Link" => Action::Keep,
+    b"Popup" => Action::Remove,
+    _ => Action::Draw,
+  });
+  remove_form(doc);
+}
+
+/// What flattening does with an annotation.
+#[derive(Clone, Copy, PartialEq)]
+enum Action {
+  Keep,
+  Remove,
+  /// Draw its appearance into the page content, then remove it
+  Draw,
+}
+
+/// Burn the annotations selected by `action` (by subtype) into the page content and
+/// remove them from the pages. `appearances` overrides the appearance stream of some
+/// annotations.
+fn flatten_annotations_where(
+  doc: &mut Document,
+  appearances: &HashMap<ObjectId, ObjectId>,
+  action: impl Fn(&[u8]) -> Action,
+) {
+  for page_id in doc.get_pages().into_values() {
+    if let Some(plan) = plan_page(doc, page_id, appearances, &action) {
+      apply_page(doc, page_id, plan);
+    }
+  }
+}
+
+/// An appearance stream to draw on a page.
+struct Draw {
+  appearance: ObjectId,
+  /// Placement of the appearance on the page
+  matrix: Matrix,
+  /// Constant opacity of the annotation (/CA), when below 1
+  opacity: Option<f64>,
+}
+
+/// Changes to the annotations of a page.
+struct PagePlan {
+  /// Annotations left on the page
+  kept: Vec<Object>,
+  draws: Vec<Draw>,
+}
+
+/// What to change on a page; `None` when there is nothing to flatten.
+fn plan_page(
+  doc: &Document,
+  page_id: ObjectId,
+  appearances: &HashMap<ObjectId, ObjectId>,
+  action: &impl Fn(&[u8]) -> Action,
+) -> Option<PagePlan> {
+  let page = doc.get_dictionary(page_id).ok()?;
+  let annots = dict_get(doc, page, b"Annots")?.as_array().ok()?;
+
+  let mut kept = Vec::new();
+  let mut draws = Vec::new();
+  let mut changed = false;
+  for annot_obj in annots {
+    let Ok(annot) = resolve(doc, annot_obj).as_dict() else {
+      continue;
+    };
+    let subtype = dict_get(doc, annot, b"Subtype")
+      .and_then(|s| s.as_name().ok())
+      .unwrap_or(b"");
+    match action(subtype) {
+      Action::Keep => {
+        kept.push(annot_obj.clone());
+        continue;
+      }
+      Action::Remove => {
+        changed = true;
+        continue;
+      }
+      Action::Draw => changed = true,
+    }
+
+    let flags = dict_get(doc, annot, b"F")
+      .and_then(|f| f.as_i64().ok())
+      .unwrap_or(0);
+    if flags & (HIDDEN | NO_VIEW) != 0 {
+      continue;
+    }
+    let appearance = annot_obj
+      .as_reference()
+      .ok()
+      .and_then(|id| appearances.get(&id).copied())
+      .or_else(|| appearance_stream(doc, annot));
+    let draw = appearance.and_then(|appearance| {
+      let rect = as_rect(doc, annot.get(b"Rect").ok()?)?;
+      Some(Draw {
+        appearance,
+        matrix: placement(doc, appearance, rect)?,
+        opacity: dict_get(doc, annot, b"CA")
+          .and_then(as_number)
+          .filter(|ca| *ca < 1.0),
+      })
+    });
+    draws.extend(draw);
+  }
+  changed.then_some(PagePlan { kept, draws })
+}
+
+/// Normal appearance stream of an annotation, in its current state (/AS) when it has several.
+fn appearance_stream(doc: &Document, annot: &Dictionary) -> Option<ObjectId> {
+  let appearances = dict_get_dict(doc, annot, b"AP")?;
+  let normal = appearances.get(b"N").ok()?;
+  if let Ok(id) = normal.as_reference() {
+    if doc.get_object(id).ok()?.as_stream().is_ok() {
+      return Some(id);
+    }
+  }
+  let states = resolve(doc, normal).as_dict().ok()?;
+  let state = dict_get(doc, annot, b"AS")?.as_name().ok()?;
+  let id = states.get(state).ok()?.as_reference().ok()?;
+  doc.get_object(id).ok()?.as_stream().ok()?;
+  Some(id)
+}
+
+/// Matrix mapping an appearance stream onto the annotation rectangle: its bounding box,
+/// once transformed by its own /Matrix, is scaled and moved to fit the rectangle.
+fn placement(doc: &Document, appearance: ObjectId, rect: [f64; 4]) -> Option<Matrix> {
+  let stream = doc.get_object(appearance).ok()?.as_stream().ok()?;
+  let bbox = as_rect(doc, stream.dict.get(b"BBox").ok()?)?;
+  let matrix = dict_get(doc, &stream.dict, b"Matrix")
+    .and_then(|m| m.as_array().ok())
+    .and_then(|m| {
+      let values: Vec<Object> = m.iter().map(|v| resolve(doc, v).clone()).collect();
+      Matrix::from_operands(&values)
+    })
+    .unwrap_or(Matrix::IDENTITY);
+  let [x0, y0, x1, y1] = matrix.transform_rect(bbox);
+  if x1 - x0 <= 0.0 || y1 - y0 <= 0.0 {
+    return None;
+  }
+  let sx = (rect[2] - rect[0]) / (x1 - x0);
+  let sy = (rect[3] - rect[1]) / (y1 - y0);
+  Some(Matrix {
+    a: sx,
+    d: sy,
+    e: rect[0] - x0 * sx,
+    f: rect[1] - y0 * sy,
+    ..Matrix::IDENTITY
+  })
+}
+
+/// Replace the annotations of a page and draw the appearance streams over its content.
+fn apply_page(doc: &mut Document, page_id: ObjectId, PagePlan { kept, draws }: PagePlan) {
+  let mut updates = Vec::new();
+  if !draws.is_empty() {
+    let mut resources = page_resources(doc, page_id).cloned().unwrap_or_default();
+    let mut xobjects = dict_get_dict(doc, &resources, b"XObject")
+      .cloned()
+      .unwrap_or_default();
+    let mut states = dict_get_dict(doc, &resources, b"ExtGState")
+      .cloned()
+      .unwrap_or_default();
+    let mut content = String::from("Q\n");
+    for draw in draws {
+      let name = unused_name(&xobjects, "FlatAnnot");
+      xobjects.set(name.as_bytes(), Object::Reference(draw.appearance));
+      let m = draw.matrix;
+      content.push_str("q ");
+      if let Some(opacity) = draw.opacity {
+        let state = unused_name(&states, "FlatAnnotGS");
+        states.set(
+          state.as_bytes(),
+          Dictionary::from_iter([("CA", opacity.into()), ("ca", opacity.into())]),
+        );
+        let _ = write!(content, "/{state} gs ");
+      }
+      let _ = writeln!(
+        content,
+        "{} {} {} {} {} {} cm /{name} Do Q",
+        m.a, m.b, m.c, m.d, m.e, m.f
+      );
+    }
+    resources.set("XObject", xobjects);
+    if !states.is_empty() {
+      resources.set("ExtGState", states);
+    }
+
+    // The page content is wrapped in q/Q so its graphics state does not leak into the
+    // annotations drawn after it.
+    let existing = match doc.get_dictionary(page_id).and_then(|p| p.get(b"Contents")) {
+      Ok(Object::Reference(id)) => match doc.get_object(*id) {
+        Ok(Object::Array(items)) => items.clone(),
+        _ => vec![Object::Reference(*id)],
+      },
+      Ok(Object::Array(items)) => items.clone(),
+      _ => Vec::new(),
+    };
+    let before = doc.add_object(Stream::new(Dictionary::new(), b"q\n".to_vec()));
+    let after = doc.add_object(Stream::new(Dictionary::new(), content.into_bytes()));
+    let mut contents = vec![Object::Reference(before)];
+    contents.extend(existing);
+    contents.push(Object::Reference(after));
+
+    updates.push(("Contents", Object::Array(contents)));
+    updates.push(("Resources", Object::Dictionary(resources)));
+  }
+
+  let Ok(page) = doc.get_dictionary_mut(page_id) else {
+    return;
+  };
+  for (key, value) in updates {
+    page.set(key, value);
+  }
+  if kept.is_empty() {
+    page.remove(b"Annots");
+  } else {
+    page.set("Annots", Object::Array(kept));
+  }
+}
+
+/// First `{prefix}{n}` name not used in a resource dictionary.
+fn unused_name(entries: &Dictionary, prefix: &str) -> String {
+  (1..)
+    .map(|n| format!("{prefix}{n}"))
+    .find(|name| !entries.has(name.as_bytes()))
+    .unwrap_or_default()
+}
+
+/// Appearance streams built for the field widgets that need one, by widget: text and
+/// choice fields show their value, checked checkboxes and radio buttons a check mark.
+/// They are set in DejaVu Sans, embedded as a subset, so any value can be shown.
+fn generate_field_appearances(doc: &mut Document) -> HashMap<ObjectId, ObjectId> {
+  let regenerate = needs_appearances(doc);
+  let fonts = FontFamily::dejavu_sans();
+  let font = fonts.get(FontStyle::default());
+  let mut pending = Vec::new();
+  for field in form_fields(doc) {
+    let Some(value) = &field.info.value else {
+      continue;
+    };
+    let button = matches!(field.info.field_type.as_str(), "checkbox" | "radio");
+    if !button && !matches!(field.info.field_type.as_str(), "text" | "choice") {
+      continue;
+    }
+    for widget_id in &field.widgets {
+      let Ok(widget) = doc.get_dictionary(*widget_id) else {
+        continue;
+      };
+      let needed = if button {
+        dict_get(doc, widget, b"AP").is_none() && is_checked(doc, &field, widget, value)
+      } else {
+        regenerate || appearance_stream(doc, widget).is_none()
+      };
+      if !needed {
+        continue;
+      }
+      let Some(rect) = widget.get(b"Rect").ok().and_then(|r| as_rect(doc, r)) else {
+        continue;
+      };
+      let width = rect[2] - rect[0];
+      let height = rect[3] - rect[1];
+      if width <= 0.0 || height <= 0.0 {
+        continue;
+      }
+      let content = if button {
+        check_mark_content(&field, font, width, height)
+      } else {
+        field_appearance_content(&field, font, value, width, height)
+      };
+      pending.push((*widget_id, width, height, content));
+    }
+  }
+  if pending.is_empty() {
+    return HashMap::new();
+  }
+
+  let Some(font_id) = add_font(doc, font) else {
+    return HashMap::new();
+  };
+  pending
+    .into_iter()
+    .map(|(widget_id, width, height, content)| {
+      let fonts = Dictionary::from_iter([(FIELD_FONT, Object::Reference(font_id))]);
+      let resources = Dictionary::from_iter([("Font", Object::Dictionary(fonts))]);
+      let dict = Dictionary::from_iter([
+        ("Type", Object::Name(b"XObject".to_vec())),
+        ("Subtype", Object::Name(b"Form".to_vec())),
+        (
+          "BBox",
+          Object::Array(vec![0.into(), 0.into(), width.into(), height.into()]),
+        ),
+        ("Resources", Object::Dictionary(resources)),
+      ]);
+      (widget_id, doc.add_object(Stream::new(dict, content)))
+    })
+    .collect()
+}
+
+/// Whether a checkbox or radio button widget is checked: its state (/AS) when it has
+/// one, otherwise the field value, which only tells a radio button with a single widget.
+fn is_checked(doc: &Document, field: &Field, widget: &Dictionary, value: &str) -> bool {
+  match dict_get(doc, widget, b"AS").and_then(|s| s.as_name().ok()) {
+    Some(state) => state != b"Off",
+    None => value != "Off" && (field.info.field_type == "checkbox" || field.widgets.len() == 1),
+  }
+}
+
+/// Add `font` to `doc` as a Type0 font, subset to the glyphs encoded so far.
+///
+/// The font objects are written the way generated PDFs embed them, then moved into
+/// `doc` with their object numbers shifted past its own.
+fn add_font(doc: &mut Document, font: &EmbeddedFont) -> Option<ObjectId> {
+  let mut pdf = PdfWriter::new();
+  let catalog = pdf.reserve();
+  let font_id = pdf.reserve();
+  pdf.object(catalog, "<< /Type /Catalog >>");
+  font.write(&mut pdf, font_id);
+  let written = Document::load_mem(&pdf.finish(catalog)).ok()?;
+
+  let offset = doc.max_id;
+  for ((number, generation), mut object) in written.objects {
+    shift_references(&mut object, offset);
+    doc.objects.insert((number + offset, generation), object);
+  }
+  doc.max_id += written.max_id;
+  Some((font_id + offset, 0))
+}
+
+fn shift_references(object: &mut Object, offset: u32) {
+  match object {
+    Object::Reference(id) => id.0 += offset,
+    Object::Array(items) => {
+      for item in items {
+        shift_references(item, offset);
+      }
+    }
+    Object::Dictionary(dict) => {
+      for (_, value) in dict.iter_mut() {
+        shift_references(value, offset);
+      }
+    }
+    Object::Stream(stream) => {
+      for (_, value) in stream.dict.iter_mut() {
+        shift_references(value, offset);
+      }
+    }
+    _ => {}
+  }
+}
+
+/// Appearance streams built for text markup annotations (highlight, underline,
+/// strikeout, squiggly) that have none, by annotation.
+fn generate_markup_appearances(doc: &mut Document) -> HashMap<ObjectId, ObjectId> {
+  let mut pending = Vec::new();
+  for page_id in doc.get_pages().into_values() {
+    let annots = doc
+      .get_dictionary(page_id)
+      .ok()
+      .and_then(|page| dict_get(doc, page, b"Annots"))
+      .and_then(|a| a.as_array().ok());
+    for annot_id in annots
+      .into_iter()
+      .flatten()
+      .filter_map(|a| a.as_reference().ok())
+    {
+      let Ok(annot) = doc.get_dictionary(annot_id) else {
+        continue;
+      };
+      let subtype = dict_get(doc, annot, b"Subtype").and_then(|s| s.as_name().ok());
+      let Some(subtype @ (b"Highlight" | b"Underline" | b"StrikeOut" | b"Squiggly")) = subtype
+      else {
+        continue;
+      };
+      if appearance_stream(doc, annot).is_some() {
+        continue;
+      }
+      let Some(rect) = annot.get(b"Rect").ok().and_then(|r| as_rect(doc, r)) else {
+        continue;
+      };
+      let numbers = |key: &[u8]| -> Vec<f64> {
+        dict_get(doc, annot, key)
+          .and_then(|v| v.as_array().ok())
+          .map(|v| {
+            v.iter()
+              .filter_map(|n| as_number(resolve(doc, n)))
+              .collect()
+          })
+          .unwrap_or_default()
+      };
+      let mut quads = numbers(b"QuadPoints");
+      if quads.len() < 8 {
+        quads = vec![
+          rect[0], rect[3], rect[2], rect[3], rect[0], rect[1], rect[2], rect[1],
+        ];
+      }
+      let is_highlight = subtype == b"Highlight";
+      // Without /C, highlights are yellow and other markups black; an empty /C is transparent.
+      let color = match dict_get(doc, annot, b"C") {
+        None if is_highlight => vec![1.0, 1.0, 0.0],
+        None => vec![0.0],
+        Some(_) => numbers(b"C"),
+      };
+      let Some(content) = markup_content(subtype, &quads, &color) else {
+        continue;
+      };
+      pending.push((annot_id, rect, is_highlight, content));
+    }
+  }
+
+  pending
+    .into_iter()
+    .map(|(annot_id, rect, is_highlight, content)| {
+      let mut resources = Dictionary::new();
+      if is_highlight {
+        // Highlights darken the text under them instead of covering it.
+        let multiply = Dictionary::from_iter([("BM", Object::Name(b"Multiply".to_vec()))]);
+        resources.set(
+          "ExtGState",
+          Dictionary::from_iter([("Multiply", Object::Dictionary(multiply))]),
+        );
+      }
+      let dict = Dictionary::from_iter([
+        ("Type", Object::Name(b"XObject".to_vec())),
+        ("Subtype", Object::Name(b"Form".to_vec())),
+        (
+          "BBox",
+          Object::Array(rect.iter().map(|v| Object::from(*v)).collect()),
+        ),
+        ("Resources", Object::Dictionary(resources)),
+      ]);
+      (annot_id, doc.add_object(Stream::new(dict, content)))
+    })
+    .collect()
+}
+
+/// Content of a text markup appearance, in page coordinates; `None` when transparent.
+fn markup_content(subtype: &[u8], quads: &[f64], color: &[f64]) -> Option<Vec<u8>> {
+  let components = color
+    .iter()
+    .map(|c| format!("{c:.3}"))
+    .collect::<Vec<_>>()
+    .join(" ");
+  let (fill, stroke) = match color.len() {
+    1 => ("g", "G"),
+    3 => ("rg", "RG"),
+    4 => ("k", "K"),
+    _ => return None,
+  };
+
+  let mut out = String::from("q\n");
+  if subtype == b"Highlight" {
+    let _ = writeln!(out, "/Multiply gs {components} {fill}");
+  } else {
+    let _ = writeln!(out, "{components} {stroke}");
+  }
+  for quad in quads.chunks_exact(8) {
+    // Quadrilaterals are reduced to their bounding box (text is rarely rotated).
+    let xs = quad.iter().step_by(2);
+    let ys = quad.iter().skip(1).step_by(2);
+    let (x0, x1) = (
+      xs.clone().copied().fold(f64::INFINITY, f64::min),
+      xs.copied().fold(f64::NEG_INFINITY, f64::max),
+    );
+    let (y0, y1) = (
+      ys.clone().copied().fold(f64::INFINITY, f64::min),
+      ys.copied().fold(f64::NEG_INFINITY, f64::max),
+    );
+    let height = y1 - y0;
+    let line_width = (height / 14.0).max(0.5);
+    match subtype {
+      b"Highlight" => {
+        let _ = writeln!(out, "{x0:.2} {y0:.2} {:.2} {height:.2} re f", x1 - x0);
+      }
+      b"Underline" | b"StrikeOut" => {
+        let y = y0 + height * if subtype == b"Underline" { 0.12 } else { 0.4 };
+        let _ = writeln!(out, "{line_width:.2} w {x0:.2} {y:.2} m {x1:.2} {y:.2} l S");
+      }
+      _ => {
+        // Squiggly: a zigzag under the text
+        let step = (height / 6.0).max(1.0);
+        let (low, high) = (y0 + height * 0.02, y0 + height * 0.1);
+        let _ = write!(out, "{line_width:.2} w {x0:.2} {low:.2} m");
+        let mut x = x0;
+        let mut up = true;
+        while x < x1 {
+          x = (x + step).min(x1);
+          let y = if up { high } else { low };
+          let _ = write!(out, " {x:.2} {y:.2} l");
+          up = !up;
+        }
+        out.push_str(" S\n");
+      }
+    }
+  }
+  out.push_str("Q\n");
+  Some(out.into_bytes())
+}
+
+/// Content of a field appearance: the value in `font`, sized and colored from the
+/// default appearance string and clipped to the field.
+fn field_appearance_content(
+  field: &Field,
+  font: &EmbeddedFont,
+  value: &str,
+  width: f64,
+  height: f64,
+) -> Vec<u8> {
+  let (da_size, color) = parse_default_appearance(field.default_appearance.as_deref());
+  let lines: Vec<String> = if field.multiline {
+    value.lines().map(str::to_string).collect()
+  } else {
+    vec![value.replace(['\r', '\n'], " ")]
+  };
+  let inner_width = width - 2.0 * FIELD_PADDING;
+  let size = match da_size {
+    Some(size) if size > 0.0 => size,
+    // Auto size: fit the height, then the width of the longest line.
+    _ => {
+      let widest = lines.iter().map(|l| font.width(l, 1.0)).fold(0.0, f64::max);
+      let by_height = if field.multiline {
+        MAX_AUTO_FONT_SIZE
+      } else {
+        (height - 2.0 * FIELD_PADDING) / (font.ascent() + font.descent())
+      };
+      let by_width = if widest > 0.0 {
+        inner_width / widest
+      } else {
+        f64::INFINITY
+      };
+      by_height.min(by_width).clamp(4.0, MAX_AUTO_FONT_SIZE)
+    }
+  };
+
+  let mut out = format!(
+    "/Tx BMC\nq\n{p} {p} {:.2} {:.2} re W n\nBT\n/{FIELD_FONT} {size:.2} Tf\n{color}\n",
+    width - 2.0 * FIELD_PADDING,
+    height - 2.0 * FIELD_PADDING,
+    p = FIELD_PADDING,
+  );
+  let leading = size * 1.15;
+  let mut y = if field.multiline {
+    height - FIELD_PADDING - size * font.ascent()
+  } else {
+    // Vertically centered between the ascent and descent of the font.
+    (height - size * (font.ascent() - font.descent())) / 2.0
+  };
+  for line in &lines {
+    let line_width = font.width(line, size);
+    let x = match field.quadding {
+      1 => (width - line_width) / 2.0,
+      2 => width - FIELD_PADDING - line_width,
+      _ => FIELD_PADDING,
+    };
+    let _ = writeln!(out, "1 0 0 1 {x:.2} {y:.2} Tm <{}> Tj", font.encode(line));
+    y -= leading;
+  }
+  out.push_str("ET\nQ\nEMC\n");
+  out.into_bytes()
+}
+
+/// Content of a checked checkbox or radio button: a check mark in `font`, centered
+/// and colored from the default appearance string.
+fn check_mark_content(field: &Field, font: &EmbeddedFont, width: f64, height: f64) -> Vec<u8> {
+  let (da_size, color) = parse_default_appearance(field.default_appearance.as_deref());
+  let fit = ((width.min(height) - 2.0 * FIELD_PADDING) / (font.ascent() + font.descent())).max(1.0);
+  let size = da_size.filter(|size| *size > 0.0).unwrap_or(fit).min(fit);
+  let x = (width - font.width(CHECK_MARK, size)) / 2.0;
+  let y = (height - size * (font.ascent() - font.descent())) / 2.0;
+  format!(
+    "q\nBT\n/{FIELD_FONT} {size:.2} Tf\n{color}\n1 0 0 1 {x:.2} {y:.2} Tm <{}> Tj\nET\nQ\n",
+    font.encode(CHECK_MARK)
+  )
+  .into_bytes()
+}
+
+/// Font size (`Tf`) and color operators of a default appearance string.
+fn parse_default_appearance(da: Option<&str>) -> (Option<f64>, String) {
+  let mut size = None;
+  let mut color = String::from("0 g");
+  for op in parse_content(da.unwrap_or("").as_bytes()) {
+    match op.operator.as_str() {
+      "Tf" => size = op.operands.get(1).and_then(as_number),
+      "g" | "rg" | "k" => {
+        let values: Option<Vec<String>> = op
+          .operands
+          .iter()
+          .map(|v| as_number(v).map(|v| v.to_string()))
+          .collect();
+        if let Some(values) = values {
+          color = format!("{} {}", values.join(" "), op.operator);
+        }
+      }
+      _ => {}
+    }
+  }
+  (size, color)
+}
+
+/// Load a PDF, apply `flatten` to it and save it again.
+pub(crate) fn rewrite_pdf(
+  input: &[u8],
+  flatten: impl FnOnce(&mut Document),
+) -> napi::Result<Vec<u8>> {
+  catch_panic(|| {
+    let mut doc = load_document(input).map_err(|e| {
+      Error::new(
+        Status::InvalidArg,
+        format!("Failed to read PDF for flattening: {e}"),
+      )
+    })?;
+    flatten(&mut doc);
+    doc.prune_objects();
+    let mut output = Vec::new();
+    doc.save_to(&mut output).map_err(|e| {
+      Error::new(
+        Status::GenericFailure,
+        format!("Failed to write flattened PDF: {e}"),
+      )
+    })?;
+    Ok(output)
+  })
+}
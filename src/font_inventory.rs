@@ -0,0 +1,151 @@
+use std::collections::HashSet;
+
+use lopdf::{Dictionary, Document, Object};
+use napi::bindgen_prelude::Uint8Array;
+use napi::{Error, Status};
+use napi_derive::napi;
+
+use crate::pdf::{catch_panic, load_document};
+use crate::pdf_content::walk_page;
+use crate::pdf_objects::{dict_get, font_base_name, font_descriptor, resource};
+
+/// A font used on a PDF page.
+///
+/// - `page_number`: 1-based page number
+/// - `name`: PostScript name (`BaseFont`) without the subset prefix, or the resource
+///   name for fonts without one (Type 3)
+/// - `subtype`: "Type1", "TrueType", "Type0", "Type3", "MMType1", …
+/// - `encoding`: Encoding name (`WinAnsiEncoding`, `Identity-H`, …); "built-in" when
+///   the font uses its own, "embedded CMap" for embedded CMaps; custom differences
+///   are noted as "with Differences"
+/// - `embedded`: Whether the font program is embedded (Type 3 glyphs always are)
+/// - `subset`: Whether the embedded font is a subset (`ABCDEF+` name prefix)
+/// - `has_to_unicode`: Whether a ToUnicode map lets text be extracted reliably
+#[napi(object)]
+pub struct PdfFont {
+  pub page_number: u32,
+  pub name: String,
+  pub subtype: String,
+  pub encoding: String,
+  pub embedded: bool,
+  pub subset: bool,
+  pub has_to_unicode: bool,
+}
+
+/// List the fonts used on each page of a PDF.
+///
+/// Only fonts selected by the page content (including form XObjects) are reported,
+/// once per page. Non-embedded fonts are rendered with substitutes by viewers and
+/// rejected by most printers; fonts without ToUnicode map explain many bad extractions.
+#[napi]
+pub fn list_pdf_fonts(bytes: Uint8Array) -> napi::Result<Vec<PdfFont>> {
+  let input = bytes.to_vec();
+  catch_panic(|| {
+    let doc = load_document(&input)
+      .map_err(|e| Error::new(Status::InvalidArg, format!("Failed to read PDF fonts: {e}")))?;
+    Ok(font_inventory(&doc))
+  })
+}
+
+/// Fonts of a loaded document (see `list_pdf_fonts`).
+pub(crate) fn font_inventory(doc: &Document) -> Vec<PdfFont> {
+  let mut fonts = Vec::new();
+  for (page_number, page_id) in doc.get_pages() {
+    // Font dictionaries already reported for this page
+    let mut seen: HashSet<*const Dictionary> = HashSet::new();
+    walk_page(doc, page_id, &mut |op, frame| {
+      if op.operator != "Tf" {
+        return;
+      }
+      let Some(resource_name) = op.operands.first().and_then(|n| n.as_name().ok()) else {
+        return;
+      };
+      let Some(font) =
+        resource(doc, frame.resources, b"Font", resource_name).and_then(|f| f.as_dict().ok())
+      else {
+        return;
+      };
+      if seen.insert(font as *const Dictionary) {
+        fonts.push(font_info(doc, font, page_number, resource_name));
+      }
+    });
+  }
+  fonts
+}
+
+fn font_info(doc: &Document, font: &Dictionary, page_number: u32, resource_name: &[u8]) -> PdfFont {
+  let name_of = |key: &[u8]| {
+    dict_get(doc, font, key)
+      .and_then(|n| n.as_name().ok())
+      .map(|n| String::from_utf8_lossy(n).into_owned())
+  };
+  let subtype = name_of(b"Subtype").unwrap_or_default();
+  let base_font = name_of(b"BaseFont");
+  let subset = base_font.as_deref().is_some_and(|name| {
+    name.split_once('+').is_some_and(|(prefix, _)| {
+      prefix.len() == 6 && prefix.chars().all(|c| c.is_ascii_uppercase())
+    })
+  });
+  let embedded = subtype == "Type3"
+    || font_descriptor(doc, font).is_some_and(|descriptor| {
+      ["FontFile", "FontFile2", "FontFile3"]
+        .iter()
+        .any(|key| descriptor.has(key.as_bytes()))
+    });
+
+  PdfFont {
+    page_number,
+    name: font_base_name(doc, font)
+      .unwrap_or_else(|| String::from_utf8_lossy(resource_name).into_owned()),
+    subtype,
+    encoding: encoding_name(doc, font),
+    embedded,
+    subset: subset && embedded,
+    has_to_unicode: font.has(b"ToUnicode"),
+  }
+}
+
+fn encoding_name(doc: &Document, font: &Dictionary) -> String {
+  match dict_get(doc, font, b"Encoding") {
+    Some(Object::Name(name)) => String::from_utf8_lossy(name).into_owned(),
+    Some(Object::Stream(_)) => "embedded CMap".to_string(),
+    Some(Object::Dictionary(encoding)) => {
+      let base = dict_get(doc, encoding, b"BaseEncoding")
+        .and_then(|b| b.as_name().ok())
+        .map(|b| String::from_utf8_lossy(b).into_owned());
+      match (base, encoding.has(b"Differences")) {
+        (Some(base), true) => format!("{base} with Differences"),
+        (Some(base), false) => base,
+        (None, true) => "built-in with Differences".to_string(),
+        (None, false) => "built-in".to_string(),
+      }
+    }
+    _ => "built-in".to_string(),
+  }
+}
+
+/// Fail when a PDF uses fonts that are not embedded, naming them.
+pub(crate) fn check_embedded_fonts(input: &[u8]) -> napi::Result<()> {
+  catch_panic(|| {
+    let doc = load_document(input).map_err(|e| {
+      Error::new(
+        Status::InvalidArg,
+        format!("Failed to check PDF fonts: {e}"),
+      )
+    })?;
+    let mut names: Vec<String> = font_inventory(&doc)
+      .into_iter()
+      .filter(|font| !font.embedded)
+      .map(|font| font.name)
+      .collect();
+    names.sort();
+    names.dedup();
+    if names.is_empty() {
+      return Ok(());
+    }
+    Err(Error::new(
+      Status::InvalidArg,
+      format!("PDF uses non-embedded fonts: {}", names.join(", ")),
+    ))
+  })
+}
@@ -0,0 +1,346 @@
+use std::collections::{HashMap, HashSet};
+
+use lopdf::{decode_text_string, Document, Object, ObjectId};
+use napi::bindgen_prelude::Uint8Array;
+use napi::{Error, Status};
+use napi_derive::napi;
+
+use crate::pdf::{catch_panic, load_document};
+use crate::pdf_objects::{dict_get, dict_get_dict, resolve};
+
+/// Field flags (/Ff): multiline text fields, radio buttons and push buttons.
+const FF_MULTILINE: i64 = 1 << 12;
+const FF_RADIO: i64 = 1 << 15;
+const FF_PUSHBUTTON: i64 = 1 << 16;
+
+/// Maximum depth of the field tree, against cyclic /Kids.
+const MAX_FIELD_DEPTH: usize = 32;
+
+/// A form field of a PDF (AcroForm).
+///
+/// - `name`: Fully qualified field name (partial names joined with `.`)
+/// - `field_type`: "text", "checkbox", "radio", "button", "choice" or "signature"
+/// - `value`: Field value: the text, the selected option(s) joined with ", ", or the
+///   state of a checkbox or radio group ("Off" when unchecked); absent when empty
+/// - `page_number`: 1-based page of the field's first widget, when it is on a page
+#[napi(object)]
+pub struct FormField {
+  pub name: String,
+  pub field_type: String,
+  pub value: Option<String>,
+  pub page_number: Option<u32>,
+}
+
+/// Extract the form fields of a PDF (filled-in application forms).
+///
+/// Field values live in the form dictionary and its widget annotations rather than in
+/// the page content, so `extract_text_from_pdf` does not see them. Fields are returned
+/// in document order; push buttons are included with no value.
+#[napi]
+pub fn extract_form_fields(bytes: Uint8Array) -> napi::Result<Vec<FormField>> {
+  let input = bytes.to_vec();
+  catch_panic(|| {
+    let doc = load_document(&input).map_err(|e| {
+      Error::new(
+        Status::InvalidArg,
+        format!("Failed to read PDF form fields: {e}"),
+      )
+    })?;
+    Ok(form_fields(&doc).into_iter().map(|f| f.info).collect())
+  })
+}
+
+/// Attributes a field inherits from its ancestors in the field tree.
+#[derive(Clone, Default)]
+struct Inherited<'a> {
+  field_type: Option<&'a [u8]>,
+  flags: i64,
+  value: Option<&'a Object>,
+  default_appearance: Option<&'a Object>,
+  quadding: Option<i64>,
+}
+
+/// A terminal field, with what is needed to draw its value.
+pub(crate) struct Field {
+  pub info: FormField,
+  /// Default appearance string (`/Helv 0 Tf 0 g`)
+  pub default_appearance: Option<String>,
+  /// Alignment of the text: 0 left, 1 centered, 2 right
+  pub quadding: i64,
+  pub multiline: bool,
+  /// Widget annotations of the field
+  pub widgets: Vec<ObjectId>,
+}
+
+/// Terminal fields of the document's form, in document order.
+pub(crate) fn form_fields(doc: &Document) -> Vec<Field> {
+  let Some(form) = doc
+    .catalog()
+    .ok()
+    .and_then(|catalog| dict_get_dict(doc, catalog, b"AcroForm"))
+  else {
+    return Vec::new();
+  };
+  let Some(roots) = dict_get(doc, form, b"Fields").and_then(|f| f.as_array().ok()) else {
+    return Vec::new();
+  };
+
+  let pages: HashMap<ObjectId, u32> = doc.get_pages().into_iter().map(|(n, id)| (id, n)).collect();
+  let mut widget_pages = HashMap::new();
+  for (&page_id, &page_number) in &pages {
+    let annots = doc
+      .get_dictionary(page_id)
+      .ok()
+      .and_then(|page| dict_get(doc, page, b"Annots"))
+      .and_then(|a| a.as_array().ok());
+    for annot in annots.into_iter().flatten() {
+      if let Ok(id) = annot.as_reference() {
+        widget_pages.insert(id, page_number);
+      }
+    }
+  }
+
+  let mut walker = FieldWalker {
+    doc,
+    pages,
+    widget_pages,
+    visited: HashSet::new(),
+    fields: Vec::new(),
+  };
+  let inherited = Inherited {
+    default_appearance: dict_get(doc, form, b"DA"),
+    quadding: dict_get(doc, form, b"Q").and_then(|q| q.as_i64().ok()),
+    ..Default::default()
+  };
+  for root in roots {
+    walker.visit(root, "", &inherited, 0);
+  }
+  walker.fields
+}
+
+struct FieldWalker<'a> {
+  doc: &'a Document,
+  pages: HashMap<ObjectId, u32>,
+  widget_pages: HashMap<ObjectId, u32>,
+  visited: HashSet<ObjectId>,
+  fields: Vec<Field>,
+}
+
+impl<'a> FieldWalker<'a> {
+  fn visit(&mut self, node: &'a Object, prefix: &str, parent: &Inherited<'a>, depth: usize) {
+    let doc = self.doc;
+    if depth > MAX_FIELD_DEPTH {
+      return;
+    }
+    let id = node.as_reference().ok();
+    if let Some(id) = id {
+      if !self.visited.insert(id) {
+        return;
+      }
+    }
+    let Ok(dict) = resolve(doc, node).as_dict() else {
+      return;
+    };
+
+    let partial = dict_get(doc, dict, b"T").and_then(|t| decode_text_string(t).ok());
+    let name = match (&partial, prefix.is_empty()) {
+      (Some(partial), true) => partial.clone(),
+      (Some(partial), false) => format!("{prefix}.{partial}"),
+      (None, _) => prefix.to_string(),
+    };
+    let inherited = Inherited {
+      field_type: dict_get(doc, dict, b"FT")
+        .and_then(|t| t.as_name().ok())
+        .or(parent.field_type),
+      flags: dict_get(doc, dict, b"Ff")
+        .and_then(|f| f.as_i64().ok())
+        .unwrap_or(parent.flags),
+      value: dict_get(doc, dict, b"V").or(parent.value),
+      default_appearance: dict_get(doc, dict, b"DA").or(parent.default_appearance),
+      quadding: dict_get(doc, dict, b"Q")
+        .and_then(|q| q.as_i64().ok())
+        .or(parent.quadding),
+    };
+
+    // Kids with a partial name are fields; kids without one are widgets of this field.
+    let kids: Vec<&'a Object> = dict_get(doc, dict, b"Kids")
+      .and_then(|k| k.as_array().ok())
+      .map(|k| k.iter().collect())
+      .unwrap_or_default();
+    let (child_fields, widgets): (Vec<&Object>, Vec<&Object>) = kids.into_iter().partition(|kid| {
+      resolve(doc, kid)
+        .as_dict()
+        .is_ok_and(|k| k.has(b"T") || (k.has(b"Kids") && !k.has(b"Subtype")))
+    });
+    if !child_fields.is_empty() {
+      for child in child_fields {
+        self.visit(child, &name, &inherited, depth + 1);
+      }
+      return;
+    }
+
+    let mut widget_ids: Vec<ObjectId> = widgets
+      .iter()
+      .filter_map(|w| w.as_reference().ok())
+      .collect();
+    if widgets.is_empty() {
+      // A field merged with its single widget annotation
+      widget_ids.extend(id);
+    }
+    let page_number = widget_ids.iter().find_map(|w| {
+      self.widget_pages.get(w).copied().or_else(|| {
+        let widget = doc.get_dictionary(*w).ok()?;
+        let page = widget.get(b"P").ok()?.as_reference().ok()?;
+        self.pages.get(&page).copied()
+      })
+    });
+
+    let field_type = field_type(inherited.field_type, inherited.flags);
+    let value = inherited
+      .value
+      .and_then(|v| field_value(doc, v, field_type))
+      .filter(|v| !v.is_empty());
+    self.fields.push(Field {
+      info: FormField {
+        name,
+        field_type: field_type.to_string(),
+        value,
+        page_number,
+      },
+      default_appearance: inherited
+        .default_appearance
+        .and_then(|da| da.as_str().ok())
+        .map(|da| String::from_utf8_lossy(da).into_owned()),
+      quadding: inherited.quadding.unwrap_or(0),
+      multiline: field_type == "text" && inherited.flags & FF_MULTILINE != 0,
+      widgets: widget_ids,
+    });
+  }
+}
+
+fn field_type(field_type: Option<&[u8]>, flags: i64) -> &'static str {
+  match field_type {
+    Some(b"Tx") => "text",
+    Some(b"Btn") if flags & FF_PUSHBUTTON != 0 => "button",
+    Some(b"Btn") if flags & FF_RADIO != 0 => "radio",
+    Some(b"Btn") => "checkbox",
+    Some(b"Ch") => "choice",
+    Some(b"Sig") => "signature",
+    _ => "text",
+  }
+}
+
+/// Field value as text; signature and push button values are not reported.
+fn field_value(doc: &Document, value: &Object, field_type: &str) -> Option<String> {
+  if matches!(field_type, "signature" | "button") {
+    return None;
+  }
+  match resolve(doc, value) {
+    Object::Name(name) => Some(String::from_utf8_lossy(name).into_owned()),
+    text @ Object::String(..) => decode_text_string(text).ok(),
+    Object::Array(items) => Some(
+      items
+        .iter()
+        .filter_map(|item| decode_text_string(resolve(doc, item)).ok())
+        .collect::<Vec<_>>()
+        .join(", "),
+    ),
+    // Multi-line text fields may store long values in a stream.
+    Object::Stream(stream) => stream
+      .decompressed_content()
+      .ok()
+      .map(|data| String::from_utf8_lossy(&data).into_owned()),
+    _ => None,
+  }
+}
+
+/// Whether the form asks viewers to regenerate the field appearances.
+pub(crate) fn needs_appearances(doc: &Document) -> bool {
+  doc
+    .catalog()
+    .ok()
+    .and_then(|catalog| dict_get_dict(doc, catalog, b"AcroForm"))
+    .and_then(|form| dict_get(doc, form, b"NeedAppearances"))
+    .and_then(|n| n.as_bool().ok())
+    .unwrap_or(false)
+}
+
+/// Remove the interactive form from the catalog.
+pub(crate) fn remove_form(doc: &mut Document) {
+  let root = doc.trailer.get(b"Root").and_then(Object::as_reference);
+  if let Ok(catalog) = root
+    .and_then(|id| doc.get_object_mut(id))
+    .and_then(Object::as_dict_mut)
+  {
+    catalog.remove(b"AcroForm");
+  }
+}
+
+/// Remove widget annotations from the form, value and appearance included.
+///
+/// Each widget leaves the `/Kids` of its parent field, or the form's `/Fields`, and
+/// loses its `/V`, `/DV` and `/AP`; a field left without kids is removed from the
+/// form the same way, so its value is no longer in the file.
+pub(crate) fn remove_widgets(doc: &mut Document, widgets: &[ObjectId]) {
+  for &widget in widgets {
+    let mut node = widget;
+    for _ in 0..MAX_FIELD_DEPTH {
+      let Ok(dict) = doc.get_dictionary_mut(node) else {
+        break;
+      };
+      for key in [b"V".as_slice(), b"DV", b"AP"] {
+        dict.remove(key);
+      }
+      let parent = dict.get(b"Parent").and_then(Object::as_reference).ok();
+      let remaining = match parent {
+        Some(parent) => remove_kid(doc, parent, b"Kids", node),
+        None => form_id(doc).and_then(|form| remove_kid(doc, form, b"Fields", node)),
+      };
+      match (parent, remaining) {
+        (Some(parent), Some(0)) => node = parent,
+        _ => break,
+      }
+    }
+  }
+}
+
+/// Id of the form dictionary, moved to an object of its own when it is inline in
+/// the catalog.
+fn form_id(doc: &mut Document) -> Option<ObjectId> {
+  let root = doc
+    .trailer
+    .get(b"Root")
+    .and_then(Object::as_reference)
+    .ok()?;
+  match doc.get_dictionary(root).ok()?.get(b"AcroForm").ok()? {
+    Object::Reference(id) => Some(*id),
+    Object::Dictionary(form) => {
+      let id = doc.add_object(form.clone());
+      doc
+        .get_dictionary_mut(root)
+        .ok()?
+        .set("AcroForm", Object::Reference(id));
+      Some(id)
+    }
+    _ => None,
+  }
+}
+
+/// Remove `kid` from the array at `key` of dictionary `id`; returns how many entries
+/// are left.
+fn remove_kid(doc: &mut Document, id: ObjectId, key: &[u8], kid: ObjectId) -> Option<usize> {
+  let dict = doc.get_dictionary(id).ok()?;
+  let kids: Vec<Object> = dict_get(doc, dict, key)?
+    .as_array()
+    .ok()?
+    .iter()
+    .filter(|k| k.as_reference().ok() != Some(kid))
+    .cloned()
+    .collect();
+  let remaining = kids.len();
+  doc
+    .get_dictionary_mut(id)
+    .ok()?
+    .set(key.to_vec(), Object::Array(kids));
+  Some(remaining)
+}
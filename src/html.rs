@@ -0,0 +1,1571 @@
+use base64::{engine::general_purpose, Engine as _};
+use encoding_rs::{Encoding, UTF_8, WINDOWS_1252};
+
+use crate::charset::{decode_with, detect_encoding, encoding_for_label};
+use crate::css::{box_lengths, font_size, length, Declaration, Stylesheet};
+use crate::embedded_fonts::FontFamily;
+use crate::typeset::{
+  render_pdf, trim_runs, Align, Block, Paragraph, Picture, Rgb, Run, Table, TableCell, TextStyle,
+  BLACK,
+};
+use crate::utils::format_number;
+
+/// Elements without content or end tag.
+const VOID_ELEMENTS: &[&str] = &[
+  "area", "base", "br", "col", "embed", "hr", "img", "input", "link", "meta", "param", "source",
+  "track", "wbr",
+];
+
+/// Elements whose start tag closes an open `<p>`.
+const CLOSES_P: &[&str] = &[
+  "address",
+  "article",
+  "aside",
+  "blockquote",
+  "center",
+  "details",
+  "dialog",
+  "dir",
+  "div",
+  "dl",
+  "fieldset",
+  "figcaption",
+  "figure",
+  "footer",
+  "form",
+  "h1",
+  "h2",
+  "h3",
+  "h4",
+  "h5",
+  "h6",
+  "header",
+  "hgroup",
+  "hr",
+  "li",
+  "main",
+  "menu",
+  "nav",
+  "ol",
+  "p",
+  "pre",
+  "section",
+  "table",
+  "ul",
+];
+
+/// Elements whose content is not rendered.
+const HIDDEN_ELEMENTS: &[&str] = &[
+  "head", "script", "style", "template", "noscript", "svg", "math", "iframe", "object", "select",
+  "canvas", "audio", "video", "map", "datalist", "title", "input", "textarea", "button",
+];
+
+/// Font sizes of the heading levels, in points.
+pub(crate) const HEADING_SIZES: [f64; 6] = [20.0, 16.0, 13.5, 12.0, 11.0, 10.0];
+
+/// Space above the heading levels, in points.
+pub(crate) const HEADING_SPACE_BEFORE: [f64; 6] = [12.0, 10.0, 8.0, 6.0, 6.0, 6.0];
+
+pub(crate) const LINK_COLOR: Rgb = [0.02, 0.27, 0.68];
+pub(crate) const RULE_COLOR: Rgb = [0.6, 0.6, 0.6];
+
+/// Values of `list-style-type` that are supported.
+const LIST_STYLES: &[&str] = &[
+  "none",
+  "disc",
+  "circle",
+  "square",
+  "decimal",
+  "lower-alpha",
+  "lower-latin",
+  "upper-alpha",
+  "upper-latin",
+  "lower-roman",
+  "upper-roman",
+];
+
+/// Maximum depth of the element tree, against pathological input.
+const MAX_DEPTH: usize = 256;
+
+/// An element of an HTML document.
+#[derive(Clone, Debug)]
+pub(crate) struct Element {
+  /// Lowercase tag name
+  pub name: String,
+  /// Attributes with lowercase names and decoded values
+  pub attrs: Vec<(String, String)>,
+  pub children: Vec<usize>,
+}
+
+impl Element {
+  pub fn attr(&self, name: &str) -> Option<&str> {
+    self
+      .attrs
+      .iter()
+      .find(|(n, _)| n == name)
+      .map(|(_, v)| v.as_str())
+  }
+}
+
+#[derive(Clone, Debug)]
+pub(crate) enum HtmlNode {
+  Element(Element),
+  Text(String),
+}
+
+/// A parsed HTML document: nodes by index, node 0 is the document root.
+///
+/// The parser is tolerant the way browsers are for the common cases: unclosed
+/// `<p>`, `<li>`, `<td>` and `<tr>` are closed by the next sibling, stray end
+/// tags are ignored, and `<script>`/`<style>` content is kept as raw text.
+pub(crate) struct HtmlDocument {
+  pub nodes: Vec<HtmlNode>,
+}
+
+impl HtmlDocument {
+  pub fn element(&self, id: usize) -> Option<&Element> {
+    match self.nodes.get(id) {
+      Some(HtmlNode::Element(element)) => Some(element),
+      _ => None,
+    }
+  }
+}
+
+/// Decode an HTML file: a byte order mark, then a `<meta charset>` declaration
+/// in the first 1024 bytes, then the detected encoding (see `detect_encoding`).
+pub(crate) fn decode_html(bytes: &[u8]) -> String {
+  let encoding = Encoding::for_bom(bytes)
+    .map(|(encoding, _)| encoding)
+    .or_else(|| meta_charset(&bytes[..bytes.len().min(1024)]))
+    .unwrap_or_else(|| detect_encoding(bytes));
+  decode_with(bytes, encoding)
+}
+
+/// Encoding declared by `<meta charset="…">` or `<meta http-equiv … content="…; charset=…">`.
+fn meta_charset(head: &[u8]) -> Option<&'static Encoding> {
+  let head = String::from_utf8_lossy(head).to_ascii_lowercase();
+  let mut rest = head.as_str();
+  while let Some(meta) = rest.find("<meta") {
+    rest = &rest[meta + 5..];
+    let tag = &rest[..rest.find('>').unwrap_or(rest.len())];
+    if let Some(position) = tag.find("charset") {
+      let value = tag[position + 7..]
+        .trim_start()
+        .strip_prefix('=')
+        .unwrap_or("")
+        .trim_start()
+        .trim_start_matches(['"', '\'']);
+      let label: String = value
+        .chars()
+        .take_while(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | ':' | '.'))
+        .collect();
+      // A page that is being decoded as ASCII cannot be UTF-16.
+      return encoding_for_label(&label).map(|encoding| {
+        if encoding.name().starts_with("UTF-16") {
+          UTF_8
+        } else {
+          encoding
+        }
+      });
+    }
+  }
+  None
+}
+
+/// Parse `html` into an element tree.
+pub(crate) fn parse_html(html: &str) -> HtmlDocument {
+  let mut builder = TreeBuilder {
+    nodes: vec![HtmlNode::Element(Element {
+      name: "#document".to_string(),
+      attrs: Vec::new(),
+      children: Vec::new(),
+    })],
+    stack: vec![0],
+  };
+  let mut rest = html;
+  while !rest.is_empty() {
+    let Some(lt) = rest.find('<') else {
+      builder.text(&decode_entities(rest));
+      break;
+    };
+    if lt > 0 {
+      builder.text(&decode_entities(&rest[..lt]));
+      rest = &rest[lt..];
+    }
+    if let Some(comment) = rest.strip_prefix("<!--") {
+      rest = comment.find("-->").map_or("", |end| &comment[end + 3..]);
+    } else if rest.starts_with("<!") || rest.starts_with("<?") {
+      rest = rest.find('>').map_or("", |end| &rest[end + 1..]);
+    } else if let Some(end_tag) = rest.strip_prefix("</") {
+      let name = tag_name(end_tag);
+      rest = end_tag.find('>').map_or("", |end| &end_tag[end + 1..]);
+      if !name.is_empty() {
+        builder.close(&name);
+      }
+    } else if rest[1..].starts_with(|c: char| c.is_ascii_alphabetic()) {
+      let (name, attrs, self_closing, after) = start_tag(&rest[1..]);
+      rest = after;
+      let raw_text = matches!(name.as_str(), "script" | "style" | "textarea" | "title");
+      builder.open(name.clone(), attrs, self_closing);
+      if raw_text && !self_closing {
+        // Raw text up to the end tag
+        let end = find_ignore_case(rest, &format!("</{name}")).unwrap_or(rest.len());
+        let content = &rest[..end];
+        if name == "textarea" || name == "title" {
+          builder.text(&decode_entities(content));
+        } else {
+          builder.text(content);
+        }
+        rest = &rest[end..];
+        rest = rest.find('>').map_or("", |gt| &rest[gt + 1..]);
+        builder.close(&name);
+      }
+    } else {
+      builder.text("<");
+      rest = &rest[1..];
+    }
+  }
+  HtmlDocument {
+    nodes: builder.nodes,
+  }
+}
+
+fn find_ignore_case(haystack: &str, needle: &str) -> Option<usize> {
+  haystack
+    .as_bytes()
+    .windows(needle.len())
+    .position(|window| window.eq_ignore_ascii_case(needle.as_bytes()))
+}
+
+fn tag_name(s: &str) -> String {
+  s.chars()
+    .take_while(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | ':' | '_'))
+    .collect::<String>()
+    .to_ascii_lowercase()
+}
+
+/// Parse a start tag after its `<`: name, attributes, whether it ends with `/>`,
+/// and the input after it.
+fn start_tag(s: &str) -> (String, Vec<(String, String)>, bool, &str) {
+  let name = tag_name(s);
+  let mut rest = &s[name.len()..];
+  let mut attrs = Vec::new();
+  let mut self_closing = false;
+  loop {
+    rest = rest.trim_start();
+    if rest.is_empty() {
+      break;
+    }
+    if let Some(after) = rest.strip_prefix('>') {
+      rest = after;
+      break;
+    }
+    if let Some(after) = rest.strip_prefix("/>") {
+      self_closing = true;
+      rest = after;
+      break;
+    }
+    if let Some(after) = rest.strip_prefix('/') {
+      rest = after;
+      continue;
+    }
+    let name_end = rest
+      .find(|c: char| c.is_whitespace() || matches!(c, '=' | '>' | '/'))
+      .unwrap_or(rest.len())
+      .max(1);
+    let attr_name = rest[..name_end].to_ascii_lowercase();
+    rest = rest[name_end..].trim_start();
+    let mut value = String::new();
+    if let Some(after) = rest.strip_prefix('=') {
+      rest = after.trim_start();
+      if let Some(quote) = rest.chars().next().filter(|c| *c == '"' || *c == '\'') {
+        let body = &rest[1..];
+        let end = body.find(quote).unwrap_or(body.len());
+        value = decode_entities(&body[..end]);
+        rest = body.get(end + 1..).unwrap_or("");
+      } else {
+        let end = rest
+          .find(|c: char| c.is_whitespace() || c == '>')
+          .unwrap_or(rest.len());
+        value = decode_entities(&rest[..end]);
+        rest = &rest[end..];
+      }
+    }
+    if !attrs.iter().any(|(n, _)| *n == attr_name) {
+      attrs.push((attr_name, value));
+    }
+  }
+  (name, attrs, self_closing, rest)
+}
+
+/// Replace character references (`&amp;`, `&eacute;`, `&#233;`, `&#xE9;`).
+pub(crate) fn decode_entities(text: &str) -> String {
+  if !text.contains('&') {
+    return text.to_string();
+  }
+  let mut out = String::with_capacity(text.len());
+  let mut rest = text;
+  while let Some(amp) = rest.find('&') {
+    out.push_str(&rest[..amp]);
+    rest = &rest[amp..];
+    match character_reference(&rest[1..]) {
+      Some((c, len)) => {
+        out.push(c);
+        rest = &rest[1 + len..];
+      }
+      None => {
+        out.push('&');
+        rest = &rest[1..];
+      }
+    }
+  }
+  out.push_str(rest);
+  out
+}
+
+/// Character of the reference at the start of `s` (after `&`), with its length.
+fn character_reference(s: &str) -> Option<(char, usize)> {
+  if let Some(number) = s.strip_prefix('#') {
+    let (digits, radix, prefix) = match number.strip_prefix(['x', 'X']) {
+      Some(hex) => (hex, 16, 2),
+      None => (number, 10, 1),
+    };
+    let len = digits
+      .find(|c: char| !c.is_digit(radix))
+      .unwrap_or(digits.len());
+    if len == 0 || len > 8 {
+      return None;
+    }
+    let code = u32::from_str_radix(&digits[..len], radix).ok()?;
+    // Windows-1252 characters referenced by their byte, as browsers do
+    let c = if (0x80..0xA0).contains(&code) {
+      let byte = [code as u8];
+      let (decoded, _) = WINDOWS_1252.decode_without_bom_handling(&byte);
+      decoded.chars().next()?
+    } else {
+      char::from_u32(code).filter(|c| *c != '\0')?
+    };
+    let semicolon = digits[len..].starts_with(';') as usize;
+    return Some((c, prefix + len + semicolon));
+  }
+  let len = s
+    .find(|c: char| !c.is_ascii_alphanumeric())
+    .unwrap_or(s.len());
+  let name = &s[..len];
+  let index = ENTITIES.binary_search_by(|(n, _)| n.cmp(&name)).ok()?;
+  let semicolon = s[len..].starts_with(';') as usize;
+  Some((ENTITIES[index].1, len + semicolon))
+}
+
+struct TreeBuilder {
+  nodes: Vec<HtmlNode>,
+  /// Open elements, the document root first
+  stack: Vec<usize>,
+}
+
+impl TreeBuilder {
+  fn name(&self, id: usize) -> &str {
+    match &self.nodes[id] {
+      HtmlNode::Element(element) => &element.name,
+      HtmlNode::Text(_) => "",
+    }
+  }
+
+  fn append(&mut self, node: HtmlNode) -> usize {
+    let id = self.nodes.len();
+    self.nodes.push(node);
+    let parent = *self.stack.last().unwrap_or(&0);
+    if let HtmlNode::Element(element) = &mut self.nodes[parent] {
+      element.children.push(id);
+    }
+    id
+  }
+
+  fn text(&mut self, text: &str) {
+    if text.is_empty() {
+      return;
+    }
+    let parent = *self.stack.last().unwrap_or(&0);
+    let last_child = match &self.nodes[parent] {
+      HtmlNode::Element(element) => element.children.last().copied(),
+      HtmlNode::Text(_) => None,
+    };
+    if let Some(HtmlNode::Text(existing)) = last_child.map(|id| &mut self.nodes[id]) {
+      existing.push_str(text);
+      return;
+    }
+    self.append(HtmlNode::Text(text.to_string()));
+  }
+
+  /// Index in the stack of the innermost open element named `name`, without
+  /// crossing any of the `boundaries`.
+  fn in_scope(&self, names: &[&str], boundaries: &[&str]) -> Option<usize> {
+    for (index, id) in self
+      .stack
+      .iter()
+      .enumerate()
+      .rev()
+      .take(self.stack.len() - 1)
+    {
+      let name = self.name(*id);
+      if names.contains(&name) {
+        return Some(index);
+      }
+      if boundaries.contains(&name) {
+        return None;
+      }
+    }
+    None
+  }
+
+  fn close_in_scope(&mut self, names: &[&str], boundaries: &[&str]) {
+    if let Some(index) = self.in_scope(names, boundaries) {
+      self.stack.truncate(index);
+    }
+  }
+
+  fn open(&mut self, name: String, attrs: Vec<(String, String)>, self_closing: bool) {
+    const TABLE_SCOPE: &[&str] = &["table", "html"];
+    const SCOPE: &[&str] = &["table", "td", "th", "caption", "html", "button"];
+    if CLOSES_P.contains(&name.as_str()) {
+      self.close_in_scope(&["p"], SCOPE);
+    }
+    match name.as_str() {
+      "li" => self.close_in_scope(&["li"], &["ul", "ol", "table", "td", "th", "html"]),
+      "dt" | "dd" => self.close_in_scope(&["dt", "dd"], &["dl", "table", "td", "th", "html"]),
+      "tr" => self.close_in_scope(&["tr"], TABLE_SCOPE),
+      "td" | "th" => self.close_in_scope(&["td", "th"], &["tr", "table", "html"]),
+      "thead" | "tbody" | "tfoot" => self.close_in_scope(&["thead", "tbody", "tfoot"], TABLE_SCOPE),
+      "h1" | "h2" | "h3" | "h4" | "h5" | "h6" => {
+        let top = *self.stack.last().unwrap_or(&0);
+        if matches!(self.name(top), "h1" | "h2" | "h3" | "h4" | "h5" | "h6") {
+          self.stack.pop();
+        }
+      }
+      "option" => self.close_in_scope(&["option"], &["select", "html"]),
+      _ => {}
+    }
+    let void = VOID_ELEMENTS.contains(&name.as_str());
+    let id = self.append(HtmlNode::Element(Element {
+      name,
+      attrs,
+      children: Vec::new(),
+    }));
+    if !void && !self_closing && self.stack.len() < MAX_DEPTH {
+      self.stack.push(id);
+    }
+  }
+
+  fn close(&mut self, name: &str) {
+    if name == "br" {
+      self.open("br".to_string(), Vec::new(), true);
+      return;
+    }
+    // End tags of table parts close through their children; others stop at
+    // table boundaries, so a stray `</div>` in a cell does not close the table.
+    let boundaries: &[&str] = match name {
+      "table" | "tr" | "td" | "th" | "thead" | "tbody" | "tfoot" | "caption" => &["html"],
+      _ => &["table", "td", "th", "caption", "html"],
+    };
+    self.close_in_scope(&[name], boundaries);
+  }
+}
+
+/// Text formatting and list state inherited by the children of an element.
+#[derive(Clone)]
+struct Context {
+  style: TextStyle,
+  preformatted: bool,
+  align: Align,
+  indent: f64,
+  /// Nesting level of lists
+  list_level: usize,
+  /// `list-style-type` from CSS
+  list_style: Option<String>,
+  /// `text-transform: uppercase`
+  uppercase: bool,
+  /// Line height as a multiple of single spacing
+  line_spacing: f64,
+}
+
+/// Box properties of an element from CSS, which its children do not inherit.
+#[derive(Clone, Default)]
+struct BoxStyle {
+  /// Left margin and padding, in points
+  indent: f64,
+  space_before: Option<f64>,
+  space_after: Option<f64>,
+  border_bottom: Option<Rgb>,
+  /// Color of a border around the element (tables and cells)
+  border: Option<Rgb>,
+  background: Option<Rgb>,
+  width: Option<f64>,
+  height: Option<f64>,
+  /// `list-style-type` declared on the element itself
+  list_style: Option<String>,
+}
+
+/// Open paragraph: its formatting and the runs collected so far.
+struct Flow {
+  blocks: Vec<Block>,
+  runs: Vec<Run>,
+  template: Paragraph,
+}
+
+impl Flow {
+  fn new(template: Paragraph) -> Self {
+    Flow {
+      blocks: Vec::new(),
+      runs: Vec::new(),
+      template,
+    }
+  }
+
+  /// End the open paragraph; text that follows continues its block.
+  fn flush(&mut self) {
+    let mut runs = std::mem::take(&mut self.runs);
+    trim_runs(&mut runs);
+    if runs.is_empty() {
+      return;
+    }
+    self.blocks.push(Block::Paragraph(Paragraph {
+      runs,
+      ..self.template.clone()
+    }));
+    self.template.marker = None;
+    self.template.space_before = 0.0;
+    self.template.first_line_indent = 0.0;
+  }
+
+  fn push(&mut self, text: &str, style: &TextStyle) {
+    match self.runs.last_mut() {
+      Some(run) if run.style == *style => run.text.push_str(text),
+      _ => self.runs.push(Run {
+        text: text.to_string(),
+        style: style.clone(),
+      }),
+    }
+  }
+
+  /// Whether the text so far ends with a collapsible space (or nothing).
+  fn ends_with_space(&self) -> bool {
+    self
+      .runs
+      .last()
+      .is_none_or(|run| run.text.ends_with([' ', '\n']))
+  }
+}
+
+/// Content of an HTML document as blocks: headings, paragraphs, lists, tables,
+/// images and inline formatting (bold, italic, underline, strike, sizes), with
+/// the CSS of `<style>` elements and `style` attributes applied. Scripts,
+/// styles, forms and the document head are left out.
+pub(crate) fn html_blocks(doc: &HtmlDocument) -> Vec<Block> {
+  HtmlReader::new(doc).blocks()
+}
+
+/// Render an HTML document to PDF, on the page size and margins of its
+/// `@page` rule (A4 by default), in DejaVu Sans.
+pub(crate) fn html_to_pdf(bytes: &[u8]) -> Vec<u8> {
+  let doc = parse_html(&decode_html(bytes));
+  let reader = HtmlReader::new(&doc);
+  let page = reader.stylesheet.page_setup();
+  render_pdf(&reader.blocks(), &page, &FontFamily::dejavu_sans())
+}
+
+struct HtmlReader<'d> {
+  doc: &'d HtmlDocument,
+  /// Parent of each node
+  parents: Vec<usize>,
+  stylesheet: Stylesheet,
+}
+
+impl<'d> HtmlReader<'d> {
+  fn new(doc: &'d HtmlDocument) -> Self {
+    let mut parents = vec![0; doc.nodes.len()];
+    let mut css = String::new();
+    for (id, node) in doc.nodes.iter().enumerate() {
+      if let HtmlNode::Element(element) = node {
+        for child in &element.children {
+          parents[*child] = id;
+        }
+        if element.name == "style" {
+          for child in &element.children {
+            if let HtmlNode::Text(text) = &doc.nodes[*child] {
+              css.push_str(text);
+              css.push('\n');
+            }
+          }
+        }
+      }
+    }
+    HtmlReader {
+      doc,
+      parents,
+      stylesheet: Stylesheet::parse(&css),
+    }
+  }
+
+  fn blocks(&self) -> Vec<Block> {
+    let context = Context {
+      style: TextStyle::default(),
+      preformatted: false,
+      align: Align::Left,
+      indent: 0.0,
+      list_level: 0,
+      list_style: None,
+      uppercase: false,
+      line_spacing: 1.0,
+    };
+    let mut flow = Flow::new(Paragraph::default());
+    self.children(0, &context, &mut flow, 0);
+    flow.flush();
+    flow.blocks
+  }
+
+  fn children(&self, id: usize, context: &Context, flow: &mut Flow, depth: usize) {
+    let Some(element) = self.doc.element(id) else {
+      return;
+    };
+    if depth > MAX_DEPTH {
+      return;
+    }
+    for child in &element.children {
+      match &self.doc.nodes[*child] {
+        HtmlNode::Text(text) => self.text(text, context, flow),
+        HtmlNode::Element(_) => self.element(*child, context, flow, depth + 1),
+      }
+    }
+  }
+
+  fn text(&self, text: &str, context: &Context, flow: &mut Flow) {
+    let text = if context.uppercase {
+      text.to_uppercase()
+    } else {
+      text.to_string()
+    };
+    if context.preformatted {
+      let text = text.replace("\r\n", "\n").replace('\t', "    ");
+      flow.push(&text, &context.style);
+      return;
+    }
+    let mut collapsed = String::with_capacity(text.len());
+    let mut space = flow.ends_with_space();
+    for c in text.chars() {
+      if c.is_whitespace() && c != '\u{A0}' {
+        if !space {
+          collapsed.push(' ');
+          space = true;
+        }
+      } else {
+        collapsed.push(c);
+        space = false;
+      }
+    }
+    if !collapsed.is_empty() {
+      flow.push(&collapsed, &context.style);
+    }
+  }
+
+  fn element(&self, id: usize, context: &Context, flow: &mut Flow, depth: usize) {
+    let Some(element) = self.doc.element(id) else {
+      return;
+    };
+    let name = element.name.as_str();
+    if HIDDEN_ELEMENTS.contains(&name) || element.attr("hidden").is_some() {
+      return;
+    }
+    let mut inner = context.clone();
+    let style = &mut inner.style;
+    match name {
+      "b" | "strong" => style.bold = true,
+      "i" | "em" | "cite" | "var" | "dfn" | "address" => style.italic = true,
+      "u" | "ins" => style.underline = true,
+      "s" | "strike" | "del" => style.strike = true,
+      "small" => style.size *= 0.85,
+      "big" => style.size *= 1.2,
+      "sub" | "sup" => style.size *= 0.75,
+      "th" | "dt" => style.bold = true,
+      "a" if element.attr("href").is_some() => {
+        style.underline = true;
+        style.color = LINK_COLOR;
+      }
+      "h1" | "h2" | "h3" | "h4" | "h5" | "h6" => {
+        let level = name[1..].parse::<usize>().unwrap_or(1);
+        style.bold = true;
+        style.size = HEADING_SIZES[level - 1];
+      }
+      "pre" => inner.preformatted = true,
+      "center" => inner.align = Align::Center,
+      _ => {}
+    }
+    let Some((inner, boxed)) = self.styled(id, element, inner, context.style.size) else {
+      return;
+    };
+
+    match name {
+      "br" => flow.push("\n", &context.style),
+      "hr" => {
+        flow.flush();
+        flow.blocks.push(Block::Paragraph(Paragraph {
+          runs: vec![Run {
+            text: String::new(),
+            style: TextStyle {
+              size: 4.0,
+              ..TextStyle::default()
+            },
+          }],
+          indent_left: inner.indent + boxed.indent,
+          space_before: boxed.space_before.unwrap_or(4.0),
+          space_after: boxed.space_after.unwrap_or(8.0),
+          border_bottom: Some(boxed.border_bottom.or(boxed.border).unwrap_or(RULE_COLOR)),
+          ..Paragraph::default()
+        }));
+      }
+      "img" => {
+        if let Some(picture) = self.picture(element, &inner, &boxed) {
+          flow.flush();
+          flow.blocks.push(Block::Picture(picture));
+        }
+      }
+      "h1" | "h2" | "h3" | "h4" | "h5" | "h6" => {
+        let level = name[1..].parse::<usize>().unwrap_or(1);
+        let template = Paragraph {
+          space_before: HEADING_SPACE_BEFORE[level - 1],
+          space_after: 6.0,
+          keep_with_next: true,
+          outline_level: Some(level - 1),
+          ..self.paragraph(&inner)
+        };
+        self.block(id, &inner, template, &boxed, flow, depth);
+      }
+      "p" | "pre" => {
+        let template = Paragraph {
+          space_after: 6.0,
+          ..self.paragraph(&inner)
+        };
+        self.block(id, &inner, template, &boxed, flow, depth);
+      }
+      "blockquote" | "dd" => {
+        let inner = Context {
+          indent: inner.indent + 30.0,
+          ..inner
+        };
+        let template = self.paragraph(&inner);
+        self.block(id, &inner, template, &boxed, flow, depth);
+      }
+      "ul" | "ol" | "menu" | "dir" => self.list(element, &inner, &boxed, flow, depth),
+      "li" => {
+        // List item outside a list
+        let inner = Context {
+          indent: inner.indent + 18.0,
+          ..inner
+        };
+        let template = Paragraph {
+          marker: list_marker(&inner, 1).map(|text| Run {
+            text,
+            style: inner.style.clone(),
+          }),
+          first_line_indent: -12.0,
+          ..self.paragraph(&inner)
+        };
+        self.block(id, &inner, template, &boxed, flow, depth);
+      }
+      "table" => {
+        flow.flush();
+        let inner = Context {
+          indent: inner.indent + boxed.indent,
+          ..inner
+        };
+        if let Some(caption) = element
+          .children
+          .iter()
+          .find(|c| self.doc.element(**c).is_some_and(|e| e.name == "caption"))
+        {
+          let mut caption_context = inner.clone();
+          caption_context.align = Align::Center;
+          let template = self.paragraph(&caption_context);
+          self.block(
+            *caption,
+            &caption_context,
+            template,
+            &BoxStyle::default(),
+            flow,
+            depth,
+          );
+        }
+        if let Some(table) = self.table(id, element, &inner, &boxed, depth) {
+          if let Some(space) = boxed.space_before {
+            flow.blocks.push(spacer(space));
+          }
+          flow.blocks.push(Block::Table(table));
+          if let Some(space) = boxed.space_after {
+            flow.blocks.push(spacer(space));
+          }
+        }
+      }
+      "center" | "div" | "section" | "article" | "header" | "footer" | "main" | "nav" | "aside"
+      | "figure" | "figcaption" | "form" | "fieldset" | "details" | "summary" | "dl" | "dt"
+      | "body" | "html" | "hgroup" | "caption" | "tr" | "td" | "th" | "thead" | "tbody"
+      | "tfoot" | "legend" | "dialog" => {
+        let template = self.paragraph(&inner);
+        self.block(id, &inner, template, &boxed, flow, depth);
+      }
+      _ => self.children(id, &inner, flow, depth),
+    }
+  }
+
+  /// The context of an element with its CSS declarations applied, and its box
+  /// properties; `None` when it is not displayed. Relative font sizes refer to
+  /// `parent_size`.
+  fn styled(
+    &self,
+    id: usize,
+    element: &Element,
+    mut context: Context,
+    parent_size: f64,
+  ) -> Option<(Context, BoxStyle)> {
+    if element.attr("style").is_none() && self.stylesheet.is_empty() {
+      return Some((context, BoxStyle::default()));
+    }
+    let mut chain = vec![element];
+    let mut ancestor = id;
+    while ancestor != 0 && chain.len() <= MAX_DEPTH {
+      ancestor = self.parents[ancestor];
+      if let Some(parent) = self.doc.element(ancestor) {
+        chain.push(parent);
+      }
+    }
+    chain.reverse();
+
+    let mut boxed = BoxStyle::default();
+    let declarations = self.stylesheet.cascade(&chain);
+    // The font size first, as `em` lengths refer to it.
+    for declaration in &declarations {
+      if declaration.property == "font-size" {
+        if let Some(size) = font_size(&declaration.value, parent_size) {
+          context.style.size = size;
+        }
+      } else if declaration.property == "font" {
+        if let Some(size) = declaration
+          .value
+          .split_whitespace()
+          .find_map(|word| font_size(word.split('/').next().unwrap_or(word), parent_size))
+        {
+          context.style.size = size;
+        }
+      }
+    }
+    let size = context.style.size;
+    let length = |value: &str| length(value, size);
+
+    for Declaration {
+      property, value, ..
+    } in &declarations
+    {
+      let keyword = value.trim().to_ascii_lowercase();
+      let style = &mut context.style;
+      match property.as_str() {
+        "display" if keyword == "none" => return None,
+        "visibility" if keyword == "hidden" || keyword == "collapse" => return None,
+        "color" => style.color = parse_color(value).unwrap_or(style.color),
+        "font-weight" => style.bold = is_bold(&keyword, style.bold),
+        "font-style" => style.italic = keyword == "italic" || keyword == "oblique",
+        "font" => {
+          for word in keyword.split_whitespace() {
+            match word {
+              "italic" | "oblique" => style.italic = true,
+              _ => style.bold = is_bold(word, style.bold),
+            }
+          }
+        }
+        "text-decoration" | "text-decoration-line" => {
+          style.underline = keyword.contains("underline");
+          style.strike = keyword.contains("line-through");
+        }
+        "text-align" => context.align = parse_align(&keyword).unwrap_or(context.align),
+        "text-transform" => context.uppercase = keyword == "uppercase",
+        "white-space" => context.preformatted = keyword.starts_with("pre"),
+        "line-height" => {
+          let spacing = match keyword.strip_suffix('%') {
+            Some(percent) => percent.trim().parse::<f64>().ok().map(|p| p / 100.0),
+            None if keyword == "normal" => Some(1.0),
+            None => keyword
+              .parse::<f64>()
+              .ok()
+              .or_else(|| length(&keyword).map(|l| l / size)),
+          };
+          // Single spacing is about 1.2 times the font size.
+          if let Some(spacing) = spacing.filter(|s| *s > 0.0) {
+            context.line_spacing = (spacing / 1.2).clamp(0.5, 5.0);
+          }
+        }
+        "list-style-type" | "list-style" => {
+          if let Some(kind) = keyword.split_whitespace().find(|w| LIST_STYLES.contains(w)) {
+            context.list_style = Some(kind.to_string());
+            boxed.list_style = Some(kind.to_string());
+          }
+        }
+        "margin" | "padding" => {
+          if let Some([top, _, bottom, left]) = box_lengths(value, size) {
+            boxed.indent += left.max(0.0);
+            if property == "margin" {
+              boxed.space_before = Some(top.max(0.0));
+              boxed.space_after = Some(bottom.max(0.0));
+            }
+          }
+        }
+        "margin-left" | "padding-left" => boxed.indent += length(value).unwrap_or(0.0).max(0.0),
+        "margin-top" => boxed.space_before = length(value).map(|l| l.max(0.0)),
+        "margin-bottom" => boxed.space_after = length(value).map(|l| l.max(0.0)),
+        "background" | "background-color" => {
+          boxed.background = value.split_whitespace().find_map(parse_color);
+        }
+        "border" | "border-bottom" => {
+          let color = border_color(&keyword);
+          if property == "border" {
+            boxed.border = color;
+          } else {
+            boxed.border_bottom = color;
+          }
+        }
+        "width" => boxed.width = length(value),
+        "height" => boxed.height = length(value),
+        _ => {}
+      }
+    }
+    Some((context, boxed))
+  }
+
+  /// An `<img>` with an embedded (`data:`) source; other sources are not fetched.
+  fn picture(&self, element: &Element, context: &Context, boxed: &BoxStyle) -> Option<Picture> {
+    let src = element.attr("src")?.trim();
+    let (header, data) = src.split_once(',')?;
+    if !header.get(..5)?.eq_ignore_ascii_case("data:") || !header.ends_with(";base64") {
+      return None;
+    }
+    let data: String = data.chars().filter(|c| !c.is_whitespace()).collect();
+    let data = general_purpose::STANDARD.decode(data).ok()?;
+    // The size attributes are in CSS pixels.
+    let attribute = |name: &str| {
+      let value = element.attr(name)?.trim();
+      value
+        .parse::<f64>()
+        .ok()
+        .map(|px| px * 0.75)
+        .or_else(|| length(value, context.style.size))
+    };
+    Some(Picture {
+      data,
+      width: boxed.width.or_else(|| attribute("width")),
+      height: boxed.height.or_else(|| attribute("height")),
+      align: context.align,
+    })
+  }
+
+  /// Paragraph formatting of a block in `context`.
+  fn paragraph(&self, context: &Context) -> Paragraph {
+    Paragraph {
+      align: context.align,
+      indent_left: context.indent,
+      line_spacing: context.line_spacing,
+      ..Paragraph::default()
+    }
+  }
+
+  /// Lay out a block element: its text starts and ends a paragraph.
+  fn block(
+    &self,
+    id: usize,
+    context: &Context,
+    mut template: Paragraph,
+    boxed: &BoxStyle,
+    flow: &mut Flow,
+    depth: usize,
+  ) {
+    flow.flush();
+    let mut context = context.clone();
+    context.indent += boxed.indent;
+    template.indent_left += boxed.indent;
+    template.space_before = boxed.space_before.unwrap_or(template.space_before);
+    template.space_after = boxed.space_after.unwrap_or(template.space_after);
+    template.border_bottom = boxed.border_bottom.or(template.border_bottom);
+    // The marker of a list item goes to its first paragraph.
+    if template.marker.is_none() && flow.template.marker.is_some() {
+      template.marker = flow.template.marker.take();
+      template.first_line_indent = flow.template.first_line_indent;
+    }
+    let saved = std::mem::replace(&mut flow.template, template);
+    self.children(id, &context, flow, depth);
+    flow.flush();
+    flow.template = saved;
+  }
+
+  fn list(
+    &self,
+    element: &Element,
+    context: &Context,
+    boxed: &BoxStyle,
+    flow: &mut Flow,
+    depth: usize,
+  ) {
+    let ordered = element.name == "ol";
+    let mut counter: i64 = element
+      .attr("start")
+      .and_then(|s| s.trim().parse().ok())
+      .unwrap_or(1);
+    let mut inner = context.clone();
+    // An explicit left margin or padding replaces the default list indent.
+    inner.indent += if boxed.indent > 0.0 {
+      boxed.indent
+    } else {
+      24.0
+    };
+    inner.list_level += 1;
+    inner.list_style = Some(boxed.list_style.clone().unwrap_or_else(|| {
+      match element.attr("type").unwrap_or("1") {
+        _ if !ordered => ["disc", "circle", "square"][(inner.list_level - 1) % 3],
+        "a" => "lower-alpha",
+        "A" => "upper-alpha",
+        "i" => "lower-roman",
+        "I" => "upper-roman",
+        _ => "decimal",
+      }
+      .to_string()
+    }));
+
+    flow.flush();
+    if let Some(space) = boxed.space_before {
+      flow.blocks.push(spacer(space));
+    }
+    for child in &element.children {
+      match &self.doc.nodes[*child] {
+        HtmlNode::Element(item) if item.name == "li" => {
+          if let Some(value) = item.attr("value").and_then(|v| v.trim().parse().ok()) {
+            counter = value;
+          }
+          let Some((item_context, item_box)) =
+            self.styled(*child, item, inner.clone(), inner.style.size)
+          else {
+            continue;
+          };
+          let marker = list_marker(&item_context, counter);
+          counter += 1;
+          let template = Paragraph {
+            marker: marker.map(|text| Run {
+              text,
+              style: item_context.style.clone(),
+            }),
+            first_line_indent: -14.0,
+            space_after: 2.0,
+            ..self.paragraph(&item_context)
+          };
+          self.block(*child, &item_context, template, &item_box, flow, depth + 1);
+        }
+        HtmlNode::Element(_) => self.element(*child, &inner, flow, depth + 1),
+        HtmlNode::Text(text) => self.text(text, &inner, flow),
+      }
+    }
+    flow.flush();
+    if let Some(space) = boxed.space_after {
+      flow.blocks.push(spacer(space));
+    }
+  }
+
+  fn table(
+    &self,
+    id: usize,
+    element: &Element,
+    context: &Context,
+    boxed: &BoxStyle,
+    depth: usize,
+  ) -> Option<Table> {
+    let mut rows = Vec::new();
+    let mut row_ids = Vec::new();
+    let mut cell_borders = None;
+    self.collect_rows(id, &mut row_ids, 0);
+    for row_id in row_ids {
+      let Some(row) = self.doc.element(row_id) else {
+        continue;
+      };
+      let Some((row_context, row_box)) =
+        self.styled(row_id, row, context.clone(), context.style.size)
+      else {
+        continue;
+      };
+      let mut cells = Vec::new();
+      for cell_id in &row.children {
+        let Some(cell) = self.doc.element(*cell_id) else {
+          continue;
+        };
+        if cell.name != "td" && cell.name != "th" {
+          continue;
+        }
+        let mut cell_context = Context {
+          indent: 0.0,
+          list_level: 0,
+          ..row_context.clone()
+        };
+        if cell.name == "th" {
+          cell_context.style.bold = true;
+          cell_context.align = Align::Center;
+        }
+        if let Some(align) = cell.attr("align").and_then(parse_align) {
+          cell_context.align = align;
+        }
+        let size = cell_context.style.size;
+        let Some((cell_context, cell_box)) = self.styled(*cell_id, cell, cell_context, size) else {
+          continue;
+        };
+        cell_borders = cell_borders.or(cell_box.border);
+        let mut cell_flow = Flow::new(self.paragraph(&cell_context));
+        self.children(*cell_id, &cell_context, &mut cell_flow, depth + 1);
+        cell_flow.flush();
+        cells.push(TableCell {
+          blocks: cell_flow.blocks,
+          span: cell
+            .attr("colspan")
+            .and_then(|s| s.trim().parse().ok())
+            .unwrap_or(1usize)
+            .clamp(1, 1000),
+          background: cell_box
+            .background
+            .or_else(|| cell.attr("bgcolor").and_then(parse_color))
+            .or(row_box.background)
+            .or_else(|| row.attr("bgcolor").and_then(parse_color)),
+        });
+      }
+      if !cells.is_empty() {
+        rows.push(cells);
+      }
+    }
+    if rows.is_empty() {
+      return None;
+    }
+    let border_attribute = element
+      .attr("border")
+      .is_some_and(|b| b.trim().parse::<f64>().map_or(true, |width| width > 0.0));
+    Some(Table {
+      columns: Vec::new(),
+      rows,
+      borders: boxed
+        .border
+        .or(cell_borders)
+        .or(border_attribute.then_some(RULE_COLOR)),
+      indent_left: context.indent,
+    })
+  }
+
+  /// Rows of a table, inside row groups but not in nested tables.
+  fn collect_rows(&self, id: usize, rows: &mut Vec<usize>, depth: usize) {
+    let Some(element) = self.doc.element(id) else {
+      return;
+    };
+    for child in &element.children {
+      match self.doc.element(*child).map(|e| e.name.as_str()) {
+        Some("tr") => rows.push(*child),
+        Some("thead" | "tbody" | "tfoot" | "form") if depth < 4 => {
+          self.collect_rows(*child, rows, depth + 1)
+        }
+        _ => {}
+      }
+    }
+  }
+}
+
+fn parse_align(value: &str) -> Option<Align> {
+  match value.trim().to_ascii_lowercase().as_str() {
+    "left" | "start" => Some(Align::Left),
+    "center" => Some(Align::Center),
+    "right" | "end" => Some(Align::Right),
+    "justify" => Some(Align::Justify),
+    _ => None,
+  }
+}
+
+/// Color as `#rgb`, `#rrggbb`, `rgb(…)`/`rgba(…)` or one of the common color names.
+pub(crate) fn parse_color(value: &str) -> Option<Rgb> {
+  let value = value.trim().to_ascii_lowercase();
+  if let Some(args) = value
+    .strip_prefix("rgba(")
+    .or_else(|| value.strip_prefix("rgb("))
+  {
+    let channels: Vec<f64> = args
+      .trim_end_matches(')')
+      .split([',', ' ', '/'])
+      .filter(|c| !c.is_empty())
+      .take(3)
+      .map(|c| match c.strip_suffix('%') {
+        Some(percent) => percent.parse::<f64>().ok().map(|p| p / 100.0),
+        None => c.parse::<f64>().ok().map(|v| v / 255.0),
+      })
+      .collect::<Option<_>>()?;
+    return match channels.as_slice() {
+      [r, g, b] => Some([*r, *g, *b].map(|c| c.clamp(0.0, 1.0))),
+      _ => None,
+    };
+  }
+  if let Some(hex) = value.strip_prefix('#') {
+    let digits: Vec<u32> = hex.chars().map(|c| c.to_digit(16)).collect::<Option<_>>()?;
+    let (r, g, b) = match digits.as_slice() {
+      [r, g, b] => (r * 17, g * 17, b * 17),
+      [r1, r2, g1, g2, b1, b2] => (r1 * 16 + r2, g1 * 16 + g2, b1 * 16 + b2),
+      _ => return None,
+    };
+    return Some([r as f64 / 255.0, g as f64 / 255.0, b as f64 / 255.0]);
+  }
+  let rgb = match value.as_str() {
+    "black" => [0, 0, 0],
+    "white" => [255, 255, 255],
+    "gray" | "grey" => [128, 128, 128],
+    "silver" => [192, 192, 192],
+    "red" => [255, 0, 0],
+    "maroon" => [128, 0, 0],
+    "green" => [0, 128, 0],
+    "lime" => [0, 255, 0],
+    "blue" => [0, 0, 255],
+    "navy" => [0, 0, 128],
+    "teal" => [0, 128, 128],
+    "purple" => [128, 0, 128],
+    "orange" => [255, 165, 0],
+    "yellow" => [255, 255, 0],
+    "olive" => [128, 128, 0],
+    "aqua" | "cyan" => [0, 255, 255],
+    "fuchsia" | "magenta" => [255, 0, 255],
+    "darkgray" | "darkgrey" => [169, 169, 169],
+    "dimgray" | "dimgrey" => [105, 105, 105],
+    "lightgray" | "lightgrey" => [211, 211, 211],
+    "slategray" | "slategrey" => [112, 128, 144],
+    "gainsboro" => [220, 220, 220],
+    "whitesmoke" => [245, 245, 245],
+    "darkblue" => [0, 0, 139],
+    "steelblue" => [70, 130, 180],
+    "royalblue" => [65, 105, 225],
+    "darkred" => [139, 0, 0],
+    "crimson" => [220, 20, 60],
+    "darkgreen" => [0, 100, 0],
+    "brown" => [165, 42, 42],
+    "gold" => [255, 215, 0],
+    _ => return None,
+  };
+  Some(rgb.map(|c| c as f64 / 255.0))
+}
+
+/// Marker of list item number `n` in the `list-style-type` of `context`.
+fn list_marker(context: &Context, n: i64) -> Option<String> {
+  let kind = context.list_style.as_deref().unwrap_or("disc");
+  let number = |kind| format!("{}.", list_number(n, kind));
+  Some(match kind {
+    "none" => return None,
+    "circle" => "◦".to_string(),
+    "square" => "▪".to_string(),
+    "decimal" => number("1"),
+    "lower-alpha" | "lower-latin" => number("a"),
+    "upper-alpha" | "upper-latin" => number("A"),
+    "lower-roman" => number("i"),
+    "upper-roman" => number("I"),
+    _ => "•".to_string(),
+  })
+}
+
+/// Whether a `font-weight` value is bold; `current` for unknown values.
+fn is_bold(value: &str, current: bool) -> bool {
+  match value {
+    "bold" | "bolder" => true,
+    "normal" | "lighter" => false,
+    _ => value.parse::<u32>().map_or(current, |weight| weight >= 600),
+  }
+}
+
+/// Color of a `border` declaration (`1px solid #ccc`); `None` without a border.
+fn border_color(value: &str) -> Option<Rgb> {
+  let words: Vec<&str> = value.split_whitespace().collect();
+  let none = words.iter().any(|w| *w == "none" || *w == "hidden")
+    || words
+      .iter()
+      .any(|w| length(w, 0.0).is_some_and(|width| width <= 0.0));
+  if none {
+    return None;
+  }
+  Some(words.iter().find_map(|w| parse_color(w)).unwrap_or(BLACK))
+}
+
+/// Vertical space between blocks, for the margins of tables and lists.
+fn spacer(height: f64) -> Block {
+  Block::Paragraph(Paragraph {
+    runs: vec![Run {
+      text: String::new(),
+      style: TextStyle {
+        size: 0.1,
+        ..TextStyle::default()
+      },
+    }],
+    space_after: height,
+    ..Paragraph::default()
+  })
+}
+
+/// Number of an ordered list item for a `type` attribute (`1`, `a`, `A`, `i`, `I`).
+fn list_number(n: i64, kind: &str) -> String {
+  if n < 1 {
+    return n.to_string();
+  }
+  format_number(n.min(u32::MAX as i64) as u32, kind)
+}
+
+/// Named character references of HTML 4 (and `&apos;`), sorted for binary search.
+const ENTITIES: &[(&str, char)] = &[
+  ("AElig", 'Æ'),
+  ("Aacute", 'Á'),
+  ("Acirc", 'Â'),
+  ("Agrave", 'À'),
+  ("Alpha", 'Α'),
+  ("Aring", 'Å'),
+  ("Atilde", 'Ã'),
+  ("Auml", 'Ä'),
+  ("Beta", 'Β'),
+  ("Ccedil", 'Ç'),
+  ("Chi", 'Χ'),
+  ("Dagger", '‡'),
+  ("Delta", 'Δ'),
+  ("ETH", 'Ð'),
+  ("Eacute", 'É'),
+  ("Ecirc", 'Ê'),
+  ("Egrave", 'È'),
+  ("Epsilon", 'Ε'),
+  ("Eta", 'Η'),
+  ("Euml", 'Ë'),
+  ("Gamma", 'Γ'),
+  ("Iacute", 'Í'),
+  ("Icirc", 'Î'),
+  ("Igrave", 'Ì'),
+  ("Iota", 'Ι'),
+  ("Iuml", 'Ï'),
+  ("Kappa", 'Κ'),
+  ("Lambda", 'Λ'),
+  ("Mu", 'Μ'),
+  ("Ntilde", 'Ñ'),
+  ("Nu", 'Ν'),
+  ("OElig", 'Œ'),
+  ("Oacute", 'Ó'),
+  ("Ocirc", 'Ô'),
+  ("Ograve", 'Ò'),
+  ("Omega", 'Ω'),
+  ("Omicron", 'Ο'),
+  ("Oslash", 'Ø'),
+  ("Otilde", 'Õ'),
+  ("Ouml", 'Ö'),
+  ("Phi", 'Φ'),
+  ("Pi", 'Π'),
+  ("Prime", '″'),
+  ("Psi", 'Ψ'),
+  ("Rho", 'Ρ'),
+  ("Scaron", 'Š'),
+  ("Sigma", 'Σ'),
+  ("THORN", 'Þ'),
+  ("Tau", 'Τ'),
+  ("Theta", 'Θ'),
+  ("Uacute", 'Ú'),
+  ("Ucirc", 'Û'),
+  ("Ugrave", 'Ù'),
+  ("Upsilon", 'Υ'),
+  ("Uuml", 'Ü'),
+  ("Xi", 'Ξ'),
+  ("Yacute", 'Ý'),
+  ("Yuml", 'Ÿ'),
+  ("Zeta", 'Ζ'),
+  ("aacute", 'á'),
+  ("acirc", 'â'),
+  ("acute", '´'),
+  ("aelig", 'æ'),
+  ("agrave", 'à'),
+  ("alefsym", 'ℵ'),
+  ("alpha", 'α'),
+  ("amp", '&'),
+  ("and", '∧'),
+  ("ang", '∠'),
+  ("apos", '\''),
+  ("aring", 'å'),
+  ("asymp", '≈'),
+  ("atilde", 'ã'),
+  ("auml", 'ä'),
+  ("bdquo", '„'),
+  ("beta", 'β'),
+  ("brvbar", '¦'),
+  ("bull", '•'),
+  ("cap", '∩'),
+  ("ccedil", 'ç'),
+  ("cedil", '¸'),
+  ("cent", '¢'),
+  ("chi", 'χ'),
+  ("circ", 'ˆ'),
+  ("clubs", '♣'),
+  ("cong", '≅'),
+  ("copy", '©'),
+  ("crarr", '↵'),
+  ("cup", '∪'),
+  ("curren", '¤'),
+  ("dArr", '⇓'),
+  ("dagger", '†'),
+  ("darr", '↓'),
+  ("deg", '°'),
+  ("delta", 'δ'),
+  ("diams", '♦'),
+  ("divide", '÷'),
+  ("eacute", 'é'),
+  ("ecirc", 'ê'),
+  ("egrave", 'è'),
+  ("empty", '∅'),
+  ("emsp", '\u{2003}'),
+  ("ensp", '\u{2002}'),
+  ("epsilon", 'ε'),
+  ("equiv", '≡'),
+  ("eta", 'η'),
+  ("eth", 'ð'),
+  ("euml", 'ë'),
+  ("euro", '€'),
+  ("exist", '∃'),
+  ("fnof", 'ƒ'),
+  ("forall", '∀'),
+  ("frac12", '½'),
+  ("frac14", '¼'),
+  ("frac34", '¾'),
+  ("frasl", '⁄'),
+  ("gamma", 'γ'),
+  ("ge", '≥'),
+  ("gt", '>'),
+  ("hArr", '⇔'),
+  ("harr", '↔'),
+  ("hearts", '♥'),
+  ("hellip", '…'),
+  ("iacute", 'í'),
+  ("icirc", 'î'),
+  ("iexcl", '¡'),
+  ("igrave", 'ì'),
+  ("image", 'ℑ'),
+  ("infin", '∞'),
+  ("int", '∫'),
+  ("iota", 'ι'),
+  ("iquest", '¿'),
+  ("isin", '∈'),
+  ("iuml", 'ï'),
+  ("kappa", 'κ'),
+  ("lArr", '⇐'),
+  ("lambda", 'λ'),
+  ("lang", '〈'),
+  ("laquo", '«'),
+  ("larr", '←'),
+  ("lceil", '⌈'),
+  ("ldquo", '“'),
+  ("le", '≤'),
+  ("lfloor", '⌊'),
+  ("lowast", '∗'),
+  ("loz", '◊'),
+  ("lrm", '\u{200E}'),
+  ("lsaquo", '‹'),
+  ("lsquo", '‘'),
+  ("lt", '<'),
+  ("macr", '¯'),
+  ("mdash", '—'),
+  ("micro", 'µ'),
+  ("middot", '·'),
+  ("minus", '−'),
+  ("mu", 'μ'),
+  ("nabla", '∇'),
+  ("nbsp", '\u{00A0}'),
+  ("ndash", '–'),
+  ("ne", '≠'),
+  ("ni", '∋'),
+  ("not", '¬'),
+  ("notin", '∉'),
+  ("nsub", '⊄'),
+  ("ntilde", 'ñ'),
+  ("nu", 'ν'),
+  ("oacute", 'ó'),
+  ("ocirc", 'ô'),
+  ("oelig", 'œ'),
+  ("ograve", 'ò'),
+  ("oline", '‾'),
+  ("omega", 'ω'),
+  ("omicron", 'ο'),
+  ("oplus", '⊕'),
+  ("or", '∨'),
+  ("ordf", 'ª'),
+  ("ordm", 'º'),
+  ("oslash", 'ø'),
+  ("otilde", 'õ'),
+  ("otimes", '⊗'),
+  ("ouml", 'ö'),
+  ("para", '¶'),
+  ("part", '∂'),
+  ("permil", '‰'),
+  ("perp", '⊥'),
+  ("phi", 'φ'),
+  ("pi", 'π'),
+  ("piv", 'ϖ'),
+  ("plusmn", '±'),
+  ("pound", '£'),
+  ("prime", '′'),
+  ("prod", '∏'),
+  ("prop", '∝'),
+  ("psi", 'ψ'),
+  ("quot", '"'),
+  ("rArr", '⇒'),
+  ("radic", '√'),
+  ("rang", '〉'),
+  ("raquo", '»'),
+  ("rarr", '→'),
+  ("rceil", '⌉'),
+  ("rdquo", '”'),
+  ("real", 'ℜ'),
+  ("reg", '®'),
+  ("rfloor", '⌋'),
+  ("rho", 'ρ'),
+  ("rlm", '\u{200F}'),
+  ("rsaquo", '›'),
+  ("rsquo", '’'),
+  ("sbquo", '‚'),
+  ("scaron", 'š'),
+  ("sdot", '⋅'),
+  ("sect", '§'),
+  ("shy", '\u{00AD}'),
+  ("sigma", 'σ'),
+  ("sigmaf", 'ς'),
+  ("sim", '∼'),
+  ("spades", '♠'),
+  ("sub", '⊂'),
+  ("sube", '⊆'),
+  ("sum", '∑'),
+  ("sup", '⊃'),
+  ("sup1", '¹'),
+  ("sup2", '²'),
+  ("sup3", '³'),
+  ("supe", '⊇'),
+  ("szlig", 'ß'),
+  ("tau", 'τ'),
+  ("there4", '∴'),
+  ("theta", 'θ'),
+  ("thetasym", 'ϑ'),
+  ("thinsp", '\u{2009}'),
+  ("thorn", 'þ'),
+  ("tilde", '˜'),
+  ("times", '×'),
+  ("trade", '™'),
+  ("uArr", '⇑'),
+  ("uacute", 'ú'),
+  ("uarr", '↑'),
+  ("ucirc", 'û'),
+  ("ugrave", 'ù'),
+  ("uml", '¨'),
+  ("upsih", 'ϒ'),
+  ("upsilon", 'υ'),
+  ("uuml", 'ü'),
+  ("weierp", '℘'),
+  ("xi", 'ξ'),
+  ("yacute", 'ý'),
+  ("yen", '¥'),
+  ("yuml", 'ÿ'),
+  ("zeta", 'ζ'),
+  ("zwj", '\u{200D}'),
+  ("zwnj", '\u{200C}'),
+];
@@ -0,0 +1,542 @@
+use lopdf::{Dictionary, Object, ObjectId};
+use pdf_extract::{output_doc_page, Document, MediaBox, OutputDev, OutputError, Transform};
+
+use crate::pdf_content::walk_page;
+use crate::pdf_objects::resource;
+
+/// A glyph emitted by pdf-extract, in page space with a top-left origin.
+#[derive(Clone, Debug)]
+pub(crate) struct Glyph {
+  pub text: String,
+  /// Left edge
+  pub x: f64,
+  /// Baseline, measured from the top of the page
+  pub y: f64,
+  /// Advance width
+  pub width: f64,
+  /// Effective font size (text matrix and CTM applied)
+  pub size: f64,
+  /// Index of the string-showing operation that drew the glyph, in page order
+  /// (see `shown_string_fonts`)
+  pub op: usize,
+}
+
+impl Glyph {
+  fn right(&self) -> f64 {
+    self.x + self.width
+  }
+
+  fn is_space(&self) -> bool {
+    self.text.trim().is_empty()
+  }
+}
+
+/// pdf-extract output device that records glyph positions instead of writing text.
+#[derive(Default)]
+pub(crate) struct GlyphCollector {
+  pub pages: Vec<Vec<Glyph>>,
+  page_height: f64,
+  shown_strings: usize,
+}
+
+impl OutputDev for GlyphCollector {
+  fn begin_page(
+    &mut self,
+    _page_num: u32,
+    media_box: &MediaBox,
+    _art_box: Option<(f64, f64, f64, f64)>,
+  ) -> Result<(), OutputError> {
+    self.page_height = media_box.ury - media_box.lly;
+    self.shown_strings = 0;
+    self.pages.push(Vec::new());
+    Ok(())
+  }
+
+  fn end_page(&mut self) -> Result<(), OutputError> {
+    Ok(())
+  }
+
+  fn output_character(
+    &mut self,
+    trm: &Transform,
+    width: f64,
+    _spacing: f64,
+    font_size: f64,
+    char: &str,
+  ) -> Result<(), OutputError> {
+    // Same geometry as pdf-extract's PlainTextOutput: the font size is scaled by the
+    // text rendering matrix and the origin is flipped to the top of the page.
+    let sx = font_size * (trm.m11 + trm.m21);
+    let sy = font_size * (trm.m12 + trm.m22);
+    let size = (sx * sy).abs().sqrt();
+    let glyph = Glyph {
+      text: char.to_string(),
+      x: trm.m31,
+      y: self.page_height - trm.m32,
+      width: width * size,
+      size,
+      op: self.shown_strings.saturating_sub(1),
+    };
+    if let Some(page) = self.pages.last_mut() {
+      page.push(glyph);
+    }
+    Ok(())
+  }
+
+  fn begin_word(&mut self) -> Result<(), OutputError> {
+    // pdf-extract calls this once per shown string (`Tj` and each `TJ` element).
+    self.shown_strings += 1;
+    Ok(())
+  }
+
+  fn end_word(&mut self) -> Result<(), OutputError> {
+    Ok(())
+  }
+
+  fn end_line(&mut self) -> Result<(), OutputError> {
+    Ok(())
+  }
+}
+
+/// Collect the glyphs of a single page (1-based page number).
+pub(crate) fn collect_page_glyphs(
+  doc: &Document,
+  page_num: u32,
+) -> Result<Vec<Glyph>, OutputError> {
+  let mut collector = GlyphCollector::default();
+  output_doc_page(doc, &mut collector, page_num)?;
+  Ok(collector.pages.pop().unwrap_or_default())
+}
+
+/// Font of every string shown on a page, indexed like `Glyph::op`.
+///
+/// pdf-extract does not expose fonts to its output devices, so the page content is
+/// walked again with the same rules: `Tf` selects the font, `q`/`Q` save and restore
+/// it, each `Tj` string and `TJ` string element is one entry, and `Do` descends into
+/// form XObjects, which start without a font.
+pub(crate) fn shown_string_fonts(doc: &Document, page_id: ObjectId) -> Vec<Option<&Dictionary>> {
+  let mut fonts = Vec::new();
+  // Current font and `q` stack of the page and of each form being drawn.
+  let mut levels: Vec<(Option<&Dictionary>, Vec<Option<&Dictionary>>)> = Vec::new();
+  walk_page(doc, page_id, &mut |op, frame| {
+    levels.truncate(frame.depth + 1);
+    levels.resize_with(frame.depth + 1, Default::default);
+    let (font, saved) = &mut levels[frame.depth];
+    match op.operator.as_str() {
+      "q" => saved.push(*font),
+      "Q" => *font = saved.pop().unwrap_or(*font),
+      "Tf" => {
+        *font = op
+          .operands
+          .first()
+          .and_then(|o| o.as_name().ok())
+          .and_then(|n| resource(doc, frame.resources, b"Font", n))
+          .and_then(|f| f.as_dict().ok());
+      }
+      "Tj" => fonts.push(*font),
+      "TJ" => {
+        if let Some(Object::Array(items)) = op.operands.first() {
+          for item in items {
+            if matches!(item, Object::String(..)) {
+              fonts.push(*font);
+            }
+          }
+        }
+      }
+      _ => {}
+    }
+  });
+  fonts
+}
+
+/// A run of glyphs on one baseline, within a single column.
+#[derive(Clone, Debug)]
+pub(crate) struct TextLine {
+  pub glyphs: Vec<Glyph>,
+  pub x0: f64,
+  pub x1: f64,
+  pub y: f64,
+  pub size: f64,
+}
+
+impl TextLine {
+  fn from_glyphs(glyphs: Vec<Glyph>) -> TextLine {
+    let solid = || glyphs.iter().filter(|g| !g.is_space());
+    let x0 = solid().map(|g| g.x).fold(f64::INFINITY, f64::min);
+    let x1 = solid().map(|g| g.right()).fold(f64::NEG_INFINITY, f64::max);
+    let y = solid().map(|g| g.y).sum::<f64>() / solid().count().max(1) as f64;
+    let size = solid().map(|g| g.size).fold(0.0, f64::max);
+    TextLine {
+      glyphs,
+      x0,
+      x1,
+      y,
+      size,
+    }
+  }
+
+  /// Concatenate the glyphs, inserting spaces where the gap between two glyphs
+  /// is wider than a fraction of the font size.
+  pub fn text(&self) -> String {
+    let mut out = String::new();
+    for (g, space_before) in self.spaced_glyphs() {
+      if space_before && !out.is_empty() {
+        out.push(' ');
+      }
+      out.push_str(&g.text);
+    }
+    out.trim().to_string()
+  }
+
+  /// The non-space glyphs of the line, each flagged with whether a word space
+  /// precedes it (an explicit space glyph or a wide enough gap).
+  pub fn spaced_glyphs(&self) -> Vec<(&Glyph, bool)> {
+    let mut out = Vec::new();
+    let mut last_right: Option<f64> = None;
+    let mut pending_space = false;
+    for g in &self.glyphs {
+      if g.is_space() {
+        pending_space = true;
+        last_right = Some(g.right());
+        continue;
+      }
+      if let Some(right) = last_right {
+        if g.x > right + g.size * WORD_GAP {
+          pending_space = true;
+        }
+      }
+      out.push((g, pending_space && !out.is_empty()));
+      pending_space = false;
+      last_right = Some(g.right());
+    }
+    out
+  }
+}
+
+/// A group of consecutive lines of the same column (a paragraph or list).
+#[derive(Clone, Debug)]
+pub(crate) struct TextBlock {
+  pub lines: Vec<TextLine>,
+}
+
+/// Horizontal gap (in font sizes) that separates two words.
+const WORD_GAP: f64 = 0.15;
+/// Horizontal gap (in font sizes) that splits a baseline into separate segments.
+const SEGMENT_GAP: f64 = 2.0;
+/// Vertical gap (in line heights) that starts a new block.
+const BLOCK_GAP: f64 = 1.8;
+
+/// Largest page width allowed by the PDF specification (200 inches), which bounds
+/// the size of the column histogram.
+const MAX_PAGE_WIDTH: f64 = 14_400.0;
+
+/// Group glyphs into blocks of lines in natural reading order.
+///
+/// Glyphs are clustered into baselines, baselines are split into segments at wide
+/// horizontal gaps, and vertical gutters (x ranges almost no segment crosses) define
+/// columns. Lines crossing a gutter (e.g. a centered name) are emitted in place;
+/// between them each column is read top to bottom, left column first. `page` is the
+/// page box (see `page_box`); text outside it cannot form columns.
+pub(crate) fn reading_order_blocks(glyphs: &[Glyph], page: [f64; 4]) -> Vec<TextBlock> {
+  let baselines = group_baselines(glyphs);
+  let segments: Vec<Vec<TextLine>> = baselines.into_iter().map(split_segments).collect();
+  let columns = detect_columns(&segments, page);
+
+  // Walk baselines top to bottom; collect per-column lines into a band until a
+  // spanning line forces the band to be flushed.
+  let mut blocks: Vec<TextBlock> = Vec::new();
+  let mut band: Vec<Vec<TextLine>> = vec![Vec::new(); columns.len()];
+  let mut spanning_tail = false;
+
+  for line_segments in segments {
+    let assignment: Option<Vec<usize>> = line_segments
+      .iter()
+      .map(|s| column_of(&columns, s))
+      .collect();
+
+    match assignment {
+      Some(cols) => {
+        // Segments of the same column on the same baseline are merged back together.
+        let mut per_column: Vec<Option<TextLine>> = vec![None; columns.len()];
+        for (segment, col) in line_segments.into_iter().zip(cols) {
+          per_column[col] = Some(match per_column[col].take() {
+            Some(prev) => merge_lines(prev, segment),
+            None => segment,
+          });
+        }
+        for (col, line) in per_column.into_iter().enumerate() {
+          if let Some(line) = line {
+            band[col].push(line);
+          }
+        }
+        spanning_tail = false;
+      }
+      None => {
+        let flushed = flush_band(&mut band, &mut blocks);
+        let Some(line) = line_segments.into_iter().reduce(merge_lines) else {
+          continue;
+        };
+        // Consecutive spanning lines that are close together (a multi-line header)
+        // stay in one block.
+        match blocks.last_mut() {
+          Some(prev) if spanning_tail && !flushed && follows(prev, &line) => prev.lines.push(line),
+          _ => blocks.push(TextBlock { lines: vec![line] }),
+        }
+        spanning_tail = true;
+      }
+    }
+  }
+  flush_band(&mut band, &mut blocks);
+
+  blocks
+}
+
+/// Render blocks as plain text: lines separated by newlines, blocks by blank lines.
+pub(crate) fn blocks_to_text(blocks: &[TextBlock]) -> String {
+  blocks
+    .iter()
+    .map(|b| {
+      b.lines
+        .iter()
+        .map(|l| l.text())
+        .collect::<Vec<_>>()
+        .join("\n")
+    })
+    .filter(|t| !t.is_empty())
+    .collect::<Vec<_>>()
+    .join("\n\n")
+}
+
+/// Cluster glyphs into baselines (top to bottom), each sorted left to right.
+fn group_baselines(glyphs: &[Glyph]) -> Vec<Vec<Glyph>> {
+  let mut sorted: Vec<&Glyph> = glyphs.iter().collect();
+  sorted.sort_by(|a, b| a.y.total_cmp(&b.y).then(a.x.total_cmp(&b.x)));
+
+  let mut lines: Vec<(f64, Vec<Glyph>)> = Vec::new();
+  for g in sorted {
+    let tolerance = g.size.max(1.0) * 0.35;
+    match lines.last_mut() {
+      Some((y, line)) if (g.y - *y).abs() <= tolerance => line.push(g.clone()),
+      _ => lines.push((g.y, vec![g.clone()])),
+    }
+  }
+
+  lines
+    .into_iter()
+    .map(|(_, mut line)| {
+      line.sort_by(|a, b| a.x.total_cmp(&b.x));
+      line
+    })
+    .filter(|line| line.iter().any(|g| !g.is_space()))
+    .collect()
+}
+
+/// Split a baseline wherever the gap between two glyphs exceeds `SEGMENT_GAP` font sizes.
+fn split_segments(line: Vec<Glyph>) -> Vec<TextLine> {
+  let mut segments = Vec::new();
+  let mut current: Vec<Glyph> = Vec::new();
+  let mut last_right: Option<f64> = None;
+
+  for g in line {
+    if g.is_space() {
+      current.push(g);
+      continue;
+    }
+    if let Some(right) = last_right {
+      if g.x - right > g.size.max(1.0) * SEGMENT_GAP {
+        segments.push(TextLine::from_glyphs(std::mem::take(&mut current)));
+      }
+    }
+    last_right = Some(g.right());
+    current.push(g);
+  }
+  if current.iter().any(|g| !g.is_space()) {
+    segments.push(TextLine::from_glyphs(current));
+  }
+  segments
+}
+
+fn merge_lines(mut a: TextLine, b: TextLine) -> TextLine {
+  a.glyphs.extend(b.glyphs);
+  TextLine::from_glyphs(a.glyphs)
+}
+
+/// A column: segments starting at or after `x0` and ending before `x1` belong to it.
+#[derive(Clone, Copy, Debug)]
+struct Column {
+  x0: f64,
+  x1: f64,
+}
+
+/// Find vertical gutters and return the resulting columns, left to right.
+///
+/// A gutter is an x range at least one and a half font sizes wide that is crossed
+/// by at most 5% of the segments, with enough text on both sides: the smaller side
+/// must hold at least a quarter as many segments as the larger one, so that a
+/// single-column layout with a dates "column" is not split.
+fn detect_columns(segments: &[Vec<TextLine>], page: [f64; 4]) -> Vec<Column> {
+  let all: Vec<&TextLine> = segments.iter().flatten().collect();
+  let single = vec![Column {
+    x0: f64::NEG_INFINITY,
+    x1: f64::INFINITY,
+  }];
+  if all.len() < 6 {
+    return single;
+  }
+
+  // Segments are clamped to the page, whatever their coordinates.
+  let page_x0 = page[0].min(page[2]);
+  let page_x1 = page[0].max(page[2]).min(page_x0 + MAX_PAGE_WIDTH);
+  if !(page_x0.is_finite() && page_x1.is_finite()) {
+    return single;
+  }
+  let clamp = |x: f64| x.clamp(page_x0, page_x1);
+  let min_x = all
+    .iter()
+    .map(|s| clamp(s.x0))
+    .fold(f64::INFINITY, f64::min)
+    .floor();
+  let max_x = all
+    .iter()
+    .map(|s| clamp(s.x1))
+    .fold(f64::NEG_INFINITY, f64::max)
+    .ceil();
+  if !(min_x.is_finite() && max_x.is_finite()) {
+    return single;
+  }
+  let bins = ((max_x - min_x) as usize).max(1);
+  let mut coverage = vec![0usize; bins];
+  for s in &all {
+    let a = ((clamp(s.x0) - min_x).floor().max(0.0) as usize).min(bins - 1);
+    let b = ((clamp(s.x1) - min_x).ceil().max(0.0) as usize).min(bins);
+    for c in coverage.iter_mut().take(b).skip(a) {
+      *c += 1;
+    }
+  }
+
+  let median_size = median(all.iter().map(|s| s.size).collect()).max(1.0);
+  let min_width = (median_size * 1.5).ceil() as usize;
+  let tolerance = (all.len() / 20).max(1);
+
+  // Candidate gutters: runs of low coverage away from the content edges, narrowed
+  // to their least covered part so that a few spanning lines do not shift them.
+  let mut gutters: Vec<(f64, f64)> = Vec::new();
+  let mut i = 1;
+  while i < bins {
+    if coverage[i] > tolerance {
+      i += 1;
+      continue;
+    }
+    let start = i;
+    while i < bins && coverage[i] <= tolerance {
+      i += 1;
+    }
+    if i == bins {
+      break;
+    }
+    let run = &coverage[start..i];
+    let lowest = run.iter().copied().min().unwrap_or(0);
+    let (mut best, mut best_len, mut cur, mut cur_len) = (0, 0, 0, 0);
+    for (j, c) in run.iter().enumerate() {
+      if *c == lowest {
+        if cur_len == 0 {
+          cur = j;
+        }
+        cur_len += 1;
+        if cur_len > best_len {
+          best = cur;
+          best_len = cur_len;
+        }
+      } else {
+        cur_len = 0;
+      }
+    }
+    if best_len >= min_width {
+      let g0 = min_x + (start + best) as f64;
+      gutters.push((g0, g0 + best_len as f64));
+    }
+  }
+
+  let mut columns: Vec<Column> = Vec::new();
+  let mut left_bound = f64::NEG_INFINITY;
+  let mut left_mid = f64::NEG_INFINITY;
+  for (g0, g1) in gutters {
+    let mid = (g0 + g1) / 2.0;
+    let left_count = all
+      .iter()
+      .filter(|s| s.x0 >= left_mid && s.x1 <= mid)
+      .count();
+    let right: Vec<&&TextLine> = all.iter().filter(|s| s.x0 >= mid).collect();
+    let (small, large) = (left_count.min(right.len()), left_count.max(right.len()));
+    if small < 3 || small * 4 < large {
+      continue;
+    }
+    columns.push(Column {
+      x0: left_bound,
+      x1: g1,
+    });
+    // The next column starts at its usual left margin (with room for hanging
+    // bullets), so a centered line starting inside the gutter is not part of it.
+    left_bound = median(right.iter().map(|s| s.x0).collect()) - median_size * 2.0;
+    left_mid = mid;
+  }
+
+  if columns.is_empty() {
+    return single;
+  }
+  columns.push(Column {
+    x0: left_bound,
+    x1: f64::INFINITY,
+  });
+  columns
+}
+
+fn median(mut values: Vec<f64>) -> f64 {
+  if values.is_empty() {
+    return 0.0;
+  }
+  values.sort_by(f64::total_cmp);
+  values[values.len() / 2]
+}
+
+/// Index of the column that contains the segment, if any.
+fn column_of(columns: &[Column], segment: &TextLine) -> Option<usize> {
+  columns
+    .iter()
+    .position(|c| segment.x0 >= c.x0 && segment.x1 <= c.x1)
+}
+
+/// Emit the lines of each column of the current band as blocks, then reset the band.
+/// Returns whether any block was emitted.
+fn flush_band(band: &mut [Vec<TextLine>], blocks: &mut Vec<TextBlock>) -> bool {
+  let before = blocks.len();
+  for column in band.iter_mut() {
+    let mut current: Vec<TextLine> = Vec::new();
+    for line in column.drain(..) {
+      if let Some(prev) = current.last() {
+        let line_height = prev.size.max(line.size).max(1.0);
+        if line.y - prev.y > line_height * BLOCK_GAP {
+          blocks.push(TextBlock {
+            lines: std::mem::take(&mut current),
+          });
+        }
+      }
+      current.push(line);
+    }
+    if !current.is_empty() {
+      blocks.push(TextBlock { lines: current });
+    }
+  }
+  blocks.len() > before
+}
+
+/// Whether `line` directly continues the last line of `block` (overlapping x range,
+/// less than `BLOCK_GAP` line heights below it).
+fn follows(block: &TextBlock, line: &TextLine) -> bool {
+  let Some(last) = block.lines.last() else {
+    return false;
+  };
+  let line_height = last.size.max(line.size).max(1.0);
+  let gap = line.y - last.y;
+  line.x0 < last.x1 && line.x1 > last.x0 && gap > 0.0 && gap <= line_height * BLOCK_GAP
+}
@@ -1,17 +1,74 @@
 #![deny(clippy::all)]
 
+mod analysis;
+mod anonymize;
 mod base64;
+mod charset;
+mod css;
+mod diff;
+mod doc;
+mod docx;
+mod email;
+mod embedded_fonts;
+mod extract;
+mod flatten;
+mod font_inventory;
+mod forms;
+mod html;
 mod image;
+mod layout;
+mod markdown;
 mod normalize;
+mod ocr;
+mod odt;
+mod outline;
+mod package;
 mod pdf;
+mod pdf_content;
+mod pdf_fonts;
+mod pdf_images;
+mod pdf_objects;
+mod pdf_writer;
+mod pii;
+mod pii_report;
+mod quality;
+mod redact;
+mod resume;
+mod rtf;
+mod text;
+mod text_input;
+mod text_layer;
+mod typeset;
 mod utils;
 
 // Re-export all NAPI functions
+pub use analysis::{analyze_pdf, PdfAnalysis, PdfPageAnalysis};
+pub use anonymize::{anonymize_cv, AnonymizeOptions, AnonymizedCv, Redaction};
 pub use base64::{base64_to_buffer, buffer_to_base64};
+pub use diff::{diff_cv_text, CvDiffLine, CvDiffMove, CvDiffOptions, CvDiffWord, CvTextDiff};
+pub use email::{extract_cvs_from_email, EmailCv, EmailCvs};
+pub use extract::extract_text;
+pub use font_inventory::{list_pdf_fonts, PdfFont};
+pub use forms::{extract_form_fields, FormField};
 pub use image::{
   convert_images_to_webp_recursive, image_to_webp, image_to_webp_from_base64,
   image_to_webp_from_file, optimize_image, optimize_image_from_base64, optimize_image_from_file,
   ConversionStats, ImageOptimizeOptions,
 };
-pub use normalize::normalize_cv_to_pdf;
-pub use pdf::extract_text_from_pdf;
+pub use normalize::{normalize_cv_to_pdf, NormalizeOptions};
+pub use ocr::{ocr_document, OcrOptions, OcrPage, OcrResult};
+pub use outline::{extract_pdf_outline, set_pdf_outline, PdfOutlineItem};
+pub use pdf::{
+  extract_markdown_from_pdf, extract_text_from_pdf, extract_text_from_pdf_detailed, PdfTextOptions,
+  PdfTextResult,
+};
+pub use pdf_images::{extract_pdf_images, PdfImage, PdfImageOptions};
+pub use pii_report::{detect_pii, PiiFinding, PiiReport, PiiReportOptions};
+pub use quality::{score_text_quality, TextQuality};
+pub use redact::{redact_pdf, PdfRedactionArea, RedactPdfOptions, RedactPdfResult};
+pub use resume::{
+  render_resume_pdf, JsonResume, ResumeAward, ResumeBasics, ResumeCertificate, ResumeEducation,
+  ResumeLanguage, ResumeLocation, ResumeProfile, ResumeProject, ResumePublication, ResumeReference,
+  ResumeSkill, ResumeTemplate, ResumeWork,
+};
+pub use text::TextCleanupOptions;
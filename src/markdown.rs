@@ -0,0 +1,331 @@
+use std::collections::HashMap;
+
+use lopdf::{Object, ObjectId};
+use pdf_extract::Document;
+
+use crate::layout::{reading_order_blocks, shown_string_fonts, Glyph, TextBlock, TextLine};
+use crate::pdf::PageGlyphs;
+use crate::pdf_objects::{as_rect, dict_get, dict_get_dict, font_is_bold, page_box, resolve};
+
+/// Glyphs that mark a list item on their own.
+const BULLETS: &[&str] = &[
+  "•", "◦", "▪", "▫", "‣", "●", "○", "■", "□", "◆", "◇", "➢", "➤", "►", "▸", "✓", "✔", "·", "",
+];
+/// Glyphs that mark a list item when followed by a space.
+const DASHES: &[&str] = &["-", "–", "—", "*"];
+
+/// Glyphs of a line, each flagged with whether a word space precedes it.
+type SpacedGlyphs<'g> = Vec<(&'g Glyph, bool)>;
+
+/// A URI link annotation, in the same top-left page space as the glyphs.
+struct Link {
+  x0: f64,
+  y0: f64,
+  x1: f64,
+  y1: f64,
+  uri: String,
+}
+
+/// A page laid out in reading order, with the styling needed for Markdown.
+pub(crate) struct MarkdownPage {
+  blocks: Vec<TextBlock>,
+  bold_ops: Vec<bool>,
+  links: Vec<Link>,
+}
+
+enum Element {
+  Heading(usize, String),
+  Item(String),
+  Paragraph(Vec<String>),
+}
+
+impl MarkdownPage {
+  pub fn new(doc: &Document, page_id: ObjectId, page: PageGlyphs) -> MarkdownPage {
+    let glyphs = page.glyphs;
+    let fonts = page
+      .fonts
+      .unwrap_or_else(|| shown_string_fonts(doc, page_id));
+    // When the font walk disagrees with pdf-extract, styling is dropped rather than
+    // attributed to the wrong text.
+    let bold_ops = if glyphs.iter().all(|g| g.op < fonts.len()) {
+      fonts
+        .iter()
+        .map(|f| f.is_some_and(|f| font_is_bold(doc, f)))
+        .collect()
+    } else {
+      Vec::new()
+    };
+
+    MarkdownPage {
+      blocks: reading_order_blocks(&glyphs, page_box(doc, page_id)),
+      bold_ops,
+      links: page_links(doc, page_id),
+    }
+  }
+
+  fn is_bold(&self, g: &Glyph) -> bool {
+    self.bold_ops.get(g.op).copied().unwrap_or(false)
+  }
+
+  fn link_of(&self, g: &Glyph) -> Option<usize> {
+    let cx = g.x + g.width / 2.0;
+    let cy = g.y - g.size * 0.3;
+    self
+      .links
+      .iter()
+      .position(|l| cx >= l.x0 - 1.0 && cx <= l.x1 + 1.0 && cy >= l.y0 - 1.0 && cy <= l.y1 + 1.0)
+  }
+
+  fn render_block(&self, block: &TextBlock, body_size: f64, elements: &mut Vec<Element>) {
+    let first = elements.len();
+    // Left edge of the text of the current list item, for continuation lines.
+    let mut item_indent: Option<f64> = None;
+
+    for line in &block.lines {
+      let glyphs = line.spaced_glyphs();
+      if glyphs.is_empty() {
+        continue;
+      }
+
+      if let Some(rest) = strip_bullet(&glyphs) {
+        item_indent = Some(rest[0].0.x);
+        elements.push(Element::Item(self.render_inline(rest, true)));
+        continue;
+      }
+
+      if let (Some(indent), Some(Element::Item(item))) = (item_indent, elements.last_mut()) {
+        if line.x0 >= indent - line.size * 0.5 {
+          item.push(' ');
+          item.push_str(&self.render_inline(&glyphs, true));
+          continue;
+        }
+      }
+      item_indent = None;
+
+      let in_block = elements.len() > first;
+      match self.heading_level(line, &glyphs, body_size) {
+        Some(level) => {
+          let text = self.render_inline(&glyphs, false);
+          match elements.last_mut() {
+            Some(Element::Heading(prev, heading)) if *prev == level && in_block => {
+              heading.push(' ');
+              heading.push_str(&text);
+            }
+            _ => elements.push(Element::Heading(level, text)),
+          }
+        }
+        None => {
+          let text = escape_line_start(self.render_inline(&glyphs, true));
+          match elements.last_mut() {
+            Some(Element::Paragraph(lines)) if in_block => lines.push(text),
+            _ => elements.push(Element::Paragraph(vec![text])),
+          }
+        }
+      }
+    }
+  }
+
+  /// Heading level from the line size relative to the body text; bold lifts a
+  /// size-based heading by one level, and short bold lines at body size are `###`.
+  fn heading_level(
+    &self,
+    line: &TextLine,
+    glyphs: &[(&Glyph, bool)],
+    body_size: f64,
+  ) -> Option<usize> {
+    let bold = glyphs.iter().all(|(g, _)| self.is_bold(g));
+    let words = glyphs.iter().filter(|(_, space)| *space).count() + 1;
+    let ratio = line.size / body_size;
+    let tier = if ratio >= 1.6 {
+      1
+    } else if ratio >= 1.3 {
+      2
+    } else if ratio >= 1.12 {
+      3
+    } else {
+      0
+    };
+
+    if tier > 0 && words <= 15 {
+      return Some(if bold && tier > 1 { tier - 1 } else { tier });
+    }
+    let last = glyphs.last().map(|(g, _)| g.text.as_str()).unwrap_or("");
+    if bold && words <= 10 && ![".", ",", ";"].contains(&last) {
+      return Some(3);
+    }
+    None
+  }
+
+  /// Render glyphs as inline Markdown: links, and bold runs when `emphasize` is set.
+  fn render_inline(&self, glyphs: &[(&Glyph, bool)], emphasize: bool) -> String {
+    let mut out = String::new();
+    for (link, space, part) in runs(glyphs, |g| self.link_of(g)) {
+      let mut text = String::new();
+      for (bold, space, words) in runs(&part, |g| emphasize && self.is_bold(g)) {
+        let words = escape_inline(&join_glyphs(&words));
+        if bold && words.chars().any(char::is_alphanumeric) {
+          push_spaced(&mut text, space, &format!("**{words}**"));
+        } else {
+          push_spaced(&mut text, space, &words);
+        }
+      }
+      match link {
+        Some(i) => {
+          let uri = self.links[i].uri.replace(' ', "%20").replace(')', "%29");
+          push_spaced(&mut out, space, &format!("[{text}]({uri})"));
+        }
+        None => push_spaced(&mut out, space, &text),
+      }
+    }
+    out
+  }
+}
+
+/// Render pages as Markdown. Heading levels are relative to the body font size,
+/// the size that covers most characters of the document.
+pub(crate) fn render_markdown(pages: &[MarkdownPage]) -> String {
+  let mut sizes: HashMap<i64, usize> = HashMap::new();
+  for line in pages.iter().flat_map(|p| &p.blocks).flat_map(|b| &b.lines) {
+    *sizes.entry((line.size * 2.0).round() as i64).or_insert(0) += line.glyphs.len();
+  }
+  let body_size = sizes
+    .into_iter()
+    .max_by_key(|(size, count)| (*count, -*size))
+    .map(|(size, _)| size as f64 / 2.0)
+    .unwrap_or(0.0)
+    .max(1.0);
+
+  let mut elements = Vec::new();
+  for page in pages {
+    for block in &page.blocks {
+      page.render_block(block, body_size, &mut elements);
+    }
+  }
+
+  let mut out = String::new();
+  let mut previous_item = false;
+  for element in elements {
+    let is_item = matches!(element, Element::Item(_));
+    if !out.is_empty() {
+      out.push_str(if is_item && previous_item {
+        "\n"
+      } else {
+        "\n\n"
+      });
+    }
+    match element {
+      Element::Heading(level, text) => {
+        out.push_str(&"#".repeat(level));
+        out.push(' ');
+        out.push_str(&text);
+      }
+      Element::Item(text) => {
+        out.push_str("- ");
+        out.push_str(&text);
+      }
+      Element::Paragraph(lines) => out.push_str(&lines.join("\n")),
+    }
+    previous_item = is_item;
+  }
+  out
+}
+
+/// URI link annotations of a page.
+fn page_links(doc: &Document, page_id: ObjectId) -> Vec<Link> {
+  let [_, lly, _, ury] = page_box(doc, page_id);
+  let height = ury - lly;
+  let Some(annots) = doc
+    .get_dictionary(page_id)
+    .ok()
+    .and_then(|page| dict_get(doc, page, b"Annots"))
+    .and_then(|a| a.as_array().ok())
+  else {
+    return Vec::new();
+  };
+
+  annots
+    .iter()
+    .filter_map(|annot| {
+      let annot = resolve(doc, annot).as_dict().ok()?;
+      if dict_get(doc, annot, b"Subtype").and_then(|s| s.as_name().ok()) != Some(b"Link") {
+        return None;
+      }
+      let action = dict_get_dict(doc, annot, b"A")?;
+      let uri = match dict_get(doc, action, b"URI")? {
+        Object::String(bytes, _) => String::from_utf8_lossy(bytes).trim().to_string(),
+        _ => return None,
+      };
+      let [x0, y0, x1, y1] = as_rect(doc, annot.get(b"Rect").ok()?)?;
+      (!uri.is_empty()).then_some(Link {
+        x0,
+        y0: height - y1,
+        x1,
+        y1: height - y0,
+        uri,
+      })
+    })
+    .collect()
+}
+
+/// The glyphs after a leading bullet, if the line is a list item.
+fn strip_bullet<'a, 'g>(glyphs: &'a [(&'g Glyph, bool)]) -> Option<&'a [(&'g Glyph, bool)]> {
+  let (first, rest) = glyphs.split_first()?;
+  let marker = first.0.text.trim();
+  let spaced = rest.first().is_some_and(|(_, space)| *space);
+  let is_bullet = BULLETS.contains(&marker) || (DASHES.contains(&marker) && spaced);
+  (is_bullet && !rest.is_empty()).then_some(rest)
+}
+
+/// Split glyphs into maximal runs sharing the same key. Each run carries the
+/// space flag of its first glyph.
+fn runs<'g, K: PartialEq>(
+  glyphs: &[(&'g Glyph, bool)],
+  key: impl Fn(&Glyph) -> K,
+) -> Vec<(K, bool, SpacedGlyphs<'g>)> {
+  let mut out: Vec<(K, bool, SpacedGlyphs)> = Vec::new();
+  for &(g, space) in glyphs {
+    let k = key(g);
+    match out.last_mut() {
+      Some((last, _, run)) if *last == k => run.push((g, space)),
+      _ => out.push((k, space, vec![(g, space)])),
+    }
+  }
+  out
+}
+
+fn join_glyphs(glyphs: &[(&Glyph, bool)]) -> String {
+  let mut out = String::new();
+  for (g, space) in glyphs {
+    push_spaced(&mut out, *space, &g.text);
+  }
+  out
+}
+
+fn push_spaced(out: &mut String, space: bool, text: &str) {
+  if space && !out.is_empty() {
+    out.push(' ');
+  }
+  out.push_str(text);
+}
+
+/// Escape characters that would otherwise be read as emphasis, links or code.
+fn escape_inline(text: &str) -> String {
+  let mut out = String::with_capacity(text.len());
+  for c in text.chars() {
+    if matches!(c, '\\' | '*' | '[' | ']' | '`') {
+      out.push('\\');
+    }
+    out.push(c);
+  }
+  out
+}
+
+/// Escape a paragraph line that would otherwise start a heading, quote or rule.
+fn escape_line_start(line: String) -> String {
+  let is_rule = line.len() >= 3 && line.chars().all(|c| matches!(c, '-' | '=' | '_'));
+  if line.starts_with('#') || line.starts_with('>') || line.starts_with("+ ") || is_rule {
+    format!("\\{line}")
+  } else {
+    line
+  }
+}
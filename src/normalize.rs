@@ -1,5 +1,4 @@
 use std::fmt::Write as FmtWrite;
-use std::io::Write as IoWrite;
 
 use image::imageops::FilterType;
 use image::GenericImageView;
@@ -7,26 +6,84 @@ use napi::bindgen_prelude::Uint8Array;
 use napi::{Error, Status};
 use napi_derive::napi;
 
+use crate::charset::decode_text;
+use crate::doc::doc_to_pdf;
+use crate::docx::docx_to_pdf;
+use crate::embedded_fonts::FontFamily;
+use crate::flatten::{flatten_annotations, flatten_forms, rewrite_pdf};
+use crate::font_inventory::check_embedded_fonts;
+use crate::html::html_to_pdf;
 use crate::image::{encode_to_jpeg, load_image_with_orientation};
-use crate::pdf::try_optimize_pdf_with_ghostscript;
-use crate::utils::{calculate_target_size, is_pdf_mime, is_supported_image_mime, map_image_error};
+use crate::pdf::{catch_unwind_silently, try_optimize_pdf_with_ghostscript};
+use crate::pdf_writer::PdfWriter;
+use crate::text_input::{markdown_blocks, text_blocks};
+use crate::text_layer::{glyphless_font, identity_to_unicode_cmap, OcrLayout};
+use crate::typeset::{render_pdf, PageSetup};
+use crate::utils::{
+  calculate_target_size, is_doc_mime, is_docx_mime, is_html_mime, is_markdown_mime, is_pdf_mime,
+  is_plain_text_mime, is_supported_image_mime, map_image_error,
+};
+
+/// Options for CV normalization.
+///
+/// - `ocr`: OCR results for an image input, as hOCR or ALTO XML (detected from the
+///   content). The recognized words are added over the image as an invisible text
+///   layer, which makes the PDF searchable and selectable. Ignored for PDF input.
+/// - `flatten_forms`: For PDF input, draw the form field values into the page content
+///   and remove the interactive form (default: false)
+/// - `flatten`: For PDF input, draw all annotations (comments, highlights, stamps, form
+///   fields) into the page content and remove them; links are kept (default: false)
+/// - `require_embedded_fonts`: For PDF input, fail with `InvalidArg` when the input
+///   uses fonts that are not embedded, as printers reject them (default: false)
+#[napi(object)]
+#[derive(Clone, Default)]
+pub struct NormalizeOptions {
+  pub ocr: Option<String>,
+  pub flatten_forms: Option<bool>,
+  pub flatten: Option<bool>,
+  pub require_embedded_fonts: Option<bool>,
+}
 
 /// Normalize a CV file to PDF and optionally compress it.
 ///
 /// V1 behavior:
 /// - If the mime type is a supported image (`image/png`, `image/jpeg`, `image/jpg`),
 ///   the image is decoded, optionally downscaled, recompressed as JPEG,
-///   and wrapped into a single-page PDF.
-/// - If the mime type is `application/pdf`, the input is currently validated
-///   (must start with `%PDF-`) then returned unchanged. This is the hook where
-///   a real PDF optimization pipeline can be implemented later.
+///   and wrapped into a single-page PDF. With `options.ocr`, an invisible text layer
+///   is placed over the image, scaled to the page size chosen here.
+/// - If the mime type is `application/pdf`, the input is validated (must start
+///   with `%PDF-`). With `options.require_embedded_fonts`, it is rejected when its
+///   pages use a font that is not embedded. With `options.flatten` or
+///   `options.flatten_forms`, annotations or form fields are then flattened into
+///   the page content, natively. The result is optimized with Ghostscript when it
+///   is installed, and returned as is otherwise.
+/// - If the mime type is DOCX, the document is rendered to PDF natively:
+///   paragraphs, headings, lists, tables, bold/italic, colors and embedded
+///   images are laid out on the document's page size, with an embedded Unicode
+///   font. Headers, footers and floating positions are not reproduced.
+/// - If the mime type is `application/msword` (Word 97-2003), the text of the
+///   document is read from its piece table and laid out on A4 pages, keeping
+///   paragraphs and tables but not the formatting.
+/// - If the mime type is `text/plain` or `text/markdown`, the text (UTF-8,
+///   UTF-16 or Windows-1252) is laid out on A4 pages with an embedded Unicode
+///   font: lines and bullets for plain text; headings, lists, emphasis, links,
+///   quotes, code and tables for Markdown.
+/// - If the mime type is `text/html`, the document is laid out natively:
+///   headings, paragraphs, lists, tables and embedded (`data:`) images, with a
+///   CSS subset (`<style>` rules and `style` attributes) for font sizes, weights,
+///   colors, alignment, margins, borders and the `@page` size and margins.
 /// - For any other mime type, the input bytes are returned unchanged.
 #[napi]
-pub fn normalize_cv_to_pdf(bytes: Uint8Array, mime: String) -> napi::Result<Vec<u8>> {
+pub fn normalize_cv_to_pdf(
+  bytes: Uint8Array,
+  mime: String,
+  options: Option<NormalizeOptions>,
+) -> napi::Result<Vec<u8>> {
   let mime_lc = mime.to_ascii_lowercase();
   let input = bytes.to_vec();
+  let options = options.unwrap_or_default();
 
-  // PDF: validate header, then try to optimize; fall back to original on any failure.
+  // PDF: validate header, flatten, then try to optimize; keep the PDF when Ghostscript fails.
   if is_pdf_mime(&mime_lc) {
     if !input.starts_with(b"%PDF-") {
       return Err(Error::new(
@@ -34,17 +91,66 @@ pub fn normalize_cv_to_pdf(bytes: Uint8Array, mime: String) -> napi::Result<Vec<
         "Input declared as application/pdf but does not start with %PDF- header",
       ));
     }
+    if options.require_embedded_fonts.unwrap_or(false) {
+      check_embedded_fonts(&input)?;
+    }
+    let input = if options.flatten.unwrap_or(false) {
+      rewrite_pdf(&input, flatten_annotations)?
+    } else if options.flatten_forms.unwrap_or(false) {
+      rewrite_pdf(&input, flatten_forms)?
+    } else {
+      input
+    };
     if let Some(optimized) = try_optimize_pdf_with_ghostscript(&input) {
       return Ok(optimized);
     }
     return Ok(input);
   }
 
+  if is_docx_mime(&mime_lc) {
+    return rendered("DOCX", catch_unwind_silently(|| docx_to_pdf(&input)));
+  }
+
+  if is_doc_mime(&mime_lc) {
+    return rendered("DOC", catch_unwind_silently(|| doc_to_pdf(&input)));
+  }
+
+  if is_plain_text_mime(&mime_lc) || is_markdown_mime(&mime_lc) {
+    let markdown = is_markdown_mime(&mime_lc);
+    return catch_unwind_silently(|| text_to_pdf(&input, markdown)).map_err(|e| {
+      Error::new(
+        Status::GenericFailure,
+        format!("Failed to render text: {e}"),
+      )
+    });
+  }
+
+  if is_html_mime(&mime_lc) {
+    return catch_unwind_silently(|| html_to_pdf(&input)).map_err(|e| {
+      Error::new(
+        Status::GenericFailure,
+        format!("Failed to render HTML: {e}"),
+      )
+    });
+  }
+
   // Images: normalize to single-page PDF.
   if !is_supported_image_mime(&mime_lc) {
     return Ok(input);
   }
 
+  let ocr = options
+    .ocr
+    .as_deref()
+    .map(OcrLayout::parse)
+    .transpose()
+    .map_err(|e| {
+      Error::new(
+        Status::InvalidArg,
+        format!("Failed to parse OCR results: {e}"),
+      )
+    })?;
+
   let img = load_image_with_orientation(&input).map_err(map_image_error)?;
 
   // Basic downscaling to avoid huge PDFs: keep longest side <= 2000px
@@ -59,95 +165,122 @@ pub fn normalize_cv_to_pdf(bytes: Uint8Array, mime: String) -> napi::Result<Vec<
   };
 
   let jpeg_bytes = encode_to_jpeg(resized, 80).map_err(map_image_error)?;
-  let pdf_bytes = jpeg_to_single_page_pdf(&jpeg_bytes, target_w, target_h);
+  // OCR coordinates refer to the image before downscaling.
+  let text_layer = ocr.map(|ocr| {
+    ocr.content(
+      target_w as f64,
+      target_h as f64,
+      (orig_w as f64, orig_h as f64),
+      "F0",
+    )
+  });
+  let pdf_bytes = jpeg_to_single_page_pdf(&jpeg_bytes, target_w, target_h, text_layer.as_deref());
 
   Ok(pdf_bytes)
 }
 
+/// PDF rendered from a document in `format`: a document that cannot be read is an
+/// invalid argument; a panic while rendering is an internal failure.
+fn rendered(
+  format: &str,
+  result: Result<Result<Vec<u8>, String>, String>,
+) -> napi::Result<Vec<u8>> {
+  match result {
+    Ok(Ok(pdf)) => Ok(pdf),
+    Ok(Err(e)) => Err(Error::new(
+      Status::InvalidArg,
+      format!("Failed to read {format}: {e}"),
+    )),
+    Err(e) => Err(Error::new(
+      Status::GenericFailure,
+      format!("Failed to render {format}: {e}"),
+    )),
+  }
+}
+
+/// Render plain text or Markdown to A4 pages, in DejaVu Sans.
+fn text_to_pdf(bytes: &[u8], markdown: bool) -> Vec<u8> {
+  let text = decode_text(bytes);
+  let blocks = if markdown {
+    markdown_blocks(&text)
+  } else {
+    text_blocks(&text)
+  };
+  render_pdf(&blocks, &PageSetup::default(), &FontFamily::dejavu_sans())
+}
+
 /// Build a minimal single-page PDF embedding the given JPEG bytes.
 ///
 /// We embed the JPEG as an image XObject with /Filter /DCTDecode and draw it
 /// to fill the page. Dimensions are in "points" but we simply reuse the
 /// pixel dimensions, which is acceptable for CV images.
-fn jpeg_to_single_page_pdf(jpeg_bytes: &[u8], width: u32, height: u32) -> Vec<u8> {
-  let mut pdf = Vec::new();
-  let mut xref_positions: Vec<usize> = Vec::new();
-
-  // Helper to start an object and record its offset for the xref table.
-  let start_obj = |pdf: &mut Vec<u8>, xref: &mut Vec<usize>, id: u32| {
-    xref.push(pdf.len());
-    let _ = IoWrite::write_fmt(pdf, format_args!("{} 0 obj\n", id));
-  };
-
-  pdf.extend_from_slice(b"%PDF-1.4\n");
-
-  // 1: Catalog
-  start_obj(&mut pdf, &mut xref_positions, 1);
-  pdf.extend_from_slice(b"<< /Type /Catalog /Pages 2 0 R >>\nendobj\n");
+///
+/// `text_layer` is drawn after the image with font `/F0`, a Type0 font whose
+/// two-byte codes are UTF-16 code units (see `text_layer.rs`).
+fn jpeg_to_single_page_pdf(
+  jpeg_bytes: &[u8],
+  width: u32,
+  height: u32,
+  text_layer: Option<&str>,
+) -> Vec<u8> {
+  let mut pdf = PdfWriter::new();
+  let catalog = pdf.reserve();
+  let pages = pdf.reserve();
+  let page = pdf.reserve();
+  let image = pdf.reserve();
+  let contents = pdf.reserve();
 
-  // 2: Pages
-  start_obj(&mut pdf, &mut xref_positions, 2);
-  pdf.extend_from_slice(b"<< /Type /Pages /Kids [3 0 R] /Count 1 >>\nendobj\n");
+  pdf.object(catalog, &format!("<< /Type /Catalog /Pages {pages} 0 R >>"));
+  pdf.object(
+    pages,
+    &format!("<< /Type /Pages /Kids [{page} 0 R] /Count 1 >>"),
+  );
 
-  // 3: Page
-  start_obj(&mut pdf, &mut xref_positions, 3);
-  let _ = IoWrite::write_fmt(
-    &mut pdf,
-    format_args!(
-      "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {} {}] /Resources << /XObject << /Im0 4 0 R >> /ProcSet [/PDF /ImageC] >> /Contents 5 0 R >>\nendobj\n",
-      width, height
+  let font = text_layer.map(|_| pdf.reserve());
+  pdf.object(
+    page,
+    &format!(
+      "<< /Type /Page /Parent {pages} 0 R /MediaBox [0 0 {width} {height}] /Resources << /XObject << /Im0 {image} 0 R >> {}/ProcSet [/PDF /ImageC{}] >> /Contents {contents} 0 R >>",
+      font.map(|f| format!("/Font << /F0 {f} 0 R >> ")).unwrap_or_default(),
+      if text_layer.is_some() { " /Text" } else { "" },
     ),
   );
 
-  // 4: Image XObject
-  start_obj(&mut pdf, &mut xref_positions, 4);
-  let _ = IoWrite::write_fmt(
-    &mut pdf,
-    format_args!(
-      "<< /Type /XObject /Subtype /Image /Name /Im0 /Width {} /Height {} /ColorSpace /DeviceRGB /BitsPerComponent 8 /Filter /DCTDecode /Length {} >>\nstream\n",
-      width, height, jpeg_bytes.len()
+  pdf.stream(
+    image,
+    &format!(
+      "/Type /XObject /Subtype /Image /Name /Im0 /Width {width} /Height {height} /ColorSpace /DeviceRGB /BitsPerComponent 8 /Filter /DCTDecode"
     ),
+    jpeg_bytes,
   );
-  pdf.extend_from_slice(jpeg_bytes);
-  pdf.extend_from_slice(b"\nendstream\nendobj\n");
 
-  // 5: Content stream drawing the image to fill the page
-  start_obj(&mut pdf, &mut xref_positions, 5);
+  // Content stream drawing the image to fill the page
   let mut content = String::new();
   let _ = FmtWrite::write_str(&mut content, "q\n");
   let _ = FmtWrite::write_str(&mut content, &format!("{} 0 0 {} 0 0 cm\n", width, height));
   let _ = FmtWrite::write_str(&mut content, "/Im0 Do\nQ\n");
-
-  let _ = IoWrite::write_fmt(
-    &mut pdf,
-    format_args!(
-      "<< /Length {} >>\nstream\n{}endstream\nendobj\n",
-      content.len(),
-      content
-    ),
-  );
-
-  // XRef table
-  let xref_start = pdf.len();
-  let total_objects = xref_positions.len() as u32;
-
-  let _ = IoWrite::write_fmt(&mut pdf, format_args!("xref\n"));
-  let _ = IoWrite::write_fmt(&mut pdf, format_args!("0 {}\n", total_objects + 1));
-  pdf.extend_from_slice(b"0000000000 65535 f \n");
-
-  for offset in &xref_positions {
-    let _ = IoWrite::write_fmt(&mut pdf, format_args!("{:010} 00000 n \n", offset));
+  if let Some(text_layer) = text_layer {
+    let _ = FmtWrite::write_str(&mut content, text_layer);
   }
+  pdf.stream(contents, "", content.as_bytes());
 
-  let _ = IoWrite::write_fmt(
-    &mut pdf,
-    format_args!(
-      "trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{}",
-      total_objects + 1,
-      xref_start
-    ),
-  );
-  let _ = IoWrite::write_fmt(&mut pdf, format_args!("\n%%EOF\n"));
+  if let Some(font) = font {
+    // Text layer font. Nothing is painted in render mode 3, so the embedded font
+    // only has an empty glyph, which every code maps to; its ToUnicode map makes
+    // the text extractable.
+    let cid_font = pdf.reserve();
+    let descriptor = pdf.reserve();
+    let font_file = pdf.reserve();
+    let cid_to_gid = pdf.reserve();
+    let to_unicode = pdf.reserve();
+    pdf.object(font, &format!("<< /Type /Font /Subtype /Type0 /BaseFont /GlyphLessFont /Encoding /Identity-H /DescendantFonts [{cid_font} 0 R] /ToUnicode {to_unicode} 0 R >>"));
+    pdf.object(cid_font, &format!("<< /Type /Font /Subtype /CIDFontType2 /BaseFont /GlyphLessFont /CIDSystemInfo << /Registry (Adobe) /Ordering (Identity) /Supplement 0 >> /FontDescriptor {descriptor} 0 R /DW 500 /CIDToGIDMap {cid_to_gid} 0 R >>"));
+    pdf.object(descriptor, &format!("<< /Type /FontDescriptor /FontName /GlyphLessFont /Flags 5 /FontBBox [0 0 500 1000] /ItalicAngle 0 /Ascent 1000 /Descent 0 /CapHeight 1000 /StemV 80 /FontFile2 {font_file} 0 R >>"));
+    let program = glyphless_font();
+    pdf.compressed_stream(font_file, &format!("/Length1 {}", program.len()), &program);
+    pdf.compressed_stream(cid_to_gid, "", &vec![0; 0x20000]);
+    pdf.stream(to_unicode, "", identity_to_unicode_cmap().as_bytes());
+  }
 
-  pdf
+  pdf.finish(catalog)
 }
@@ -0,0 +1,242 @@
+use std::fs;
+use std::io::{Cursor, ErrorKind};
+use std::path::Path;
+use std::process::Command;
+
+use image::ImageFormat;
+use napi::bindgen_prelude::Uint8Array;
+use napi::{Error, Status};
+use napi_derive::napi;
+use tempfile::TempDir;
+
+use crate::analysis::analyze_document;
+use crate::image::load_image_with_orientation;
+use crate::pdf::{catch_panic, load_document};
+use crate::pdf_content::page_images;
+use crate::pdf_images::export_scan;
+use crate::utils::{is_pdf_mime, is_supported_image_mime, map_image_error};
+
+/// Options for OCR.
+///
+/// - `language`: Tesseract language(s), e.g. "eng" or "fra+eng" (default: "eng")
+/// - `tesseract_path`: Path of the tesseract executable (default: `tesseract` from the PATH)
+#[napi(object)]
+#[derive(Default)]
+pub struct OcrOptions {
+  pub language: Option<String>,
+  pub tesseract_path: Option<String>,
+}
+
+/// OCR output for one page.
+///
+/// - `page_number`: 1-based page number (1 for image inputs)
+/// - `text`: Recognized text
+/// - `hocr`: hOCR document (HTML with word boxes and confidences)
+#[napi(object)]
+pub struct OcrPage {
+  pub page_number: u32,
+  pub text: String,
+  pub hocr: String,
+}
+
+/// Result of an OCR run.
+///
+/// - `status`: "ok", "unavailable" (no tesseract executable), "not_needed" (a PDF
+///   without image-only pages), or "unsupported" (a PDF whose scans are all in an
+///   image format OCR cannot read, such as JBIG2)
+/// - `text`: Recognized text of all pages, separated by blank lines
+/// - `pages`: Per-page text and hOCR
+/// - `message`: Why OCR did not run, when `status` is not "ok"; with "ok", the scanned
+///   pages that were skipped, if any
+#[napi(object)]
+pub struct OcrResult {
+  pub status: String,
+  pub text: String,
+  pub pages: Vec<OcrPage>,
+  pub message: Option<String>,
+}
+
+/// Run OCR with a local Tesseract installation.
+///
+/// - Images (`image/png`, `image/jpeg`) are OCRed once EXIF orientation is applied.
+/// - For PDFs, only the pages `analyze_pdf` reports as likely scans are OCRed, using
+///   the largest image drawn on each of them. JPEG, JPEG 2000, CCITT fax and raw
+///   images are read; pages whose image is in another format (JBIG2) are skipped and
+///   listed in `message`.
+///
+/// Like `normalize_cv_to_pdf` with Ghostscript, the external tool is optional: when
+/// the executable cannot be found, `status` is "unavailable" instead of an error.
+#[napi]
+pub fn ocr_document(
+  bytes: Uint8Array,
+  mime: String,
+  options: Option<OcrOptions>,
+) -> napi::Result<OcrResult> {
+  let mime_lc = mime.to_ascii_lowercase();
+  let input = bytes.to_vec();
+  let options = options.unwrap_or_default();
+
+  let (images, skipped) = if is_pdf_mime(&mime_lc) {
+    scanned_page_images(&input)?
+  } else if is_supported_image_mime(&mime_lc) {
+    let img = load_image_with_orientation(&input).map_err(map_image_error)?;
+    let mut png = Vec::new();
+    img
+      .write_to(&mut Cursor::new(&mut png), ImageFormat::Png)
+      .map_err(map_image_error)?;
+    (vec![(1, "png", png)], Vec::new())
+  } else {
+    return Err(Error::new(
+      Status::InvalidArg,
+      format!("OCR is not supported for mime type '{mime}'"),
+    ));
+  };
+
+  let skipped_message = (!skipped.is_empty()).then(|| {
+    let pages: Vec<String> = skipped
+      .iter()
+      .map(|(page, codec)| format!("page {page} ({codec})"))
+      .collect();
+    format!(
+      "Scanned pages in an image format OCR cannot read: {}",
+      pages.join(", ")
+    )
+  });
+  if images.is_empty() {
+    return Ok(OcrResult {
+      status: if skipped.is_empty() {
+        "not_needed"
+      } else {
+        "unsupported"
+      }
+      .to_string(),
+      text: String::new(),
+      pages: Vec::new(),
+      message: Some(
+        skipped_message.unwrap_or_else(|| "The PDF has no image-only pages".to_string()),
+      ),
+    });
+  }
+
+  let dir =
+    TempDir::new().map_err(|e| Error::new(Status::GenericFailure, format!("OCR failed: {e}")))?;
+  let mut pages = Vec::new();
+  for (page_number, extension, data) in images {
+    match run_tesseract(&options, dir.path(), page_number, extension, &data)? {
+      Some((text, hocr)) => pages.push(OcrPage {
+        page_number,
+        text,
+        hocr,
+      }),
+      None => {
+        return Ok(OcrResult {
+          status: "unavailable".to_string(),
+          text: String::new(),
+          pages: Vec::new(),
+          message: Some(format!(
+            "OCR unavailable: tesseract executable not found ({})",
+            tesseract_path(&options)
+          )),
+        })
+      }
+    }
+  }
+
+  Ok(OcrResult {
+    status: "ok".to_string(),
+    text: pages
+      .iter()
+      .map(|p| p.text.trim_end())
+      .collect::<Vec<_>>()
+      .join("\n\n"),
+    pages,
+    message: skipped_message,
+  })
+}
+
+fn tesseract_path(options: &OcrOptions) -> &str {
+  options.tesseract_path.as_deref().unwrap_or("tesseract")
+}
+
+/// Images to OCR and skipped pages of a PDF.
+type ScannedPages = (Vec<(u32, &'static str, Vec<u8>)>, Vec<(u32, String)>);
+
+/// Largest image of every page likely to be a scan, as (page number, extension, data),
+/// and the pages whose image cannot be exported, with the reason.
+fn scanned_page_images(input: &[u8]) -> napi::Result<ScannedPages> {
+  catch_panic(|| {
+    let doc = load_document(input).map_err(|e| {
+      Error::new(
+        Status::InvalidArg,
+        format!("Failed to read PDF for OCR: {e}"),
+      )
+    })?;
+    let analysis = analyze_document(&doc);
+    let page_ids = doc.get_pages();
+
+    let mut images = Vec::new();
+    let mut skipped = Vec::new();
+    for page in analysis.pages.iter().filter(|p| p.likely_scanned) {
+      let Some(&page_id) = page_ids.get(&page.page_number) else {
+        continue;
+      };
+      // The largest image that can be exported; otherwise why the largest one cannot
+      let area = |r: &[f64; 4]| (r[2] - r[0]) * (r[3] - r[1]);
+      let mut candidates = page_images(&doc, page_id);
+      candidates.sort_by(|a, b| area(&b.rect).total_cmp(&area(&a.rect)));
+      let mut exported = candidates.iter().map(|i| export_scan(&doc, &i.image));
+      let Some(first) = exported.next() else {
+        continue;
+      };
+      match first.or_else(|reason| exported.find_map(Result::ok).ok_or(reason)) {
+        Ok(image) => images.push((page.page_number, image.format, image.data)),
+        Err(reason) => skipped.push((page.page_number, reason)),
+      }
+    }
+    Ok((images, skipped))
+  })
+}
+
+/// OCR one image. Returns `None` when the executable cannot be found.
+fn run_tesseract(
+  options: &OcrOptions,
+  dir: &Path,
+  page_number: u32,
+  extension: &str,
+  data: &[u8],
+) -> napi::Result<Option<(String, String)>> {
+  let ocr_error = |e: String| Error::new(Status::GenericFailure, format!("OCR failed: {e}"));
+
+  let input = dir.join(format!("page-{page_number}.{extension}"));
+  let output = dir.join(format!("page-{page_number}"));
+  fs::write(&input, data).map_err(|e| ocr_error(e.to_string()))?;
+
+  // `tesseract <image> <output base> -l <lang> txt hocr` writes <base>.txt and <base>.hocr.
+  let result = Command::new(tesseract_path(options))
+    .arg(&input)
+    .arg(&output)
+    .arg("-l")
+    .arg(options.language.as_deref().unwrap_or("eng"))
+    .arg("txt")
+    .arg("hocr")
+    .output();
+  let result = match result {
+    Ok(result) => result,
+    Err(e) if e.kind() == ErrorKind::NotFound => return Ok(None),
+    Err(e) => return Err(ocr_error(e.to_string())),
+  };
+  if !result.status.success() {
+    return Err(ocr_error(format!(
+      "tesseract exited with {}: {}",
+      result.status,
+      String::from_utf8_lossy(&result.stderr).trim()
+    )));
+  }
+
+  let read = |ext: &str| {
+    fs::read(output.with_extension(ext))
+      .map(|bytes| String::from_utf8_lossy(&bytes).into_owned())
+      .map_err(|e| ocr_error(format!("missing tesseract {ext} output: {e}")))
+  };
+  Ok(Some((read("txt")?, read("hocr")?)))
+}
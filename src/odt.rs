@@ -0,0 +1,363 @@
+use std::collections::HashMap;
+
+use roxmltree::{Document, Node};
+
+use crate::package::Package;
+use crate::typeset::{trim_runs, Block, Paragraph, Run, Table, TableCell, TextStyle};
+use crate::utils::format_number;
+
+const TEXT_NS: &str = "urn:oasis:names:tc:opendocument:xmlns:text:1.0";
+const TABLE_NS: &str = "urn:oasis:names:tc:opendocument:xmlns:table:1.0";
+const STYLE_NS: &str = "urn:oasis:names:tc:opendocument:xmlns:style:1.0";
+const DRAW_NS: &str = "urn:oasis:names:tc:opendocument:xmlns:drawing:1.0";
+const OFFICE_NS: &str = "urn:oasis:names:tc:opendocument:xmlns:office:1.0";
+
+/// Maximum depth of lists, frames and nested tables, against malformed input.
+const MAX_DEPTH: usize = 16;
+
+/// Text of an OpenDocument text (ODT) package.
+///
+/// Paragraphs, headings, lists with their markers, tables and text boxes are read
+/// from `content.xml`; list styles may come from `styles.xml`. Formatting and
+/// images are left out.
+pub(crate) fn read_odt(package: &Package) -> Result<Vec<Block>, String> {
+  let content = package
+    .read_string("content.xml")
+    .ok_or("missing content.xml")?;
+  let doc = Document::parse(&content).map_err(|e| e.to_string())?;
+  let styles_xml = package.read_string("styles.xml");
+  let styles_doc = styles_xml
+    .as_deref()
+    .and_then(|xml| Document::parse(xml).ok());
+
+  let mut lists = ListStyles::default();
+  if let Some(doc) = &styles_doc {
+    lists.add(doc.root_element());
+  }
+  lists.add(doc.root_element());
+
+  let text = doc
+    .descendants()
+    .find(|n| n.has_tag_name((OFFICE_NS, "text")))
+    .ok_or("missing document text")?;
+  let reader = OdtReader { lists };
+  Ok(reader.blocks(text, &ListContext::default(), 0))
+}
+
+/// A level of a list style: bullet character or number format.
+#[derive(Clone, Debug)]
+enum ListLevel {
+  Bullet(String),
+  Number {
+    format: String,
+    prefix: String,
+    suffix: String,
+    start: u32,
+  },
+}
+
+/// List styles by name, with their levels from 1.
+#[derive(Default)]
+struct ListStyles(HashMap<String, HashMap<usize, ListLevel>>);
+
+impl ListStyles {
+  /// List styles of `office:styles` and `office:automatic-styles` under `root`.
+  fn add(&mut self, root: Node) {
+    for container in root.children().filter(|n| {
+      n.has_tag_name((OFFICE_NS, "styles")) || n.has_tag_name((OFFICE_NS, "automatic-styles"))
+    }) {
+      for style in container
+        .children()
+        .filter(|n| n.has_tag_name((TEXT_NS, "list-style")))
+      {
+        if let Some(name) = style.attribute((STYLE_NS, "name")) {
+          self.0.insert(name.to_string(), parse_list_style(style));
+        }
+      }
+    }
+  }
+}
+
+fn parse_list_style(style: Node) -> HashMap<usize, ListLevel> {
+  style
+    .children()
+    .filter(|n| n.is_element())
+    .filter_map(|level| {
+      let index: usize = level.attribute((TEXT_NS, "level"))?.parse().ok()?;
+      let list_level = match level.tag_name().name() {
+        "list-level-style-bullet" => ListLevel::Bullet(
+          level
+            .attribute((TEXT_NS, "bullet-char"))
+            .unwrap_or("•")
+            .to_string(),
+        ),
+        "list-level-style-number" => ListLevel::Number {
+          format: level
+            .attribute((STYLE_NS, "num-format"))
+            .unwrap_or("1")
+            .to_string(),
+          prefix: level
+            .attribute((STYLE_NS, "num-prefix"))
+            .unwrap_or("")
+            .to_string(),
+          suffix: level
+            .attribute((STYLE_NS, "num-suffix"))
+            .unwrap_or("")
+            .to_string(),
+          start: level
+            .attribute((TEXT_NS, "start-value"))
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(1),
+        },
+        _ => return None,
+      };
+      Some((index, list_level))
+    })
+    .collect()
+}
+
+/// List being read: its style, nesting level and item counters.
+#[derive(Clone, Default)]
+struct ListContext {
+  style: Option<String>,
+  /// Nesting level, from 1 inside a list
+  level: usize,
+  /// Marker of the next paragraph (the first one of a list item)
+  marker: Option<String>,
+}
+
+struct OdtReader {
+  lists: ListStyles,
+}
+
+impl OdtReader {
+  fn blocks(&self, parent: Node, list: &ListContext, depth: usize) -> Vec<Block> {
+    let mut blocks = Vec::new();
+    if depth > MAX_DEPTH {
+      return blocks;
+    }
+    let mut list = list.clone();
+    for child in parent.children().filter(|n| n.is_element()) {
+      let namespace = child.tag_name().namespace();
+      let name = child.tag_name().name();
+      match (namespace, name) {
+        (Some(TEXT_NS), "p" | "h") => {
+          let marker = list.marker.take();
+          self.paragraph(child, marker, &mut blocks, depth);
+        }
+        (Some(TEXT_NS), "list") => {
+          let nested = ListContext {
+            style: child
+              .attribute((TEXT_NS, "style-name"))
+              .map(str::to_string)
+              .or(list.style.clone()),
+            level: list.level + 1,
+            marker: None,
+          };
+          blocks.extend(self.list(child, &nested, depth));
+        }
+        (Some(TEXT_NS), "section" | "index-body" | "soft-page-break")
+        | (Some(TEXT_NS), "table-of-content" | "alphabetical-index" | "illustration-index") => {
+          blocks.extend(self.blocks(child, &list, depth + 1));
+        }
+        (Some(TABLE_NS), "table") => blocks.extend(self.table(child, depth)),
+        _ => {}
+      }
+    }
+    blocks
+  }
+
+  fn list(&self, node: Node, list: &ListContext, depth: usize) -> Vec<Block> {
+    let level = self
+      .lists
+      .0
+      .get(list.style.as_deref().unwrap_or_default())
+      .and_then(|levels| levels.get(&list.level))
+      .cloned()
+      .unwrap_or(ListLevel::Bullet("•".to_string()));
+    let mut counter = match &level {
+      ListLevel::Number { start, .. } => *start,
+      ListLevel::Bullet(_) => 1,
+    };
+    if let Some(start) = node
+      .children()
+      .find_map(|n| n.attribute((TEXT_NS, "start-value")))
+      .and_then(|v| v.parse().ok())
+    {
+      counter = start;
+    }
+
+    let mut blocks = Vec::new();
+    for item in node.children().filter(|n| {
+      n.has_tag_name((TEXT_NS, "list-item")) || n.has_tag_name((TEXT_NS, "list-header"))
+    }) {
+      let mut item_list = list.clone();
+      if item.has_tag_name((TEXT_NS, "list-item")) {
+        if let Some(start) = item
+          .attribute((TEXT_NS, "start-value"))
+          .and_then(|v| v.parse().ok())
+        {
+          counter = start;
+        }
+        item_list.marker = Some(match &level {
+          ListLevel::Bullet(bullet) => bullet.clone(),
+          ListLevel::Number {
+            format,
+            prefix,
+            suffix,
+            ..
+          } => format!("{prefix}{}{suffix}", format_number(counter, format)),
+        });
+        counter += 1;
+      }
+      blocks.extend(self.blocks(item, &item_list, depth + 1));
+    }
+    blocks
+  }
+
+  fn paragraph(&self, node: Node, marker: Option<String>, blocks: &mut Vec<Block>, depth: usize) {
+    let mut text = String::new();
+    let mut text_boxes = Vec::new();
+    self.inline(node, &mut text, &mut text_boxes, depth);
+
+    let mut runs = vec![Run {
+      text,
+      style: TextStyle::default(),
+    }];
+    trim_runs(&mut runs);
+    blocks.push(Block::Paragraph(Paragraph {
+      runs,
+      marker: marker.map(|text| Run {
+        text,
+        style: TextStyle::default(),
+      }),
+      ..Paragraph::default()
+    }));
+    blocks.extend(text_boxes);
+  }
+
+  fn inline(&self, node: Node, text: &mut String, text_boxes: &mut Vec<Block>, depth: usize) {
+    if depth > MAX_DEPTH {
+      return;
+    }
+    for child in node.children() {
+      if let Some(content) = child.text().filter(|_| child.is_text()) {
+        // Spaces are collapsed, as in XML text; `text:s` encodes the others.
+        text.push_str(&collapse_spaces(content));
+        continue;
+      }
+      if !child.is_element() {
+        continue;
+      }
+      match (child.tag_name().namespace(), child.tag_name().name()) {
+        (Some(TEXT_NS), "s") => {
+          let count = child
+            .attribute((TEXT_NS, "c"))
+            .and_then(|c| c.parse::<usize>().ok())
+            .unwrap_or(1)
+            .min(100);
+          text.push_str(&" ".repeat(count));
+        }
+        (Some(TEXT_NS), "tab") => text.push_str("    "),
+        (Some(TEXT_NS), "line-break") => text.push('\n'),
+        (Some(TEXT_NS), "span" | "a" | "ruby" | "ruby-base" | "meta" | "bookmark-ref")
+        | (Some(TEXT_NS), "reference-ref" | "date" | "time" | "page-number" | "author-name")
+        | (Some(TEXT_NS), "sender-email" | "sender-phone-private" | "sender-phone-work")
+        | (Some(TEXT_NS), "sender-firstname" | "sender-lastname" | "title" | "subject") => {
+          self.inline(child, text, text_boxes, depth + 1);
+        }
+        (Some(DRAW_NS), "frame" | "a") => self.frame(child, text_boxes, depth),
+        _ => {}
+      }
+    }
+  }
+
+  /// Text boxes of a frame.
+  fn frame(&self, node: Node, text_boxes: &mut Vec<Block>, depth: usize) {
+    if depth > MAX_DEPTH {
+      return;
+    }
+    for child in node.children().filter(|n| n.is_element()) {
+      if child.has_tag_name((DRAW_NS, "text-box")) {
+        text_boxes.extend(self.blocks(child, &ListContext::default(), depth + 1));
+      } else if child.has_tag_name((DRAW_NS, "frame")) {
+        self.frame(child, text_boxes, depth + 1);
+      }
+    }
+  }
+
+  fn table(&self, node: Node, depth: usize) -> Option<Block> {
+    if depth > MAX_DEPTH {
+      return None;
+    }
+    let mut rows = Vec::new();
+    let row_nodes: Vec<Node> = node
+      .descendants()
+      .filter(|n| n.has_tag_name((TABLE_NS, "table-row")))
+      .filter(|n| {
+        // Rows of this table, not of nested ones
+        n.ancestors()
+          .find(|a| a.has_tag_name((TABLE_NS, "table")))
+          .is_some_and(|table| table == node)
+      })
+      .collect();
+    for row in row_nodes {
+      let mut cells = Vec::new();
+      // Covered cells after a cell spanning columns are part of it; others are
+      // covered by a cell spanning rows and keep their column.
+      let mut spanned = 0;
+      for cell in row.children().filter(|n| n.is_element()) {
+        if cell.has_tag_name((TABLE_NS, "table-cell")) {
+          let span = cell
+            .attribute((TABLE_NS, "number-columns-spanned"))
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(1usize)
+            .clamp(1, 1000);
+          spanned = span - 1;
+          cells.push(TableCell {
+            blocks: self.blocks(cell, &ListContext::default(), depth + 1),
+            span,
+            background: None,
+          });
+        } else if cell.has_tag_name((TABLE_NS, "covered-table-cell")) {
+          if spanned > 0 {
+            spanned -= 1;
+          } else {
+            cells.push(TableCell {
+              blocks: Vec::new(),
+              span: 1,
+              background: None,
+            });
+          }
+        }
+      }
+      if !cells.is_empty() {
+        rows.push(cells);
+      }
+    }
+    if rows.is_empty() {
+      return None;
+    }
+    Some(Block::Table(Table {
+      columns: Vec::new(),
+      rows,
+      borders: None,
+      indent_left: 0.0,
+    }))
+  }
+}
+
+/// Whitespace runs as single spaces.
+fn collapse_spaces(text: &str) -> String {
+  let mut out = String::with_capacity(text.len());
+  for c in text.chars() {
+    if c.is_whitespace() {
+      if !out.ends_with(' ') {
+        out.push(' ');
+      }
+    } else {
+      out.push(c);
+    }
+  }
+  out
+}
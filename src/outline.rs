@@ -0,0 +1,312 @@
+use std::collections::{HashMap, HashSet};
+
+use lopdf::{decode_text_string, text_string, Dictionary, Document, Object, ObjectId};
+use napi::bindgen_prelude::Uint8Array;
+use napi::{Error, Status};
+use napi_derive::napi;
+
+use crate::pdf::{catch_panic, load_document};
+use crate::pdf_objects::{dict_get, dict_get_dict, page_box, resolve};
+
+/// Maximum nesting of outline items (and of name tree nodes), against cycles.
+const MAX_OUTLINE_DEPTH: usize = 32;
+
+/// An outline (bookmark) entry.
+///
+/// - `title`: Title shown in the viewer's bookmark panel
+/// - `page_number`: 1-based target page; absent for entries without a destination
+///   (or pointing to another file)
+/// - `children`: Nested entries
+#[napi(object)]
+#[derive(Clone, Debug)]
+pub struct PdfOutlineItem {
+  pub title: String,
+  pub page_number: Option<u32>,
+  pub children: Vec<PdfOutlineItem>,
+}
+
+/// Read the outline (bookmarks) of a PDF as a tree of titles and target pages.
+///
+/// Destinations may be explicit, named (`/Dests` dictionary or name tree) or given
+/// through a GoTo action. An empty list is returned when the PDF has no outline.
+#[napi]
+pub fn extract_pdf_outline(bytes: Uint8Array) -> napi::Result<Vec<PdfOutlineItem>> {
+  let input = bytes.to_vec();
+  catch_panic(|| {
+    let doc = load_document(&input).map_err(|e| {
+      Error::new(
+        Status::InvalidArg,
+        format!("Failed to read PDF outline: {e}"),
+      )
+    })?;
+    Ok(read_outline(&doc))
+  })
+}
+
+/// Replace the outline (bookmarks) of a PDF and return the updated PDF.
+///
+/// Every item pointing to a page becomes a bookmark to the top of that page; the
+/// document opens with the bookmark panel shown. An empty list removes the outline.
+/// PDFs rendered from DOCX, text, Markdown, HTML or JSON Resume already have a
+/// bookmark for every heading.
+#[napi]
+pub fn set_pdf_outline(bytes: Uint8Array, outline: Vec<PdfOutlineItem>) -> napi::Result<Vec<u8>> {
+  let input = bytes.to_vec();
+  catch_panic(|| {
+    let mut doc = load_document(&input).map_err(|e| {
+      Error::new(
+        Status::InvalidArg,
+        format!("Failed to read PDF for outline: {e}"),
+      )
+    })?;
+    write_outline(&mut doc, &outline).map_err(|e| Error::new(Status::InvalidArg, e))?;
+    let mut output = Vec::new();
+    doc
+      .save_to(&mut output)
+      .map_err(|e| Error::new(Status::GenericFailure, format!("Failed to write PDF: {e}")))?;
+    Ok(output)
+  })
+}
+
+/// Outline of a loaded document (see `extract_pdf_outline`).
+pub(crate) fn read_outline(doc: &Document) -> Vec<PdfOutlineItem> {
+  let Some(root) = doc
+    .catalog()
+    .ok()
+    .and_then(|catalog| dict_get_dict(doc, catalog, b"Outlines"))
+  else {
+    return Vec::new();
+  };
+  let reader = OutlineReader {
+    doc,
+    pages: doc
+      .get_pages()
+      .into_iter()
+      .map(|(number, id)| (id, number))
+      .collect(),
+  };
+  let mut visited = HashSet::new();
+  reader.items(root, 0, &mut visited)
+}
+
+struct OutlineReader<'a> {
+  doc: &'a Document,
+  pages: HashMap<ObjectId, u32>,
+}
+
+impl<'a> OutlineReader<'a> {
+  /// Children of an outline node, following /First then the /Next chain.
+  fn items(
+    &self,
+    parent: &Dictionary,
+    depth: usize,
+    visited: &mut HashSet<ObjectId>,
+  ) -> Vec<PdfOutlineItem> {
+    let mut items = Vec::new();
+    if depth > MAX_OUTLINE_DEPTH {
+      return items;
+    }
+    let mut next = parent.get(b"First").and_then(Object::as_reference).ok();
+    while let Some(id) = next {
+      if !visited.insert(id) {
+        break;
+      }
+      let Ok(item) = self.doc.get_dictionary(id) else {
+        break;
+      };
+      items.push(PdfOutlineItem {
+        title: dict_get(self.doc, item, b"Title")
+          .and_then(|t| decode_text_string(t).ok())
+          .unwrap_or_default(),
+        page_number: self.target_page(item),
+        children: self.items(item, depth + 1, visited),
+      });
+      next = item.get(b"Next").and_then(Object::as_reference).ok();
+    }
+    items
+  }
+
+  /// Target page of an outline item, from /Dest or a GoTo action.
+  fn target_page(&self, item: &Dictionary) -> Option<u32> {
+    let doc = self.doc;
+    let dest = match dict_get(doc, item, b"Dest") {
+      Some(dest) => dest,
+      None => {
+        let action = dict_get_dict(doc, item, b"A")?;
+        if dict_get(doc, action, b"S").and_then(|s| s.as_name().ok()) != Some(b"GoTo") {
+          return None;
+        }
+        dict_get(doc, action, b"D")?
+      }
+    };
+    self.destination_page(dest, 0)
+  }
+
+  fn destination_page(&self, dest: &Object, depth: usize) -> Option<u32> {
+    let doc = self.doc;
+    if depth > 2 {
+      return None;
+    }
+    match resolve(doc, dest) {
+      // [page /XYZ left top zoom], with a page reference (or a page index in remote files)
+      Object::Array(items) => match items.first()? {
+        Object::Reference(id) => self.pages.get(id).copied(),
+        Object::Integer(index) => u32::try_from(*index).ok().map(|i| i + 1),
+        _ => None,
+      },
+      // Named destinations map to an array or to a dictionary with /D.
+      Object::Name(name) => self.destination_page(self.named_destination(name)?, depth + 1),
+      Object::String(name, _) => self.destination_page(self.named_destination(name)?, depth + 1),
+      Object::Dictionary(dict) => self.destination_page(dict_get(doc, dict, b"D")?, depth + 1),
+      _ => None,
+    }
+  }
+
+  /// Named destination, from the catalog's /Dests dictionary (PDF 1.1) or the /Dests
+  /// name tree of /Names.
+  fn named_destination(&self, name: &[u8]) -> Option<&'a Object> {
+    let doc = self.doc;
+    let catalog = doc.catalog().ok()?;
+    if let Some(dest) = dict_get_dict(doc, catalog, b"Dests").and_then(|d| dict_get(doc, d, name)) {
+      return Some(dest);
+    }
+    let names = dict_get_dict(doc, catalog, b"Names")?;
+    let tree = dict_get_dict(doc, names, b"Dests")?;
+    self.name_tree_lookup(tree, name, 0)
+  }
+
+  fn name_tree_lookup(
+    &self,
+    node: &'a Dictionary,
+    name: &[u8],
+    depth: usize,
+  ) -> Option<&'a Object> {
+    let doc = self.doc;
+    if depth > MAX_OUTLINE_DEPTH {
+      return None;
+    }
+    if let Some(entries) = dict_get(doc, node, b"Names").and_then(|n| n.as_array().ok()) {
+      for pair in entries.chunks_exact(2) {
+        if resolve(doc, &pair[0]).as_str().ok() == Some(name) {
+          return Some(resolve(doc, &pair[1]));
+        }
+      }
+    }
+    let kids = dict_get(doc, node, b"Kids")?.as_array().ok()?;
+    kids.iter().find_map(|kid| {
+      let kid = resolve(doc, kid).as_dict().ok()?;
+      self.name_tree_lookup(kid, name, depth + 1)
+    })
+  }
+}
+
+/// Replace the outline of a document; an empty outline removes it.
+///
+/// Fails when an item points to a page the document does not have.
+pub(crate) fn write_outline(doc: &mut Document, outline: &[PdfOutlineItem]) -> Result<(), String> {
+  let pages = doc.get_pages();
+  let root_id = doc
+    .trailer
+    .get(b"Root")
+    .and_then(Object::as_reference)
+    .map_err(|e| format!("PDF has no catalog: {e}"))?;
+
+  if outline.is_empty() {
+    if let Ok(catalog) = doc.get_dictionary_mut(root_id) {
+      catalog.remove(b"Outlines");
+    }
+    return Ok(());
+  }
+
+  // Destinations point to the top left corner of the target page.
+  let mut targets = HashMap::new();
+  collect_targets(outline, &mut |page_number| {
+    let page_id = *pages.get(&page_number).ok_or_else(|| {
+      format!(
+        "Outline item points to page {page_number}, but the PDF has {} pages",
+        pages.len()
+      )
+    })?;
+    let [x0, _, _, y1] = page_box(doc, page_id);
+    targets.insert(page_number, (page_id, x0, y1));
+    Ok(())
+  })?;
+
+  let outlines_id = doc.new_object_id();
+  let (first, last, count) = write_items(doc, outline, outlines_id, &targets);
+  let outlines = Dictionary::from_iter([
+    ("Type", Object::Name(b"Outlines".to_vec())),
+    ("First", Object::Reference(first)),
+    ("Last", Object::Reference(last)),
+    ("Count", Object::Integer(count)),
+  ]);
+  doc
+    .objects
+    .insert(outlines_id, Object::Dictionary(outlines));
+
+  let catalog = doc
+    .get_dictionary_mut(root_id)
+    .map_err(|e| format!("PDF has no catalog: {e}"))?;
+  catalog.set("Outlines", Object::Reference(outlines_id));
+  catalog.set("PageMode", Object::Name(b"UseOutlines".to_vec()));
+  Ok(())
+}
+
+fn collect_targets(
+  items: &[PdfOutlineItem],
+  visit: &mut impl FnMut(u32) -> Result<(), String>,
+) -> Result<(), String> {
+  for item in items {
+    if let Some(page_number) = item.page_number {
+      visit(page_number)?;
+    }
+    collect_targets(&item.children, visit)?;
+  }
+  Ok(())
+}
+
+/// Write sibling items under `parent`; returns the first and last item ids and the
+/// number of visible items (all items are open).
+fn write_items(
+  doc: &mut Document,
+  items: &[PdfOutlineItem],
+  parent: ObjectId,
+  targets: &HashMap<u32, (ObjectId, f64, f64)>,
+) -> (ObjectId, ObjectId, i64) {
+  let ids: Vec<ObjectId> = items.iter().map(|_| doc.new_object_id()).collect();
+  let mut count = 0;
+  for (i, item) in items.iter().enumerate() {
+    let mut dict = Dictionary::from_iter([
+      ("Title", text_string(&item.title)),
+      ("Parent", Object::Reference(parent)),
+    ]);
+    if i > 0 {
+      dict.set("Prev", Object::Reference(ids[i - 1]));
+    }
+    if let Some(next) = ids.get(i + 1) {
+      dict.set("Next", Object::Reference(*next));
+    }
+    if let Some((page_id, left, top)) = item.page_number.and_then(|n| targets.get(&n)) {
+      dict.set(
+        "Dest",
+        Object::Array(vec![
+          Object::Reference(*page_id),
+          Object::Name(b"XYZ".to_vec()),
+          (*left).into(),
+          (*top).into(),
+          Object::Null,
+        ]),
+      );
+    }
+    if !item.children.is_empty() {
+      let (first, last, descendants) = write_items(doc, &item.children, ids[i], targets);
+      dict.set("First", Object::Reference(first));
+      dict.set("Last", Object::Reference(last));
+      dict.set("Count", Object::Integer(descendants));
+      count += descendants;
+    }
+    doc.objects.insert(ids[i], Object::Dictionary(dict));
+    count += 1;
+  }
+  (ids[0], ids[ids.len() - 1], count)
+}
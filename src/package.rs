@@ -0,0 +1,55 @@
+use std::cell::RefCell;
+use std::io::{Cursor, Read};
+
+use zip::ZipArchive;
+
+/// Largest part read from a package, against ZIP bombs.
+const MAX_PART_SIZE: u64 = 64 * 1024 * 1024;
+
+/// A ZIP-based document package (OOXML, OpenDocument).
+pub(crate) struct Package<'a> {
+  archive: RefCell<ZipArchive<Cursor<&'a [u8]>>>,
+}
+
+impl<'a> Package<'a> {
+  pub fn open(bytes: &'a [u8]) -> Result<Self, String> {
+    let archive = ZipArchive::new(Cursor::new(bytes)).map_err(|e| e.to_string())?;
+    Ok(Package {
+      archive: RefCell::new(archive),
+    })
+  }
+
+  /// Bytes of the part at `path` (without leading slash), if it exists.
+  pub fn read(&self, path: &str) -> Option<Vec<u8>> {
+    let mut archive = self.archive.borrow_mut();
+    let file = archive.by_name(path.trim_start_matches('/')).ok()?;
+    let mut data = Vec::new();
+    file.take(MAX_PART_SIZE).read_to_end(&mut data).ok()?;
+    Some(data)
+  }
+
+  /// Text of an XML part; a UTF-8 byte order mark is dropped.
+  pub fn read_string(&self, path: &str) -> Option<String> {
+    let data = self.read(path)?;
+    let text = String::from_utf8_lossy(&data);
+    Some(text.trim_start_matches('\u{FEFF}').to_string())
+  }
+}
+
+/// Resolve a relationship target against the directory of the part declaring it.
+pub(crate) fn resolve_part_path(base_dir: &str, target: &str) -> String {
+  if let Some(absolute) = target.strip_prefix('/') {
+    return absolute.to_string();
+  }
+  let mut segments: Vec<&str> = base_dir.split('/').filter(|s| !s.is_empty()).collect();
+  for segment in target.split('/') {
+    match segment {
+      "" | "." => {}
+      ".." => {
+        segments.pop();
+      }
+      segment => segments.push(segment),
+    }
+  }
+  segments.join("/")
+}
@@ -1,27 +1,696 @@
-use std::io::Write as IoWrite;
+use std::cell::Cell;
+use std::collections::HashMap;
+use std::io::{Read, Write as IoWrite};
+use std::panic::{self, AssertUnwindSafe};
 use std::process::Command;
+use std::rc::Rc;
+use std::sync::Once;
+use std::time::{Duration, Instant};
+
+use flate2::read::ZlibDecoder;
+use lopdf::content::Operation;
+use lopdf::{Dictionary, Object, ObjectId, Stream};
 
 use napi::bindgen_prelude::Uint8Array;
 use napi::{Error, Status};
 use napi_derive::napi;
+use pdf_extract::{output_doc, output_doc_page, Document, OutputError, PlainTextOutput};
 use tempfile::NamedTempFile;
 
+use crate::layout::{blocks_to_text, collect_page_glyphs, reading_order_blocks, Glyph};
+use crate::markdown::{render_markdown, MarkdownPage};
+use crate::pdf_content::{walk_page_guarded, Frame, Matrix, WalkGuard};
+use crate::pdf_fonts::FontDecoder;
+use crate::pdf_objects::{as_number, page_box, resource};
+use crate::quality::{text_quality, TextQuality};
+use crate::text::{clean_pages, TextCleanupOptions};
+
+/// Options for PDF text extraction.
+///
+/// - `cleanup`: Run the text cleanup stage (ligatures, de-hyphenation, Unicode
+///   normalization, whitespace, repeated headers/footers). Omit to get the raw
+///   pdf-extract output.
+/// - `layout`: Rebuild the natural reading order from glyph positions: columns
+///   (e.g. a CV sidebar) are read one after the other instead of line by line,
+///   and text blocks are separated by blank lines (default: false)
+/// - `max_pages`: Only extract the first N pages
+/// - `max_stream_bytes`: Skip streams whose decompressed size exceeds this many bytes
+/// - `timeout_ms`: Stop extracting once this time has elapsed (checked while reading
+///   each page; the page being read is left out)
+///
+/// When a limit is hit, the text extracted so far is returned; use
+/// `extractTextFromPdfDetailed` to know whether it was truncated.
+#[napi(object)]
+#[derive(Default)]
+pub struct PdfTextOptions {
+  pub cleanup: Option<TextCleanupOptions>,
+  pub layout: Option<bool>,
+  pub max_pages: Option<u32>,
+  pub max_stream_bytes: Option<u32>,
+  pub timeout_ms: Option<u32>,
+}
+
+impl PdfTextOptions {
+  fn has_limits(&self) -> bool {
+    self.max_pages.is_some() || self.max_stream_bytes.is_some() || self.timeout_ms.is_some()
+  }
+}
+
+/// Result of a detailed PDF text extraction.
+///
+/// - `text`: Extracted text (pages separated by blank lines)
+/// - `page_count`: Number of pages in the document
+/// - `pages_extracted`: Number of pages the text was extracted from
+/// - `truncated`: Whether a limit was hit and the text is partial
+/// - `truncation_reason`: "max_pages", "max_stream_bytes" or "timeout"
+/// - `extractor`: Extractor that produced the text: "pdf-extract", "native" (the
+///   fallback decoder, used on every page pdf-extract fails on or cannot be trusted
+///   with) or "mixed"
+/// - `quality`: Quality score of the text, to detect garbled output (see
+///   `score_text_quality`)
+#[napi(object)]
+pub struct PdfTextResult {
+  pub text: String,
+  pub page_count: u32,
+  pub pages_extracted: u32,
+  pub truncated: bool,
+  pub truncation_reason: Option<String>,
+  pub extractor: String,
+  pub quality: TextQuality,
+}
+
+/// Text extractor used for a page.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum Extractor {
+  /// pdf-extract, the primary extractor
+  PdfExtract,
+  /// The simpler fallback decoder of this module, used when pdf-extract fails or
+  /// cannot be trusted with a page
+  Native,
+}
+
+impl Extractor {
+  fn name(self) -> &'static str {
+    match self {
+      Extractor::PdfExtract => "pdf-extract",
+      Extractor::Native => "native",
+    }
+  }
+}
+
+/// Glyphs of a page, with the extractor that produced them.
+pub(crate) struct PageGlyphs<'a> {
+  pub glyphs: Vec<Glyph>,
+  pub extractor: Extractor,
+  /// Font of every shown string, indexed like `Glyph::op`. Only known for the
+  /// native extractor; see `shown_string_fonts` for pdf-extract glyphs.
+  pub fonts: Option<Vec<Option<&'a Dictionary>>>,
+}
+
 /// Extract text content from a PDF document.
 ///
 /// This replaces pdf-parse (JS) with a native Rust implementation using pdf-extract.
 /// Returns the extracted text as a single string, with pages separated by newlines.
+/// Pages pdf-extract cannot read (errors or panics) are decoded by a simpler native
+/// extractor instead.
+/// Pass `options.layout` for multi-column layouts and `options.cleanup` to
+/// post-process the text for search and indexing.
 #[napi]
-pub fn extract_text_from_pdf(bytes: Uint8Array) -> napi::Result<String> {
+pub fn extract_text_from_pdf(
+  bytes: Uint8Array,
+  options: Option<PdfTextOptions>,
+) -> napi::Result<String> {
   let input = bytes.to_vec();
+  let options = options.unwrap_or_default();
+
+  if !options.layout.unwrap_or(false) && options.cleanup.is_none() && !options.has_limits() {
+    let plain = catch_unwind_silently(|| -> Result<Option<String>, OutputError> {
+      let doc = load_document(&input)?;
+      // pdf-extract reads the whole document at once here: a single page it cannot
+      // be trusted with sends the document through the page by page extraction.
+      let trusted = doc
+        .get_pages()
+        .values()
+        .all(|&page_id| pdf_extract_can_read(&doc, page_id, &mut WalkGuard::default()));
+      if !trusted {
+        return Ok(None);
+      }
+      let mut text = String::new();
+      output_doc(&doc, &mut PlainTextOutput::new(&mut text))?;
+      Ok(Some(text))
+    });
+    if let Ok(Ok(Some(text))) = plain {
+      return Ok(text);
+    }
+  }
+
+  extract_text(&input, &options).map(|result| result.text)
+}
+
+/// Extract text content from a PDF document, reporting how much of it was read.
+///
+/// Takes the same options as `extract_text_from_pdf`. When `max_pages`,
+/// `max_stream_bytes` or `timeout_ms` is hit, the partial text is returned with
+/// `truncated` set instead of failing. `extractor` tells whether the native fallback
+/// extractor had to be used.
+#[napi]
+pub fn extract_text_from_pdf_detailed(
+  bytes: Uint8Array,
+  options: Option<PdfTextOptions>,
+) -> napi::Result<PdfTextResult> {
+  let input = bytes.to_vec();
+  extract_text(&input, &options.unwrap_or_default())
+}
+
+/// Convert a PDF document (typically a CV) to Markdown.
+///
+/// Text is read in natural reading order, as with `layout: true`. Lines set larger
+/// than the body text, or short bold lines, become headings (`#` to `###`), lines
+/// starting with a bullet glyph become list items, bold runs are wrapped in `**`
+/// and text under URI link annotations becomes `[text](uri)`.
+#[napi]
+pub fn extract_markdown_from_pdf(bytes: Uint8Array) -> napi::Result<String> {
+  let input = bytes.to_vec();
+  catch_panic(|| {
+    let doc = load_document(&input).map_err(map_extract_error)?;
+    let pages = doc
+      .get_pages()
+      .into_iter()
+      .map(|(page_num, page_id)| {
+        MarkdownPage::new(&doc, page_id, page_glyphs(&doc, page_num, page_id))
+      })
+      .collect::<Vec<_>>();
+
+    Ok(render_markdown(&pages))
+  })
+}
+
+fn map_extract_error(err: OutputError) -> Error {
+  Error::new(
+    Status::InvalidArg,
+    format!("Failed to extract text from PDF: {err}"),
+  )
+}
+
+static SILENCE_HOOK: Once = Once::new();
+
+thread_local! {
+  static CATCHING_PANIC: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Run PDF parsing code, turning a panic into a `GenericFailure` error.
+///
+/// pdf-extract panics on some malformed files; unwinding out of a native call would
+/// abort the whole Node process.
+pub(crate) fn catch_panic<T>(f: impl FnOnce() -> napi::Result<T>) -> napi::Result<T> {
+  catch_unwind_silently(f).unwrap_or_else(|message| {
+    Err(Error::new(
+      Status::GenericFailure,
+      format!("PDF parser panicked on malformed input: {message}"),
+    ))
+  })
+}
+
+/// Run `f`, returning the panic message instead of unwinding if it panics.
+pub(crate) fn catch_unwind_silently<T>(f: impl FnOnce() -> T) -> Result<T, String> {
+  // Caught panics are reported as errors, so keep the default hook from printing them.
+  SILENCE_HOOK.call_once(|| {
+    let default_hook = panic::take_hook();
+    panic::set_hook(Box::new(move |info| {
+      if !CATCHING_PANIC.with(Cell::get) {
+        default_hook(info);
+      }
+    }));
+  });
+
+  let was_catching = CATCHING_PANIC.with(|c| c.replace(true));
+  let result = panic::catch_unwind(AssertUnwindSafe(f));
+  CATCHING_PANIC.with(|c| c.set(was_catching));
+
+  result.map_err(|payload| {
+    payload
+      .downcast_ref::<&str>()
+      .map(|s| s.to_string())
+      .or_else(|| payload.downcast_ref::<String>().cloned())
+      .unwrap_or_else(|| "unknown error".to_string())
+  })
+}
+
+/// Load a PDF with pdf-extract's lopdf, decrypting it with the empty password when needed.
+pub(crate) fn load_document(input: &[u8]) -> Result<Document, OutputError> {
+  let mut doc = Document::load_mem(input)?;
+  if doc.is_encrypted() {
+    doc.decrypt("")?;
+  }
+  Ok(doc)
+}
+
+/// Page-by-page extraction honouring the options and limits.
+pub(crate) fn extract_text(input: &[u8], options: &PdfTextOptions) -> napi::Result<PdfTextResult> {
+  let layout = options.layout.unwrap_or(false);
+  let deadline = options
+    .timeout_ms
+    .map(|ms| Instant::now() + Duration::from_millis(ms as u64));
+
+  catch_panic(|| {
+    let mut doc = load_document(input).map_err(map_extract_error)?;
+    let mut truncation = None;
 
-  let text = pdf_extract::extract_text_from_mem(&input).map_err(|e| {
-    Error::new(
-      Status::InvalidArg,
-      format!("Failed to extract text from PDF: {e}"),
-    )
-  })?;
+    if let Some(limit) = options.max_stream_bytes {
+      if strip_oversized_streams(&mut doc, limit as usize) > 0 {
+        truncation = Some("max_stream_bytes");
+      }
+    }
 
-  Ok(text)
+    let page_ids = doc.get_pages();
+    let mut pages = Vec::new();
+    let mut extractors = Vec::new();
+    for (page_num, page_id) in &page_ids {
+      if options
+        .max_pages
+        .is_some_and(|max| pages.len() >= max as usize)
+      {
+        truncation = Some("max_pages");
+        break;
+      }
+      if deadline.is_some_and(|d| Instant::now() >= d) {
+        truncation = Some("timeout");
+        break;
+      }
+      let Some((text, extractor)) = page_text(&doc, *page_num, *page_id, layout, deadline) else {
+        truncation = Some("timeout");
+        break;
+      };
+      pages.push(text);
+      extractors.push(extractor);
+    }
+
+    let text = match &options.cleanup {
+      Some(cleanup) => clean_pages(&pages, cleanup),
+      None => pages.join("\n\n"),
+    };
+
+    let extractor = match extractors.first() {
+      Some(first) if extractors.iter().all(|e| e == first) => first.name(),
+      Some(_) => "mixed",
+      None => Extractor::PdfExtract.name(),
+    };
+
+    Ok(PdfTextResult {
+      quality: text_quality(&text),
+      text,
+      page_count: page_ids.len() as u32,
+      pages_extracted: pages.len() as u32,
+      truncated: truncation.is_some(),
+      truncation_reason: truncation.map(str::to_string),
+      extractor: extractor.to_string(),
+    })
+  })
+}
+
+/// Text of a single page, as pdf-extract writes it or in natural reading order (see
+/// `layout.rs`). Text from the native extractor is always laid out. `None` when
+/// `deadline` passes before the page is read.
+pub(crate) fn page_text(
+  doc: &Document,
+  page_num: u32,
+  page_id: ObjectId,
+  layout: bool,
+  deadline: Option<Instant>,
+) -> Option<(String, Extractor)> {
+  let mut scan = WalkGuard::until(deadline);
+  let trusted = pdf_extract_can_read(doc, page_id, &mut scan);
+  if scan.timed_out() {
+    return None;
+  }
+  if !layout && trusted {
+    let plain = catch_unwind_silently(|| {
+      let mut text = String::new();
+      output_doc_page(doc, &mut PlainTextOutput::new(&mut text), page_num).map(|_| text)
+    });
+    if let Ok(Ok(text)) = plain {
+      return Some((text, Extractor::PdfExtract));
+    }
+  }
+  let mut walk = WalkGuard::until(deadline);
+  let page = read_page_glyphs(doc, page_num, page_id, trusted, &mut walk);
+  if walk.timed_out() {
+    return None;
+  }
+  Some((
+    blocks_to_text(&reading_order_blocks(&page.glyphs, page_box(doc, page_id))),
+    page.extractor,
+  ))
+}
+
+/// Glyphs of a page from pdf-extract, or from the native extractor when pdf-extract
+/// cannot be trusted with the page, returns an error or panics.
+pub(crate) fn page_glyphs(doc: &Document, page_num: u32, page_id: ObjectId) -> PageGlyphs<'_> {
+  let trusted = pdf_extract_can_read(doc, page_id, &mut WalkGuard::default());
+  read_page_glyphs(doc, page_num, page_id, trusted, &mut WalkGuard::default())
+}
+
+/// Whether pdf-extract can be trusted with a page.
+///
+/// pdf-extract follows form XObjects without any bound: a form drawing itself
+/// overflows the stack, which cannot be caught, and forms drawn many times at each
+/// level never end. The page is walked first, and only handed to pdf-extract when
+/// the walk met no cycle or deep nesting and stayed within its budget.
+fn pdf_extract_can_read(doc: &Document, page_id: ObjectId, guard: &mut WalkGuard) -> bool {
+  walk_page_guarded(doc, page_id, guard, &mut |_, _| {});
+  !guard.refused_forms() && !guard.exhausted()
+}
+
+fn read_page_glyphs<'a>(
+  doc: &'a Document,
+  page_num: u32,
+  page_id: ObjectId,
+  trusted: bool,
+  guard: &mut WalkGuard,
+) -> PageGlyphs<'a> {
+  if trusted {
+    if let Ok(Ok(glyphs)) = catch_unwind_silently(|| collect_page_glyphs(doc, page_num)) {
+      return PageGlyphs {
+        glyphs,
+        extractor: Extractor::PdfExtract,
+        fonts: None,
+      };
+    }
+  }
+  let mut native = NativeExtractor::new(doc, page_id);
+  walk_page_guarded(doc, page_id, guard, &mut |op, frame| {
+    native.visit(op, frame)
+  });
+  PageGlyphs {
+    glyphs: native.glyphs,
+    extractor: Extractor::Native,
+    fonts: Some(native.shown_fonts),
+  }
+}
+
+/// Text state parameters, saved and restored with the graphics state.
+#[derive(Clone)]
+struct TextState<'a> {
+  font: Option<&'a Dictionary>,
+  decoder: Option<Rc<FontDecoder>>,
+  size: f64,
+  char_spacing: f64,
+  word_spacing: f64,
+  scaling: f64,
+  leading: f64,
+  rise: f64,
+}
+
+/// Fallback extractor: decodes the text operators of a page with `FontDecoder` and
+/// produces the same glyphs as `GlyphCollector`.
+///
+/// It is deliberately simpler than pdf-extract (no Type3 glyph procedures, no
+/// vertical writing) but does not give up on a page because of a broken font or
+/// operator: unknown fonts fall back to WinAnsi and invalid operands are ignored.
+struct NativeExtractor<'a> {
+  doc: &'a Document,
+  page_height: f64,
+  decoders: HashMap<*const Dictionary, Rc<FontDecoder>>,
+  fallback: Rc<FontDecoder>,
+  state: TextState<'a>,
+  saved: Vec<TextState<'a>>,
+  /// State at each `Do`, with its depth and `q` nesting, restored after the form
+  forms: Vec<(usize, TextState<'a>, usize)>,
+  tm: Matrix,
+  tlm: Matrix,
+  glyphs: Vec<Glyph>,
+  shown_fonts: Vec<Option<&'a Dictionary>>,
+}
+
+impl<'a> NativeExtractor<'a> {
+  fn new(doc: &'a Document, page_id: ObjectId) -> NativeExtractor<'a> {
+    let [_, lly, _, ury] = page_box(doc, page_id);
+    NativeExtractor {
+      doc,
+      page_height: ury - lly,
+      decoders: HashMap::new(),
+      fallback: Rc::new(FontDecoder::fallback()),
+      state: TextState {
+        font: None,
+        decoder: None,
+        size: 0.0,
+        char_spacing: 0.0,
+        word_spacing: 0.0,
+        scaling: 1.0,
+        leading: 0.0,
+        rise: 0.0,
+      },
+      saved: Vec::new(),
+      forms: Vec::new(),
+      tm: Matrix::IDENTITY,
+      tlm: Matrix::IDENTITY,
+      glyphs: Vec::new(),
+      shown_fonts: Vec::new(),
+    }
+  }
+
+  fn visit(&mut self, op: &Operation, frame: &Frame<'a>) {
+    // Form XObjects run with their own copy of the graphics state.
+    while let Some((depth, state, saved)) = self.forms.last() {
+      if frame.depth > *depth {
+        break;
+      }
+      self.state = state.clone();
+      self.saved.truncate(*saved);
+      self.forms.pop();
+    }
+
+    let number = |i: usize| op.operands.get(i).and_then(as_number);
+    match op.operator.as_str() {
+      "q" => self.saved.push(self.state.clone()),
+      "Q" => {
+        if let Some(state) = self.saved.pop() {
+          self.state = state;
+        }
+      }
+      "Do" => self
+        .forms
+        .push((frame.depth, self.state.clone(), self.saved.len())),
+      "BT" => {
+        self.tm = Matrix::IDENTITY;
+        self.tlm = Matrix::IDENTITY;
+      }
+      "Tf" => {
+        let font = op
+          .operands
+          .first()
+          .and_then(|n| n.as_name().ok())
+          .and_then(|n| resource(self.doc, frame.resources, b"Font", n))
+          .and_then(|f| f.as_dict().ok());
+        self.state.decoder = font.map(|f| self.decoder(f));
+        self.state.font = font;
+        self.state.size = number(1).unwrap_or(self.state.size);
+      }
+      "Tc" => self.state.char_spacing = number(0).unwrap_or(0.0),
+      "Tw" => self.state.word_spacing = number(0).unwrap_or(0.0),
+      "Tz" => self.state.scaling = number(0).unwrap_or(100.0) / 100.0,
+      "TL" => self.state.leading = number(0).unwrap_or(0.0),
+      "Ts" => self.state.rise = number(0).unwrap_or(0.0),
+      "Td" | "TD" => {
+        let (Some(tx), Some(ty)) = (number(0), number(1)) else {
+          return;
+        };
+        if op.operator == "TD" {
+          self.state.leading = -ty;
+        }
+        self.move_line(tx, ty);
+      }
+      "Tm" => {
+        if let Some(m) = Matrix::from_operands(&op.operands) {
+          self.tm = m;
+          self.tlm = m;
+        }
+      }
+      "T*" => self.move_line(0.0, -self.state.leading),
+      "Tj" => self.show_operand(op.operands.first(), frame),
+      "'" => {
+        self.move_line(0.0, -self.state.leading);
+        self.show_operand(op.operands.first(), frame);
+      }
+      "\"" => {
+        self.state.word_spacing = number(0).unwrap_or(self.state.word_spacing);
+        self.state.char_spacing = number(1).unwrap_or(self.state.char_spacing);
+        self.move_line(0.0, -self.state.leading);
+        self.show_operand(op.operands.get(2), frame);
+      }
+      "TJ" => {
+        let Some(Object::Array(items)) = op.operands.first() else {
+          return;
+        };
+        for item in items {
+          match as_number(item) {
+            Some(adjust) => {
+              let tx = -adjust / 1000.0 * self.state.size * self.state.scaling;
+              self.tm = Matrix::translation(tx, 0.0).then(&self.tm);
+            }
+            None => self.show_operand(Some(item), frame),
+          }
+        }
+      }
+      _ => {}
+    }
+  }
+
+  fn decoder(&mut self, font: &'a Dictionary) -> Rc<FontDecoder> {
+    let doc = self.doc;
+    self
+      .decoders
+      .entry(font as *const Dictionary)
+      .or_insert_with(|| Rc::new(FontDecoder::new(doc, font)))
+      .clone()
+  }
+
+  fn move_line(&mut self, tx: f64, ty: f64) {
+    self.tlm = Matrix::translation(tx, ty).then(&self.tlm);
+    self.tm = self.tlm;
+  }
+
+  fn show_operand(&mut self, operand: Option<&Object>, frame: &Frame) {
+    if let Some(Object::String(bytes, _)) = operand {
+      self.show(bytes, frame);
+    }
+  }
+
+  /// Emit the glyphs of a shown string and advance the text matrix.
+  fn show(&mut self, bytes: &[u8], frame: &Frame) {
+    let decoder = self.state.decoder.clone().unwrap_or(self.fallback.clone());
+    let op = self.shown_fonts.len();
+    self.shown_fonts.push(self.state.font);
+
+    let TextState {
+      size,
+      char_spacing,
+      word_spacing,
+      scaling,
+      rise,
+      ..
+    } = self.state;
+    for code in decoder.codes(bytes) {
+      let text_space = Matrix {
+        a: scaling,
+        f: rise,
+        ..Matrix::IDENTITY
+      };
+      let trm = text_space.then(&self.tm).then(&frame.ctm);
+      let glyph_size = (size * (trm.a + trm.c) * size * (trm.b + trm.d))
+        .abs()
+        .sqrt();
+      let w0 = decoder.width(code) / 1000.0;
+      self.glyphs.push(Glyph {
+        text: decoder.decode(code),
+        x: trm.e,
+        y: self.page_height - trm.f,
+        width: w0 * glyph_size,
+        size: glyph_size,
+        op,
+      });
+
+      let mut spacing = char_spacing;
+      if code == 32 && !decoder.is_two_byte() {
+        spacing += word_spacing;
+      }
+      let tx = scaling * (w0 * size + spacing);
+      self.tm = Matrix::translation(tx, 0.0).then(&self.tm);
+    }
+  }
+}
+
+/// Empty every stream whose decompressed size exceeds `limit` bytes (decompression
+/// bombs), so that pdf-extract never inflates them. Returns the number of streams
+/// emptied.
+fn strip_oversized_streams(doc: &mut Document, limit: usize) -> usize {
+  let mut stripped = 0;
+  for object in doc.objects.values_mut() {
+    let Object::Stream(stream) = object else {
+      continue;
+    };
+    if decoded_len_exceeds(stream, limit) {
+      stream.dict.remove(b"Filter");
+      stream.dict.remove(b"DecodeParms");
+      stream.set_content(Vec::new());
+      stripped += 1;
+    }
+  }
+  stripped
+}
+
+/// Whether a stream decodes to more than `limit` bytes. Each filter of the chain
+/// is decoded with a bounded output, so a bomb is never fully decompressed.
+pub(crate) fn decoded_len_exceeds(stream: &Stream, limit: usize) -> bool {
+  let filters = stream.filters().unwrap_or_default();
+  // Image codecs are never decoded by the text extractor.
+  if let Some("DCTDecode" | "JPXDecode" | "CCITTFaxDecode" | "JBIG2Decode") =
+    filters.last().map(String::as_str)
+  {
+    return false;
+  }
+  let early_change = stream
+    .dict
+    .get(b"DecodeParms")
+    .and_then(Object::as_dict)
+    .and_then(|params| params.get(b"EarlyChange"))
+    .and_then(Object::as_i64)
+    .map_or(true, |value| value != 0);
+
+  let mut data = stream.content.clone();
+  for (i, filter) in filters.iter().enumerate() {
+    // Intermediate stages may be an ASCII encoding of the next one, twice as long.
+    let bound = if i + 1 == filters.len() {
+      limit
+    } else {
+      limit.saturating_mul(2)
+    };
+    data = match filter.as_str() {
+      "FlateDecode" | "Fl" => {
+        let mut inflated = Vec::new();
+        let mut decoder = ZlibDecoder::new(&data[..]).take(bound as u64 + 1);
+        let _ = decoder.read_to_end(&mut inflated);
+        inflated
+      }
+      "LZWDecode" | "LZW" => lzw_decode(&data, early_change, bound),
+      // At most four bytes out for each byte in.
+      "ASCII85Decode" | "A85" => {
+        let mut dict = Dictionary::new();
+        dict.set("Filter", "ASCII85Decode");
+        Stream::new(dict, data)
+          .decompressed_content()
+          .unwrap_or_default()
+      }
+      // Filters lopdf cannot decode are left undecoded by pdf-extract too.
+      _ => return false,
+    };
+    if data.len() > bound {
+      return true;
+    }
+  }
+  data.len() > limit
+}
+
+/// LZW-decode `data`, stopping once the output exceeds `bound` bytes.
+fn lzw_decode(data: &[u8], early_change: bool, bound: usize) -> Vec<u8> {
+  let mut decoder = if early_change {
+    weezl::decode::Decoder::with_tiff_size_switch(weezl::BitOrder::Msb, 8)
+  } else {
+    weezl::decode::Decoder::new(weezl::BitOrder::Msb, 8)
+  };
+  let mut output = Vec::new();
+  let mut input = data;
+  let mut buffer = [0u8; 8192];
+  while output.len() <= bound {
+    let result = decoder.decode_bytes(input, &mut buffer);
+    output.extend_from_slice(&buffer[..result.consumed_out]);
+    input = &input[result.consumed_in..];
+    match result.status {
+      Ok(weezl::LzwStatus::Ok) if result.consumed_in + result.consumed_out > 0 => {}
+      _ => break,
+    }
+  }
+  output
 }
 
 /// Try to optimize/compress a PDF using Ghostscript (`gs`) if available.
@@ -0,0 +1,788 @@
+use std::borrow::Cow;
+use std::time::Instant;
+
+use lopdf::content::Operation;
+use lopdf::{Dictionary, Document, Object, ObjectId, Stream, StringFormat};
+
+use crate::pdf::decoded_len_exceeds;
+use crate::pdf_images::inline_image_stream;
+use crate::pdf_objects::{as_number, dict_get, dict_get_dict, page_resources, resource};
+
+/// Maximum nesting of form XObjects followed by a content walk.
+const MAX_FORM_DEPTH: usize = 8;
+/// Maximum number of operations visited by a content walk, forms included.
+const MAX_WALK_OPERATIONS: usize = 2_000_000;
+/// Maximum amount of (decompressed) content parsed by a content walk.
+pub(crate) const MAX_WALK_BYTES: usize = 256 << 20;
+/// Number of operations between two checks of the deadline of a content walk.
+const DEADLINE_CHECK_INTERVAL: usize = 1024;
+/// Maximum nesting of arrays and dictionaries in a content stream.
+const MAX_OBJECT_DEPTH: usize = 32;
+
+/// Affine transformation matrix `[a b c d e f]`, as used by `cm` and `Tm`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) struct Matrix {
+  pub a: f64,
+  pub b: f64,
+  pub c: f64,
+  pub d: f64,
+  pub e: f64,
+  pub f: f64,
+}
+
+impl Matrix {
+  pub const IDENTITY: Matrix = Matrix {
+    a: 1.0,
+    b: 0.0,
+    c: 0.0,
+    d: 1.0,
+    e: 0.0,
+    f: 0.0,
+  };
+
+  pub fn from_operands(operands: &[Object]) -> Option<Matrix> {
+    let v: Vec<f64> = operands.iter().map(as_number).collect::<Option<_>>()?;
+    match v[..] {
+      [a, b, c, d, e, f] => Some(Matrix { a, b, c, d, e, f }),
+      _ => None,
+    }
+  }
+
+  pub fn translation(tx: f64, ty: f64) -> Matrix {
+    Matrix {
+      e: tx,
+      f: ty,
+      ..Matrix::IDENTITY
+    }
+  }
+
+  /// `self × other`: apply `self` first, then `other`.
+  pub fn then(&self, other: &Matrix) -> Matrix {
+    Matrix {
+      a: self.a * other.a + self.b * other.c,
+      b: self.a * other.b + self.b * other.d,
+      c: self.c * other.a + self.d * other.c,
+      d: self.c * other.b + self.d * other.d,
+      e: self.e * other.a + self.f * other.c + other.e,
+      f: self.e * other.b + self.f * other.d + other.f,
+    }
+  }
+
+  pub fn apply(&self, x: f64, y: f64) -> (f64, f64) {
+    (
+      self.a * x + self.c * y + self.e,
+      self.b * x + self.d * y + self.f,
+    )
+  }
+
+  /// The inverse transformation, if the matrix is not singular.
+  pub fn inverse(&self) -> Option<Matrix> {
+    let det = self.a * self.d - self.b * self.c;
+    if det.abs() < 1e-12 {
+      return None;
+    }
+    Some(Matrix {
+      a: self.d / det,
+      b: -self.b / det,
+      c: -self.c / det,
+      d: self.a / det,
+      e: (self.c * self.f - self.d * self.e) / det,
+      f: (self.b * self.e - self.a * self.f) / det,
+    })
+  }
+
+  /// Bounding box `[llx, lly, urx, ury]` of a rectangle once transformed.
+  pub fn transform_rect(&self, rect: [f64; 4]) -> [f64; 4] {
+    let corners = [
+      self.apply(rect[0], rect[1]),
+      self.apply(rect[2], rect[1]),
+      self.apply(rect[0], rect[3]),
+      self.apply(rect[2], rect[3]),
+    ];
+    let xs = corners.iter().map(|p| p.0);
+    let ys = corners.iter().map(|p| p.1);
+    [
+      xs.clone().fold(f64::INFINITY, f64::min),
+      ys.clone().fold(f64::INFINITY, f64::min),
+      xs.fold(f64::NEG_INFINITY, f64::max),
+      ys.fold(f64::NEG_INFINITY, f64::max),
+    ]
+  }
+}
+
+/// Decoded content of a page: all its content streams, decompressed and concatenated,
+/// or `None` when it is larger than a content walk may parse (`MAX_WALK_BYTES`).
+pub(crate) fn page_content(doc: &Document, page_id: ObjectId) -> Option<Vec<u8>> {
+  let mut content = Vec::new();
+  for id in doc.get_page_contents(page_id) {
+    if let Ok(stream) = doc.get_object(id).and_then(Object::as_stream) {
+      content.extend(stream_content(
+        stream,
+        MAX_WALK_BYTES.saturating_sub(content.len()),
+      )?);
+      // Streams are only split between tokens, but not necessarily at whitespace.
+      content.push(b'\n');
+    }
+  }
+  Some(content)
+}
+
+/// Decompressed content of a content stream (its raw content when lopdf cannot
+/// decompress it), or `None` when it is larger than `limit` bytes. Decompression
+/// stops past the limit, so a bomb is never fully inflated.
+pub(crate) fn stream_content(stream: &Stream, limit: usize) -> Option<Vec<u8>> {
+  if decoded_len_exceeds(stream, limit) {
+    return None;
+  }
+  let data = stream
+    .decompressed_content()
+    .unwrap_or_else(|_| stream.content.clone());
+  (data.len() <= limit).then_some(data)
+}
+
+/// Parse a content stream into operations.
+///
+/// Unlike lopdf's parser this one never gives up on the rest of the stream: unknown
+/// bytes are skipped, and inline images (`BI … ID … EI`) are returned as a single
+/// `BI` operation whose operands are the image dictionary and the raw image data.
+///
+/// Operations are parsed as they are iterated, so a walk stopped early does not pay
+/// for the rest of a large stream.
+pub(crate) fn parse_content(data: &[u8]) -> impl Iterator<Item = Operation> + '_ {
+  let mut lexer = Lexer { data, pos: 0 };
+  let mut operands = Vec::new();
+
+  std::iter::from_fn(move || loop {
+    match lexer.next_token(0) {
+      Token::Eof => return None,
+      Token::Object(obj) => operands.push(obj),
+      Token::Operator(op) if op == "BI" => {
+        operands.clear();
+        if let Some((dict, data)) = lexer.inline_image() {
+          return Some(Operation::new(
+            "BI",
+            vec![
+              Object::Dictionary(dict),
+              Object::String(data, StringFormat::Literal),
+            ],
+          ));
+        }
+      }
+      Token::Operator(op) => return Some(Operation::new(&op, std::mem::take(&mut operands))),
+      Token::ArrayEnd | Token::DictEnd => {}
+    }
+  })
+}
+
+enum Token {
+  Object(Object),
+  Operator(String),
+  ArrayEnd,
+  DictEnd,
+  Eof,
+}
+
+struct Lexer<'a> {
+  data: &'a [u8],
+  pos: usize,
+}
+
+fn is_whitespace(c: u8) -> bool {
+  matches!(c, b' ' | b'\t' | b'\r' | b'\n' | b'\x0c' | b'\0')
+}
+
+fn is_delimiter(c: u8) -> bool {
+  matches!(
+    c,
+    b'(' | b')' | b'<' | b'>' | b'[' | b']' | b'{' | b'}' | b'/' | b'%'
+  )
+}
+
+fn is_regular(c: u8) -> bool {
+  !is_whitespace(c) && !is_delimiter(c)
+}
+
+impl Lexer<'_> {
+  fn peek(&self) -> Option<u8> {
+    self.data.get(self.pos).copied()
+  }
+
+  fn skip_whitespace(&mut self) {
+    while let Some(c) = self.peek() {
+      if is_whitespace(c) {
+        self.pos += 1;
+      } else if c == b'%' {
+        while self.peek().is_some_and(|c| c != b'\n' && c != b'\r') {
+          self.pos += 1;
+        }
+      } else {
+        break;
+      }
+    }
+  }
+
+  fn next_token(&mut self, depth: usize) -> Token {
+    loop {
+      self.skip_whitespace();
+      let Some(c) = self.peek() else {
+        return Token::Eof;
+      };
+      let next = self.data.get(self.pos + 1).copied();
+
+      return match c {
+        b'/' => {
+          self.pos += 1;
+          Token::Object(Object::Name(self.name()))
+        }
+        b'(' => {
+          self.pos += 1;
+          Token::Object(Object::String(self.literal_string(), StringFormat::Literal))
+        }
+        b'<' if next == Some(b'<') => {
+          self.pos += 2;
+          Token::Object(Object::Dictionary(self.dictionary(depth + 1)))
+        }
+        b'<' => {
+          self.pos += 1;
+          Token::Object(Object::String(self.hex_string(), StringFormat::Hexadecimal))
+        }
+        b'>' if next == Some(b'>') => {
+          self.pos += 2;
+          Token::DictEnd
+        }
+        b'[' => {
+          self.pos += 1;
+          Token::Object(Object::Array(self.array(depth + 1)))
+        }
+        b']' => {
+          self.pos += 1;
+          Token::ArrayEnd
+        }
+        c if is_regular(c) => {
+          let start = self.pos;
+          while self.peek().is_some_and(is_regular) {
+            self.pos += 1;
+          }
+          let word = &self.data[start..self.pos];
+          match word {
+            b"true" => Token::Object(Object::Boolean(true)),
+            b"false" => Token::Object(Object::Boolean(false)),
+            b"null" => Token::Object(Object::Null),
+            _ => match parse_number(word) {
+              Some(number) => Token::Object(number),
+              None => Token::Operator(String::from_utf8_lossy(word).into_owned()),
+            },
+          }
+        }
+        _ => {
+          // Stray delimiter (`)`, `>`, `{`, `}`): skip it.
+          self.pos += 1;
+          continue;
+        }
+      };
+    }
+  }
+
+  fn name(&mut self) -> Vec<u8> {
+    let mut name = Vec::new();
+    while let Some(c) = self.peek().filter(|c| is_regular(*c)) {
+      self.pos += 1;
+      if c == b'#' {
+        let hex = self.data.get(self.pos..self.pos + 2);
+        if let Some(byte) = hex
+          .and_then(|h| std::str::from_utf8(h).ok())
+          .and_then(|h| u8::from_str_radix(h, 16).ok())
+        {
+          name.push(byte);
+          self.pos += 2;
+          continue;
+        }
+      }
+      name.push(c);
+    }
+    name
+  }
+
+  fn literal_string(&mut self) -> Vec<u8> {
+    let mut out = Vec::new();
+    let mut nesting = 0;
+    while let Some(c) = self.peek() {
+      self.pos += 1;
+      match c {
+        b'(' => {
+          nesting += 1;
+          out.push(c);
+        }
+        b')' if nesting == 0 => break,
+        b')' => {
+          nesting -= 1;
+          out.push(c);
+        }
+        b'\\' => {
+          let Some(e) = self.peek() else { break };
+          self.pos += 1;
+          match e {
+            b'n' => out.push(b'\n'),
+            b'r' => out.push(b'\r'),
+            b't' => out.push(b'\t'),
+            b'b' => out.push(0x08),
+            b'f' => out.push(0x0c),
+            b'0'..=b'7' => {
+              let mut value = (e - b'0') as u32;
+              for _ in 0..2 {
+                match self.peek() {
+                  Some(d @ b'0'..=b'7') => {
+                    value = value * 8 + (d - b'0') as u32;
+                    self.pos += 1;
+                  }
+                  _ => break,
+                }
+              }
+              out.push(value as u8);
+            }
+            // Line continuation
+            b'\r' => {
+              if self.peek() == Some(b'\n') {
+                self.pos += 1;
+              }
+            }
+            b'\n' => {}
+            _ => out.push(e),
+          }
+        }
+        _ => out.push(c),
+      }
+    }
+    out
+  }
+
+  fn hex_string(&mut self) -> Vec<u8> {
+    let mut digits = Vec::new();
+    while let Some(c) = self.peek() {
+      self.pos += 1;
+      if c == b'>' {
+        break;
+      }
+      if let Some(d) = (c as char).to_digit(16) {
+        digits.push(d as u8);
+      }
+    }
+    if digits.len() % 2 == 1 {
+      digits.push(0);
+    }
+    digits.chunks(2).map(|p| p[0] << 4 | p[1]).collect()
+  }
+
+  fn array(&mut self, depth: usize) -> Vec<Object> {
+    let mut items = Vec::new();
+    if depth > MAX_OBJECT_DEPTH {
+      return items;
+    }
+    loop {
+      match self.next_token(depth) {
+        Token::Object(obj) => items.push(obj),
+        // Operators cannot appear in arrays; keep them as names so nothing is lost.
+        Token::Operator(op) => items.push(Object::Name(op.into_bytes())),
+        Token::ArrayEnd | Token::Eof => break,
+        Token::DictEnd => {}
+      }
+    }
+    items
+  }
+
+  fn dictionary(&mut self, depth: usize) -> Dictionary {
+    let mut dict = Dictionary::new();
+    if depth > MAX_OBJECT_DEPTH {
+      return dict;
+    }
+    let mut key: Option<Vec<u8>> = None;
+    loop {
+      match self.next_token(depth) {
+        Token::Object(Object::Name(name)) if key.is_none() => key = Some(name),
+        Token::Object(value) => {
+          if let Some(key) = key.take() {
+            dict.set(key, value);
+          }
+        }
+        Token::Operator(op) => {
+          if let Some(key) = key.take() {
+            dict.set(key, Object::Name(op.into_bytes()));
+          }
+        }
+        Token::DictEnd | Token::Eof => break,
+        Token::ArrayEnd => {}
+      }
+    }
+    dict
+  }
+
+  /// Parse the rest of an inline image after `BI`: key/value pairs up to `ID`,
+  /// then the raw data up to an `EI` surrounded by whitespace.
+  fn inline_image(&mut self) -> Option<(Dictionary, Vec<u8>)> {
+    let mut dict = Dictionary::new();
+    let mut key: Option<Vec<u8>> = None;
+    loop {
+      match self.next_token(0) {
+        Token::Operator(op) if op == "ID" => break,
+        Token::Object(Object::Name(name)) if key.is_none() => key = Some(name),
+        Token::Object(value) => {
+          if let Some(key) = key.take() {
+            dict.set(key, value);
+          }
+        }
+        Token::Operator(op) => {
+          if let Some(key) = key.take() {
+            dict.set(key, Object::Name(op.into_bytes()));
+          }
+        }
+        Token::Eof => return None,
+        Token::ArrayEnd | Token::DictEnd => {}
+      }
+    }
+
+    // A single whitespace byte separates `ID` from the data.
+    if self.peek().is_some_and(is_whitespace) {
+      self.pos += 1;
+    }
+    let start = self.pos;
+    let mut i = start;
+    while i + 1 < self.data.len() {
+      let preceded = i == start || is_whitespace(self.data[i - 1]);
+      let followed = self.data.get(i + 2).is_none_or(|c| is_whitespace(*c));
+      if preceded && followed && &self.data[i..i + 2] == b"EI" {
+        let mut end = i;
+        if end > start && is_whitespace(self.data[end - 1]) {
+          end -= 1;
+        }
+        self.pos = i + 2;
+        return Some((dict, self.data[start..end].to_vec()));
+      }
+      i += 1;
+    }
+    self.pos = self.data.len();
+    None
+  }
+}
+
+fn parse_number(word: &[u8]) -> Option<Object> {
+  let text = std::str::from_utf8(word).ok()?;
+  let first = *word.first()?;
+  if !(first.is_ascii_digit() || matches!(first, b'+' | b'-' | b'.')) {
+    return None;
+  }
+  if !text.contains('.') {
+    if let Ok(i) = text.parse::<i64>() {
+      return Some(Object::Integer(i));
+    }
+  }
+  // Tolerate producers writing `--1` or `1.2.3`: keep the longest valid prefix.
+  let negative = first == b'-';
+  let digits = text.trim_start_matches(['+', '-']);
+  let mut end = 0;
+  let mut seen_dot = false;
+  for (i, c) in digits.char_indices() {
+    match c {
+      '0'..='9' => end = i + 1,
+      '.' if !seen_dot => {
+        seen_dot = true;
+        end = i + 1;
+      }
+      _ => break,
+    }
+  }
+  let value: f64 = match &digits[..end] {
+    "" | "." => return None,
+    digits => digits.parse().ok()?,
+  };
+  Some(Object::Real(if negative { -value } else { value } as f32))
+}
+
+/// Graphics state visible to a content walker.
+pub(crate) struct Frame<'a> {
+  /// Current transformation matrix (user space to default page space)
+  pub ctm: Matrix,
+  /// Resources of the content stream being walked (page or form XObject)
+  pub resources: Option<&'a Dictionary>,
+  /// Form XObject nesting level (0 for the page content)
+  pub depth: usize,
+}
+
+/// Bounds of a walk through a content stream and the form XObjects it draws.
+///
+/// A form already being drawn is not entered again (a form drawing itself would
+/// never end), forms nest at most `MAX_FORM_DEPTH` deep, and the walk is over
+/// once `MAX_WALK_OPERATIONS` operations or `MAX_WALK_BYTES` of content are spent,
+/// since forms drawn several times at each level multiply the work. A walk can
+/// also be given a deadline.
+#[derive(Default)]
+pub(crate) struct WalkGuard {
+  /// Forms being drawn, outermost first (`None` for a direct stream)
+  forms: Vec<Option<ObjectId>>,
+  operations: usize,
+  bytes: usize,
+  deadline: Option<Instant>,
+  timed_out: bool,
+  /// Whether a form was skipped for being already drawn or nested too deep
+  refused: bool,
+}
+
+impl WalkGuard {
+  /// A guard that also stops the walk once `deadline` has passed.
+  pub fn until(deadline: Option<Instant>) -> WalkGuard {
+    WalkGuard {
+      deadline,
+      ..WalkGuard::default()
+    }
+  }
+
+  /// Whether the budget of the walk is spent (or its deadline passed).
+  pub fn exhausted(&self) -> bool {
+    self.operations > MAX_WALK_OPERATIONS || self.bytes > MAX_WALK_BYTES || self.timed_out
+  }
+
+  /// Spend the whole content budget, for content too large to parse.
+  pub fn exhaust(&mut self) {
+    self.bytes = MAX_WALK_BYTES + 1;
+  }
+
+  /// Whether the walk was stopped by its deadline.
+  pub fn timed_out(&self) -> bool {
+    self.timed_out
+  }
+
+  /// Whether a form was skipped because it was already being drawn (a cycle) or
+  /// nested too deep.
+  pub fn refused_forms(&self) -> bool {
+    self.refused
+  }
+
+  /// Count one operation; false once the budget is spent.
+  pub fn step(&mut self) -> bool {
+    self.operations += 1;
+    if self.operations.is_multiple_of(DEADLINE_CHECK_INTERVAL)
+      && self.deadline.is_some_and(|d| Instant::now() >= d)
+    {
+      self.timed_out = true;
+    }
+    !self.exhausted()
+  }
+
+  /// Start drawing the form XObject named by a `Do` operation.
+  ///
+  /// Returns the form and its decompressed content, or `None` when the XObject
+  /// is not a form, is already being drawn, is nested too deep or the budget is
+  /// spent. Each form entered must be left with `leave`.
+  pub fn enter<'a>(
+    &mut self,
+    doc: &'a Document,
+    resources: Option<&'a Dictionary>,
+    op: &Operation,
+  ) -> Option<(&'a Stream, Vec<u8>)> {
+    if self.exhausted() {
+      return None;
+    }
+    let form = xobject(doc, resources, op).filter(|s| xobject_subtype(doc, s) == Some(b"Form"))?;
+    if self.forms.len() >= MAX_FORM_DEPTH {
+      self.refused = true;
+      return None;
+    }
+    let id = op
+      .operands
+      .first()
+      .and_then(|name| name.as_name().ok())
+      .and_then(|name| dict_get_dict(doc, resources?, b"XObject")?.get(name).ok())
+      .and_then(|entry| entry.as_reference().ok());
+    if id.is_some() && self.forms.contains(&id) {
+      self.refused = true;
+      return None;
+    }
+    let Some(data) = stream_content(form, MAX_WALK_BYTES.saturating_sub(self.bytes)) else {
+      self.exhaust();
+      return None;
+    };
+    self.bytes += data.len();
+    self.forms.push(id);
+    Some((form, data))
+  }
+
+  /// Done drawing the innermost form entered.
+  pub fn leave(&mut self) {
+    self.forms.pop();
+  }
+}
+
+/// Walk every operation drawn on a page, in order, descending into form XObjects.
+///
+/// The walker tracks the CTM through `q`/`Q`/`cm` and form matrices; every
+/// operation (including `Do` for forms, before descending) is passed to `visit`
+/// with the state in effect at that point. The walk is bounded by a `WalkGuard`.
+pub(crate) fn walk_page<'a>(
+  doc: &'a Document,
+  page_id: ObjectId,
+  visit: &mut dyn FnMut(&Operation, &Frame<'a>),
+) {
+  walk_page_guarded(doc, page_id, &mut WalkGuard::default(), visit);
+}
+
+/// `walk_page` with a given guard, e.g. one with a deadline, to inspect once done.
+pub(crate) fn walk_page_guarded<'a>(
+  doc: &'a Document,
+  page_id: ObjectId,
+  guard: &mut WalkGuard,
+  visit: &mut dyn FnMut(&Operation, &Frame<'a>),
+) {
+  let frame = Frame {
+    ctm: Matrix::IDENTITY,
+    resources: page_resources(doc, page_id),
+    depth: 0,
+  };
+  let Some(content) = page_content(doc, page_id) else {
+    guard.exhaust();
+    return;
+  };
+  guard.bytes += content.len();
+  walk_content(doc, &content, frame, guard, visit);
+}
+
+fn walk_content<'a>(
+  doc: &'a Document,
+  content: &[u8],
+  mut frame: Frame<'a>,
+  guard: &mut WalkGuard,
+  visit: &mut dyn FnMut(&Operation, &Frame<'a>),
+) {
+  let mut saved: Vec<Matrix> = Vec::new();
+
+  for op in parse_content(content) {
+    if !guard.step() {
+      return;
+    }
+    visit(&op, &frame);
+    match op.operator.as_str() {
+      "q" => saved.push(frame.ctm),
+      "Q" => frame.ctm = saved.pop().unwrap_or(frame.ctm),
+      "cm" => {
+        if let Some(m) = Matrix::from_operands(&op.operands) {
+          frame.ctm = m.then(&frame.ctm);
+        }
+      }
+      "Do" => {
+        let Some((form, data)) = guard.enter(doc, frame.resources, &op) else {
+          continue;
+        };
+        let matrix = dict_get(doc, &form.dict, b"Matrix")
+          .and_then(|m| m.as_array().ok())
+          .and_then(|m| Matrix::from_operands(m))
+          .unwrap_or(Matrix::IDENTITY);
+        let inner = Frame {
+          ctm: matrix.then(&frame.ctm),
+          resources: dict_get_dict(doc, &form.dict, b"Resources").or(frame.resources),
+          depth: frame.depth + 1,
+        };
+        walk_content(doc, &data, inner, guard, visit);
+        guard.leave();
+      }
+      _ => {}
+    }
+  }
+}
+
+/// The XObject named by a `Do` operation.
+pub(crate) fn xobject<'a>(
+  doc: &'a Document,
+  resources: Option<&'a Dictionary>,
+  op: &Operation,
+) -> Option<&'a Stream> {
+  let name = op.operands.first()?.as_name().ok()?;
+  resource(doc, resources, b"XObject", name)?.as_stream().ok()
+}
+
+/// Subtype of an XObject (`Form` or `Image`).
+pub(crate) fn xobject_subtype<'a>(doc: &'a Document, stream: &'a Stream) -> Option<&'a [u8]> {
+  dict_get(doc, &stream.dict, b"Subtype").and_then(|s| s.as_name().ok())
+}
+
+/// An image drawn on a page.
+#[derive(Clone, Debug)]
+pub(crate) struct ImagePlacement<'a> {
+  /// Bounding box in default page space, `[llx, lly, urx, ury]`
+  pub rect: [f64; 4],
+  /// Image XObject, or inline image turned into an equivalent stream
+  pub image: Cow<'a, Stream>,
+}
+
+/// Every image drawn on a page (image XObjects and inline images), in drawing order.
+pub(crate) fn page_images(doc: &Document, page_id: ObjectId) -> Vec<ImagePlacement<'_>> {
+  let mut images = Vec::new();
+  walk_page(doc, page_id, &mut |op, frame| {
+    let image = match op.operator.as_str() {
+      "Do" => xobject(doc, frame.resources, op)
+        .filter(|stream| xobject_subtype(doc, stream) == Some(b"Image"))
+        .map(Cow::Borrowed),
+      "BI" => match &op.operands[..] {
+        [Object::Dictionary(dict), Object::String(data, _)] => {
+          Some(Cow::Owned(inline_image_stream(dict, data)))
+        }
+        _ => None,
+      },
+      _ => None,
+    };
+    if let Some(image) = image {
+      // Images are drawn into the unit square of the current user space.
+      images.push(ImagePlacement {
+        rect: frame.ctm.transform_rect([0.0, 0.0, 1.0, 1.0]),
+        image,
+      });
+    }
+  });
+  images
+}
+
+/// Area of the union of rectangles, clipped to `clip`.
+pub(crate) fn union_area(rects: &[[f64; 4]], clip: [f64; 4]) -> f64 {
+  let rects: Vec<[f64; 4]> = rects
+    .iter()
+    .map(|r| {
+      [
+        r[0].max(clip[0]),
+        r[1].max(clip[1]),
+        r[2].min(clip[2]),
+        r[3].min(clip[3]),
+      ]
+    })
+    .filter(|r| r[2] > r[0] && r[3] > r[1])
+    .collect();
+
+  let mut xs: Vec<f64> = rects.iter().flat_map(|r| [r[0], r[2]]).collect();
+  xs.sort_by(f64::total_cmp);
+  xs.dedup();
+
+  // Sweep over x slabs; in each slab, merge the covered y intervals.
+  let mut area = 0.0;
+  for slab in xs.windows(2) {
+    let (x0, x1) = (slab[0], slab[1]);
+    let mut spans: Vec<(f64, f64)> = rects
+      .iter()
+      .filter(|r| r[0] <= x0 && r[2] >= x1)
+      .map(|r| (r[1], r[3]))
+      .collect();
+    spans.sort_by(|a, b| a.0.total_cmp(&b.0));
+    let mut covered = 0.0;
+    let mut current: Option<(f64, f64)> = None;
+    for (y0, y1) in spans {
+      current = match current {
+        Some((c0, c1)) if y0 <= c1 => Some((c0, c1.max(y1))),
+        Some((c0, c1)) => {
+          covered += c1 - c0;
+          Some((y0, y1))
+        }
+        None => Some((y0, y1)),
+      };
+    }
+    if let Some((c0, c1)) = current {
+      covered += c1 - c0;
+    }
+    area += covered * (x1 - x0);
+  }
+  area
+}
@@ -0,0 +1,606 @@
+use std::collections::HashMap;
+
+use lopdf::{Dictionary, Document, Object};
+use unicode_normalization::UnicodeNormalization;
+
+use crate::pdf_content::parse_content;
+use crate::pdf_objects::{as_number, dict_get, dict_get_dict, font_base_name, resolve};
+
+/// Upper bound on ToUnicode entries, against CMaps declaring huge ranges.
+const MAX_CMAP_ENTRIES: usize = 1 << 17;
+
+/// Maps the character codes of a font to Unicode text and glyph widths.
+///
+/// Decoding prefers the font's ToUnicode CMap, then its encoding (base encoding
+/// plus `/Differences`). Codes that cannot be mapped decode to U+FFFD.
+pub(crate) struct FontDecoder {
+  /// Composite (Type0) fonts use two-byte codes
+  two_byte: bool,
+  to_unicode: HashMap<u32, String>,
+  encoding: Option<[Option<char>; 256]>,
+  widths: HashMap<u32, f64>,
+  default_width: f64,
+  /// Metrics used for standard 14 fonts, which usually come without `/Widths`
+  builtin: Option<BuiltinMetrics>,
+}
+
+#[derive(Clone, Copy)]
+enum BuiltinMetrics {
+  Helvetica,
+  Courier,
+}
+
+impl FontDecoder {
+  pub fn new(doc: &Document, font: &Dictionary) -> FontDecoder {
+    let subtype = dict_get(doc, font, b"Subtype")
+      .and_then(|s| s.as_name().ok())
+      .unwrap_or(b"");
+    let base_name = font_base_name(doc, font).unwrap_or_default();
+    let to_unicode = dict_get(doc, font, b"ToUnicode")
+      .and_then(|o| o.as_stream().ok())
+      .map(|s| {
+        parse_to_unicode(
+          &s.decompressed_content()
+            .unwrap_or_else(|_| s.content.clone()),
+        )
+      })
+      .unwrap_or_default();
+
+    if subtype == b"Type0" {
+      let descendant = dict_get(doc, font, b"DescendantFonts")
+        .and_then(|d| d.as_array().ok())
+        .and_then(|d| d.first())
+        .and_then(|d| resolve(doc, d).as_dict().ok());
+      let (widths, default_width) = descendant
+        .map(|d| cid_widths(doc, d))
+        .unwrap_or((HashMap::new(), 1000.0));
+      return FontDecoder {
+        two_byte: true,
+        to_unicode,
+        encoding: None,
+        widths,
+        default_width,
+        builtin: None,
+      };
+    }
+
+    let default_encoding = if subtype == b"TrueType" {
+      "WinAnsiEncoding"
+    } else {
+      "StandardEncoding"
+    };
+    let encoding = match dict_get(doc, font, b"Encoding") {
+      Some(Object::Name(name)) => base_encoding(&String::from_utf8_lossy(name)),
+      Some(Object::Dictionary(dict)) => {
+        let base = dict_get(doc, dict, b"BaseEncoding")
+          .and_then(|b| b.as_name().ok())
+          .map(|b| String::from_utf8_lossy(b).into_owned());
+        let mut table = base_encoding(base.as_deref().unwrap_or(default_encoding));
+        apply_differences(doc, dict, &mut table);
+        table
+      }
+      _ => base_encoding(default_encoding),
+    };
+
+    let mut widths = HashMap::new();
+    let first_char = dict_get(doc, font, b"FirstChar")
+      .and_then(as_number)
+      .unwrap_or(0.0) as u32;
+    let explicit = dict_get(doc, font, b"Widths").and_then(|w| w.as_array().ok());
+    if let Some(explicit) = explicit {
+      for (i, w) in explicit.iter().enumerate() {
+        if let Some(w) = as_number(resolve(doc, w)) {
+          widths.insert(first_char + i as u32, w);
+        }
+      }
+    }
+    let missing_width = dict_get_dict(doc, font, b"FontDescriptor")
+      .and_then(|d| dict_get(doc, d, b"MissingWidth"))
+      .and_then(as_number);
+    let builtin = match explicit {
+      Some(_) => None,
+      None if base_name.contains("Courier") => Some(BuiltinMetrics::Courier),
+      None => Some(BuiltinMetrics::Helvetica),
+    };
+
+    FontDecoder {
+      two_byte: false,
+      to_unicode,
+      encoding: Some(encoding),
+      widths,
+      default_width: missing_width.unwrap_or(500.0),
+      builtin,
+    }
+  }
+
+  /// Decoder used when text is shown without a valid font: WinAnsi with Helvetica metrics.
+  pub fn fallback() -> FontDecoder {
+    FontDecoder {
+      two_byte: false,
+      to_unicode: HashMap::new(),
+      encoding: Some(base_encoding("WinAnsiEncoding")),
+      widths: HashMap::new(),
+      default_width: 500.0,
+      builtin: Some(BuiltinMetrics::Helvetica),
+    }
+  }
+
+  pub fn is_two_byte(&self) -> bool {
+    self.two_byte
+  }
+
+  /// Split a shown string into character codes.
+  pub fn codes(&self, bytes: &[u8]) -> Vec<u32> {
+    if self.two_byte {
+      bytes
+        .chunks(2)
+        .map(|c| ((c[0] as u32) << 8) | c.get(1).copied().unwrap_or(0) as u32)
+        .collect()
+    } else {
+      bytes.iter().map(|b| *b as u32).collect()
+    }
+  }
+
+  pub fn decode(&self, code: u32) -> String {
+    if let Some(text) = self.to_unicode.get(&code) {
+      return text.clone();
+    }
+    self
+      .encoding
+      .as_ref()
+      .and_then(|table| table.get(code as usize).copied().flatten())
+      .unwrap_or('\u{FFFD}')
+      .to_string()
+  }
+
+  /// Advance width of a code, in thousandths of the font size.
+  pub fn width(&self, code: u32) -> f64 {
+    if let Some(w) = self.widths.get(&code) {
+      return *w;
+    }
+    match self.builtin {
+      Some(BuiltinMetrics::Courier) => 600.0,
+      Some(BuiltinMetrics::Helvetica) => {
+        let c = self.decode(code).chars().next().unwrap_or(' ');
+        helvetica_width(c).unwrap_or(self.default_width)
+      }
+      None => self.default_width,
+    }
+  }
+}
+
+/// Widths of a CID font (`/W` array and `/DW`).
+fn cid_widths(doc: &Document, font: &Dictionary) -> (HashMap<u32, f64>, f64) {
+  let default_width = dict_get(doc, font, b"DW")
+    .and_then(as_number)
+    .unwrap_or(1000.0);
+  let mut widths = HashMap::new();
+  let Some(w) = dict_get(doc, font, b"W").and_then(|w| w.as_array().ok()) else {
+    return (widths, default_width);
+  };
+
+  let items: Vec<&Object> = w.iter().map(|o| resolve(doc, o)).collect();
+  let mut i = 0;
+  while i < items.len() && widths.len() < MAX_CMAP_ENTRIES {
+    let Some(first) = as_number(items[i]) else {
+      i += 1;
+      continue;
+    };
+    match items.get(i + 1) {
+      // `c [w1 w2 …]`
+      Some(Object::Array(list)) => {
+        for (j, w) in list.iter().enumerate() {
+          if let Some(w) = as_number(resolve(doc, w)) {
+            widths.insert(first as u32 + j as u32, w);
+          }
+        }
+        i += 2;
+      }
+      // `c_first c_last w`
+      Some(last) => {
+        let (Some(last), Some(w)) = (as_number(last), items.get(i + 2).and_then(|w| as_number(w)))
+        else {
+          break;
+        };
+        let (first, last) = (first as u32, last as u32);
+        for code in first..=last.min(first + MAX_CMAP_ENTRIES as u32) {
+          widths.insert(code, w);
+        }
+        i += 3;
+      }
+      None => break,
+    }
+  }
+  (widths, default_width)
+}
+
+/// Parse the `bfchar` and `bfrange` sections of a ToUnicode CMap.
+fn parse_to_unicode(data: &[u8]) -> HashMap<u32, String> {
+  let mut map = HashMap::new();
+  let code_of = |o: &Object| match o {
+    Object::String(bytes, _) if bytes.len() <= 4 => {
+      Some(bytes.iter().fold(0u32, |acc, b| (acc << 8) | *b as u32))
+    }
+    _ => None,
+  };
+  let utf16 = |o: &Object| match o {
+    Object::String(bytes, _) => Some(
+      bytes
+        .chunks(2)
+        .map(|c| ((c[0] as u16) << 8) | c.get(1).copied().unwrap_or(0) as u16)
+        .collect::<Vec<u16>>(),
+    ),
+    _ => None,
+  };
+
+  for op in parse_content(data) {
+    match op.operator.as_str() {
+      "endbfchar" => {
+        for pair in op.operands.chunks(2) {
+          if let (Some(code), Some(units)) = (code_of(&pair[0]), pair.get(1).and_then(utf16)) {
+            map.insert(code, String::from_utf16_lossy(&units));
+          }
+        }
+      }
+      "endbfrange" => {
+        for range in op.operands.chunks(3) {
+          let (Some(lo), Some(hi), Some(dst)) = (
+            code_of(&range[0]),
+            range.get(1).and_then(code_of),
+            range.get(2),
+          ) else {
+            continue;
+          };
+          let hi = hi.min(lo.saturating_add(0xFFFF));
+          match dst {
+            Object::Array(items) => {
+              for (code, item) in (lo..=hi).zip(items) {
+                if let Some(units) = utf16(item) {
+                  map.insert(code, String::from_utf16_lossy(&units));
+                }
+              }
+            }
+            _ => {
+              let Some(units) = utf16(dst).filter(|u| !u.is_empty()) else {
+                continue;
+              };
+              // The last UTF-16 unit is incremented through the range.
+              for (offset, code) in (lo..=hi).enumerate() {
+                let mut units = units.clone();
+                if let Some(last) = units.last_mut() {
+                  *last = last.wrapping_add(offset as u16);
+                }
+                map.insert(code, String::from_utf16_lossy(&units));
+              }
+            }
+          }
+        }
+      }
+      _ => {}
+    }
+    if map.len() > MAX_CMAP_ENTRIES {
+      break;
+    }
+  }
+  map
+}
+
+/// Code-to-character table of a predefined simple encoding.
+fn base_encoding(name: &str) -> [Option<char>; 256] {
+  let mut table = [None; 256];
+  match name {
+    "StandardEncoding" | "MacExpertEncoding" => {
+      for (code, slot) in table.iter_mut().enumerate().take(0x7F).skip(0x20) {
+        *slot = Some(code as u8 as char);
+      }
+      table[0x27] = Some('’');
+      table[0x60] = Some('‘');
+      for (code, name) in STANDARD_HIGH {
+        table[*code as usize] = glyph_name_to_char(name);
+      }
+    }
+    _ => {
+      let encoding = if name == "MacRomanEncoding" {
+        encoding_rs::MACINTOSH
+      } else {
+        // PDFDocEncoding differs from WinAnsi only in rarely used positions.
+        encoding_rs::WINDOWS_1252
+      };
+      for (code, slot) in table.iter_mut().enumerate().skip(0x20) {
+        let byte = [code as u8];
+        let (text, _) = encoding.decode_without_bom_handling(&byte);
+        *slot = text.chars().next().filter(|c| !c.is_control());
+      }
+    }
+  }
+  table
+}
+
+/// Apply an encoding dictionary's `/Differences` array to a table.
+fn apply_differences(doc: &Document, encoding: &Dictionary, table: &mut [Option<char>; 256]) {
+  let Some(differences) = dict_get(doc, encoding, b"Differences").and_then(|d| d.as_array().ok())
+  else {
+    return;
+  };
+  let mut code = 0usize;
+  for item in differences {
+    match resolve(doc, item) {
+      Object::Integer(i) => code = (*i).clamp(0, 255) as usize,
+      Object::Name(name) => {
+        if code < 256 {
+          table[code] = glyph_name_to_char(&String::from_utf8_lossy(name));
+        }
+        code += 1;
+      }
+      _ => {}
+    }
+  }
+}
+
+/// High half of the Adobe StandardEncoding.
+const STANDARD_HIGH: &[(u8, &str)] = &[
+  (0xA1, "exclamdown"),
+  (0xA2, "cent"),
+  (0xA3, "sterling"),
+  (0xA4, "fraction"),
+  (0xA5, "yen"),
+  (0xA6, "florin"),
+  (0xA7, "section"),
+  (0xA8, "currency"),
+  (0xA9, "quotesingle"),
+  (0xAA, "quotedblleft"),
+  (0xAB, "guillemotleft"),
+  (0xAC, "guilsinglleft"),
+  (0xAD, "guilsinglright"),
+  (0xAE, "fi"),
+  (0xAF, "fl"),
+  (0xB1, "endash"),
+  (0xB2, "dagger"),
+  (0xB3, "daggerdbl"),
+  (0xB4, "periodcentered"),
+  (0xB6, "paragraph"),
+  (0xB7, "bullet"),
+  (0xB8, "quotesinglbase"),
+  (0xB9, "quotedblbase"),
+  (0xBA, "quotedblright"),
+  (0xBB, "guillemotright"),
+  (0xBC, "ellipsis"),
+  (0xBD, "perthousand"),
+  (0xBF, "questiondown"),
+  (0xC1, "grave"),
+  (0xC2, "acute"),
+  (0xC3, "circumflex"),
+  (0xC4, "tilde"),
+  (0xC5, "macron"),
+  (0xC6, "breve"),
+  (0xC7, "dotaccent"),
+  (0xC8, "dieresis"),
+  (0xCA, "ring"),
+  (0xCB, "cedilla"),
+  (0xCD, "hungarumlaut"),
+  (0xCE, "ogonek"),
+  (0xCF, "caron"),
+  (0xD0, "emdash"),
+  (0xE1, "AE"),
+  (0xE3, "ordfeminine"),
+  (0xE8, "Lslash"),
+  (0xE9, "Oslash"),
+  (0xEA, "OE"),
+  (0xEB, "ordmasculine"),
+  (0xF1, "ae"),
+  (0xF5, "dotlessi"),
+  (0xF8, "lslash"),
+  (0xF9, "oslash"),
+  (0xFA, "oe"),
+  (0xFB, "germandbls"),
+];
+
+/// Adobe glyph names that are not a letter, a `uniXXXX` code or an accented letter.
+const GLYPH_NAMES: &[(&str, char)] = &[
+  ("space", ' '),
+  ("nbspace", '\u{A0}'),
+  ("exclam", '!'),
+  ("quotedbl", '"'),
+  ("numbersign", '#'),
+  ("dollar", '$'),
+  ("percent", '%'),
+  ("ampersand", '&'),
+  ("quotesingle", '\''),
+  ("quoteright", '’'),
+  ("quoteleft", '‘'),
+  ("parenleft", '('),
+  ("parenright", ')'),
+  ("asterisk", '*'),
+  ("plus", '+'),
+  ("comma", ','),
+  ("hyphen", '-'),
+  ("sfthyphen", '\u{AD}'),
+  ("minus", '−'),
+  ("period", '.'),
+  ("slash", '/'),
+  ("zero", '0'),
+  ("one", '1'),
+  ("two", '2'),
+  ("three", '3'),
+  ("four", '4'),
+  ("five", '5'),
+  ("six", '6'),
+  ("seven", '7'),
+  ("eight", '8'),
+  ("nine", '9'),
+  ("colon", ':'),
+  ("semicolon", ';'),
+  ("less", '<'),
+  ("equal", '='),
+  ("greater", '>'),
+  ("question", '?'),
+  ("at", '@'),
+  ("bracketleft", '['),
+  ("backslash", '\\'),
+  ("bracketright", ']'),
+  ("asciicircum", '^'),
+  ("underscore", '_'),
+  ("grave", '`'),
+  ("braceleft", '{'),
+  ("bar", '|'),
+  ("braceright", '}'),
+  ("asciitilde", '~'),
+  ("exclamdown", '¡'),
+  ("cent", '¢'),
+  ("sterling", '£'),
+  ("currency", '¤'),
+  ("yen", '¥'),
+  ("brokenbar", '¦'),
+  ("section", '§'),
+  ("dieresis", '¨'),
+  ("copyright", '©'),
+  ("ordfeminine", 'ª'),
+  ("guillemotleft", '«'),
+  ("logicalnot", '¬'),
+  ("registered", '®'),
+  ("macron", '¯'),
+  ("degree", '°'),
+  ("plusminus", '±'),
+  ("twosuperior", '²'),
+  ("threesuperior", '³'),
+  ("acute", '´'),
+  ("mu", 'µ'),
+  ("paragraph", '¶'),
+  ("periodcentered", '·'),
+  ("cedilla", '¸'),
+  ("onesuperior", '¹'),
+  ("ordmasculine", 'º'),
+  ("guillemotright", '»'),
+  ("onequarter", '¼'),
+  ("onehalf", '½'),
+  ("threequarters", '¾'),
+  ("questiondown", '¿'),
+  ("multiply", '×'),
+  ("divide", '÷'),
+  ("AE", 'Æ'),
+  ("ae", 'æ'),
+  ("OE", 'Œ'),
+  ("oe", 'œ'),
+  ("Oslash", 'Ø'),
+  ("oslash", 'ø'),
+  ("germandbls", 'ß'),
+  ("Eth", 'Ð'),
+  ("eth", 'ð'),
+  ("Thorn", 'Þ'),
+  ("thorn", 'þ'),
+  ("dotlessi", 'ı'),
+  ("Lslash", 'Ł'),
+  ("lslash", 'ł'),
+  ("fi", 'ﬁ'),
+  ("fl", 'ﬂ'),
+  ("ff", 'ﬀ'),
+  ("ffi", 'ﬃ'),
+  ("ffl", 'ﬄ'),
+  ("endash", '–'),
+  ("emdash", '—'),
+  ("bullet", '•'),
+  ("ellipsis", '…'),
+  ("dagger", '†'),
+  ("daggerdbl", '‡'),
+  ("perthousand", '‰'),
+  ("quotedblleft", '“'),
+  ("quotedblright", '”'),
+  ("quotesinglbase", '‚'),
+  ("quotedblbase", '„'),
+  ("guilsinglleft", '‹'),
+  ("guilsinglright", '›'),
+  ("trademark", '™'),
+  ("Euro", '€'),
+  ("euro", '€'),
+  ("florin", 'ƒ'),
+  ("fraction", '⁄'),
+  ("circumflex", 'ˆ'),
+  ("tilde", '˜'),
+  ("breve", '˘'),
+  ("dotaccent", '˙'),
+  ("ring", '˚'),
+  ("ogonek", '˛'),
+  ("caron", 'ˇ'),
+  ("hungarumlaut", '˝'),
+];
+
+/// Accent suffixes of glyph names such as `eacute`, with their combining mark.
+const ACCENTS: &[(&str, char)] = &[
+  ("acute", '\u{301}'),
+  ("grave", '\u{300}'),
+  ("circumflex", '\u{302}'),
+  ("tilde", '\u{303}'),
+  ("macron", '\u{304}'),
+  ("breve", '\u{306}'),
+  ("dotaccent", '\u{307}'),
+  ("dieresis", '\u{308}'),
+  ("ring", '\u{30A}'),
+  ("hungarumlaut", '\u{30B}'),
+  ("caron", '\u{30C}'),
+  ("cedilla", '\u{327}'),
+  ("ogonek", '\u{328}'),
+];
+
+/// Unicode character of an Adobe glyph name.
+pub(crate) fn glyph_name_to_char(name: &str) -> Option<char> {
+  // Variants such as `a.sc` or `one.oldstyle` map to their base glyph.
+  let name = name.split('.').next().unwrap_or(name);
+
+  if let Some((_, c)) = GLYPH_NAMES.iter().find(|(n, _)| *n == name) {
+    return Some(*c);
+  }
+  let hex = name
+    .strip_prefix("uni")
+    .map(|h| &h[..h.len().min(4)])
+    .or_else(|| name.strip_prefix('u'));
+  if let Some(hex) = hex.filter(|h| (4..=6).contains(&h.len())) {
+    if let Some(c) = u32::from_str_radix(hex, 16).ok().and_then(char::from_u32) {
+      return Some(c);
+    }
+  }
+  let mut chars = name.chars();
+  if let (Some(c), None) = (chars.next(), chars.next()) {
+    return c.is_ascii_alphabetic().then_some(c);
+  }
+  for (suffix, mark) in ACCENTS {
+    if let Some(base) = name.strip_suffix(suffix) {
+      let mut base_chars = base.chars();
+      if let (Some(b), None) = (base_chars.next(), base_chars.next()) {
+        if b.is_ascii_alphabetic() {
+          let composed: String = [b, *mark].iter().collect::<String>().nfc().collect();
+          let mut composed_chars = composed.chars();
+          if let (Some(c), None) = (composed_chars.next(), composed_chars.next()) {
+            return Some(c);
+          }
+        }
+      }
+    }
+  }
+  None
+}
+
+/// Helvetica advance widths for printable ASCII (AFM metrics), from space to `~`.
+const HELVETICA_ASCII: [u16; 95] = [
+  278, 278, 355, 556, 556, 889, 667, 191, 333, 333, 389, 584, 278, 333, 278,
+  278, // ' ' to '/'
+  556, 556, 556, 556, 556, 556, 556, 556, 556, 556, // digits
+  278, 278, 584, 584, 584, 556, 1015, // ':' to '@'
+  667, 667, 722, 722, 667, 611, 778, 722, 278, 500, 667, 556, 833, // 'A' to 'M'
+  722, 778, 667, 778, 722, 667, 611, 722, 667, 944, 667, 667, 611, // 'N' to 'Z'
+  278, 278, 278, 469, 556, 333, // '[' to '`'
+  556, 556, 500, 556, 556, 278, 556, 556, 222, 222, 500, 222, 833, // 'a' to 'm'
+  556, 556, 556, 556, 333, 500, 278, 556, 500, 722, 500, 500, 500, // 'n' to 'z'
+  334, 260, 334, 584, // '{' to '~'
+];
+
+/// Helvetica width of a character; accented letters use the width of their base letter.
+fn helvetica_width(c: char) -> Option<f64> {
+  let base = c.to_string().nfd().next().unwrap_or(c);
+  match base {
+    ' '..='~' => Some(HELVETICA_ASCII[base as usize - 0x20] as f64),
+    '’' | '‘' => Some(222.0),
+    '–' | '•' => Some(556.0),
+    '—' => Some(1000.0),
+    _ => None,
+  }
+}
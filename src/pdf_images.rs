@@ -0,0 +1,591 @@
+use std::io::Cursor;
+
+use image::{DynamicImage, GrayImage, ImageFormat, RgbImage};
+use lopdf::{Dictionary, Document, Object, Stream};
+use napi::bindgen_prelude::Uint8Array;
+use napi::{Error, Status};
+use napi_derive::napi;
+
+use crate::pdf::{catch_panic, load_document};
+use crate::pdf_content::page_images;
+use crate::pdf_objects::{as_number, dict_get, page_box, resolve};
+
+/// Upper bound on decoded image pixels, against corrupt or hostile dimensions.
+const MAX_IMAGE_PIXELS: u64 = 100_000_000;
+
+/// Portrait photos are roughly square or taller than wide (height / width).
+const PORTRAIT_ASPECT: std::ops::RangeInclusive<f64> = 0.7..=1.8;
+/// Share of the page a portrait photo covers: more than an icon, less than a background.
+const PORTRAIT_COVERAGE: std::ops::RangeInclusive<f64> = 0.004..=0.25;
+/// Minimum pixel size of the shorter side of a portrait photo.
+const PORTRAIT_MIN_PIXELS: u32 = 48;
+/// Minimum luminance entropy (bits) of a photo; logos and flat drawings score lower.
+const PHOTO_MIN_ENTROPY: f64 = 4.0;
+
+/// Options for PDF image extraction.
+///
+/// - `detect_portrait`: Flag the image most likely to be the candidate's photo
+///   (default: false)
+#[napi(object)]
+#[derive(Default)]
+pub struct PdfImageOptions {
+  pub detect_portrait: Option<bool>,
+}
+
+/// An image drawn on a PDF page.
+///
+/// - `page_number`: 1-based page number
+/// - `rect`: Placement on the page in points, `[x0, y0, x1, y1]` from the bottom left corner
+/// - `pixel_width`, `pixel_height`: Dimensions of the image in pixels
+/// - `format`: "jpeg" (DCT data passed through unchanged) or "png" (decoded samples)
+/// - `data`: Encoded image bytes
+/// - `likely_portrait`: Whether this is the likely portrait photo (only with
+///   `detect_portrait`; at most one image is flagged)
+#[napi(object)]
+pub struct PdfImage {
+  pub page_number: u32,
+  pub rect: Vec<f64>,
+  pub pixel_width: u32,
+  pub pixel_height: u32,
+  pub format: String,
+  pub data: Vec<u8>,
+  pub likely_portrait: bool,
+}
+
+/// Extract the images drawn on the pages of a PDF, e.g. to retrieve the candidate's
+/// profile photo.
+///
+/// Every placement of an image XObject (including those drawn inside form XObjects)
+/// and every inline image is listed, in page and drawing order. JPEG data is passed
+/// through unchanged; other images are decoded and re-encoded as PNG. Images in codecs
+/// that cannot be decoded (JPEG 2000, CCITT, JBIG2) are skipped.
+///
+/// With `detect_portrait`, one image is flagged as the likely portrait: a photograph
+/// (rich tones, ideally skin tones) of portrait or square shape, displayed small and
+/// preferably near the top of the first page.
+#[napi]
+pub fn extract_pdf_images(
+  bytes: Uint8Array,
+  options: Option<PdfImageOptions>,
+) -> napi::Result<Vec<PdfImage>> {
+  let input = bytes.to_vec();
+  let options = options.unwrap_or_default();
+  catch_panic(|| {
+    let doc = load_document(&input).map_err(|e| {
+      Error::new(
+        Status::InvalidArg,
+        format!("Failed to extract images from PDF: {e}"),
+      )
+    })?;
+    Ok(pdf_images(&doc, options.detect_portrait.unwrap_or(false)))
+  })
+}
+
+/// Images of the pages of `doc`, as listed by `extract_pdf_images`.
+pub(crate) fn pdf_images(doc: &Document, detect_portrait: bool) -> Vec<PdfImage> {
+  let mut images = Vec::new();
+  let mut scores = Vec::new();
+  for (page_number, page_id) in doc.get_pages() {
+    let page = page_box(doc, page_id);
+    for placement in page_images(doc, page_id) {
+      let Some(exported) = export_image(doc, &placement.image) else {
+        continue;
+      };
+      let image = PdfImage {
+        page_number,
+        rect: placement.rect.to_vec(),
+        pixel_width: exported.width,
+        pixel_height: exported.height,
+        format: exported.format.to_string(),
+        data: exported.data,
+        likely_portrait: false,
+      };
+      if detect_portrait {
+        scores.push(portrait_score(&image, page));
+      }
+      images.push(image);
+    }
+  }
+
+  let best = scores
+    .iter()
+    .enumerate()
+    .filter(|(_, score)| **score > 0.0)
+    .max_by(|a, b| a.1.total_cmp(b.1));
+  if let Some((index, _)) = best {
+    images[index].likely_portrait = true;
+  }
+  images
+}
+
+/// How likely an image is to be the candidate's photo; 0 when it cannot be.
+pub(crate) fn portrait_score(image: &PdfImage, page: [f64; 4]) -> f64 {
+  let [x0, y0, x1, y1] = [image.rect[0], image.rect[1], image.rect[2], image.rect[3]];
+  let (width, height) = (x1 - x0, y1 - y0);
+  let page_area = (page[2] - page[0]) * (page[3] - page[1]);
+  if width <= 0.0 || page_area <= 0.0 {
+    return 0.0;
+  }
+  if !PORTRAIT_ASPECT.contains(&(height / width))
+    || !PORTRAIT_COVERAGE.contains(&(width * height / page_area))
+    || image.pixel_width.min(image.pixel_height) < PORTRAIT_MIN_PIXELS
+  {
+    return 0.0;
+  }
+  let Ok(decoded) = image::load_from_memory(&image.data) else {
+    return 0.0;
+  };
+  let (entropy, skin) = tone_statistics(&decoded);
+  if entropy < PHOTO_MIN_ENTROPY {
+    return 0.0;
+  }
+
+  let page_factor = match image.page_number {
+    1 => 1.0,
+    2 => 0.5,
+    _ => 0.25,
+  };
+  // Photos usually sit in the header or at the top of a sidebar.
+  let center_y = (y0 + y1) / 2.0;
+  let top_factor = if center_y > (page[1] + page[3]) / 2.0 {
+    1.0
+  } else {
+    0.7
+  };
+  let skin_factor = 0.5 + (skin * 5.0).min(0.5);
+  page_factor * top_factor * skin_factor * (entropy / 8.0)
+}
+
+/// Luminance entropy (bits, 0–8) and share of skin-toned pixels of an image, measured
+/// on a thumbnail.
+fn tone_statistics(image: &DynamicImage) -> (f64, f64) {
+  let thumbnail = image.thumbnail(64, 64).to_rgb8();
+  let mut histogram = [0usize; 256];
+  let mut skin = 0usize;
+  for pixel in thumbnail.pixels() {
+    let [r, g, b] = pixel.0.map(f64::from);
+    let luma = 0.299 * r + 0.587 * g + 0.114 * b;
+    histogram[luma.round().clamp(0.0, 255.0) as usize] += 1;
+    // Skin tones in YCbCr space (Chai & Ngan)
+    let cb = 128.0 - 0.168736 * r - 0.331264 * g + 0.5 * b;
+    let cr = 128.0 + 0.5 * r - 0.418688 * g - 0.081312 * b;
+    if (77.0..=127.0).contains(&cb) && (133.0..=173.0).contains(&cr) {
+      skin += 1;
+    }
+  }
+  let total = thumbnail.pixels().len().max(1) as f64;
+  let entropy = histogram
+    .iter()
+    .filter(|n| **n > 0)
+    .map(|n| {
+      let p = *n as f64 / total;
+      -p * p.log2()
+    })
+    .sum();
+  (entropy, skin as f64 / total)
+}
+
+/// An image exported from a PDF, as a standalone file.
+pub(crate) struct ExportedImage {
+  pub data: Vec<u8>,
+  /// "jpeg" (DCT data passed through unchanged) or "png"; for `export_scan`, also
+  /// "tiff" (CCITT fax data), "jp2" or "j2k" (JPEG 2000 data passed through)
+  pub format: &'static str,
+  pub width: u32,
+  pub height: u32,
+}
+
+/// Inline image (`BI … ID … EI`) as an image XObject stream, with the abbreviated
+/// keys and names expanded.
+pub(crate) fn inline_image_stream(dict: &Dictionary, data: &[u8]) -> Stream {
+  let mut expanded = Dictionary::new();
+  expanded.set("Type", Object::Name(b"XObject".to_vec()));
+  expanded.set("Subtype", Object::Name(b"Image".to_vec()));
+  for (key, value) in dict.iter() {
+    let key: &[u8] = match key.as_slice() {
+      b"W" => b"Width",
+      b"H" => b"Height",
+      b"BPC" => b"BitsPerComponent",
+      b"CS" => b"ColorSpace",
+      b"F" => b"Filter",
+      b"DP" => b"DecodeParms",
+      b"D" => b"Decode",
+      b"IM" => b"ImageMask",
+      b"I" => b"Interpolate",
+      other => other,
+    };
+    expanded.set(key, expand_inline_names(value));
+  }
+  Stream::new(expanded, data.to_vec())
+}
+
+fn expand_inline_names(value: &Object) -> Object {
+  match value {
+    Object::Name(name) => Object::Name(
+      match name.as_slice() {
+        b"G" => &b"DeviceGray"[..],
+        b"RGB" => b"DeviceRGB",
+        b"CMYK" => b"DeviceCMYK",
+        b"I" => b"Indexed",
+        b"AHx" => b"ASCIIHexDecode",
+        b"A85" => b"ASCII85Decode",
+        b"LZW" => b"LZWDecode",
+        b"Fl" => b"FlateDecode",
+        b"RL" => b"RunLengthDecode",
+        b"CCF" => b"CCITTFaxDecode",
+        b"DCT" => b"DCTDecode",
+        other => other,
+      }
+      .to_vec(),
+    ),
+    Object::Array(items) => Object::Array(items.iter().map(expand_inline_names).collect()),
+    other => other.clone(),
+  }
+}
+
+/// Export an image XObject as a JPEG (DCT data, unchanged) or a PNG (decoded samples).
+///
+/// Returns `None` for codecs that cannot be decoded here (JPEG 2000, CCITT, JBIG2)
+/// and for unsupported color spaces.
+pub(crate) fn export_image(doc: &Document, stream: &Stream) -> Option<ExportedImage> {
+  let (codec, data, width, height) = image_data(doc, stream)?;
+  match codec.as_deref() {
+    Some("DCTDecode") => Some(ExportedImage {
+      data,
+      format: "jpeg",
+      width,
+      height,
+    }),
+    Some(_) => None,
+    None => {
+      let image = decode_samples(doc, &stream.dict, &data, width, height)?;
+      let mut png = Vec::new();
+      image
+        .write_to(&mut Cursor::new(&mut png), ImageFormat::Png)
+        .ok()?;
+      Some(ExportedImage {
+        data: png,
+        format: "png",
+        width,
+        height,
+      })
+    }
+  }
+}
+
+/// Export an image XObject for OCR: as `export_image`, and also CCITT fax data
+/// wrapped in a TIFF file and JPEG 2000 data unchanged, both of which Tesseract
+/// reads. Fails with the codec of images that cannot be exported (JBIG2).
+pub(crate) fn export_scan(doc: &Document, stream: &Stream) -> Result<ExportedImage, String> {
+  let Some((codec, data, width, height)) = image_data(doc, stream) else {
+    return Err("unreadable image data".to_string());
+  };
+  let format = match codec.as_deref() {
+    Some("CCITTFaxDecode") => {
+      let data = ccitt_tiff(doc, &stream.dict, &data, width, height);
+      return Ok(ExportedImage {
+        data,
+        format: "tiff",
+        width,
+        height,
+      });
+    }
+    // A JP2 file starts with its signature box; otherwise the data is a bare codestream.
+    Some("JPXDecode") if data.starts_with(b"\0\0\0\x0CjP  ") => "jp2",
+    Some("JPXDecode") => "j2k",
+    Some("DCTDecode") | None => {
+      return export_image(doc, stream).ok_or_else(|| "unsupported color space".to_string())
+    }
+    Some(codec) => return Err(codec.to_string()),
+  };
+  Ok(ExportedImage {
+    data,
+    format,
+    width,
+    height,
+  })
+}
+
+/// CCITT fax data in a single-strip TIFF file, with the coding of its `DecodeParms`.
+fn ccitt_tiff(doc: &Document, dict: &Dictionary, data: &[u8], width: u32, height: u32) -> Vec<u8> {
+  // Parameters of the codec, the last filter
+  let params = match dict_get(doc, dict, b"DecodeParms") {
+    Some(Object::Array(items)) => items.last().map(|p| resolve(doc, p)),
+    other => other,
+  }
+  .and_then(|p| p.as_dict().ok());
+  let param = |key: &[u8]| params.and_then(|p| dict_get(doc, p, key));
+  let k = param(b"K").and_then(as_number).unwrap_or(0.0);
+  let byte_align = param(b"EncodedByteAlign").and_then(|v| v.as_bool().ok()) == Some(true);
+  let inverted = dict_get(doc, dict, b"Decode")
+    .and_then(|d| d.as_array().ok())
+    .and_then(|d| d.first())
+    .and_then(as_number)
+    == Some(1.0);
+
+  const SHORT: u16 = 3;
+  const LONG: u16 = 4;
+  let (compression, options_tag, options) = if k < 0.0 {
+    (4, 293, 0)
+  } else {
+    // T4Options: 2-D coding, fill bits before each EOL
+    (3, 292, u32::from(k > 0.0) | if byte_align { 4 } else { 0 })
+  };
+  let entries: [(u16, u16, u32); 10] = [
+    (256, LONG, width),
+    (257, LONG, height),
+    (258, SHORT, 1),
+    (259, SHORT, compression),
+    // WhiteIsZero, as the runs of fax coding are white first
+    (262, SHORT, u32::from(inverted)),
+    (273, LONG, 0),
+    (277, SHORT, 1),
+    (278, LONG, height),
+    (279, LONG, data.len() as u32),
+    (options_tag, LONG, options),
+  ];
+  let data_offset = 8 + 2 + 12 * entries.len() as u32 + 4;
+
+  let mut tiff = b"II*\0".to_vec();
+  tiff.extend(8u32.to_le_bytes());
+  tiff.extend((entries.len() as u16).to_le_bytes());
+  for (tag, kind, value) in entries {
+    let value = if tag == 273 { data_offset } else { value };
+    tiff.extend(tag.to_le_bytes());
+    tiff.extend(kind.to_le_bytes());
+    tiff.extend(1u32.to_le_bytes());
+    match kind {
+      SHORT => {
+        tiff.extend((value as u16).to_le_bytes());
+        tiff.extend([0, 0]);
+      }
+      _ => tiff.extend(value.to_le_bytes()),
+    }
+  }
+  tiff.extend(0u32.to_le_bytes());
+  tiff.extend_from_slice(data);
+  tiff
+}
+
+/// Pixels of an image XObject, with the same limits as `export_image`.
+pub(crate) fn decode_image(doc: &Document, stream: &Stream) -> Option<DynamicImage> {
+  let (codec, data, width, height) = image_data(doc, stream)?;
+  match codec.as_deref() {
+    Some("DCTDecode") => image::load_from_memory_with_format(&data, ImageFormat::Jpeg).ok(),
+    Some(_) => None,
+    None => decode_samples(doc, &stream.dict, &data, width, height),
+  }
+}
+
+/// Image codec of an image XObject (none for raw samples), and its data with the
+/// other filters decoded, width and height.
+fn image_data(doc: &Document, stream: &Stream) -> Option<(Option<String>, Vec<u8>, u32, u32)> {
+  let dict = &stream.dict;
+  let number = |key: &[u8]| dict_get(doc, dict, key).and_then(as_number);
+  let width = number(b"Width")? as u32;
+  let height = number(b"Height")? as u32;
+  if width == 0 || height == 0 || width as u64 * height as u64 > MAX_IMAGE_PIXELS {
+    return None;
+  }
+
+  let filters = filter_names(doc, dict);
+  let (codec, data_filters) = match filters.split_last() {
+    Some((last, rest)) if is_image_codec(last) => (Some(last.clone()), rest),
+    _ => (None, &filters[..]),
+  };
+  let data = decode_filters(doc, stream, data_filters)?;
+  Some((codec, data, width, height))
+}
+
+fn filter_names(doc: &Document, dict: &Dictionary) -> Vec<String> {
+  match dict_get(doc, dict, b"Filter") {
+    Some(Object::Name(name)) => vec![String::from_utf8_lossy(name).into_owned()],
+    Some(Object::Array(names)) => names
+      .iter()
+      .filter_map(|n| resolve(doc, n).as_name().ok())
+      .map(|n| String::from_utf8_lossy(n).into_owned())
+      .collect(),
+    _ => Vec::new(),
+  }
+}
+
+fn is_image_codec(filter: &str) -> bool {
+  matches!(
+    filter,
+    "DCTDecode" | "JPXDecode" | "CCITTFaxDecode" | "JBIG2Decode"
+  )
+}
+
+/// Apply the general-purpose filters of an image stream. lopdf refuses to decode
+/// image streams, so a copy holding only the filters is decoded instead.
+fn decode_filters(doc: &Document, stream: &Stream, filters: &[String]) -> Option<Vec<u8>> {
+  if filters.is_empty() {
+    return Some(stream.content.clone());
+  }
+  let mut dict = Dictionary::new();
+  dict.set(
+    "Filter",
+    Object::Array(
+      filters
+        .iter()
+        .map(|f| Object::Name(f.as_bytes().to_vec()))
+        .collect(),
+    ),
+  );
+  if let Some(params) = dict_get(doc, &stream.dict, b"DecodeParms") {
+    let params = match params {
+      Object::Array(items) => items.first().map(|p| resolve(doc, p).clone()),
+      other => Some(other.clone()),
+    };
+    if let Some(params) = params.filter(|p| p.as_dict().is_ok()) {
+      dict.set("DecodeParms", params);
+    }
+  }
+  Stream::new(dict, stream.content.clone())
+    .decompressed_content()
+    .ok()
+}
+
+/// Color space of an image, reduced to what is needed to convert its samples.
+enum ColorSpace {
+  Gray,
+  Rgb,
+  Cmyk,
+  /// Palette of RGB colors
+  Indexed(Vec<[u8; 3]>),
+}
+
+fn color_space(doc: &Document, obj: &Object) -> Option<ColorSpace> {
+  match resolve(doc, obj) {
+    Object::Name(name) => match name.as_slice() {
+      b"DeviceGray" | b"CalGray" => Some(ColorSpace::Gray),
+      b"DeviceRGB" | b"CalRGB" => Some(ColorSpace::Rgb),
+      b"DeviceCMYK" => Some(ColorSpace::Cmyk),
+      _ => None,
+    },
+    Object::Array(items) => {
+      let family = resolve(doc, items.first()?).as_name().ok()?;
+      match family {
+        b"ICCBased" => {
+          let profile = resolve(doc, items.get(1)?).as_stream().ok()?;
+          match dict_get(doc, &profile.dict, b"N").and_then(as_number)? as u32 {
+            1 => Some(ColorSpace::Gray),
+            3 => Some(ColorSpace::Rgb),
+            4 => Some(ColorSpace::Cmyk),
+            _ => None,
+          }
+        }
+        b"Indexed" | b"I" => {
+          let base = color_space(doc, items.get(1)?)?;
+          let lookup = match resolve(doc, items.get(3)?) {
+            Object::String(bytes, _) => bytes.clone(),
+            Object::Stream(s) => s
+              .decompressed_content()
+              .unwrap_or_else(|_| s.content.clone()),
+            _ => return None,
+          };
+          let components = match base {
+            ColorSpace::Gray => 1,
+            ColorSpace::Rgb => 3,
+            ColorSpace::Cmyk => 4,
+            ColorSpace::Indexed(_) => return None,
+          };
+          let palette = lookup
+            .chunks_exact(components)
+            .map(|c| match c {
+              [g] => [*g, *g, *g],
+              [r, g, b] => [*r, *g, *b],
+              [c, m, y, k] => cmyk_to_rgb(*c, *m, *y, *k),
+              _ => [0, 0, 0],
+            })
+            .collect();
+          Some(ColorSpace::Indexed(palette))
+        }
+        _ => color_space(doc, &Object::Name(family.to_vec())),
+      }
+    }
+    _ => None,
+  }
+}
+
+fn cmyk_to_rgb(c: u8, m: u8, y: u8, k: u8) -> [u8; 3] {
+  let channel = |v: u8| ((255 - v as u32) * (255 - k as u32) / 255) as u8;
+  [channel(c), channel(m), channel(y)]
+}
+
+/// Decode raw image samples into an 8-bit grayscale or RGB image.
+fn decode_samples(
+  doc: &Document,
+  dict: &Dictionary,
+  data: &[u8],
+  width: u32,
+  height: u32,
+) -> Option<DynamicImage> {
+  let is_mask = dict_get(doc, dict, b"ImageMask").and_then(|m| m.as_bool().ok()) == Some(true);
+  let (space, bits) = if is_mask {
+    (ColorSpace::Gray, 1)
+  } else {
+    let space = color_space(doc, dict_get(doc, dict, b"ColorSpace")?)?;
+    let bits = dict_get(doc, dict, b"BitsPerComponent").and_then(as_number)? as u32;
+    (space, bits)
+  };
+  if !matches!(bits, 1 | 2 | 4 | 8 | 16) {
+    return None;
+  }
+  let components = match space {
+    ColorSpace::Gray | ColorSpace::Indexed(_) => 1,
+    ColorSpace::Rgb => 3,
+    ColorSpace::Cmyk => 4,
+  };
+  // `/Decode [1 0]` (e.g. inverted masks and scans) flips the sample values.
+  let inverted = dict_get(doc, dict, b"Decode")
+    .and_then(|d| d.as_array().ok())
+    .and_then(|d| d.first())
+    .and_then(|v| as_number(resolve(doc, v)))
+    .is_some_and(|v| v > 0.0);
+
+  let row_bytes = (width as usize * components * bits as usize).div_ceil(8);
+  if data.len() < row_bytes * height as usize {
+    return None;
+  }
+  let max = (1u32 << bits.min(8)) - 1;
+  let scale = |v: u32| {
+    let v = if bits == 16 { v } else { v * 255 / max };
+    (if inverted { 255 - v } else { v }) as u8
+  };
+  // Samples are scaled to 8 bits as they are read, except palette indices.
+  let indexed = matches!(space, ColorSpace::Indexed(_));
+  let mut samples = Vec::with_capacity(width as usize * height as usize * components);
+  for row in data.chunks_exact(row_bytes).take(height as usize) {
+    for i in 0..width as usize * components {
+      let value = match bits {
+        16 => row[i * 2] as u32,
+        8 => row[i] as u32,
+        _ => {
+          let bit = i * bits as usize;
+          (row[bit / 8] as u32 >> (8 - bits as usize - bit % 8)) & max
+        }
+      };
+      samples.push(if indexed { value as u8 } else { scale(value) });
+    }
+  }
+
+  match space {
+    ColorSpace::Gray => GrayImage::from_raw(width, height, samples).map(DynamicImage::ImageLuma8),
+    ColorSpace::Rgb => RgbImage::from_raw(width, height, samples).map(DynamicImage::ImageRgb8),
+    ColorSpace::Cmyk => {
+      let pixels = samples
+        .chunks_exact(4)
+        .flat_map(|p| cmyk_to_rgb(p[0], p[1], p[2], p[3]))
+        .collect();
+      RgbImage::from_raw(width, height, pixels).map(DynamicImage::ImageRgb8)
+    }
+    ColorSpace::Indexed(palette) => {
+      let pixels = samples
+        .iter()
+        .flat_map(|i| palette.get(*i as usize).copied().unwrap_or([0, 0, 0]))
+        .collect();
+      RgbImage::from_raw(width, height, pixels).map(DynamicImage::ImageRgb8)
+    }
+  }
+}
@@ -0,0 +1,149 @@
+use lopdf::{Dictionary, Document, Object, ObjectId};
+
+/// Follow references until a direct object is reached.
+pub(crate) fn resolve<'a>(doc: &'a Document, obj: &'a Object) -> &'a Object {
+  doc.dereference(obj).map(|(_, o)| o).unwrap_or(obj)
+}
+
+/// Dictionary entry, dereferenced.
+pub(crate) fn dict_get<'a>(
+  doc: &'a Document,
+  dict: &'a Dictionary,
+  key: &[u8],
+) -> Option<&'a Object> {
+  dict.get(key).ok().map(|o| resolve(doc, o))
+}
+
+/// Dictionary entry that is (or points to) a dictionary.
+pub(crate) fn dict_get_dict<'a>(
+  doc: &'a Document,
+  dict: &'a Dictionary,
+  key: &[u8],
+) -> Option<&'a Dictionary> {
+  match dict_get(doc, dict, key)? {
+    Object::Dictionary(d) => Some(d),
+    Object::Stream(s) => Some(&s.dict),
+    _ => None,
+  }
+}
+
+/// Numeric value of an integer or real object.
+pub(crate) fn as_number(obj: &Object) -> Option<f64> {
+  match obj {
+    Object::Integer(i) => Some(*i as f64),
+    Object::Real(r) => Some(*r as f64),
+    _ => None,
+  }
+}
+
+/// Rectangle as `[llx, lly, urx, ury]`, with the corners normalized.
+pub(crate) fn as_rect(doc: &Document, obj: &Object) -> Option<[f64; 4]> {
+  let values: Vec<f64> = resolve(doc, obj)
+    .as_array()
+    .ok()?
+    .iter()
+    .map(|o| as_number(resolve(doc, o)))
+    .collect::<Option<_>>()?;
+  if values.len() != 4 {
+    return None;
+  }
+  Some([
+    values[0].min(values[2]),
+    values[1].min(values[3]),
+    values[0].max(values[2]),
+    values[1].max(values[3]),
+  ])
+}
+
+/// Page attribute, looked up through the page tree when it is inherited.
+pub(crate) fn inherited<'a>(
+  doc: &'a Document,
+  page_id: ObjectId,
+  key: &[u8],
+) -> Option<&'a Object> {
+  let mut node = doc.get_dictionary(page_id).ok()?;
+  // The depth bound protects against cyclic /Parent chains.
+  for _ in 0..32 {
+    if let Some(value) = dict_get(doc, node, key) {
+      return Some(value);
+    }
+    node = match dict_get(doc, node, b"Parent")? {
+      Object::Dictionary(parent) => parent,
+      _ => return None,
+    };
+  }
+  None
+}
+
+/// Page media box as `[llx, lly, urx, ury]` (US Letter when missing or invalid).
+pub(crate) fn page_box(doc: &Document, page_id: ObjectId) -> [f64; 4] {
+  inherited(doc, page_id, b"MediaBox")
+    .and_then(|b| as_rect(doc, b))
+    .unwrap_or([0.0, 0.0, 612.0, 792.0])
+}
+
+/// Resource dictionary of a page, including inherited resources.
+pub(crate) fn page_resources(doc: &Document, page_id: ObjectId) -> Option<&Dictionary> {
+  match inherited(doc, page_id, b"Resources")? {
+    Object::Dictionary(d) => Some(d),
+    _ => None,
+  }
+}
+
+/// Named resource of a given category (`Font`, `XObject`, …).
+pub(crate) fn resource<'a>(
+  doc: &'a Document,
+  resources: Option<&'a Dictionary>,
+  category: &[u8],
+  name: &[u8],
+) -> Option<&'a Object> {
+  let entries = dict_get_dict(doc, resources?, category)?;
+  dict_get(doc, entries, name)
+}
+
+/// Font descriptor of a simple font, or of the descendant of a composite font.
+pub(crate) fn font_descriptor<'a>(
+  doc: &'a Document,
+  font: &'a Dictionary,
+) -> Option<&'a Dictionary> {
+  if let Some(descriptor) = dict_get_dict(doc, font, b"FontDescriptor") {
+    return Some(descriptor);
+  }
+  let descendants = dict_get(doc, font, b"DescendantFonts")?.as_array().ok()?;
+  let descendant = resolve(doc, descendants.first()?).as_dict().ok()?;
+  dict_get_dict(doc, descendant, b"FontDescriptor")
+}
+
+/// PostScript name of a font, without the subset prefix (`ABCDEF+`).
+pub(crate) fn font_base_name(doc: &Document, font: &Dictionary) -> Option<String> {
+  let name = dict_get(doc, font, b"BaseFont")?.as_name().ok()?;
+  let name = String::from_utf8_lossy(name).into_owned();
+  Some(match name.split_once('+') {
+    Some((prefix, rest)) if prefix.len() == 6 && prefix.chars().all(|c| c.is_ascii_uppercase()) => {
+      rest.to_string()
+    }
+    _ => name,
+  })
+}
+
+/// ForceBold flag of the font descriptor /Flags entry.
+const FORCE_BOLD: i64 = 1 << 18;
+
+/// Whether a font is bold, judging by its name, weight or flags.
+pub(crate) fn font_is_bold(doc: &Document, font: &Dictionary) -> bool {
+  if let Some(name) = font_base_name(doc, font) {
+    let name = name.to_ascii_lowercase();
+    if ["bold", "black", "heavy", "semibold", "demi"]
+      .iter()
+      .any(|w| name.contains(w))
+    {
+      return true;
+    }
+  }
+  let Some(descriptor) = font_descriptor(doc, font) else {
+    return false;
+  };
+  let weight = dict_get(doc, descriptor, b"FontWeight").and_then(as_number);
+  let flags = dict_get(doc, descriptor, b"Flags").and_then(|f| f.as_i64().ok());
+  weight.is_some_and(|w| w >= 600.0) || flags.is_some_and(|f| f & FORCE_BOLD != 0)
+}
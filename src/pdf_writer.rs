@@ -0,0 +1,93 @@
+use std::io::Write as IoWrite;
+
+use flate2::write::ZlibEncoder;
+use flate2::Compression;
+
+/// Minimal writer for the PDF files this crate generates.
+///
+/// Object ids are handed out by `reserve`, so objects can reference each other
+/// before they are written.
+pub(crate) struct PdfWriter {
+  pdf: Vec<u8>,
+  /// Offset of every object, indexed by id - 1
+  offsets: Vec<Option<usize>>,
+}
+
+impl PdfWriter {
+  pub fn new() -> Self {
+    PdfWriter {
+      pdf: b"%PDF-1.4\n".to_vec(),
+      offsets: Vec::new(),
+    }
+  }
+
+  /// Allocate the next object id.
+  pub fn reserve(&mut self) -> u32 {
+    self.offsets.push(None);
+    self.offsets.len() as u32
+  }
+
+  /// Write object `id` with the given body (a dictionary, array, …).
+  pub fn object(&mut self, id: u32, body: &str) {
+    self.start(id);
+    self.pdf.extend_from_slice(body.as_bytes());
+    self.pdf.extend_from_slice(b"\nendobj\n");
+  }
+
+  /// Write stream object `id`; `dict` holds the entries of the stream dictionary
+  /// other than /Length.
+  pub fn stream(&mut self, id: u32, dict: &str, data: &[u8]) {
+    self.start(id);
+    let separator = if dict.is_empty() { "" } else { " " };
+    let _ = write!(
+      self.pdf,
+      "<< {dict}{separator}/Length {} >>\nstream\n",
+      data.len()
+    );
+    self.pdf.extend_from_slice(data);
+    self.pdf.extend_from_slice(b"\nendstream\nendobj\n");
+  }
+
+  /// Write stream object `id`, Flate-compressed.
+  pub fn compressed_stream(&mut self, id: u32, dict: &str, data: &[u8]) {
+    let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+    let compressed = encoder.write_all(data).and_then(|_| encoder.finish()).ok();
+    match compressed {
+      Some(compressed) => {
+        let separator = if dict.is_empty() { "" } else { " " };
+        self.stream(
+          id,
+          &format!("{dict}{separator}/Filter /FlateDecode"),
+          &compressed,
+        )
+      }
+      None => self.stream(id, dict, data),
+    }
+  }
+
+  fn start(&mut self, id: u32) {
+    self.offsets[id as usize - 1] = Some(self.pdf.len());
+    let _ = writeln!(self.pdf, "{id} 0 obj");
+  }
+
+  /// Write the cross-reference table and trailer, with `root` as the catalog.
+  pub fn finish(mut self, root: u32) -> Vec<u8> {
+    let xref_start = self.pdf.len();
+    let size = self.offsets.len() + 1;
+    let _ = write!(self.pdf, "xref\n0 {size}\n0000000000 65535 f \n");
+    for offset in &self.offsets {
+      match offset {
+        Some(offset) => {
+          let _ = writeln!(self.pdf, "{offset:010} 00000 n ");
+        }
+        // A reserved id that was never written is a free entry.
+        None => self.pdf.extend_from_slice(b"0000000000 65535 f \n"),
+      }
+    }
+    let _ = write!(
+      self.pdf,
+      "trailer\n<< /Size {size} /Root {root} 0 R >>\nstartxref\n{xref_start}\n%%EOF\n"
+    );
+    self.pdf
+  }
+}
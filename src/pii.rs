@@ -0,0 +1,869 @@
+use std::ops::Range;
+
+use unicode_normalization::UnicodeNormalization;
+
+/// Words of job titles and headings, which a name line cannot contain.
+const NOT_NAME_WORDS: &[&str] = &[
+  "cv",
+  "curriculum",
+  "vitae",
+  "resume",
+  "profil",
+  "profile",
+  "contact",
+  "experience",
+  "experiences",
+  "formation",
+  "education",
+  "competences",
+  "skills",
+  "developpeur",
+  "developpeuse",
+  "developer",
+  "ingenieur",
+  "ingenieure",
+  "engineer",
+  "manager",
+  "consultant",
+  "consultante",
+  "chef",
+  "directeur",
+  "directrice",
+  "director",
+  "designer",
+  "analyste",
+  "analyst",
+  "architecte",
+  "architect",
+  "stagiaire",
+  "intern",
+  "responsable",
+  "lead",
+  "senior",
+  "junior",
+  "technicien",
+  "technicienne",
+  "assistant",
+  "assistante",
+  "commercial",
+  "commerciale",
+  "full",
+  "stack",
+  "data",
+  "web",
+  "product",
+  "project",
+  "projet",
+  "software",
+  "logiciel",
+];
+
+/// Street types after a house number, in French and English.
+const STREET_WORDS: &[&str] = &[
+  "rue",
+  "avenue",
+  "av",
+  "bd",
+  "boulevard",
+  "allee",
+  "chemin",
+  "impasse",
+  "place",
+  "route",
+  "quai",
+  "cours",
+  "square",
+  "faubourg",
+  "street",
+  "st",
+  "road",
+  "rd",
+  "lane",
+  "ln",
+  "drive",
+  "dr",
+  "way",
+  "court",
+  "ct",
+  "ave",
+  "blvd",
+];
+
+/// Labels whose value, up to the end of the segment, is a home address.
+const ADDRESS_LABELS: &[&str] = &["adresse", "address", "domicile", "home address"];
+
+/// Labels of a date (and place) of birth.
+const BIRTH_LABELS: &[&str] = &[
+  "ne le",
+  "nee le",
+  "ne(e) le",
+  "nee a",
+  "ne a",
+  "date de naissance",
+  "date of birth",
+  "birth date",
+  "birthdate",
+  "born on",
+  "born",
+  "dob",
+  "d.o.b",
+];
+
+/// Labels of an age.
+const AGE_LABELS: &[&str] = &["age"];
+
+/// Labels whose value, up to the end of the clause, is a nationality.
+const NATIONALITY_LABELS: &[&str] = &[
+  "nationalite",
+  "nationalites",
+  "nationality",
+  "nationalities",
+  "citizenship",
+  "citoyennete",
+];
+
+/// Nationalities, detected when they make up a whole clause ("Française",
+/// "French citizen", "French nationality").
+const NATIONALITIES: &[&str] = &[
+  "francais",
+  "francaise",
+  "french",
+  "belge",
+  "belgian",
+  "suisse",
+  "swiss",
+  "allemand",
+  "allemande",
+  "german",
+  "italien",
+  "italienne",
+  "italian",
+  "espagnol",
+  "espagnole",
+  "spanish",
+  "portugais",
+  "portugaise",
+  "portuguese",
+  "britannique",
+  "british",
+  "anglais",
+  "anglaise",
+  "americain",
+  "americaine",
+  "american",
+  "canadien",
+  "canadienne",
+  "canadian",
+  "marocain",
+  "marocaine",
+  "moroccan",
+  "algerien",
+  "algerienne",
+  "algerian",
+  "tunisien",
+  "tunisienne",
+  "tunisian",
+  "senegalais",
+  "senegalaise",
+  "senegalese",
+  "ivoirien",
+  "ivoirienne",
+  "polonais",
+  "polonaise",
+  "polish",
+  "roumain",
+  "roumaine",
+  "romanian",
+  "chinois",
+  "chinoise",
+  "chinese",
+  "indien",
+  "indienne",
+  "indian",
+  "russe",
+  "russian",
+  "ukrainien",
+  "ukrainienne",
+  "ukrainian",
+  "turc",
+  "turque",
+  "turkish",
+  "bresilien",
+  "bresilienne",
+  "brazilian",
+];
+
+/// Labels whose value, up to the end of the clause, is a marital status.
+const MARITAL_LABELS: &[&str] = &[
+  "situation familiale",
+  "situation de famille",
+  "etat civil",
+  "marital status",
+  "family status",
+];
+
+/// Marital statuses, detected at the start of a clause ("Marié, 2 enfants").
+const MARITAL_STATUSES: &[&str] = &[
+  "celibataire",
+  "marie",
+  "mariee",
+  "pacse",
+  "pacsee",
+  "divorce",
+  "divorcee",
+  "separe",
+  "separee",
+  "veuf",
+  "veuve",
+  "en couple",
+  "concubinage",
+  "single",
+  "married",
+  "divorced",
+  "separated",
+  "widowed",
+  "widow",
+  "widower",
+];
+
+/// Words counted after a number of children ("2 enfants", "3 children").
+const CHILDREN_WORDS: &[&str] = &["enfant", "enfants", "child", "children", "kids"];
+
+/// Labels of a social security (or national insurance) number.
+const SOCIAL_SECURITY_LABELS: &[&str] = &[
+  "numero de securite sociale",
+  "n° de securite sociale",
+  "n° securite sociale",
+  "securite sociale",
+  "n° secu",
+  "secu",
+  "nir",
+  "social security number",
+  "social security no",
+  "social security",
+  "ssn",
+  "national insurance number",
+  "ni number",
+];
+
+/// Mentions of the candidate's health or disability.
+const HEALTH_TERMS: &[&str] = &[
+  "rqth",
+  "travailleur handicape",
+  "travailleuse handicapee",
+  "reconnaissance handicap",
+  "en situation de handicap",
+  "handicap",
+  "handicape",
+  "handicapee",
+  "invalidite",
+  "maladie chronique",
+  "affection longue duree",
+  "arret maladie",
+  "conge maladie",
+  "longue maladie",
+  "etat de sante",
+  "probleme de sante",
+  "problemes de sante",
+  "enceinte",
+  "grossesse",
+  "disability",
+  "disabled",
+  "chronic illness",
+  "medical condition",
+  "health condition",
+  "health issues",
+  "sick leave",
+  "pregnant",
+  "pregnancy",
+];
+
+/// Hosts of social and professional profiles.
+const PROFILE_HOSTS: &[&str] = &[
+  "linkedin.com",
+  "github.com",
+  "gitlab.com",
+  "twitter.com",
+  "x.com/",
+  "facebook.com",
+  "instagram.com",
+  "viadeo.com",
+  "malt.fr",
+  "behance.net",
+  "dribbble.com",
+  "stackoverflow.com/users",
+];
+
+/// Separators between the parts of a line ("a@b.c · 06 12 34 56 78 | Paris").
+const SEPARATORS: &[&str] = &[" · ", " | ", " • ", " – ", "\t", "   "];
+
+/// Personal data found in a text.
+///
+/// `line` is the 0-based line of the text and `range` the byte range in that line.
+/// `confidence` (0–1) is higher for labelled values ("Nationalité : …") and strict
+/// formats than for values recognized from their shape alone.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct PiiMatch {
+  pub category: &'static str,
+  pub line: usize,
+  pub range: Range<usize>,
+  pub confidence: f64,
+}
+
+/// Personal data in the text of a CV: the candidate's name (`name` when given,
+/// else a name-like line among the first lines), email addresses, phone numbers,
+/// profile URLs, home address, date of birth, age, nationality, marital status,
+/// social security number and health mentions, in French and English. Matches are
+/// sorted by line and position, without overlaps.
+pub(crate) fn find_pii(text: &str, name: Option<&str>) -> Vec<PiiMatch> {
+  let lines: Vec<&str> = text.lines().collect();
+  let mut matches = Vec::new();
+
+  let name_line = match name {
+    Some(_) => None,
+    None => lines
+      .iter()
+      .enumerate()
+      .filter(|(_, line)| !line.trim().is_empty())
+      .take(5)
+      .find(|(_, line)| is_name_line(line))
+      .map(|(i, _)| i),
+  };
+  let name = name.or(name_line.map(|i| lines[i].trim()));
+  let name_words: Vec<String> = name
+    .map(|name| {
+      Folded::new(name)
+        .words()
+        .into_iter()
+        .map(|(word, _)| word)
+        .filter(|word| word.chars().count() > 1)
+        .collect()
+    })
+    .unwrap_or_default();
+
+  for (index, line) in lines.iter().enumerate() {
+    let mut found = Vec::new();
+    if Some(index) == name_line {
+      let start = line.len() - line.trim_start().len();
+      found.push(("name", start..line.trim_end().len(), 0.7));
+    }
+    let folded = Folded::new(line);
+    found.extend(emails(line).into_iter().map(|r| ("email", r, 0.95)));
+    found.extend(urls(line, &name_words).into_iter().map(|r| ("url", r, 0.9)));
+    // Before phones, which take precedence on the same range.
+    found.extend(
+      social_security_numbers(line)
+        .into_iter()
+        .map(|(r, c)| ("social_security_number", r, c)),
+    );
+    found.extend(phones(line).into_iter().map(|r| ("phone", r, 0.8)));
+    for segment in segments(line) {
+      let part = folded.slice(&segment);
+      found.extend(address(&part, line).map(|(r, c)| ("address", r, c)));
+      let mut marital: Option<(Range<usize>, f64)> = None;
+      for clause in clauses(line, &segment) {
+        let part = folded.slice(&clause);
+        found.extend(labelled(&part, BIRTH_LABELS, true).map(|r| ("date_of_birth", r, 0.95)));
+        found.extend(ages(&part, line).into_iter().map(|(r, c)| ("age", r, c)));
+        found.extend(nationality(&part).map(|(r, c)| ("nationality", r, c)));
+        found.extend(
+          labelled(&part, SOCIAL_SECURITY_LABELS, true)
+            .map(|r| ("social_security_number", r, 0.95)),
+        );
+        let Some((range, confidence)) = marital_status(&part) else {
+          continue;
+        };
+        // A status and the clauses right after it ("Mariée, 2 enfants") are one match.
+        match &mut marital {
+          Some((previous, _))
+            if line[previous.end..range.start]
+              .trim_start_matches([',', ';', ' '])
+              .is_empty() =>
+          {
+            previous.end = range.end;
+          }
+          _ => found.extend(
+            marital
+              .replace((range, confidence))
+              .map(|(r, c)| ("marital_status", r, c)),
+          ),
+        }
+      }
+      found.extend(marital.map(|(r, c)| ("marital_status", r, c)));
+      found.extend(health(&part).map(|r| ("health", r, 0.75)));
+    }
+    for (word, range) in folded.words() {
+      let capitalized = line[range.clone()]
+        .chars()
+        .next()
+        .is_some_and(char::is_uppercase);
+      if capitalized && name_words.contains(&word) {
+        found.push(("name", range, if name_line.is_some() { 0.7 } else { 0.9 }));
+      }
+    }
+
+    // Keep the earliest, then longest, of overlapping matches.
+    found.sort_by_key(|(_, range, _)| (range.start, usize::MAX - range.end));
+    let mut end = 0;
+    for (category, range, confidence) in found {
+      if range.start >= end && !range.is_empty() {
+        end = range.end;
+        matches.push(PiiMatch {
+          category,
+          line: index,
+          range,
+          confidence,
+        });
+      }
+    }
+  }
+  matches
+}
+
+/// Ranges of the parts of `line` between separators.
+pub(crate) fn segments(line: &str) -> Vec<Range<usize>> {
+  let mut segments = Vec::new();
+  let mut start = 0;
+  let mut i = 0;
+  while i < line.len() {
+    match SEPARATORS.iter().find(|s| line[i..].starts_with(**s)) {
+      Some(separator) => {
+        segments.push(start..i);
+        i += separator.len();
+        while line[i..].starts_with(' ') {
+          i += 1;
+        }
+        start = i;
+      }
+      None => i += line[i..].chars().next().map_or(1, char::len_utf8),
+    }
+  }
+  segments.push(start..line.len());
+  segments
+}
+
+/// Ranges of the clauses of a segment, split at commas and semicolons ("Née le
+/// 03/05/1990, 34 ans, célibataire"). A year after a comma stays with its date
+/// ("May 3, 1990").
+fn clauses(line: &str, segment: &Range<usize>) -> Vec<Range<usize>> {
+  let mut clauses = Vec::new();
+  let mut start = segment.start;
+  for (i, _) in line[segment.clone()].match_indices([',', ';']) {
+    let at = segment.start + i;
+    let rest = &line[at + 1..segment.end];
+    let next = rest.trim_start();
+    let next = next[..next.find([',', ';']).unwrap_or(next.len())].trim_end();
+    let year = next.len() == 4 && next.bytes().all(|b| b.is_ascii_digit());
+    if !rest.starts_with(' ') || year {
+      continue;
+    }
+    clauses.push(start..at);
+    start = at + 1 + rest.len() - rest.trim_start().len();
+  }
+  clauses.push(start..segment.end);
+  clauses
+}
+
+/// Whether `line` looks like a person's name: two to four capitalized words,
+/// none of them a job title or heading word.
+fn is_name_line(line: &str) -> bool {
+  let words: Vec<&str> = line.split_whitespace().collect();
+  (2..=4).contains(&words.len())
+    && words.iter().all(|word| {
+      word.chars().next().is_some_and(char::is_uppercase)
+        && word
+          .chars()
+          .all(|c| c.is_alphabetic() || matches!(c, '-' | '\'' | '’' | '.'))
+        && !NOT_NAME_WORDS.contains(&Folded::new(word).text().as_str())
+    })
+}
+
+/// Email addresses in `line`.
+fn emails(line: &str) -> Vec<Range<usize>> {
+  let is_local = |c: char| c.is_alphanumeric() || "._%+-".contains(c);
+  let is_domain = |c: char| c.is_alphanumeric() || ".-".contains(c);
+  let mut found = Vec::new();
+  for (at, _) in line.match_indices('@') {
+    let start = line[..at]
+      .char_indices()
+      .rev()
+      .take_while(|(_, c)| is_local(*c))
+      .last()
+      .map_or(at, |(i, _)| i);
+    let domain = line[at + 1..]
+      .char_indices()
+      .take_while(|(_, c)| is_domain(*c))
+      .last()
+      .map_or(at + 1, |(i, c)| at + 1 + i + c.len_utf8());
+    let domain = at + 1 + line[at + 1..domain].trim_end_matches(['.', '-']).len();
+    if start < at && line[at + 1..domain].contains('.') {
+      found.push(start..domain);
+    }
+  }
+  found
+}
+
+/// Profile URLs in `line`, and URLs containing a word of the candidate's name.
+fn urls(line: &str, name_words: &[String]) -> Vec<Range<usize>> {
+  let mut found = Vec::new();
+  let mut offset = 0;
+  for token in line.split(' ') {
+    let start = offset + token.len() - token.trim_start_matches(['(', '<', '[']).len();
+    let end = offset + token.trim_end_matches([')', '>', ']', ',', ';', '.']).len();
+    offset += token.len() + 1;
+    if start >= end || token.contains('@') {
+      continue;
+    }
+    let url = Folded::new(&line[start..end]).text();
+    let is_url = url.contains("://")
+      || url.starts_with("www.")
+      || url.split('/').next().is_some_and(|host| {
+        host.contains('.')
+          && host.rsplit('.').next().is_some_and(|tld| tld.len() >= 2)
+          && host
+            .chars()
+            .all(|c| c.is_alphanumeric() || ".-".contains(c))
+      }) && url.contains('/');
+    let personal = PROFILE_HOSTS.iter().any(|host| url.contains(host))
+      || name_words
+        .iter()
+        .any(|word| word.chars().count() > 2 && url.contains(word.as_str()));
+    if is_url && personal {
+      found.push(start..end);
+    }
+  }
+  found
+}
+
+/// Phone numbers in `line`: 9 to 15 digits, possibly with a leading `+` and
+/// spaces, dots, dashes or parentheses between groups.
+fn phones(line: &str) -> Vec<Range<usize>> {
+  let chars: Vec<(usize, char)> = line.char_indices().collect();
+  let mut found = Vec::new();
+  let mut i = 0;
+  while i < chars.len() {
+    let (start, c) = chars[i];
+    let after_word = i > 0 && chars[i - 1].1.is_alphanumeric();
+    if after_word || !(c.is_ascii_digit() || c == '+' || c == '(') {
+      i += 1;
+      continue;
+    }
+    let mut j = i + 1;
+    while j < chars.len() && (chars[j].1.is_ascii_digit() || " .-()".contains(chars[j].1)) {
+      j += 1;
+    }
+    // Back to the last digit
+    let mut last = j;
+    while last > i && !chars[last - 1].1.is_ascii_digit() {
+      last -= 1;
+    }
+    let end = chars.get(last).map_or(line.len(), |(index, _)| *index);
+    let number = &line[start..end];
+    let digits = number.chars().filter(char::is_ascii_digit).count();
+    let groups: Vec<&str> = number
+      .split(|c: char| !c.is_ascii_digit())
+      .filter(|g| !g.is_empty())
+      .collect();
+    let years = groups
+      .iter()
+      .filter(|g| g.len() == 4 && (g.starts_with("19") || g.starts_with("20")))
+      .count();
+    let followed_by_word = chars.get(last).is_some_and(|(_, c)| c.is_alphanumeric());
+    if (9..=15).contains(&digits) && years < 2 && !followed_by_word {
+      found.push(start..end);
+    }
+    i = j.max(i + 1);
+  }
+  found
+}
+
+/// A home address in a segment: a house number followed by a street type, a
+/// five-digit postal code followed by a city, or an "Address:" label. The
+/// address runs to the end of the segment.
+fn address(part: &Folded, line: &str) -> Option<(Range<usize>, f64)> {
+  let end = part.end();
+  if let Some(range) = labelled(part, ADDRESS_LABELS, false) {
+    return Some((range, 0.95));
+  }
+  let words = part.words();
+  for (i, (word, range)) in words.iter().enumerate() {
+    // "12", "12b", "12bis"
+    let number = word.trim_end_matches(char::is_alphabetic);
+    if number.is_empty() || number.len() > 4 || !number.chars().all(|c| c.is_ascii_digit()) {
+      continue;
+    }
+    let street = words[i + 1..]
+      .iter()
+      .take(2)
+      .any(|(next, _)| STREET_WORDS.contains(&next.as_str()));
+    let city = || {
+      word.len() == 5
+        && words.get(i + 1).is_some_and(|(_, next)| {
+          line[next.clone()]
+            .chars()
+            .next()
+            .is_some_and(char::is_uppercase)
+        })
+    };
+    if street {
+      return Some((range.start..end, 0.85));
+    }
+    if city() {
+      return Some((range.start..end, 0.7));
+    }
+  }
+  None
+}
+
+/// An age in a clause: "32 ans", "32 years old" or "Âge : 32".
+fn ages(part: &Folded, line: &str) -> Vec<(Range<usize>, f64)> {
+  if let Some(range) = labelled(part, AGE_LABELS, true) {
+    return vec![(range, 0.95)];
+  }
+  let words = part.words();
+  let mut found = Vec::new();
+  for (i, (word, range)) in words.iter().enumerate() {
+    let Ok(age) = word.parse::<u32>() else {
+      continue;
+    };
+    let next = |n: usize| words.get(i + n).map(|(w, _)| w.as_str());
+    let end = match (next(1), next(2), next(3)) {
+      // Not "10 ans d'expérience" nor "5 ans de Rust"
+      (Some("ans"), following, _) if !matches!(following, Some("d" | "de" | "dans" | "en")) => {
+        words[i + 1].1.end
+      }
+      (Some("years" | "year" | "yrs"), Some("old"), _) => words[i + 2].1.end,
+      _ => continue,
+    };
+    if (14..=99).contains(&age) {
+      let mut range = range.start..end;
+      // Parentheses around the age go with it.
+      if line[..range.start].ends_with('(') && line[range.end..].starts_with(')') {
+        range = range.start - 1..range.end + 1;
+      }
+      found.push((range, 0.85));
+    }
+  }
+  found
+}
+
+/// A nationality in a clause: after a label, or as the whole clause.
+fn nationality(part: &Folded) -> Option<(Range<usize>, f64)> {
+  if let Some(range) = labelled(part, NATIONALITY_LABELS, false) {
+    return Some((range, 0.95));
+  }
+  let words = part.words();
+  let is_nationality = match &words[..] {
+    [(word, _)] => NATIONALITIES.contains(&word.as_str()),
+    [(word, _), (next, _)] => {
+      NATIONALITIES.contains(&word.as_str())
+        && matches!(next.as_str(), "citizen" | "national" | "nationality")
+    }
+    _ => false,
+  };
+  match (is_nationality, words.first(), words.last()) {
+    (true, Some(first), Some(last)) => Some((first.1.start..last.1.end, 0.7)),
+    _ => None,
+  }
+}
+
+/// A marital status in a clause: after a label, starting the clause ("Marié"),
+/// or a number of children ("2 enfants").
+fn marital_status(part: &Folded) -> Option<(Range<usize>, f64)> {
+  if let Some(range) = labelled(part, MARITAL_LABELS, false) {
+    return Some((range, 0.95));
+  }
+  let words = part.words();
+  let is_number = |word: &str| word.len() <= 2 && word.chars().all(|c| c.is_ascii_digit());
+  let status_words = MARITAL_STATUSES.iter().find_map(|status| {
+    let count = status.split(' ').count();
+    let found = words.len() >= count
+      && (words[..count].iter())
+        .map(|(word, _)| word.as_str())
+        .eq(status.split(' '));
+    found.then_some(count)
+  });
+  if let Some(count) = status_words {
+    // Not a first name ("Marie Dupont")
+    let next = words.get(count).map(|(word, _)| word.as_str());
+    if next.is_none_or(|next| matches!(next, "avec" | "with" | "et" | "and") || is_number(next)) {
+      return Some((words[0].1.start..part.end(), 0.8));
+    }
+  }
+  words.windows(2).find_map(|pair| {
+    let [(count, range), (word, _)] = pair else {
+      return None;
+    };
+    (is_number(count) && CHILDREN_WORDS.contains(&word.as_str()))
+      .then(|| (range.start..part.end(), 0.8))
+  })
+}
+
+/// A mention of the candidate's health or disability: the segment containing it.
+fn health(part: &Folded) -> Option<Range<usize>> {
+  HEALTH_TERMS
+    .iter()
+    .any(|term| part.find_word(&term.chars().collect::<Vec<_>>()).is_some())
+    .then(|| {
+      let words = part.words();
+      words.first().map_or(0, |w| w.1.start)..words.last().map_or(0, |w| w.1.end)
+    })
+}
+
+/// Social security numbers in `line`: French NIR (13 digits and their 2-digit
+/// key, e.g. "2 90 03 75 112 345 67") and US numbers ("123-45-6789").
+fn social_security_numbers(line: &str) -> Vec<(Range<usize>, f64)> {
+  let chars: Vec<(usize, char)> = line.char_indices().collect();
+  let mut found = Vec::new();
+  let mut i = 0;
+  while i < chars.len() {
+    let (start, c) = chars[i];
+    if !c.is_ascii_digit() || (i > 0 && chars[i - 1].1.is_alphanumeric()) {
+      i += 1;
+      continue;
+    }
+    let mut j = i + 1;
+    while j < chars.len() && (chars[j].1.is_ascii_digit() || " -.".contains(chars[j].1)) {
+      j += 1;
+    }
+    let mut last = j;
+    while !chars[last - 1].1.is_ascii_digit() {
+      last -= 1;
+    }
+    i = j;
+    if chars.get(last).is_some_and(|(_, c)| c.is_alphanumeric()) {
+      continue;
+    }
+    let end = chars.get(last).map_or(line.len(), |(index, _)| *index);
+    let number = &line[start..end];
+    let digits: String = number.chars().filter(char::is_ascii_digit).collect();
+    let groups: Vec<usize> = number
+      .split(|c: char| !c.is_ascii_digit())
+      .filter(|g| !g.is_empty())
+      .map(str::len)
+      .collect();
+    let confidence = match digits.len() {
+      15 if is_nir(&digits[..13]) && nir_key(&digits[..13]) == digits[13..].parse().ok() => 0.95,
+      13 | 15 if is_nir(&digits[..13]) => 0.6,
+      9 if groups == [3, 2, 4] && number.contains('-') => 0.6,
+      _ => continue,
+    };
+    found.push((start..end, confidence));
+  }
+  found
+}
+
+/// Whether 13 digits can be a French NIR: sex, year, then a month (or 20 and
+/// above for unknown months).
+fn is_nir(digits: &str) -> bool {
+  let month: u32 = digits[3..5].parse().unwrap_or(0);
+  matches!(&digits[..1], "1" | "2" | "7" | "8") && ((1..=12).contains(&month) || month >= 20)
+}
+
+/// Key of a NIR: 97 minus the number modulo 97.
+fn nir_key(digits: &str) -> Option<u64> {
+  let number: u64 = digits.parse().ok()?;
+  Some(97 - number % 97)
+}
+
+/// From one of `labels` to the end of `part`, when the label starts a word
+/// (and the value contains a digit, with `numeric`).
+fn labelled(part: &Folded, labels: &[&str], numeric: bool) -> Option<Range<usize>> {
+  for label in labels {
+    let label: Vec<char> = Folded::new(label).chars;
+    let Some(at) = part.find_word(&label) else {
+      continue;
+    };
+    let value = part.offsets[at + label.len()]..part.end();
+    let has_value = part.chars[at + label.len()..].iter().any(|c| {
+      if numeric {
+        c.is_ascii_digit()
+      } else {
+        c.is_alphanumeric()
+      }
+    });
+    if has_value && !value.is_empty() {
+      return Some(part.offsets[at]..part.end());
+    }
+  }
+  None
+}
+
+/// Text folded for matching (lowercase, without accents), with the byte offset
+/// of every character in the original text.
+pub(crate) struct Folded {
+  pub chars: Vec<char>,
+  /// Offsets of the characters, and of the end of the text
+  pub offsets: Vec<usize>,
+}
+
+impl Folded {
+  pub fn new(text: &str) -> Folded {
+    let mut chars = Vec::new();
+    let mut offsets = Vec::new();
+    for (offset, c) in text.char_indices() {
+      let c = match c {
+        '’' => '\'',
+        c => c.to_string().nfd().next().unwrap_or(c),
+      };
+      chars.extend(c.to_lowercase().take(1));
+      offsets.push(offset);
+    }
+    offsets.push(text.len());
+    Folded { chars, offsets }
+  }
+
+  pub fn text(&self) -> String {
+    self.chars.iter().collect()
+  }
+
+  fn end(&self) -> usize {
+    *self.offsets.last().unwrap_or(&0)
+  }
+
+  /// The characters of `range` (byte offsets of the original text).
+  pub fn slice(&self, range: &Range<usize>) -> Folded {
+    let start = self.offsets.partition_point(|&o| o < range.start);
+    let end = self.offsets.partition_point(|&o| o < range.end);
+    Folded {
+      chars: self.chars[start..end].to_vec(),
+      offsets: self.offsets[start..=end].to_vec(),
+    }
+  }
+
+  /// Alphanumeric words, with their byte range in the original text.
+  pub fn words(&self) -> Vec<(String, Range<usize>)> {
+    let mut words = Vec::new();
+    let mut i = 0;
+    while i < self.chars.len() {
+      if !self.chars[i].is_alphanumeric() {
+        i += 1;
+        continue;
+      }
+      let start = i;
+      while i < self.chars.len() && self.chars[i].is_alphanumeric() {
+        i += 1;
+      }
+      words.push((
+        self.chars[start..i].iter().collect(),
+        self.offsets[start]..self.offsets[i],
+      ));
+    }
+    words
+  }
+
+  /// Index of the first occurrence of `needle` at word boundaries.
+  pub fn find_word(&self, needle: &[char]) -> Option<usize> {
+    if needle.is_empty() || needle.len() > self.chars.len() {
+      return None;
+    }
+    (0..=self.chars.len() - needle.len()).find(|&i| {
+      self.chars[i..i + needle.len()] == *needle
+        && (i == 0 || !self.chars[i - 1].is_alphanumeric())
+        && self
+          .chars
+          .get(i + needle.len())
+          .is_none_or(|c| !c.is_alphanumeric())
+    })
+  }
+}
@@ -0,0 +1,137 @@
+use napi::bindgen_prelude::Uint8Array;
+use napi::{Error, Status};
+use napi_derive::napi;
+
+use crate::pdf::{catch_panic, load_document, page_text};
+use crate::pdf_images::{pdf_images, portrait_score};
+use crate::pdf_objects::page_box;
+use crate::pii::find_pii;
+use crate::text::{clean_pages, TextCleanupOptions};
+
+/// Categories of sensitive data reported, in report order.
+const REPORT_CATEGORIES: &[&str] = &[
+  "date_of_birth",
+  "age",
+  "marital_status",
+  "nationality",
+  "photo",
+  "social_security_number",
+  "address",
+  "health",
+];
+
+/// Options for the PII report.
+///
+/// - `min_confidence`: Leave out findings below this confidence, from 0 to 1
+///   (default: 0, everything is reported)
+#[napi(object)]
+#[derive(Default)]
+pub struct PiiReportOptions {
+  pub min_confidence: Option<f64>,
+}
+
+/// Sensitive data found in a CV.
+///
+/// - `category`: "date_of_birth", "age", "marital_status", "nationality", "photo",
+///   "social_security_number", "address" or "health"
+/// - `text`: The text found (none for the photo)
+/// - `page_number`: 1-based page number
+/// - `line`: 1-based line of the text in the page, as extracted with `layout: true`
+/// - `rect`: Placement of the photo on the page in points, `[x0, y0, x1, y1]` from the
+///   bottom left corner
+/// - `confidence`: How sure the detection is, from 0 to 1: labelled values
+///   ("Nationalité : française") and checked formats (a social security number
+///   with a valid key) score higher than values recognized from their shape alone
+#[napi(object)]
+pub struct PiiFinding {
+  pub category: String,
+  pub text: Option<String>,
+  pub page_number: u32,
+  pub line: Option<u32>,
+  pub rect: Option<Vec<f64>>,
+  pub confidence: f64,
+}
+
+/// Report of the sensitive data in a CV.
+///
+/// - `categories`: Categories found, without duplicates
+/// - `findings`: Each finding, in page and reading order (the photo after the text
+///   of its page)
+/// - `page_count`: Number of pages in the document
+#[napi(object)]
+pub struct PiiReport {
+  pub categories: Vec<String>,
+  pub findings: Vec<PiiFinding>,
+  pub page_count: u32,
+}
+
+/// Report which sensitive categories of personal data appear in a PDF CV, e.g.
+/// to check a CV before storing it under the GDPR.
+///
+/// The text of each page is extracted in reading order and cleaned up, as with
+/// `extract_text_from_pdf` and `layout: true`, and searched in French and English
+/// for a date of birth, age, marital status, nationality, social security number,
+/// home address and mentions of health or disability. The candidate's photo is
+/// detected as with `extract_pdf_images` and `detect_portrait`. Detection is
+/// heuristic: findings come with a confidence.
+#[napi]
+pub fn detect_pii(bytes: Uint8Array, options: Option<PiiReportOptions>) -> napi::Result<PiiReport> {
+  let input = bytes.to_vec();
+  let min_confidence = options.unwrap_or_default().min_confidence.unwrap_or(0.0);
+
+  catch_panic(|| {
+    let doc = load_document(&input)
+      .map_err(|e| Error::new(Status::InvalidArg, format!("Failed to read PDF: {e}")))?;
+    let pages = doc.get_pages();
+    let images = pdf_images(&doc, true);
+
+    let mut findings = Vec::new();
+    for (&page_number, &page_id) in &pages {
+      let Some((text, _)) = page_text(&doc, page_number, page_id, true, None) else {
+        continue;
+      };
+      let text = clean_pages(&[text], &TextCleanupOptions::default());
+      let lines: Vec<&str> = text.lines().collect();
+      for found in find_pii(&text, None) {
+        if REPORT_CATEGORIES.contains(&found.category) {
+          findings.push(PiiFinding {
+            category: found.category.to_string(),
+            text: Some(lines[found.line][found.range].to_string()),
+            page_number,
+            line: Some(found.line as u32 + 1),
+            rect: None,
+            confidence: found.confidence,
+          });
+        }
+      }
+
+      let photo = images
+        .iter()
+        .find(|image| image.likely_portrait && image.page_number == page_number);
+      if let Some(photo) = photo {
+        // From 0.5, for an image just shaped and placed like a photo, up to 1.
+        let score = portrait_score(photo, page_box(&doc, page_id));
+        findings.push(PiiFinding {
+          category: "photo".to_string(),
+          text: None,
+          page_number,
+          line: None,
+          rect: Some(photo.rect.clone()),
+          confidence: (0.5 + score / 2.0).min(1.0),
+        });
+      }
+    }
+    findings.retain(|finding| finding.confidence >= min_confidence);
+
+    let categories = REPORT_CATEGORIES
+      .iter()
+      .filter(|category| findings.iter().any(|f| f.category == **category))
+      .map(|category| category.to_string())
+      .collect();
+    Ok(PiiReport {
+      categories,
+      findings,
+      page_count: pages.len() as u32,
+    })
+  })
+}
@@ -0,0 +1,364 @@
+use napi_derive::napi;
+
+/// Scores at or above this are considered clean text.
+const GOOD_SCORE: f64 = 0.7;
+/// Scores below this are considered garbled.
+const GARBLED_SCORE: f64 = 0.4;
+/// Class entropy (normalized) above which the score is penalized.
+const MAX_NATURAL_ENTROPY: f64 = 0.6;
+/// Consonant pairs common in the Latin-script languages of CVs (English, French,
+/// German, Spanish, Italian, Dutch); any pair with a vowel is plausible.
+const CONSONANT_CLUSTERS: &str = "bb bl br bs bt bj cc ch ck cl cr cs ct dd dg dj dl dm dn \
+  dr ds dt dv dw ff fl fr fs ft gg gh gl gm gn gr gs gt hl hm hn hr hs ht kk kl kn kr ks \
+  lb lc ld lf lg lk ll lm ln lp ls lt lv lz mb mf mm mn mp ms nc nd nf ng nh nk nl nn nq \
+  ns nt nv nz pf ph pl pn pp pr ps pt rb rc rd rf rg rh rk rl rm rn rp rr rs rt rv rw rz \
+  sc sh sk sl sm sn sp sq ss st sw tc th tl tn tr ts tt tw tz wh wl wn wr ws xc xp xt zw zz";
+
+/// Quality estimate of extracted text.
+///
+/// - `score`: Overall quality, from 0 (garbage) to 1 (clean text)
+/// - `verdict`: "good", "suspect", "garbled", or "empty" (no characters)
+/// - `word_ratio`: Share of the token characters that belong to word-like tokens
+///   (words with plausible letter pairs, numbers, dates, e-mails, URLs). Scripts
+///   written without spaces (Chinese, Japanese) are scored character by character
+/// - `invalid_char_ratio`: Share of the characters that are private-use, replacement
+///   (U+FFFD) or control characters
+/// - `class_entropy`: Entropy of the character class distribution (letters, digits,
+///   punctuation, symbols, …), normalized to 0–1; mojibake mixes classes
+/// - `char_count`: Number of non-whitespace characters scored
+#[napi(object)]
+#[derive(Clone, Debug)]
+pub struct TextQuality {
+  pub score: f64,
+  pub verdict: String,
+  pub word_ratio: f64,
+  pub invalid_char_ratio: f64,
+  pub class_entropy: f64,
+  pub char_count: u32,
+}
+
+/// Score the quality of extracted text, to tell real text from mojibake.
+///
+/// PDFs whose fonts lack a ToUnicode map often extract to private-use glyphs or to
+/// letter soup that looks like text. The score combines the share of word-like
+/// tokens (including whether their letter pairs are plausible in a natural
+/// language), the share of invalid characters and the character class entropy; use
+/// `verdict` (or a threshold on `score`) to decide whether to OCR or reject a file.
+#[napi]
+pub fn score_text_quality(text: String) -> TextQuality {
+  text_quality(&text)
+}
+
+pub(crate) fn text_quality(text: &str) -> TextQuality {
+  let chars: Vec<char> = text.chars().filter(|c| !c.is_whitespace()).collect();
+  if chars.is_empty() {
+    return TextQuality {
+      score: 0.0,
+      verdict: "empty".to_string(),
+      word_ratio: 0.0,
+      invalid_char_ratio: 0.0,
+      class_entropy: 0.0,
+      char_count: 0,
+    };
+  }
+
+  let invalid = chars.iter().filter(|c| is_invalid(**c)).count();
+  let invalid_char_ratio = invalid as f64 / chars.len() as f64;
+
+  let (mut word_chars, mut token_chars) = (0usize, 0usize);
+  for token in tokens(text) {
+    let core = token.trim_matches(is_edge_punctuation);
+    if core.is_empty() {
+      continue;
+    }
+    // A few surrounding punctuation marks are normal (`(Paris),`); more are not.
+    let len = token.chars().count();
+    let core_len = core.chars().count();
+    token_chars += len;
+    if is_word_like(core) {
+      word_chars += if len - core_len <= 3 { len } else { core_len };
+    }
+  }
+  let word_ratio = if token_chars > 0 {
+    word_chars as f64 / token_chars as f64
+  } else {
+    0.0
+  };
+
+  let class_entropy = class_entropy(&chars);
+  let entropy_factor = 1.0
+    - ((class_entropy - MAX_NATURAL_ENTROPY) / (1.0 - MAX_NATURAL_ENTROPY)).clamp(0.0, 1.0) * 0.5;
+  let score = (word_ratio * (1.0 - invalid_char_ratio).powi(4) * entropy_factor).clamp(0.0, 1.0);
+
+  let verdict = if score >= GOOD_SCORE {
+    "good"
+  } else if score >= GARBLED_SCORE {
+    "suspect"
+  } else {
+    "garbled"
+  };
+
+  TextQuality {
+    score,
+    verdict: verdict.to_string(),
+    word_ratio,
+    invalid_char_ratio,
+    class_entropy,
+    char_count: chars.len() as u32,
+  }
+}
+
+/// Tokens separated by whitespace or CJK punctuation, with each character of the
+/// scripts written without spaces taken as a token of its own (with the punctuation
+/// that follows it).
+fn tokens(text: &str) -> Vec<&str> {
+  let mut tokens = Vec::new();
+  let separated = text.split(|c: char| c.is_whitespace() || is_cjk_punctuation(c));
+  for token in separated.filter(|token| !token.is_empty()) {
+    let mut start = 0;
+    let mut after_unspaced = false;
+    for (i, c) in token.char_indices() {
+      let unspaced = is_unspaced(c);
+      if i > start && (unspaced || (after_unspaced && !is_edge_punctuation(c))) {
+        tokens.push(&token[start..i]);
+        start = i;
+      }
+      if unspaced || !is_edge_punctuation(c) {
+        after_unspaced = unspaced;
+      }
+    }
+    tokens.push(&token[start..]);
+  }
+  tokens
+}
+
+/// Han ideographs and kana: scripts written without spaces between words.
+fn is_unspaced(c: char) -> bool {
+  matches!(
+    c as u32,
+    0x3040..=0x30FF | 0x3400..=0x4DBF | 0x4E00..=0x9FFF | 0xF900..=0xFAFF | 0x20000..=0x2FFFF
+  )
+}
+
+/// Private-use, replacement and control characters.
+fn is_invalid(c: char) -> bool {
+  matches!(c as u32, 0xE000..=0xF8FF | 0xF0000..=0x10FFFF | 0xFFFD) || c.is_control()
+}
+
+/// Punctuation that may surround a word without being part of it.
+fn is_edge_punctuation(c: char) -> bool {
+  c.is_ascii_punctuation()
+    || matches!(
+      c,
+      '‘'
+        | '’'
+        | '“'
+        | '”'
+        | '«'
+        | '»'
+        | '…'
+        | '•'
+        | '·'
+        | '–'
+        | '—'
+        | '‹'
+        | '›'
+        | '¿'
+        | '¡'
+    )
+}
+
+/// CJK and fullwidth punctuation (`，`, `。`, `（`, `：`), which separates words
+/// like spaces do in other scripts.
+fn is_cjk_punctuation(c: char) -> bool {
+  matches!(
+    c as u32,
+    0x3000..=0x303F | 0xFF01..=0xFF0F | 0xFF1A..=0xFF20 | 0xFF3B..=0xFF40 | 0xFF5B..=0xFF65
+  )
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Script {
+  Latin,
+  Greek,
+  Cyrillic,
+  Other,
+}
+
+fn script(c: char) -> Script {
+  match c as u32 {
+    0..=0x24F | 0x1E00..=0x1EFF => Script::Latin,
+    0x370..=0x3FF | 0x1F00..=0x1FFF => Script::Greek,
+    0x400..=0x52F => Script::Cyrillic,
+    _ => Script::Other,
+  }
+}
+
+/// Latin letters that are rarely used to write words: Latin Extended-B, except
+/// the Romanian `ș ț` and the Vietnamese `ơ ư`.
+fn is_rare_latin(c: char) -> bool {
+  (0x180..=0x24F).contains(&(c as u32)) && !"ȘșȚțƠơƯư".contains(c)
+}
+
+fn is_vowel(c: char) -> bool {
+  "aeiouyàáâãäåæèéêëìíîïòóôõöøœùúûüýÿ".contains(c.to_lowercase().next().unwrap_or(c))
+}
+
+/// Whether a token looks like a real word, number, date, e-mail or URL.
+fn is_word_like(token: &str) -> bool {
+  let chars: Vec<char> = token.chars().collect();
+
+  // Numbers, dates, phone numbers, amounts
+  if chars.iter().any(char::is_ascii_digit)
+    && chars
+      .iter()
+      .all(|c| c.is_ascii_digit() || "+-–./:,%()€$£".contains(*c))
+  {
+    return true;
+  }
+  // E-mails and URLs
+  if is_email(token) || is_url(token) {
+    return true;
+  }
+  // Short alphanumeric identifiers (`B2B`, `CSS3`, `H2O`)
+  if chars.iter().all(char::is_ascii_alphanumeric) && chars.iter().any(char::is_ascii_digit) {
+    return chars.len() <= 10;
+  }
+  if !chars
+    .iter()
+    .all(|c| c.is_alphabetic() || "'’-.&/".contains(*c))
+  {
+    return false;
+  }
+
+  let letters: Vec<char> = chars
+    .iter()
+    .copied()
+    .filter(|c| c.is_alphabetic())
+    .collect();
+  let Some(first) = letters.first() else {
+    return false;
+  };
+  let first_script = script(*first);
+  if letters.iter().any(|c| script(*c) != first_script) {
+    return false;
+  }
+  if first_script != Script::Latin {
+    return true;
+  }
+
+  // Case: lower, UPPER, Capitalized, or a few inner capitals (`McDonald`, `PostgreSQL`)
+  let case_changes = letters
+    .windows(2)
+    .filter(|w| w[0].is_lowercase() && w[1].is_uppercase())
+    .count();
+  if case_changes > 2 {
+    return false;
+  }
+  // Mojibake turns bytes into runs of Latin-1 letters (`ÊÌÏÐ`) and rare letters
+  // (`Ǧǝ`), which words only use a few of.
+  let accented = letters
+    .iter()
+    .filter(|c| ('\u{C0}'..='\u{FF}').contains(*c))
+    .count();
+  let rare = letters.iter().filter(|c| is_rare_latin(**c)).count();
+  if rare * 2 > letters.len() || (letters.len() >= 3 && accented * 3 > letters.len() * 2) {
+    return false;
+  }
+  // Words rarely are mostly vowels; few consonant clusters have no vowel at all
+  // (`Strengths`, `Schwarz`), which the pair check below judges.
+  let vowels = letters.iter().filter(|c| is_vowel(**c)).count();
+  if letters.len() > 4 && vowels * 5 > letters.len() * 4 {
+    return false;
+  }
+  let mut run = 0;
+  for c in &letters {
+    run = if is_vowel(*c) { 0 } else { run + 1 };
+    if run > 5 {
+      return false;
+    }
+  }
+  // Letter soup (`kjh`, `qwx`) pairs consonants no language puts together. Short
+  // tokens in capitals are acronyms (`AWS`, `GCP`) and are not checked.
+  let acronym = letters.len() <= 5 && letters.iter().all(|c| !c.is_lowercase());
+  if letters.len() >= 3 && !acronym {
+    let lower: Vec<char> = letters.iter().flat_map(|c| c.to_lowercase()).collect();
+    let implausible = lower
+      .windows(2)
+      .filter(|pair| !is_plausible_pair(pair[0], pair[1]))
+      .count();
+    if implausible * 2 > lower.len() - 1 {
+      return false;
+    }
+  }
+  true
+}
+
+/// Whether two lowercase letters are a plausible pair in a word: one of them is a
+/// vowel, or they are a common consonant cluster. Letters outside ASCII (`ß`, `ñ`,
+/// `ł`) are not judged.
+fn is_plausible_pair(a: char, b: char) -> bool {
+  if is_vowel(a) || is_vowel(b) || !a.is_ascii() || !b.is_ascii() {
+    return true;
+  }
+  CONSONANT_CLUSTERS
+    .split_whitespace()
+    .any(|pair| pair.as_bytes() == [a as u8, b as u8])
+}
+
+fn is_email(token: &str) -> bool {
+  let Some((local, domain)) = token.split_once('@') else {
+    return false;
+  };
+  let valid = |part: &str, extra: &str| {
+    !part.is_empty()
+      && part
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || extra.contains(c))
+  };
+  valid(local, ".-_+") && valid(domain, ".-") && domain.contains('.')
+}
+
+fn is_url(token: &str) -> bool {
+  let lower = token.to_ascii_lowercase();
+  ["http://", "https://", "www."]
+    .iter()
+    .any(|prefix| lower.starts_with(prefix) && lower.len() > prefix.len())
+    && token.chars().all(|c| c.is_ascii_graphic())
+}
+
+/// Normalized Shannon entropy of the character class distribution.
+fn class_entropy(chars: &[char]) -> f64 {
+  const CLASSES: usize = 8;
+  let mut counts = [0usize; CLASSES];
+  for c in chars {
+    let class = if is_invalid(*c) {
+      0
+    } else if c.is_lowercase() {
+      1
+    } else if c.is_uppercase() {
+      2
+    } else if c.is_alphabetic() {
+      3
+    } else if c.is_numeric() {
+      4
+    } else if c.is_ascii_punctuation() {
+      5
+    } else if is_edge_punctuation(*c) {
+      6
+    } else {
+      7
+    };
+    counts[class] += 1;
+  }
+  let total = chars.len() as f64;
+  let entropy: f64 = counts
+    .iter()
+    .filter(|n| **n > 0)
+    .map(|n| {
+      let p = *n as f64 / total;
+      -p * p.log2()
+    })
+    .sum();
+  entropy / (CLASSES as f64).log2()
+}
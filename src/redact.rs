@@ -0,0 +1,1028 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::rc::Rc;
+
+use lopdf::content::{Content, Operation};
+use lopdf::{decode_text_string, Dictionary, Document, Object, ObjectId, Stream, StringFormat};
+use napi::bindgen_prelude::Uint8Array;
+use napi::{Error, Status};
+use napi_derive::napi;
+use unicode_normalization::UnicodeNormalization;
+
+use crate::forms::{form_fields, remove_widgets};
+use crate::html::parse_color;
+use crate::pdf::{catch_panic, load_document};
+use crate::pdf_content::{
+  page_content, parse_content, stream_content, xobject, xobject_subtype, Matrix, WalkGuard,
+  MAX_WALK_BYTES,
+};
+use crate::pdf_fonts::FontDecoder;
+use crate::pdf_images::{decode_image, inline_image_stream};
+use crate::pdf_objects::{
+  as_number, as_rect, dict_get, dict_get_dict, page_resources, resolve, resource,
+};
+use crate::typeset::Rgb;
+
+/// Share of a glyph's box that must lie inside a redaction area for the glyph to
+/// be removed.
+const MIN_GLYPH_OVERLAP: f64 = 0.3;
+
+/// Vertical extent of a glyph's box, in units of the font size from the baseline.
+const GLYPH_DESCENT: f64 = -0.2;
+const GLYPH_ASCENT: f64 = 0.8;
+
+/// Images are drawn into the unit square of the current user space.
+const UNIT_SQUARE: [f64; 4] = [0.0, 0.0, 1.0, 1.0];
+
+/// Properties of a marked-content span that replace or describe its content, and
+/// would still tell what was removed from it.
+const REPLACEMENT_TEXT_KEYS: [&[u8]; 3] = [b"ActualText", b"Alt", b"E"];
+
+/// A region of a PDF page.
+///
+/// - `page_number`: 1-based page number
+/// - `rect`: Region in points, `[x0, y0, x1, y1]` from the bottom left corner of
+///   the page (as `PdfImage.rect`)
+#[napi(object)]
+pub struct PdfRedactionArea {
+  pub page_number: u32,
+  pub rect: Vec<f64>,
+}
+
+/// What to redact in a PDF.
+///
+/// - `areas`: Regions to redact
+/// - `texts`: Text to find and redact on every page, e.g. a name or an email
+///   address; whitespace is ignored when matching. Annotations whose contents,
+///   form field value or appearance match are redacted whole
+/// - `match_case`: Match `texts` case-sensitively (default: false)
+/// - `fill_color`: Color of the boxes drawn over the redacted regions, as `#rrggbb`,
+///   `rgb(…)` or a color name (default: "black")
+#[napi(object)]
+#[derive(Default)]
+pub struct RedactPdfOptions {
+  pub areas: Option<Vec<PdfRedactionArea>>,
+  pub texts: Option<Vec<String>>,
+  pub match_case: Option<bool>,
+  pub fill_color: Option<String>,
+}
+
+/// Result of a PDF redaction.
+///
+/// - `pdf`: The redacted PDF
+/// - `areas`: Regions redacted, including the boxes of the text matches
+/// - `glyphs_removed`: Number of glyphs removed from the content streams
+/// - `images_redacted`: Number of image placements blanked out in the regions (or
+///   removed, for images that cannot be decoded)
+#[napi(object)]
+pub struct RedactPdfResult {
+  pub pdf: Vec<u8>,
+  pub areas: Vec<PdfRedactionArea>,
+  pub glyphs_removed: u32,
+  pub images_redacted: u32,
+}
+
+/// Redact regions of a PDF for good: the content inside them is deleted, not just
+/// covered.
+///
+/// Glyphs overlapping a region are removed from the content streams (the other
+/// glyphs keep their position, and each run of removed glyphs leaves a single gap),
+/// along with the replacement texts of the marked-content spans around them. Image
+/// pixels inside it are painted over in the image data itself, soft mask included,
+/// and annotations over it (links, comments, form fields) are deleted; the fields
+/// of deleted widgets leave the form with their values. Images that cannot be
+/// decoded (JPEG 2000, CCITT, JBIG2, stencil masks) are removed entirely when they
+/// overlap a region. Form XObjects are rewritten as
+/// needed. An opaque box is then drawn over each region, and objects no longer
+/// used are dropped from the file, so that the redacted content cannot be
+/// extracted with `extract_text_from_pdf` or `extract_pdf_images`.
+///
+/// Regions are given as `areas`, or found from `texts` (a box per matched run of
+/// glyphs on a line, and the box of every matching annotation). Vector graphics inside the regions are only covered.
+#[napi]
+pub fn redact_pdf(bytes: Uint8Array, options: RedactPdfOptions) -> napi::Result<RedactPdfResult> {
+  let input = bytes.to_vec();
+  let fill = match &options.fill_color {
+    Some(color) => parse_color(color)
+      .ok_or_else(|| Error::new(Status::InvalidArg, format!("Invalid fill color: {color}")))?,
+    None => [0.0; 3],
+  };
+  let match_case = options.match_case.unwrap_or(false);
+  let needles: Vec<Vec<char>> = (options.texts.iter().flatten())
+    .map(|text| match_chars(text, match_case))
+    .filter(|needle| !needle.is_empty())
+    .collect();
+
+  catch_panic(|| {
+    let mut doc = load_document(&input).map_err(|e| {
+      Error::new(
+        Status::InvalidArg,
+        format!("Failed to read PDF for redaction: {e}"),
+      )
+    })?;
+    let pages = doc.get_pages();
+    // Values of the form fields, by widget, for matching `texts`.
+    let field_values: HashMap<ObjectId, String> = if needles.is_empty() {
+      HashMap::new()
+    } else {
+      (form_fields(&doc).into_iter())
+        .flat_map(|field| {
+          let value = field.info.value.unwrap_or_default();
+          field.widgets.into_iter().map(move |id| (id, value.clone()))
+        })
+        .collect()
+    };
+
+    let mut areas: BTreeMap<u32, Vec<[f64; 4]>> = BTreeMap::new();
+    for area in options.areas.iter().flatten() {
+      let rect = match area.rect[..] {
+        [x0, y0, x1, y1] if area.rect.iter().all(|v| v.is_finite()) => {
+          [x0.min(x1), y0.min(y1), x0.max(x1), y0.max(y1)]
+        }
+        _ => {
+          return Err(Error::new(
+            Status::InvalidArg,
+            format!(
+              "Invalid redaction rect on page {}: expected [x0, y0, x1, y1]",
+              area.page_number
+            ),
+          ))
+        }
+      };
+      if !pages.contains_key(&area.page_number) {
+        return Err(Error::new(
+          Status::InvalidArg,
+          format!(
+            "Page {} does not exist (the PDF has {} pages)",
+            area.page_number,
+            pages.len()
+          ),
+        ));
+      }
+      areas.entry(area.page_number).or_default().push(rect);
+    }
+
+    let mut redacted_areas = Vec::new();
+    let mut glyphs_removed = 0;
+    let mut images_redacted = 0;
+    for (page_number, page_id) in pages {
+      // Content left unwalked would be left unredacted.
+      let too_complex = || {
+        Error::new(
+          Status::InvalidArg,
+          format!("Page {page_number} is too complex to redact"),
+        )
+      };
+      let mut rects = areas.remove(&page_number).unwrap_or_default();
+      if !needles.is_empty() {
+        let mut finder = Redactor::new(&doc, &[], fill, 0);
+        finder.page(page_id).ok_or_else(too_complex)?;
+        rects.extend(text_matches(&finder.glyphs, &needles, match_case));
+        rects.extend(annotation_matches(
+          &doc,
+          page_id,
+          &field_values,
+          &needles,
+          match_case,
+        ));
+      }
+      if rects.is_empty() {
+        continue;
+      }
+
+      let mut redactor = Redactor::new(&doc, &rects, fill, doc.max_id + 1);
+      let edit = redactor.page(page_id).ok_or_else(too_complex)?;
+      glyphs_removed += redactor.glyphs_removed;
+      images_redacted += redactor.images_redacted;
+      let objects = redactor.objects;
+      apply_page(&mut doc, page_id, edit, objects, &rects, fill);
+      redacted_areas.extend(rects.iter().map(|rect| PdfRedactionArea {
+        page_number,
+        rect: rect.to_vec(),
+      }));
+    }
+
+    // Drop the original content streams, images and forms.
+    doc.prune_objects();
+    let mut pdf = Vec::new();
+    doc.save_to(&mut pdf).map_err(|e| {
+      Error::new(
+        Status::GenericFailure,
+        format!("Failed to write redacted PDF: {e}"),
+      )
+    })?;
+    Ok(RedactPdfResult {
+      pdf,
+      areas: redacted_areas,
+      glyphs_removed,
+      images_redacted,
+    })
+  })
+}
+
+/// Characters of `text` compared when matching: NFKC, without whitespace,
+/// lowercased unless `match_case`.
+fn match_chars(text: &str, match_case: bool) -> Vec<char> {
+  text
+    .nfkc()
+    .filter(|c| !c.is_whitespace())
+    .flat_map(|c| {
+      let lower: Vec<char> = if match_case {
+        vec![c]
+      } else {
+        c.to_lowercase().collect()
+      };
+      lower
+    })
+    .collect()
+}
+
+/// Boxes of the glyphs matching one of `needles`: one per run of consecutive
+/// matched glyphs on a line.
+fn text_matches(
+  glyphs: &[(String, [f64; 4])],
+  needles: &[Vec<char>],
+  match_case: bool,
+) -> Vec<[f64; 4]> {
+  let chars: Vec<(char, usize)> = (glyphs.iter().enumerate())
+    .flat_map(|(i, (text, _))| {
+      match_chars(text, match_case)
+        .into_iter()
+        .map(move |c| (c, i))
+    })
+    .collect();
+  let mut matched = vec![false; glyphs.len()];
+  for needle in needles {
+    let mut start = 0;
+    while start + needle.len() <= chars.len() {
+      let window = &chars[start..start + needle.len()];
+      if window.iter().map(|(c, _)| c).eq(needle.iter()) {
+        // Spaces between the matched glyphs are redacted with them.
+        for glyph in &mut matched[window[0].1..=window[needle.len() - 1].1] {
+          *glyph = true;
+        }
+        start += needle.len();
+      } else {
+        start += 1;
+      }
+    }
+  }
+
+  let mut rects: Vec<[f64; 4]> = Vec::new();
+  let mut previous: Option<usize> = None;
+  for (i, (_, b)) in glyphs.iter().enumerate() {
+    if !matched[i] {
+      continue;
+    }
+    match rects.last_mut() {
+      Some(last) if previous == Some(i - 1) && b[1] < last[3] && b[3] > last[1] => {
+        *last = [
+          last[0].min(b[0]),
+          last[1].min(b[1]),
+          last[2].max(b[2]),
+          last[3].max(b[3]),
+        ];
+      }
+      _ => rects.push(*b),
+    }
+    previous = Some(i);
+  }
+  rects
+}
+
+/// Boxes of the annotations of a page whose text matches one of `needles`: their
+/// `/Contents`, the value of their form field, or the text of their appearance
+/// streams.
+fn annotation_matches(
+  doc: &Document,
+  page_id: ObjectId,
+  field_values: &HashMap<ObjectId, String>,
+  needles: &[Vec<char>],
+  match_case: bool,
+) -> Vec<[f64; 4]> {
+  let matches = |text: &str| {
+    let chars = match_chars(text, match_case);
+    (needles.iter()).any(|needle| chars.windows(needle.len()).any(|w| w == needle.as_slice()))
+  };
+  let annots = doc
+    .get_dictionary(page_id)
+    .ok()
+    .and_then(|page| dict_get(doc, page, b"Annots"))
+    .and_then(|annots| annots.as_array().ok());
+  let mut rects = Vec::new();
+  for annot in annots.into_iter().flatten() {
+    let Ok((id, Object::Dictionary(dict))) = doc.dereference(annot) else {
+      continue;
+    };
+    let Some(rect) = dict_get(doc, dict, b"Rect").and_then(|r| as_rect(doc, r)) else {
+      continue;
+    };
+    let contents = dict_get(doc, dict, b"Contents").and_then(|c| decode_text_string(c).ok());
+    let value = id.and_then(|id| field_values.get(&id));
+    if contents.iter().chain(value).any(|text| matches(text))
+      || appearance_streams(doc, dict).any(|stream| matches(&appearance_text(doc, stream)))
+    {
+      rects.push(rect);
+    }
+  }
+  rects
+}
+
+/// Appearance streams of an annotation, in every state.
+fn appearance_streams<'a>(
+  doc: &'a Document,
+  annot: &'a Dictionary,
+) -> impl Iterator<Item = &'a Stream> + 'a {
+  let appearances = dict_get_dict(doc, annot, b"AP");
+  (appearances.into_iter())
+    .flat_map(|ap| ap.iter())
+    .flat_map(move |(_, appearance)| match doc.dereference(appearance) {
+      Ok((_, Object::Stream(stream))) => vec![stream],
+      Ok((_, Object::Dictionary(states))) => (states.iter())
+        .filter_map(|(_, state)| resolve(doc, state).as_stream().ok())
+        .collect(),
+      _ => Vec::new(),
+    })
+}
+
+/// Text shown by an appearance stream.
+fn appearance_text(doc: &Document, stream: &Stream) -> String {
+  let Some(content) = stream_content(stream, MAX_WALK_BYTES) else {
+    return String::new();
+  };
+  let resources = dict_get_dict(doc, &stream.dict, b"Resources");
+  let mut finder = Redactor::new(doc, &[], [0.0; 3], 0);
+  finder.rewrite(&content, resources, Matrix::IDENTITY, TextState::default());
+  finder.glyphs.into_iter().map(|(text, _)| text).collect()
+}
+
+/// Replace the content of a page with its redacted version, followed by the
+/// fills over the regions, and delete the annotations over the regions, along
+/// with the form fields of the widgets among them.
+fn apply_page(
+  doc: &mut Document,
+  page_id: ObjectId,
+  edit: ContentEdit,
+  objects: Vec<(ObjectId, Object)>,
+  rects: &[[f64; 4]],
+  fill: Rgb,
+) {
+  for (id, object) in objects {
+    doc.max_id = doc.max_id.max(id.0);
+    doc.objects.insert(id, object);
+  }
+
+  let mut resources = page_resources(doc, page_id).cloned().unwrap_or_default();
+  update_resources(doc, &mut resources, &edit);
+
+  // The page content is wrapped in q/Q so its graphics state does not leak into
+  // the fills.
+  let mut content = b"q\n".to_vec();
+  content.extend(edit.content);
+  content.extend(format!("\nQ\nq {:.3} {:.3} {:.3} rg\n", fill[0], fill[1], fill[2]).bytes());
+  for r in rects {
+    content.extend(
+      format!(
+        "{:.2} {:.2} {:.2} {:.2} re f\n",
+        r[0],
+        r[1],
+        r[2] - r[0],
+        r[3] - r[1]
+      )
+      .bytes(),
+    );
+  }
+  content.extend(b"Q\n");
+  let mut stream = Stream::new(Dictionary::new(), content);
+  let _ = stream.compress();
+  let contents = doc.add_object(stream);
+
+  let mut widgets = Vec::new();
+  let annotations = doc
+    .get_dictionary(page_id)
+    .ok()
+    .and_then(|page| dict_get(doc, page, b"Annots"))
+    .and_then(|annots| annots.as_array().ok())
+    .map(|annots| {
+      let (removed, kept): (Vec<&Object>, Vec<&Object>) = annots.iter().partition(|annot| {
+        let rect = doc
+          .dereference(annot)
+          .ok()
+          .and_then(|(_, a)| a.as_dict().ok())
+          .and_then(|a| dict_get(doc, a, b"Rect"))
+          .and_then(|r| as_rect(doc, r));
+        rect.is_some_and(|rect| rects.iter().any(|area| overlap(area, &rect) > 0.0))
+      });
+      widgets.extend(removed.into_iter().filter_map(|annot| {
+        let id = annot.as_reference().ok()?;
+        let subtype = doc.get_dictionary(id).ok()?.get(b"Subtype").ok()?;
+        (subtype.as_name().ok()? == b"Widget").then_some(id)
+      }));
+      kept.into_iter().cloned().collect::<Vec<_>>()
+    });
+  // The field values would otherwise stay in the form.
+  remove_widgets(doc, &widgets);
+
+  let Ok(page) = doc.get_dictionary_mut(page_id) else {
+    return;
+  };
+  page.set("Contents", Object::Reference(contents));
+  page.set("Resources", Object::Dictionary(resources));
+  match annotations {
+    Some(kept) if kept.is_empty() => {
+      page.remove(b"Annots");
+    }
+    Some(kept) => page.set("Annots", Object::Array(kept)),
+    None => {}
+  }
+}
+
+/// Update the resources of rewritten content: add the XObjects it draws and drop
+/// those it no longer draws (the originals of redacted images and forms would
+/// otherwise stay in the file), and strip the replacement texts of the named
+/// properties of spans that lost content.
+fn update_resources(doc: &Document, resources: &mut Dictionary, edit: &ContentEdit) {
+  if !edit.properties.is_empty() {
+    let mut properties = dict_get_dict(doc, resources, b"Properties")
+      .cloned()
+      .unwrap_or_default();
+    for name in &edit.properties {
+      if let Some(mut entry) = dict_get_dict(doc, &properties, name).cloned() {
+        for key in REPLACEMENT_TEXT_KEYS {
+          entry.remove(key);
+        }
+        properties.set(name.clone(), entry);
+      }
+    }
+    resources.set("Properties", properties);
+  }
+
+  let mut xobjects = dict_get_dict(doc, resources, b"XObject")
+    .cloned()
+    .unwrap_or_default();
+  for (name, id) in &edit.xobjects {
+    xobjects.set(name.as_bytes(), Object::Reference(*id));
+  }
+  resources.set("XObject", xobjects.clone());
+
+  let mut drawn = BTreeSet::new();
+  drawn_xobjects(doc, &edit.content, resources, &mut drawn);
+  let kept = xobjects
+    .iter()
+    .filter(|(name, _)| drawn.contains(*name))
+    .map(|(name, xobject)| (name.clone(), xobject.clone()));
+  resources.set("XObject", Dictionary::from_iter(kept));
+}
+
+/// Names of the XObjects drawn by a content stream, including those drawn by the
+/// forms it draws that have no resources of their own (and so use `resources`).
+fn drawn_xobjects(
+  doc: &Document,
+  content: &[u8],
+  resources: &Dictionary,
+  drawn: &mut BTreeSet<Vec<u8>>,
+) {
+  for op in parse_content(content) {
+    let name = op.operands.first().and_then(|n| n.as_name().ok());
+    let Some(name) = name.filter(|_| op.operator == "Do") else {
+      continue;
+    };
+    if !drawn.insert(name.to_vec()) {
+      continue;
+    }
+    let form = resource(doc, Some(resources), b"XObject", name)
+      .and_then(|xobject| xobject.as_stream().ok())
+      .filter(|xobject| xobject_subtype(doc, xobject) == Some(b"Form"))
+      .filter(|form| !form.dict.has(b"Resources"));
+    if let Some(data) = form.and_then(|form| stream_content(form, MAX_WALK_BYTES)) {
+      drawn_xobjects(doc, &data, resources, drawn);
+    }
+  }
+}
+
+/// Area of the intersection of two rectangles.
+fn overlap(a: &[f64; 4], b: &[f64; 4]) -> f64 {
+  let width = (a[2].min(b[2]) - a[0].max(b[0])).max(0.0);
+  let height = (a[3].min(b[3]) - a[1].max(b[1])).max(0.0);
+  width * height
+}
+
+/// Remove the replacement texts (see `REPLACEMENT_TEXT_KEYS`) from the properties
+/// of a `BDC` operation. Returns the name of properties given in the resources
+/// instead, to strip there.
+fn strip_replacement_text(op: &mut Operation) -> Option<Vec<u8>> {
+  match op.operands.get_mut(1)? {
+    Object::Dictionary(properties) => {
+      for key in REPLACEMENT_TEXT_KEYS {
+        properties.remove(key);
+      }
+      None
+    }
+    Object::Name(name) => Some(name.clone()),
+    _ => None,
+  }
+}
+
+/// Text state parameters, saved and restored with the graphics state.
+#[derive(Clone)]
+struct TextState {
+  decoder: Option<Rc<FontDecoder>>,
+  size: f64,
+  char_spacing: f64,
+  word_spacing: f64,
+  scaling: f64,
+  leading: f64,
+  rise: f64,
+}
+
+impl Default for TextState {
+  fn default() -> Self {
+    TextState {
+      decoder: None,
+      size: 0.0,
+      char_spacing: 0.0,
+      word_spacing: 0.0,
+      scaling: 1.0,
+      leading: 0.0,
+      rise: 0.0,
+    }
+  }
+}
+
+/// A rewritten content stream.
+struct ContentEdit {
+  content: Vec<u8>,
+  /// XObjects drawn by the new content, to add to its resources
+  xobjects: Vec<(String, ObjectId)>,
+  /// Named properties (in the resources) of marked-content spans that lost content
+  properties: Vec<Vec<u8>>,
+  /// Whether anything was removed or redacted
+  changed: bool,
+}
+
+/// Rewrites the content of a page without what is drawn inside `areas`, keeping
+/// track of the text state like the native text extractor of `pdf.rs`.
+struct Redactor<'a> {
+  doc: &'a Document,
+  areas: &'a [[f64; 4]],
+  fill: Rgb,
+  decoders: HashMap<*const Dictionary, Rc<FontDecoder>>,
+  fallback: Rc<FontDecoder>,
+  /// Text and box (in default page space) of every glyph kept
+  glyphs: Vec<(String, [f64; 4])>,
+  /// New objects (redacted images, rewritten forms) and their ids
+  objects: Vec<(ObjectId, Object)>,
+  next_id: u32,
+  glyphs_removed: u32,
+  images_redacted: u32,
+  guard: WalkGuard,
+}
+
+impl<'a> Redactor<'a> {
+  fn new(doc: &'a Document, areas: &'a [[f64; 4]], fill: Rgb, next_id: u32) -> Redactor<'a> {
+    Redactor {
+      doc,
+      areas,
+      fill,
+      decoders: HashMap::new(),
+      fallback: Rc::new(FontDecoder::fallback()),
+      glyphs: Vec::new(),
+      objects: Vec::new(),
+      next_id,
+      glyphs_removed: 0,
+      images_redacted: 0,
+      guard: WalkGuard::default(),
+    }
+  }
+
+  /// Rewrite the content of a page, or `None` when it is too complex to walk
+  /// (see `WalkGuard`).
+  fn page(&mut self, page_id: ObjectId) -> Option<ContentEdit> {
+    let content = page_content(self.doc, page_id)?;
+    let resources = page_resources(self.doc, page_id);
+    self.guard = WalkGuard::default();
+    let edit = self.rewrite(&content, resources, Matrix::IDENTITY, TextState::default());
+    (!self.guard.exhausted()).then_some(edit)
+  }
+
+  fn add_object(&mut self, object: Object) -> ObjectId {
+    let id = (self.next_id, 0);
+    self.next_id += 1;
+    self.objects.push((id, object));
+    id
+  }
+
+  fn rewrite(
+    &mut self,
+    content: &[u8],
+    resources: Option<&'a Dictionary>,
+    mut ctm: Matrix,
+    mut state: TextState,
+  ) -> ContentEdit {
+    let doc = self.doc;
+    let existing = resources.and_then(|r| dict_get_dict(doc, r, b"XObject"));
+    let mut operations = Vec::new();
+    let mut xobjects: Vec<(String, ObjectId)> = Vec::new();
+    let mut changed = false;
+    let mut saved: Vec<(Matrix, TextState)> = Vec::new();
+    let mut tm = Matrix::IDENTITY;
+    let mut tlm = Matrix::IDENTITY;
+    // Open marked-content spans: index of their `BDC` operation (none for `BMC`)
+    // and number of removals when they were opened.
+    let mut spans: Vec<(Option<usize>, u32)> = Vec::new();
+    let mut properties = Vec::new();
+    // Draw an XObject added to the resources instead of the current operation.
+    let draw = |this: &mut Self, xobjects: &mut Vec<(String, ObjectId)>, object: Stream| {
+      let id = this.add_object(Object::Stream(object));
+      let name = (1..)
+        .map(|n| format!("Redacted{n}"))
+        .find(|name| {
+          !existing.is_some_and(|e| e.has(name.as_bytes()))
+            && !xobjects.iter().any(|(added, _)| added == name)
+        })
+        .unwrap_or_default();
+      xobjects.push((name.clone(), id));
+      Operation::new("Do", vec![Object::Name(name.into_bytes())])
+    };
+
+    for op in parse_content(content) {
+      if !self.guard.step() {
+        break;
+      }
+      let number = |i: usize| op.operands.get(i).and_then(as_number);
+      let mut move_line = |tx: f64, ty: f64, tm: &mut Matrix| {
+        tlm = Matrix::translation(tx, ty).then(&tlm);
+        *tm = tlm;
+      };
+      match op.operator.as_str() {
+        "q" => saved.push((ctm, state.clone())),
+        "Q" => {
+          if let Some((saved_ctm, saved_state)) = saved.pop() {
+            ctm = saved_ctm;
+            state = saved_state;
+          }
+        }
+        "cm" => {
+          if let Some(m) = Matrix::from_operands(&op.operands) {
+            ctm = m.then(&ctm);
+          }
+        }
+        "BT" => {
+          tm = Matrix::IDENTITY;
+          tlm = Matrix::IDENTITY;
+        }
+        "BDC" | "BMC" => spans.push((
+          (op.operator == "BDC").then_some(operations.len()),
+          self.removals(),
+        )),
+        "EMC" => {
+          if let Some((Some(index), removals)) = spans.pop() {
+            if self.removals() > removals {
+              properties.extend(strip_replacement_text(&mut operations[index]));
+            }
+          }
+        }
+        "Tf" => {
+          let font = (op.operands.first())
+            .and_then(|n| n.as_name().ok())
+            .and_then(|n| resource(doc, resources, b"Font", n))
+            .and_then(|f| f.as_dict().ok());
+          state.decoder = font.map(|f| self.decoder(f));
+          state.size = number(1).unwrap_or(state.size);
+        }
+        "Tc" => state.char_spacing = number(0).unwrap_or(0.0),
+        "Tw" => state.word_spacing = number(0).unwrap_or(0.0),
+        "Tz" => state.scaling = number(0).unwrap_or(100.0) / 100.0,
+        "TL" => state.leading = number(0).unwrap_or(0.0),
+        "Ts" => state.rise = number(0).unwrap_or(0.0),
+        "Td" | "TD" => {
+          if let (Some(tx), Some(ty)) = (number(0), number(1)) {
+            if op.operator == "TD" {
+              state.leading = -ty;
+            }
+            move_line(tx, ty, &mut tm);
+          }
+        }
+        "Tm" => {
+          if let Some(m) = Matrix::from_operands(&op.operands) {
+            tm = m;
+            tlm = m;
+          }
+        }
+        "T*" => move_line(0.0, -state.leading, &mut tm),
+        "Tj" | "'" | "\"" | "TJ" => {
+          let items = match op.operator.as_str() {
+            "TJ" => match op.operands.first() {
+              Some(Object::Array(items)) => items.clone(),
+              _ => Vec::new(),
+            },
+            "\"" => op.operands.get(2).cloned().into_iter().collect(),
+            _ => op.operands.first().cloned().into_iter().collect(),
+          };
+          if op.operator == "\"" {
+            state.word_spacing = number(0).unwrap_or(state.word_spacing);
+            state.char_spacing = number(1).unwrap_or(state.char_spacing);
+          }
+          if op.operator == "'" || op.operator == "\"" {
+            move_line(0.0, -state.leading, &mut tm);
+          }
+          let (array, removed) = self.show(&items, &mut tm, &state, ctm);
+          if removed > 0 {
+            changed = true;
+            if op.operator == "\"" {
+              operations.push(Operation::new("Tw", vec![state.word_spacing.into()]));
+              operations.push(Operation::new("Tc", vec![state.char_spacing.into()]));
+            }
+            if op.operator == "'" || op.operator == "\"" {
+              operations.push(Operation::new("T*", Vec::new()));
+            }
+            operations.push(Operation::new("TJ", vec![Object::Array(array)]));
+            continue;
+          }
+        }
+        "Do" => {
+          let stream = xobject(doc, resources, &op);
+          match stream.and_then(|s| Some((s, xobject_subtype(doc, s)?))) {
+            Some((image, b"Image")) if self.intersects(ctm.transform_rect(UNIT_SQUARE)) => {
+              changed = true;
+              self.images_redacted += 1;
+              if let Some(redacted) = self.redact_image(image, ctm) {
+                operations.push(draw(self, &mut xobjects, redacted));
+              }
+              continue;
+            }
+            Some((_, b"Form")) => {
+              let Some((form, data)) = self.guard.enter(doc, resources, &op) else {
+                operations.push(op);
+                continue;
+              };
+              let matrix = dict_get(doc, &form.dict, b"Matrix")
+                .and_then(|m| m.as_array().ok())
+                .and_then(|m| Matrix::from_operands(m))
+                .unwrap_or(Matrix::IDENTITY);
+              let form_resources = dict_get_dict(doc, &form.dict, b"Resources").or(resources);
+              let edit = self.rewrite(&data, form_resources, matrix.then(&ctm), state.clone());
+              self.guard.leave();
+              if edit.changed {
+                changed = true;
+                let mut dict = form.dict.clone();
+                dict.remove(b"Filter");
+                dict.remove(b"DecodeParms");
+                let mut form_resources = form_resources.cloned().unwrap_or_default();
+                update_resources(doc, &mut form_resources, &edit);
+                dict.set("Resources", form_resources);
+                let mut copy = Stream::new(dict, edit.content);
+                let _ = copy.compress();
+                operations.push(draw(self, &mut xobjects, copy));
+                continue;
+              }
+            }
+            _ => {}
+          }
+        }
+        "BI" => {
+          // Inline images become XObjects, redacted when inside an area.
+          let [Object::Dictionary(dict), Object::String(data, _)] = &op.operands[..] else {
+            continue;
+          };
+          let mut image = inline_image_stream(dict, data);
+          if let Ok(Object::Name(name)) = image.dict.get(b"ColorSpace") {
+            if let Some(space) = resource(doc, resources, b"ColorSpace", name) {
+              image.dict.set("ColorSpace", space.clone());
+            }
+          }
+          let image = if self.intersects(ctm.transform_rect(UNIT_SQUARE)) {
+            changed = true;
+            self.images_redacted += 1;
+            self.redact_image(&image, ctm)
+          } else {
+            Some(image)
+          };
+          if let Some(image) = image {
+            operations.push(draw(self, &mut xobjects, image));
+          }
+          continue;
+        }
+        _ => {}
+      }
+      operations.push(op);
+    }
+
+    // Spans left open at the end of the stream.
+    for (index, removals) in spans {
+      if let Some(index) = index.filter(|_| self.removals() > removals) {
+        properties.extend(strip_replacement_text(&mut operations[index]));
+      }
+    }
+
+    ContentEdit {
+      content: Content { operations }.encode().unwrap_or_default(),
+      xobjects,
+      properties,
+      changed,
+    }
+  }
+
+  /// Number of glyphs removed and images redacted so far.
+  fn removals(&self) -> u32 {
+    self.glyphs_removed + self.images_redacted
+  }
+
+  fn decoder(&mut self, font: &'a Dictionary) -> Rc<FontDecoder> {
+    let doc = self.doc;
+    self
+      .decoders
+      .entry(font as *const Dictionary)
+      .or_insert_with(|| Rc::new(FontDecoder::new(doc, font)))
+      .clone()
+  }
+
+  /// Show a `TJ` array (or the string of `Tj`) and advance the text matrix.
+  ///
+  /// Returns the array drawing the glyphs outside the areas, with the glyphs
+  /// inside replaced by the equivalent spacing, and the number of glyphs removed.
+  fn show(
+    &mut self,
+    items: &[Object],
+    tm: &mut Matrix,
+    state: &TextState,
+    ctm: Matrix,
+  ) -> (Vec<Object>, u32) {
+    let decoder = state.decoder.clone().unwrap_or(self.fallback.clone());
+    let scale = state.size * state.scaling;
+    let mut array = Vec::new();
+    let mut kept = Vec::new();
+    // Adjustments since the last glyph kept, summed so that the width of each
+    // removed glyph cannot be read back from the array.
+    let mut gap: Option<f64> = None;
+    let mut removed = 0;
+    let flush = |kept: &mut Vec<u8>, array: &mut Vec<Object>| {
+      if !kept.is_empty() {
+        array.push(Object::String(
+          std::mem::take(kept),
+          StringFormat::Hexadecimal,
+        ));
+      }
+    };
+    let flush_gap = |gap: &mut Option<f64>, array: &mut Vec<Object>| {
+      if let Some(adjust) = gap.take() {
+        array.push(Object::Real(adjust as f32));
+      }
+    };
+
+    for item in items {
+      let Object::String(bytes, _) = item else {
+        if let Some(adjust) = as_number(item) {
+          flush(&mut kept, &mut array);
+          *tm = Matrix::translation(-adjust / 1000.0 * scale, 0.0).then(tm);
+          *gap.get_or_insert(0.0) += adjust;
+        }
+        continue;
+      };
+      let code_size = if decoder.is_two_byte() { 2 } else { 1 };
+      for chunk in bytes.chunks(code_size) {
+        let Some(&code) = decoder.codes(chunk).first() else {
+          continue;
+        };
+        let w0 = decoder.width(code) / 1000.0;
+        let glyph_space = Matrix {
+          a: scale,
+          d: state.size,
+          f: state.rise,
+          ..Matrix::IDENTITY
+        };
+        let glyph_box =
+          glyph_space
+            .then(tm)
+            .then(&ctm)
+            .transform_rect([0.0, GLYPH_DESCENT, w0, GLYPH_ASCENT]);
+        let mut spacing = state.char_spacing;
+        if code == 32 && !decoder.is_two_byte() {
+          spacing += state.word_spacing;
+        }
+        let tx = state.scaling * (w0 * state.size + spacing);
+
+        if self.covers(&glyph_box) {
+          removed += 1;
+          flush(&mut kept, &mut array);
+          if scale != 0.0 {
+            *gap.get_or_insert(0.0) -= tx / scale * 1000.0;
+          }
+        } else {
+          flush_gap(&mut gap, &mut array);
+          kept.extend_from_slice(chunk);
+          self.glyphs.push((decoder.decode(code), glyph_box));
+        }
+        *tm = Matrix::translation(tx, 0.0).then(tm);
+      }
+    }
+    flush(&mut kept, &mut array);
+    flush_gap(&mut gap, &mut array);
+    self.glyphs_removed += removed;
+    (array, removed)
+  }
+
+  /// Whether a glyph box lies enough inside an area to be removed.
+  fn covers(&self, glyph: &[f64; 4]) -> bool {
+    let area = (glyph[2] - glyph[0]) * (glyph[3] - glyph[1]);
+    self.areas.iter().any(|a| {
+      if area > 0.0 {
+        overlap(a, glyph) >= MIN_GLYPH_OVERLAP * area
+      } else {
+        let (x, y) = ((glyph[0] + glyph[2]) / 2.0, (glyph[1] + glyph[3]) / 2.0);
+        a[0] <= x && x <= a[2] && a[1] <= y && y <= a[3]
+      }
+    })
+  }
+
+  fn intersects(&self, rect: [f64; 4]) -> bool {
+    self.areas.iter().any(|a| overlap(a, &rect) > 0.0)
+  }
+
+  /// Copy of an image drawn with `ctm`, with the pixels inside the areas painted
+  /// in the fill color, as 8-bit RGB. `None` for images that cannot be decoded.
+  ///
+  /// A soft mask would keep the outline of what was under the areas: it is made
+  /// opaque there, or dropped when it cannot be decoded.
+  fn redact_image(&mut self, stream: &Stream, ctm: Matrix) -> Option<Stream> {
+    let doc = self.doc;
+    let is_mask =
+      dict_get(doc, &stream.dict, b"ImageMask").and_then(|m| m.as_bool().ok()) == Some(true);
+    if is_mask {
+      return None;
+    }
+    let mut pixels = decode_image(doc, stream)?.to_rgb8();
+    let inverse = ctm.inverse()?;
+    let color = image::Rgb(self.fill.map(|c| (c.clamp(0.0, 1.0) * 255.0).round() as u8));
+    for [x0, y0, x1, y1] in self.pixel_regions(&inverse, pixels.width(), pixels.height()) {
+      for y in y0..y1 {
+        for x in x0..x1 {
+          pixels.put_pixel(x, y, color);
+        }
+      }
+    }
+
+    let mut dict = Dictionary::from_iter([
+      ("Type", Object::Name(b"XObject".to_vec())),
+      ("Subtype", Object::Name(b"Image".to_vec())),
+      ("Width", Object::Integer(pixels.width() as i64)),
+      ("Height", Object::Integer(pixels.height() as i64)),
+      ("ColorSpace", Object::Name(b"DeviceRGB".to_vec())),
+      ("BitsPerComponent", Object::Integer(8)),
+    ]);
+    for key in [&b"Interpolate"[..], b"Intent"] {
+      if let Ok(value) = stream.dict.get(key) {
+        dict.set(key, value.clone());
+      }
+    }
+    let soft_mask = dict_get(doc, &stream.dict, b"SMask")
+      .and_then(|m| m.as_stream().ok())
+      .and_then(|m| decode_image(doc, m));
+    if let Some(soft_mask) = soft_mask {
+      let mut alpha = soft_mask.to_luma8();
+      for [x0, y0, x1, y1] in self.pixel_regions(&inverse, alpha.width(), alpha.height()) {
+        for y in y0..y1 {
+          for x in x0..x1 {
+            alpha.put_pixel(x, y, image::Luma([255]));
+          }
+        }
+      }
+      let mask_dict = Dictionary::from_iter([
+        ("Type", Object::Name(b"XObject".to_vec())),
+        ("Subtype", Object::Name(b"Image".to_vec())),
+        ("Width", Object::Integer(alpha.width() as i64)),
+        ("Height", Object::Integer(alpha.height() as i64)),
+        ("ColorSpace", Object::Name(b"DeviceGray".to_vec())),
+        ("BitsPerComponent", Object::Integer(8)),
+      ]);
+      let mut mask = Stream::new(mask_dict, alpha.into_raw());
+      let _ = mask.compress();
+      dict.set(
+        "SMask",
+        Object::Reference(self.add_object(Object::Stream(mask))),
+      );
+    }
+    let mut image = Stream::new(dict, pixels.into_raw());
+    let _ = image.compress();
+    Some(image)
+  }
+
+  /// Pixel rectangles `[x0, y0, x1, y1)` inside the areas of an image of `width` ×
+  /// `height` pixels, drawn with the inverse of `inverse`.
+  fn pixel_regions(&self, inverse: &Matrix, width: u32, height: u32) -> Vec<[u32; 4]> {
+    let (width, height) = (width as f64, height as f64);
+    self
+      .areas
+      .iter()
+      .map(|area| {
+        // Image space: the unit square, with rows from the top.
+        let [u0, v0, u1, v1] = inverse.transform_rect(*area).map(|v| v.clamp(0.0, 1.0));
+        [
+          (u0 * width).floor() as u32,
+          ((1.0 - v1) * height).floor() as u32,
+          (u1 * width).ceil() as u32,
+          ((1.0 - v0) * height).ceil() as u32,
+        ]
+      })
+      .collect()
+  }
+}
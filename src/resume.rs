@@ -0,0 +1,851 @@
+use std::collections::HashMap;
+
+use napi::bindgen_prelude::Uint8Array;
+use napi::{Error, Status};
+use napi_derive::napi;
+
+use crate::embedded_fonts::FontFamily;
+use crate::html::parse_color;
+use crate::image::load_image_with_orientation;
+use crate::pdf::catch_unwind_silently;
+use crate::typeset::{
+  render_pdf, Align, Block, PageSetup, Paragraph, Picture, Rgb, Run, TextStyle,
+};
+use crate::utils::map_image_error;
+
+/// Sections of a JSON Resume, in their default order.
+const SECTIONS: &[&str] = &[
+  "summary",
+  "work",
+  "projects",
+  "education",
+  "skills",
+  "languages",
+  "certificates",
+  "awards",
+  "publications",
+  "volunteer",
+  "interests",
+  "references",
+];
+
+/// Contact details and summary of the candidate (`basics` of JSON Resume).
+#[napi(object)]
+#[derive(Default)]
+pub struct ResumeBasics {
+  pub name: Option<String>,
+  /// Job title, e.g. "Développeuse Rust"
+  pub label: Option<String>,
+  pub email: Option<String>,
+  pub phone: Option<String>,
+  pub url: Option<String>,
+  pub summary: Option<String>,
+  pub location: Option<ResumeLocation>,
+  pub profiles: Option<Vec<ResumeProfile>>,
+}
+
+#[napi(object)]
+pub struct ResumeLocation {
+  pub address: Option<String>,
+  pub postal_code: Option<String>,
+  pub city: Option<String>,
+  pub country_code: Option<String>,
+  pub region: Option<String>,
+}
+
+/// A social network profile (`basics.profiles`).
+#[napi(object)]
+pub struct ResumeProfile {
+  pub network: Option<String>,
+  pub username: Option<String>,
+  pub url: Option<String>,
+}
+
+/// A job (`work`) or a volunteering position (`volunteer`, where the
+/// organization is in `organization` rather than `name`).
+#[napi(object)]
+pub struct ResumeWork {
+  pub name: Option<String>,
+  pub organization: Option<String>,
+  pub position: Option<String>,
+  pub location: Option<String>,
+  pub url: Option<String>,
+  pub start_date: Option<String>,
+  pub end_date: Option<String>,
+  pub summary: Option<String>,
+  pub highlights: Option<Vec<String>>,
+}
+
+#[napi(object)]
+pub struct ResumeEducation {
+  pub institution: Option<String>,
+  pub url: Option<String>,
+  pub area: Option<String>,
+  pub study_type: Option<String>,
+  pub start_date: Option<String>,
+  pub end_date: Option<String>,
+  pub score: Option<String>,
+  pub courses: Option<Vec<String>>,
+}
+
+#[napi(object)]
+pub struct ResumeProject {
+  pub name: Option<String>,
+  pub description: Option<String>,
+  pub highlights: Option<Vec<String>>,
+  pub keywords: Option<Vec<String>>,
+  pub start_date: Option<String>,
+  pub end_date: Option<String>,
+  pub url: Option<String>,
+  pub roles: Option<Vec<String>>,
+  pub entity: Option<String>,
+}
+
+/// A skill (`skills`) or an interest (`interests`).
+#[napi(object)]
+pub struct ResumeSkill {
+  pub name: Option<String>,
+  pub level: Option<String>,
+  pub keywords: Option<Vec<String>>,
+}
+
+#[napi(object)]
+pub struct ResumeLanguage {
+  pub language: Option<String>,
+  pub fluency: Option<String>,
+}
+
+#[napi(object)]
+pub struct ResumeCertificate {
+  pub name: Option<String>,
+  pub date: Option<String>,
+  pub issuer: Option<String>,
+  pub url: Option<String>,
+}
+
+#[napi(object)]
+pub struct ResumeAward {
+  pub title: Option<String>,
+  pub date: Option<String>,
+  pub awarder: Option<String>,
+  pub summary: Option<String>,
+}
+
+#[napi(object)]
+pub struct ResumePublication {
+  pub name: Option<String>,
+  pub publisher: Option<String>,
+  pub release_date: Option<String>,
+  pub url: Option<String>,
+  pub summary: Option<String>,
+}
+
+#[napi(object)]
+pub struct ResumeReference {
+  pub name: Option<String>,
+  pub reference: Option<String>,
+}
+
+/// A CV in the JSON Resume format (https://jsonresume.org/schema), as parsed with
+/// `JSON.parse`. Every field is optional; unknown fields are ignored.
+#[napi(object)]
+#[derive(Default)]
+pub struct JsonResume {
+  pub basics: Option<ResumeBasics>,
+  pub work: Option<Vec<ResumeWork>>,
+  pub volunteer: Option<Vec<ResumeWork>>,
+  pub education: Option<Vec<ResumeEducation>>,
+  pub awards: Option<Vec<ResumeAward>>,
+  pub certificates: Option<Vec<ResumeCertificate>>,
+  pub publications: Option<Vec<ResumePublication>>,
+  pub skills: Option<Vec<ResumeSkill>>,
+  pub languages: Option<Vec<ResumeLanguage>>,
+  pub interests: Option<Vec<ResumeSkill>>,
+  pub references: Option<Vec<ResumeReference>>,
+  pub projects: Option<Vec<ResumeProject>>,
+}
+
+/// Look of a PDF generated by `render_resume_pdf`.
+///
+/// - `font`, `bold_font`, `italic_font`, `bold_italic_font`: TrueType fonts
+///   (`.ttf`) to embed; missing styles fall back to the regular font (default:
+///   DejaVu Sans)
+/// - `font_size`: Body text size in points (default: 10); the name and headings
+///   are sized from it
+/// - `primary_color`: Color of the name, the section headings and their rules, as
+///   `#rrggbb`, `rgb(…)` or a color name (default: "#1f3864")
+/// - `text_color`: Body text color (default: "#222222")
+/// - `muted_color`: Color of dates, places and contact details (default: "#666666")
+/// - `section_order`: Sections to show, in order, among "summary", "work",
+///   "projects", "education", "skills", "languages", "certificates", "awards",
+///   "publications", "volunteer", "interests" and "references" (default: all, in
+///   this order)
+/// - `section_titles`: Titles replacing the default ones, by section name
+/// - `language`: Language of the default titles and dates, "en" or "fr" (default: "en")
+/// - `logo`: Image (PNG, JPEG, WebP) shown at the top right of the first page
+/// - `logo_width`: Width of the logo in points (default: 90)
+/// - `page_size`: "A4" or "Letter" (default: "A4")
+/// - `anonymize`: Leave out the name, contact details, location, profiles and
+///   references (default: false)
+#[napi(object)]
+#[derive(Default)]
+pub struct ResumeTemplate {
+  pub font: Option<Uint8Array>,
+  pub bold_font: Option<Uint8Array>,
+  pub italic_font: Option<Uint8Array>,
+  pub bold_italic_font: Option<Uint8Array>,
+  pub font_size: Option<f64>,
+  pub primary_color: Option<String>,
+  pub text_color: Option<String>,
+  pub muted_color: Option<String>,
+  pub section_order: Option<Vec<String>>,
+  pub section_titles: Option<HashMap<String, String>>,
+  pub language: Option<String>,
+  pub logo: Option<Uint8Array>,
+  pub logo_width: Option<f64>,
+  pub page_size: Option<String>,
+  pub anonymize: Option<bool>,
+}
+
+/// Render a JSON Resume to a PDF in the look described by `template`.
+///
+/// The name and title come first, then the contact details and the sections in
+/// the template's order; empty sections are left out. Text is laid out on as many
+/// pages as needed, with the fonts embedded as subsets. Fails with `InvalidArg`
+/// for an invalid color, font, logo, language, page size or section name.
+#[napi]
+pub fn render_resume_pdf(
+  mut resume: JsonResume,
+  template: Option<ResumeTemplate>,
+) -> napi::Result<Vec<u8>> {
+  let template = template.unwrap_or_default();
+  let look = Look::new(&template)?;
+  let page = match template.page_size.as_deref() {
+    None => PageSetup::default(),
+    Some(size) if size.eq_ignore_ascii_case("a4") => PageSetup::default(),
+    Some(size) if size.eq_ignore_ascii_case("letter") => PageSetup {
+      width: 612.0,
+      height: 792.0,
+      ..PageSetup::default()
+    },
+    Some(size) => return Err(invalid(format!("Unsupported page size: {size}"))),
+  };
+  let sections = match &template.section_order {
+    Some(order) => order
+      .iter()
+      .map(|name| {
+        SECTIONS
+          .iter()
+          .find(|s| s.eq_ignore_ascii_case(name.trim()))
+          .copied()
+          .ok_or_else(|| invalid(format!("Unknown resume section: {name}")))
+      })
+      .collect::<napi::Result<Vec<_>>>()?,
+    None => SECTIONS.to_vec(),
+  };
+  let fonts = match &template.font {
+    Some(regular) => FontFamily::from_fonts(
+      regular,
+      template.bold_font.as_deref(),
+      template.italic_font.as_deref(),
+      template.bold_italic_font.as_deref(),
+    )
+    .map_err(|e| invalid(format!("Invalid font: {e}")))?,
+    None => FontFamily::dejavu_sans(),
+  };
+  let logo = match &template.logo {
+    Some(logo) => {
+      load_image_with_orientation(logo).map_err(map_image_error)?;
+      Some(Picture {
+        data: logo.to_vec(),
+        width: Some(template.logo_width.unwrap_or(90.0).clamp(8.0, page.width)),
+        height: None,
+        align: Align::Right,
+      })
+    }
+    None => None,
+  };
+
+  let mut writer = ResumeWriter {
+    look: &look,
+    blocks: Vec::new(),
+  };
+  if let Some(logo) = logo {
+    writer.blocks.push(Block::Picture(logo));
+  }
+  let basics = resume.basics.take().unwrap_or_default();
+  writer.header(&basics, template.anonymize.unwrap_or(false));
+  for section in sections {
+    let entries = resume_section(&resume, &basics, section);
+    let dates = section_dates(&resume, section, &look);
+    writer.section(section, &entries, &dates, &template);
+  }
+  let blocks = writer.blocks;
+
+  catch_unwind_silently(|| render_pdf(&blocks, &page, &fonts)).map_err(|e| {
+    Error::new(
+      Status::GenericFailure,
+      format!("Failed to render resume: {e}"),
+    )
+  })
+}
+
+fn invalid(message: String) -> Error {
+  Error::new(Status::InvalidArg, message)
+}
+
+/// Colors, sizes and language of a template.
+struct Look {
+  size: f64,
+  primary: Rgb,
+  text: Rgb,
+  muted: Rgb,
+  french: bool,
+}
+
+impl Look {
+  fn new(template: &ResumeTemplate) -> napi::Result<Look> {
+    let color = |value: &Option<String>, default: Rgb| match value {
+      Some(value) => parse_color(value).ok_or_else(|| invalid(format!("Invalid color: {value}"))),
+      None => Ok(default),
+    };
+    let french = match template.language.as_deref() {
+      None => false,
+      Some(language) if language.eq_ignore_ascii_case("en") => false,
+      Some(language) if language.eq_ignore_ascii_case("fr") => true,
+      Some(language) => return Err(invalid(format!("Unsupported language: {language}"))),
+    };
+    Ok(Look {
+      size: template.font_size.unwrap_or(10.0).clamp(6.0, 24.0),
+      primary: color(&template.primary_color, [0.12, 0.22, 0.39])?,
+      text: color(&template.text_color, [0.13, 0.13, 0.13])?,
+      muted: color(&template.muted_color, [0.4, 0.4, 0.4])?,
+      french,
+    })
+  }
+
+  fn style(&self) -> TextStyle {
+    TextStyle {
+      size: self.size,
+      color: self.text,
+      ..TextStyle::default()
+    }
+  }
+
+  fn bold(&self) -> TextStyle {
+    TextStyle {
+      bold: true,
+      ..self.style()
+    }
+  }
+
+  fn muted(&self) -> TextStyle {
+    TextStyle {
+      size: self.size * 0.92,
+      color: self.muted,
+      ..TextStyle::default()
+    }
+  }
+
+  fn title(&self, section: &str) -> &'static str {
+    let (en, fr) = match section {
+      "summary" => ("Profile", "Profil"),
+      "work" => ("Experience", "Expérience professionnelle"),
+      "projects" => ("Projects", "Projets"),
+      "education" => ("Education", "Formation"),
+      "skills" => ("Skills", "Compétences"),
+      "languages" => ("Languages", "Langues"),
+      "certificates" => ("Certifications", "Certifications"),
+      "awards" => ("Awards", "Distinctions"),
+      "publications" => ("Publications", "Publications"),
+      "volunteer" => ("Volunteering", "Bénévolat"),
+      "interests" => ("Interests", "Centres d’intérêt"),
+      _ => ("References", "Références"),
+    };
+    if self.french {
+      fr
+    } else {
+      en
+    }
+  }
+
+  /// `2020-03` as "Mar 2020" (or "mars 2020"); other values unchanged.
+  fn date(&self, date: &str) -> String {
+    const EN: [&str; 12] = [
+      "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+    ];
+    const FR: [&str; 12] = [
+      "janv.", "févr.", "mars", "avr.", "mai", "juin", "juil.", "août", "sept.", "oct.", "nov.",
+      "déc.",
+    ];
+    let date = date.trim();
+    let mut parts = date.split('-');
+    let year = parts
+      .next()
+      .filter(|y| y.len() == 4 && y.parse::<u32>().is_ok());
+    let month = parts
+      .next()
+      .and_then(|m| m.parse::<usize>().ok())
+      .filter(|m| (1..=12).contains(m));
+    match (year, month) {
+      (Some(year), Some(month)) => {
+        let names = if self.french { FR } else { EN };
+        format!("{} {year}", names[month - 1])
+      }
+      (Some(year), None) => year.to_string(),
+      _ => date.to_string(),
+    }
+  }
+
+  fn date_range(&self, start: &Option<String>, end: &Option<String>) -> Option<String> {
+    let start = present(start).map(|s| self.date(s));
+    let end = present(end).map(|e| self.date(e));
+    match (start, end) {
+      (Some(start), Some(end)) if start == end => Some(start),
+      (Some(start), Some(end)) => Some(format!("{start} – {end}")),
+      (Some(start), None) => Some(format!(
+        "{start} – {}",
+        if self.french {
+          "aujourd’hui"
+        } else {
+          "present"
+        }
+      )),
+      (None, end) => end,
+    }
+  }
+}
+
+/// A non-blank value, trimmed.
+fn present(value: &Option<String>) -> Option<&str> {
+  value.as_deref().map(str::trim).filter(|v| !v.is_empty())
+}
+
+/// Non-blank values joined with `separator`.
+fn join(values: &[Option<&str>], separator: &str) -> Option<String> {
+  let values: Vec<&str> = values
+    .iter()
+    .flatten()
+    .map(|v| v.trim())
+    .filter(|v| !v.is_empty())
+    .collect();
+  (!values.is_empty()).then(|| values.join(separator))
+}
+
+/// An entry of a section: a bold title, a line of dates and places, a text and
+/// bullet points.
+#[derive(Default)]
+struct Entry {
+  title: Option<String>,
+  /// Text after the title, not bold
+  detail: Option<String>,
+  meta: Option<String>,
+  text: Option<String>,
+  bullets: Vec<String>,
+}
+
+/// Entries of `section` in `resume`.
+fn resume_section(resume: &JsonResume, basics: &ResumeBasics, section: &str) -> Vec<Entry> {
+  let text = |t: &Option<String>| present(t).map(str::to_string);
+  let list = |items: &Option<Vec<String>>| {
+    items
+      .iter()
+      .flatten()
+      .map(|i| i.trim().to_string())
+      .filter(|i| !i.is_empty())
+      .collect::<Vec<_>>()
+  };
+  match section {
+    "summary" => present(&basics.summary)
+      .map(|summary| Entry {
+        text: Some(summary.to_string()),
+        ..Entry::default()
+      })
+      .into_iter()
+      .collect(),
+    "work" | "volunteer" => {
+      let jobs = if section == "work" {
+        &resume.work
+      } else {
+        &resume.volunteer
+      };
+      jobs
+        .iter()
+        .flatten()
+        .map(|job| Entry {
+          title: text(&job.position),
+          detail: text(&job.name).or_else(|| text(&job.organization)),
+          meta: join(&[present(&job.location), present(&job.url)], " · "),
+          text: text(&job.summary),
+          bullets: list(&job.highlights),
+        })
+        .collect()
+    }
+    "education" => resume
+      .education
+      .iter()
+      .flatten()
+      .map(|study| Entry {
+        title: join(&[present(&study.study_type), present(&study.area)], ", "),
+        detail: text(&study.institution),
+        meta: present(&study.score).map(str::to_string),
+        text: None,
+        bullets: list(&study.courses),
+      })
+      .collect(),
+    "projects" => resume
+      .projects
+      .iter()
+      .flatten()
+      .map(|project| Entry {
+        title: text(&project.name),
+        detail: join(
+          &[
+            Some(list(&project.roles).join(", ").as_str()),
+            present(&project.entity),
+          ],
+          ", ",
+        ),
+        meta: present(&project.url).map(str::to_string),
+        text: join(
+          &[
+            present(&project.description),
+            Some(list(&project.keywords).join(", ").as_str()),
+          ],
+          "\n",
+        ),
+        bullets: list(&project.highlights),
+      })
+      .collect(),
+    "skills" | "interests" => {
+      let skills = if section == "skills" {
+        &resume.skills
+      } else {
+        &resume.interests
+      };
+      skills
+        .iter()
+        .flatten()
+        .filter_map(|skill| {
+          let keywords = list(&skill.keywords).join(", ");
+          let detail = join(&[present(&skill.level), Some(keywords.as_str())], " · ");
+          (skill.name.is_some() || detail.is_some()).then(|| Entry {
+            title: text(&skill.name),
+            detail,
+            ..Entry::default()
+          })
+        })
+        .collect()
+    }
+    "languages" => resume
+      .languages
+      .iter()
+      .flatten()
+      .map(|language| Entry {
+        title: text(&language.language),
+        detail: text(&language.fluency),
+        ..Entry::default()
+      })
+      .collect(),
+    "certificates" => resume
+      .certificates
+      .iter()
+      .flatten()
+      .map(|certificate| Entry {
+        title: text(&certificate.name),
+        detail: text(&certificate.issuer),
+        meta: text(&certificate.url),
+        ..Entry::default()
+      })
+      .collect(),
+    "awards" => resume
+      .awards
+      .iter()
+      .flatten()
+      .map(|award| Entry {
+        title: text(&award.title),
+        detail: text(&award.awarder),
+        text: text(&award.summary),
+        ..Entry::default()
+      })
+      .collect(),
+    "publications" => resume
+      .publications
+      .iter()
+      .flatten()
+      .map(|publication| Entry {
+        title: text(&publication.name),
+        detail: text(&publication.publisher),
+        meta: text(&publication.url),
+        text: text(&publication.summary),
+        ..Entry::default()
+      })
+      .collect(),
+    _ => resume
+      .references
+      .iter()
+      .flatten()
+      .map(|reference| Entry {
+        title: text(&reference.name),
+        text: text(&reference.reference),
+        ..Entry::default()
+      })
+      .collect(),
+  }
+}
+
+/// Dates of the entries of `section`, formatted for `look`.
+fn section_dates(resume: &JsonResume, section: &str, look: &Look) -> Vec<Option<String>> {
+  let single = |date: &Option<String>| present(date).map(|d| look.date(d));
+  match section {
+    "work" => (resume.work.iter().flatten())
+      .map(|job| look.date_range(&job.start_date, &job.end_date))
+      .collect(),
+    "volunteer" => (resume.volunteer.iter().flatten())
+      .map(|job| look.date_range(&job.start_date, &job.end_date))
+      .collect(),
+    "education" => (resume.education.iter().flatten())
+      .map(|study| look.date_range(&study.start_date, &study.end_date))
+      .collect(),
+    "projects" => (resume.projects.iter().flatten())
+      .map(|project| look.date_range(&project.start_date, &project.end_date))
+      .collect(),
+    "certificates" => (resume.certificates.iter().flatten())
+      .map(|certificate| single(&certificate.date))
+      .collect(),
+    "awards" => (resume.awards.iter().flatten())
+      .map(|award| single(&award.date))
+      .collect(),
+    "publications" => (resume.publications.iter().flatten())
+      .map(|publication| single(&publication.release_date))
+      .collect(),
+    _ => Vec::new(),
+  }
+}
+
+struct ResumeWriter<'l> {
+  look: &'l Look,
+  blocks: Vec<Block>,
+}
+
+impl ResumeWriter<'_> {
+  fn paragraph(&mut self, runs: Vec<Run>, paragraph: Paragraph) {
+    if runs.iter().any(|run| !run.text.trim().is_empty()) {
+      self
+        .blocks
+        .push(Block::Paragraph(Paragraph { runs, ..paragraph }));
+    }
+  }
+
+  /// Name, title and contact details.
+  fn header(&mut self, basics: &ResumeBasics, anonymize: bool) {
+    let look = self.look;
+    if !anonymize {
+      if let Some(name) = present(&basics.name) {
+        let run = Run {
+          text: name.to_string(),
+          style: TextStyle {
+            bold: true,
+            size: look.size * 2.2,
+            color: look.primary,
+            ..TextStyle::default()
+          },
+        };
+        self.paragraph(
+          vec![run],
+          Paragraph {
+            space_after: 2.0,
+            ..Paragraph::default()
+          },
+        );
+      }
+    }
+    if let Some(label) = present(&basics.label) {
+      let run = Run {
+        text: label.to_string(),
+        style: TextStyle {
+          size: look.size * 1.3,
+          color: look.muted,
+          ..TextStyle::default()
+        },
+      };
+      self.paragraph(
+        vec![run],
+        Paragraph {
+          space_after: 4.0,
+          ..Paragraph::default()
+        },
+      );
+    }
+    if anonymize {
+      return;
+    }
+
+    let location = basics.location.as_ref().and_then(|location| {
+      join(
+        &[
+          present(&location.address),
+          join(
+            &[present(&location.postal_code), present(&location.city)],
+            " ",
+          )
+          .as_deref(),
+          present(&location.region),
+          present(&location.country_code),
+        ],
+        ", ",
+      )
+    });
+    let profiles: Vec<String> = (basics.profiles.iter().flatten())
+      .filter_map(|profile| {
+        present(&profile.url).map(str::to_string).or_else(|| {
+          join(
+            &[present(&profile.network), present(&profile.username)],
+            ": ",
+          )
+        })
+      })
+      .collect();
+    let mut contact = vec![
+      present(&basics.email),
+      present(&basics.phone),
+      present(&basics.url),
+      location.as_deref(),
+    ];
+    contact.extend(profiles.iter().map(|p| Some(p.as_str())));
+    if let Some(contact) = join(&contact, "  ·  ") {
+      self.paragraph(
+        vec![Run {
+          text: contact,
+          style: look.muted(),
+        }],
+        Paragraph {
+          space_after: 6.0,
+          ..Paragraph::default()
+        },
+      );
+    }
+  }
+
+  fn section(
+    &mut self,
+    section: &str,
+    entries: &[Entry],
+    dates: &[Option<String>],
+    template: &ResumeTemplate,
+  ) {
+    if entries.is_empty() || (section == "references" && template.anonymize.unwrap_or(false)) {
+      return;
+    }
+    let look = self.look;
+    let title = template
+      .section_titles
+      .as_ref()
+      .and_then(|titles| titles.get(section))
+      .map_or_else(|| look.title(section).to_string(), |t| t.clone());
+    self.paragraph(
+      vec![Run {
+        text: title.to_uppercase(),
+        style: TextStyle {
+          bold: true,
+          size: look.size * 1.15,
+          color: look.primary,
+          ..TextStyle::default()
+        },
+      }],
+      Paragraph {
+        space_before: 14.0,
+        space_after: 6.0,
+        keep_with_next: true,
+        border_bottom: Some(look.primary),
+        outline_level: Some(0),
+        ..Paragraph::default()
+      },
+    );
+
+    for (i, entry) in entries.iter().enumerate() {
+      self.entry(entry, dates.get(i).and_then(|d| d.as_deref()));
+    }
+  }
+
+  fn entry(&mut self, entry: &Entry, dates: Option<&str>) {
+    let look = self.look;
+    let mut runs = Vec::new();
+    if let Some(title) = &entry.title {
+      runs.push(Run {
+        text: title.clone(),
+        style: look.bold(),
+      });
+    }
+    if let Some(detail) = &entry.detail {
+      let separator = if entry.title.is_some() { " — " } else { "" };
+      runs.push(Run {
+        text: format!("{separator}{detail}"),
+        style: look.style(),
+      });
+    }
+    let has_more = entry.text.is_some() || !entry.bullets.is_empty();
+    let meta = join(&[dates, entry.meta.as_deref()], "  ·  ");
+    self.paragraph(
+      runs,
+      Paragraph {
+        space_before: 4.0,
+        space_after: if meta.is_some() || has_more { 1.0 } else { 2.0 },
+        keep_with_next: meta.is_some() || has_more,
+        ..Paragraph::default()
+      },
+    );
+    if let Some(meta) = meta {
+      self.paragraph(
+        vec![Run {
+          text: meta,
+          style: TextStyle {
+            italic: true,
+            ..look.muted()
+          },
+        }],
+        Paragraph {
+          space_after: 2.0,
+          keep_with_next: has_more,
+          ..Paragraph::default()
+        },
+      );
+    }
+    if let Some(text) = &entry.text {
+      for line in text.lines() {
+        self.paragraph(
+          vec![Run {
+            text: line.to_string(),
+            style: look.style(),
+          }],
+          Paragraph {
+            space_after: 2.0,
+            align: Align::Justify,
+            ..Paragraph::default()
+          },
+        );
+      }
+    }
+    for bullet in &entry.bullets {
+      self.paragraph(
+        vec![Run {
+          text: bullet.clone(),
+          style: look.style(),
+        }],
+        Paragraph {
+          marker: Some(Run {
+            text: "•".to_string(),
+            style: TextStyle {
+              color: look.primary,
+              ..look.style()
+            },
+          }),
+          indent_left: 16.0,
+          first_line_indent: -10.0,
+          space_after: 1.0,
+          ..Paragraph::default()
+        },
+      );
+    }
+  }
+}
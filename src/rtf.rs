@@ -0,0 +1,440 @@
+use encoding_rs::{Encoding, WINDOWS_1252};
+
+use crate::typeset::{trim_runs, Block, Paragraph, Run, Table, TableCell, TextStyle};
+
+/// Maximum nesting of groups, against malformed input.
+const MAX_GROUP_DEPTH: usize = 256;
+
+/// Destinations whose text is not part of the document body.
+const SKIPPED_DESTINATIONS: &[&str] = &[
+  "fonttbl",
+  "colortbl",
+  "stylesheet",
+  "info",
+  "pict",
+  "object",
+  "header",
+  "headerl",
+  "headerr",
+  "headerf",
+  "footer",
+  "footerl",
+  "footerr",
+  "footerf",
+  "footnote",
+  "annotation",
+  "fldinst",
+  "themedata",
+  "colorschememapping",
+  "datastore",
+  "latentstyles",
+  "listtable",
+  "listoverridetable",
+  "rsidtbl",
+  "generator",
+  "xmlnstbl",
+  "filetbl",
+  "revtbl",
+  "pgdsctbl",
+  "bkmkstart",
+  "bkmkend",
+];
+
+/// Destination the text of the current group goes to.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Destination {
+  Body,
+  /// Rendered list number or bullet (`\listtext`, `\pntext`)
+  ListText,
+  Skip,
+}
+
+/// State of a group.
+#[derive(Clone, Debug)]
+struct GroupState {
+  destination: Destination,
+  /// Number of fallback characters written after `\uN` (`\ucN`)
+  unicode_skip: usize,
+  /// Fallback characters still to skip
+  pending_skip: usize,
+}
+
+impl Default for GroupState {
+  fn default() -> Self {
+    GroupState {
+      destination: Destination::Body,
+      unicode_skip: 1,
+      pending_skip: 0,
+    }
+  }
+}
+
+/// Text of an RTF document: paragraphs with their list markers, and tables.
+///
+/// Text in `\'hh` escapes is decoded in the document code page (`\ansicpg`,
+/// Windows-1252 by default); `\uN` characters are read as Unicode. Headers,
+/// footers, footnotes, field instructions, pictures and formatting are left out.
+pub(crate) fn read_rtf(bytes: &[u8]) -> Result<Vec<Block>, String> {
+  let start = bytes
+    .iter()
+    .position(|b| !b.is_ascii_whitespace())
+    .unwrap_or(0);
+  if !bytes[start..].starts_with(b"{\\rtf") {
+    return Err("not an RTF document".to_string());
+  }
+  let mut reader = RtfReader::new();
+  reader.read(&bytes[start..]);
+  Ok(reader.finish())
+}
+
+struct RtfReader {
+  encoding: &'static Encoding,
+  groups: Vec<GroupState>,
+  group: GroupState,
+  /// Whether the current paragraph is in a table (`\intbl`), until `\pard`
+  in_table: bool,
+  /// Bytes of `\'hh` escapes and text not decoded yet
+  pending: Vec<u8>,
+  text: String,
+  marker: String,
+  blocks: Vec<Block>,
+  cell_blocks: Vec<Block>,
+  row: Vec<TableCell>,
+  rows: Vec<Vec<TableCell>>,
+}
+
+impl RtfReader {
+  fn new() -> Self {
+    RtfReader {
+      encoding: WINDOWS_1252,
+      groups: Vec::new(),
+      group: GroupState::default(),
+      in_table: false,
+      pending: Vec::new(),
+      text: String::new(),
+      marker: String::new(),
+      blocks: Vec::new(),
+      cell_blocks: Vec::new(),
+      row: Vec::new(),
+      rows: Vec::new(),
+    }
+  }
+
+  fn read(&mut self, bytes: &[u8]) {
+    let mut i = 0;
+    // Whether the group just opened, for `\*` and destination control words
+    let mut group_start = false;
+    while i < bytes.len() {
+      let b = bytes[i];
+      match b {
+        b'{' => {
+          self.flush_text();
+          if self.groups.len() < MAX_GROUP_DEPTH {
+            self.groups.push(self.group.clone());
+          }
+          group_start = true;
+          i += 1;
+          continue;
+        }
+        b'}' => {
+          self.flush_text();
+          if let Some(group) = self.groups.pop() {
+            self.group = group;
+          }
+          i += 1;
+        }
+        b'\\' => {
+          let (token, next) = control(bytes, i);
+          i = next;
+          self.control(token, group_start);
+        }
+        b'\r' | b'\n' => i += 1,
+        _ => {
+          self.text_byte(b);
+          i += 1;
+        }
+      }
+      group_start = false;
+    }
+  }
+
+  fn text_byte(&mut self, b: u8) {
+    if self.group.unicode_skip_pending() {
+      return;
+    }
+    match self.group.destination {
+      Destination::Skip => {}
+      Destination::Body | Destination::ListText => self.pending.push(b),
+    }
+  }
+
+  fn control(&mut self, token: Control, group_start: bool) {
+    match token {
+      Control::Hex(byte) => self.text_byte(byte),
+      Control::Symbol(symbol) => match symbol {
+        b'*' if group_start => self.group.destination = Destination::Skip,
+        b'~' => self.text("\u{A0}"),
+        b'_' => self.text("\u{2011}"),
+        b'\\' | b'{' | b'}' => self.text_byte(symbol),
+        b'\n' | b'\r' => self.end_paragraph(),
+        b'\t' => self.text("    "),
+        _ => {}
+      },
+      Control::Word(word, param) => self.control_word(word, param, group_start),
+    }
+  }
+
+  fn control_word(&mut self, word: &str, param: Option<i32>, group_start: bool) {
+    let skipping = self.group.destination == Destination::Skip;
+    match word {
+      "u" => {
+        if let Some(n) = param {
+          let code = if n < 0 { n + 65536 } else { n } as u32;
+          if let Some(c) = char::from_u32(code) {
+            self.text(&c.to_string());
+          }
+          self.group.pending_skip = self.group.unicode_skip;
+        }
+        return;
+      }
+      "uc" => {
+        self.group.unicode_skip = param.unwrap_or(1).clamp(0, 16) as usize;
+        return;
+      }
+      _ => {}
+    }
+    // Any other control word ends the fallback text of `\uN`.
+    self.group.pending_skip = 0;
+
+    if group_start && SKIPPED_DESTINATIONS.contains(&word) {
+      self.flush_text();
+      self.group.destination = Destination::Skip;
+      return;
+    }
+    if skipping {
+      return;
+    }
+    match word {
+      "ansicpg" => {
+        if let Some(encoding) = param.and_then(codepage_encoding) {
+          self.encoding = encoding;
+        }
+      }
+      "mac" => self.encoding = encoding_rs::MACINTOSH,
+      "listtext" | "pntext" if group_start => {
+        self.flush_text();
+        self.group.destination = Destination::ListText;
+      }
+      "par" | "sect" | "page" => self.end_paragraph(),
+      "line" => self.text("\n"),
+      "tab" => self.text("    "),
+      "cell" | "nestcell" => self.end_cell(),
+      "row" | "nestrow" => self.end_row(),
+      "pard" => {
+        self.flush_text();
+        self.in_table = false;
+      }
+      "intbl" => self.in_table = true,
+      "emdash" => self.text("—"),
+      "endash" => self.text("–"),
+      "bullet" => self.text("•"),
+      "lquote" => self.text("‘"),
+      "rquote" => self.text("’"),
+      "ldblquote" => self.text("“"),
+      "rdblquote" => self.text("”"),
+      "emspace" | "enspace" | "qmspace" => self.text(" "),
+      _ => {}
+    }
+  }
+
+  fn text(&mut self, text: &str) {
+    if self.group.unicode_skip_pending() {
+      return;
+    }
+    match self.group.destination {
+      Destination::Body | Destination::ListText => {
+        self.flush_text();
+        self.push_text(text);
+      }
+      _ => {}
+    }
+  }
+
+  /// Decode the pending bytes in the document code page.
+  fn flush_text(&mut self) {
+    if self.pending.is_empty() {
+      return;
+    }
+    let bytes = std::mem::take(&mut self.pending);
+    let (text, _) = self.encoding.decode_without_bom_handling(&bytes);
+    self.push_text(&text);
+  }
+
+  fn push_text(&mut self, text: &str) {
+    if self.group.destination == Destination::ListText {
+      self.marker.push_str(text);
+      return;
+    }
+    self.text.push_str(text);
+  }
+
+  fn end_paragraph(&mut self) {
+    self.flush_text();
+    let mut runs = vec![Run {
+      text: std::mem::take(&mut self.text),
+      style: TextStyle::default(),
+    }];
+    trim_runs(&mut runs);
+    let marker = std::mem::take(&mut self.marker);
+    let marker = marker.trim();
+    let paragraph = Paragraph {
+      marker: (!marker.is_empty()).then(|| Run {
+        text: bullet(marker),
+        style: TextStyle::default(),
+      }),
+      runs,
+      ..Paragraph::default()
+    };
+    if self.in_table {
+      self.cell_blocks.push(Block::Paragraph(paragraph));
+    } else {
+      self.flush_table();
+      self.blocks.push(Block::Paragraph(paragraph));
+    }
+  }
+
+  fn end_cell(&mut self) {
+    self.flush_text();
+    if !self.text.is_empty() || !self.marker.is_empty() {
+      self.in_table = true;
+      self.end_paragraph();
+    }
+    self.row.push(TableCell {
+      blocks: std::mem::take(&mut self.cell_blocks),
+      span: 1,
+      background: None,
+    });
+  }
+
+  fn end_row(&mut self) {
+    self.flush_text();
+    if !self.text.is_empty() {
+      self.end_cell();
+    }
+    let row = std::mem::take(&mut self.row);
+    if !row.is_empty() {
+      self.rows.push(row);
+    }
+  }
+
+  fn flush_table(&mut self) {
+    if !self.row.is_empty() {
+      let row = std::mem::take(&mut self.row);
+      self.rows.push(row);
+    }
+    if self.rows.is_empty() {
+      return;
+    }
+    self.blocks.push(Block::Table(Table {
+      columns: Vec::new(),
+      rows: std::mem::take(&mut self.rows),
+      borders: None,
+      indent_left: 0.0,
+    }));
+  }
+
+  fn finish(mut self) -> Vec<Block> {
+    self.flush_text();
+    if !self.text.is_empty() {
+      self.end_paragraph();
+    }
+    self.flush_table();
+    self.blocks
+  }
+}
+
+impl GroupState {
+  fn unicode_skip_pending(&mut self) -> bool {
+    if self.pending_skip > 0 {
+      self.pending_skip -= 1;
+      true
+    } else {
+      false
+    }
+  }
+}
+
+/// Bullets set in the Symbol font (`\'b7`) decode as a middle dot.
+fn bullet(marker: &str) -> String {
+  match marker {
+    "·" | "\u{F0B7}" => "•".to_string(),
+    "o" => "◦".to_string(),
+    "§" | "\u{F0A7}" => "▪".to_string(),
+    _ => marker.to_string(),
+  }
+}
+
+enum Control<'a> {
+  /// Control word with its numeric parameter
+  Word(&'a str, Option<i32>),
+  /// `\'hh`
+  Hex(u8),
+  /// Control symbol (`\~`, `\*`, `\{`, …)
+  Symbol(u8),
+}
+
+/// Parse the control word or symbol at `start` (a backslash); returns it with the
+/// index of the next byte.
+fn control(bytes: &[u8], start: usize) -> (Control<'_>, usize) {
+  let mut i = start + 1;
+  let Some(&first) = bytes.get(i) else {
+    return (Control::Symbol(b'\\'), i);
+  };
+  if !first.is_ascii_alphabetic() {
+    if first == b'\'' {
+      let hex = bytes
+        .get(i + 1..i + 3)
+        .and_then(|h| std::str::from_utf8(h).ok());
+      if let Some(byte) = hex.and_then(|h| u8::from_str_radix(h, 16).ok()) {
+        return (Control::Hex(byte), i + 3);
+      }
+    }
+    return (Control::Symbol(first), i + 1);
+  }
+  let word_start = i;
+  while i < bytes.len() && bytes[i].is_ascii_alphabetic() && i - word_start < 32 {
+    i += 1;
+  }
+  let word = std::str::from_utf8(&bytes[word_start..i]).unwrap_or("");
+  let number_start = i;
+  if bytes.get(i) == Some(&b'-') {
+    i += 1;
+  }
+  while i < bytes.len() && bytes[i].is_ascii_digit() && i - number_start < 10 {
+    i += 1;
+  }
+  let param = std::str::from_utf8(&bytes[number_start..i])
+    .ok()
+    .and_then(|n| n.parse().ok());
+  // A space delimiting the control word is part of it.
+  if bytes.get(i) == Some(&b' ') {
+    i += 1;
+  }
+  (Control::Word(word, param), i)
+}
+
+/// Encoding of a Windows code page (`\ansicpg`).
+fn codepage_encoding(codepage: i32) -> Option<&'static Encoding> {
+  let label = match codepage {
+    437 | 850 => "windows-1252".to_string(),
+    65001 => "utf-8".to_string(),
+    932 => "shift_jis".to_string(),
+    936 => "gbk".to_string(),
+    949 => "euc-kr".to_string(),
+    950 => "big5".to_string(),
+    10000 => "macintosh".to_string(),
+    874 | 1250..=1258 => format!("windows-{codepage}"),
+    _ => return None,
+  };
+  Encoding::for_label(label.as_bytes())
+}
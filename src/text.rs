@@ -0,0 +1,259 @@
+use std::collections::{HashMap, HashSet};
+
+use napi_derive::napi;
+use unicode_normalization::UnicodeNormalization;
+
+/// Options for the text cleanup stage applied to extracted text.
+///
+/// Every step is enabled by default; set a flag to `false` to skip it.
+/// - `expand_ligatures`: Expand typographic ligatures (`ﬁ`, `ﬂ`, `ﬀ`, …) into plain letters
+/// - `dehyphenate`: Drop soft hyphens and re-join words split across lines with a hyphen
+/// - `normalization`: Unicode normalization form: "nfc", "nfkc" or "none" (default: "nfc")
+/// - `collapse_whitespace`: Collapse repeated spaces and blank lines, trim line ends
+/// - `remove_control_chars`: Remove control characters (newlines and tabs are kept)
+/// - `remove_headers_footers`: Remove lines repeated at the top or bottom of most pages
+#[napi(object)]
+#[derive(Clone, Default)]
+pub struct TextCleanupOptions {
+  pub expand_ligatures: Option<bool>,
+  pub dehyphenate: Option<bool>,
+  pub normalization: Option<String>,
+  pub collapse_whitespace: Option<bool>,
+  pub remove_control_chars: Option<bool>,
+  pub remove_headers_footers: Option<bool>,
+}
+
+/// Clean the text of a multi-page document.
+///
+/// Page-level steps run on each page first so that repeated headers/footers can be
+/// detected across pages; the pages are then joined and cleaned as a whole.
+pub(crate) fn clean_pages(pages: &[String], opts: &TextCleanupOptions) -> String {
+  let mut pages: Vec<String> = pages.iter().map(|p| clean_chars(p, opts)).collect();
+
+  if opts.remove_headers_footers.unwrap_or(true) {
+    remove_headers_footers(&mut pages);
+  }
+
+  let joined = pages.join("\n\n");
+  clean_layout(&joined, opts)
+}
+
+/// Character-level cleanup: line endings, control characters, ligatures and normalization.
+fn clean_chars(text: &str, opts: &TextCleanupOptions) -> String {
+  let text = text.replace("\r\n", "\n").replace(['\r', '\u{0C}'], "\n");
+
+  let text: String = if opts.remove_control_chars.unwrap_or(true) {
+    text.chars().filter(|c| !is_removable_control(*c)).collect()
+  } else {
+    text
+  };
+
+  let text = if opts.expand_ligatures.unwrap_or(true) {
+    expand_ligatures(&text)
+  } else {
+    text
+  };
+
+  match opts
+    .normalization
+    .as_deref()
+    .unwrap_or("nfc")
+    .to_ascii_lowercase()
+    .as_str()
+  {
+    "nfkc" => text.nfkc().collect(),
+    "none" => text,
+    _ => text.nfc().collect(),
+  }
+}
+
+/// Layout-level cleanup: de-hyphenation and whitespace collapsing.
+fn clean_layout(text: &str, opts: &TextCleanupOptions) -> String {
+  let text = if opts.dehyphenate.unwrap_or(true) {
+    dehyphenate(text)
+  } else {
+    text.to_string()
+  };
+
+  if opts.collapse_whitespace.unwrap_or(true) {
+    collapse_whitespace(&text)
+  } else {
+    text
+  }
+}
+
+/// Control and format characters that never carry meaning in extracted text.
+fn is_removable_control(c: char) -> bool {
+  if c == '\n' || c == '\t' {
+    return false;
+  }
+  c.is_control()
+    || matches!(
+      c,
+      '\u{200B}' | '\u{200C}' | '\u{200D}' | '\u{2060}' | '\u{FEFF}' | '\u{FFFE}' | '\u{FFFF}'
+    )
+}
+
+fn expand_ligatures(text: &str) -> String {
+  let mut out = String::with_capacity(text.len());
+  for c in text.chars() {
+    match c {
+      'ﬀ' => out.push_str("ff"),
+      'ﬁ' => out.push_str("fi"),
+      'ﬂ' => out.push_str("fl"),
+      'ﬃ' => out.push_str("ffi"),
+      'ﬄ' => out.push_str("ffl"),
+      'ﬅ' | 'ﬆ' => out.push_str("st"),
+      'Ĳ' => out.push_str("IJ"),
+      'ĳ' => out.push_str("ij"),
+      _ => out.push(c),
+    }
+  }
+  out
+}
+
+/// Remove soft hyphens and join `exam-\nple` into `example`.
+///
+/// A line-final hyphen is only removed when it follows a letter and the next line
+/// starts with a lowercase letter, so that ranges ("2019-\n2021") and compound
+/// names ("Jean-\nPierre") are left alone.
+fn dehyphenate(text: &str) -> String {
+  let chars: Vec<char> = text.chars().filter(|c| *c != '\u{00AD}').collect();
+  let mut out = String::with_capacity(chars.len());
+  let mut i = 0;
+
+  while i < chars.len() {
+    let c = chars[i];
+    if (c == '-' || c == '\u{2010}') && i > 0 && chars[i - 1].is_alphabetic() {
+      // Look past trailing spaces to the line break.
+      let mut j = i + 1;
+      while j < chars.len() && (chars[j] == ' ' || chars[j] == '\t') {
+        j += 1;
+      }
+      if j < chars.len() && chars[j] == '\n' {
+        let mut k = j + 1;
+        while k < chars.len() && chars[k].is_whitespace() && chars[k] != '\n' {
+          k += 1;
+        }
+        if k < chars.len() && chars[k].is_lowercase() {
+          // Move the rest of the broken word up to this line and keep the line break after it.
+          let mut end = k;
+          while end < chars.len() && !chars[end].is_whitespace() {
+            end += 1;
+          }
+          out.extend(&chars[k..end]);
+          i = end;
+          if i < chars.len() && chars[i] == ' ' {
+            i += 1;
+            out.push('\n');
+          }
+          continue;
+        }
+      }
+    }
+    out.push(c);
+    i += 1;
+  }
+
+  out
+}
+
+/// Collapse horizontal whitespace runs, trim lines and keep at most one blank line in a row.
+fn collapse_whitespace(text: &str) -> String {
+  let mut out = String::with_capacity(text.len());
+  let mut blank_run = 0;
+
+  for line in text.split('\n') {
+    let collapsed = line.split_whitespace().collect::<Vec<_>>().join(" ");
+    if collapsed.is_empty() {
+      blank_run += 1;
+      if blank_run > 1 || out.is_empty() {
+        continue;
+      }
+    } else {
+      blank_run = 0;
+    }
+    out.push_str(&collapsed);
+    out.push('\n');
+  }
+
+  out.trim_end().to_string()
+}
+
+/// Number of lines at the top and bottom of a page that may be a header or footer.
+const EDGE_LINES: usize = 2;
+
+/// Key used to compare header/footer candidates: page numbers and dates vary from
+/// page to page, so digits are masked.
+fn edge_line_key(line: &str) -> String {
+  line
+    .split_whitespace()
+    .collect::<Vec<_>>()
+    .join(" ")
+    .to_lowercase()
+    .chars()
+    .map(|c| if c.is_ascii_digit() { '#' } else { c })
+    .collect()
+}
+
+fn edge_line_indices(lines: &[&str]) -> Vec<usize> {
+  let non_empty: Vec<usize> = lines
+    .iter()
+    .enumerate()
+    .filter(|(_, l)| !l.trim().is_empty())
+    .map(|(i, _)| i)
+    .collect();
+
+  let mut indices: Vec<usize> = non_empty.iter().take(EDGE_LINES).copied().collect();
+  indices.extend(non_empty.iter().rev().take(EDGE_LINES).copied());
+  indices.sort_unstable();
+  indices.dedup();
+  indices
+}
+
+/// Remove lines that repeat at the top or bottom of most pages (running headers,
+/// footers, "Page 2 of 3" markers).
+fn remove_headers_footers(pages: &mut [String]) {
+  if pages.len() < 2 {
+    return;
+  }
+
+  let mut counts: HashMap<String, usize> = HashMap::new();
+  for page in pages.iter() {
+    let lines: Vec<&str> = page.split('\n').collect();
+    let keys: HashSet<String> = edge_line_indices(&lines)
+      .into_iter()
+      .map(|i| edge_line_key(lines[i]))
+      .collect();
+    for key in keys {
+      *counts.entry(key).or_insert(0) += 1;
+    }
+  }
+
+  // A line must appear on at least 60% of the pages (and on at least two of them).
+  let threshold = ((pages.len() as f64) * 0.6).ceil().max(2.0) as usize;
+  let repeated: HashSet<String> = counts
+    .into_iter()
+    .filter(|(_, count)| *count >= threshold)
+    .map(|(key, _)| key)
+    .collect();
+
+  if repeated.is_empty() {
+    return;
+  }
+
+  for page in pages.iter_mut() {
+    let lines: Vec<&str> = page.split('\n').collect();
+    let drop: HashSet<usize> = edge_line_indices(&lines)
+      .into_iter()
+      .filter(|i| repeated.contains(&edge_line_key(lines[*i])))
+      .collect();
+    *page = lines
+      .iter()
+      .enumerate()
+      .filter(|(i, _)| !drop.contains(i))
+      .map(|(_, l)| *l)
+      .collect::<Vec<_>>()
+      .join("\n");
+  }
+}
@@ -0,0 +1,841 @@
+use crate::html::{HEADING_SIZES, HEADING_SPACE_BEFORE, LINK_COLOR, RULE_COLOR};
+use crate::typeset::{trim_runs, Align, Block, Paragraph, Rgb, Run, Table, TableCell, TextStyle};
+
+/// Bullets of nested list levels, as for HTML lists.
+const BULLETS: [&str; 3] = ["•", "◦", "▪"];
+
+/// Characters that start a list item in plain text when followed by a space.
+const TEXT_BULLETS: &[char] = &['•', '◦', '▪', '●', '○', '■', '‣', '-', '–', '*', '·'];
+
+/// Indent of each list level, in points.
+const LIST_INDENT: f64 = 24.0;
+
+/// Indent of block quotes and code blocks, in points.
+const QUOTE_INDENT: f64 = 18.0;
+
+const CODE_COLOR: Rgb = [0.25, 0.25, 0.25];
+const QUOTE_COLOR: Rgb = [0.35, 0.35, 0.35];
+
+/// Maximum nesting of block quotes, against pathological input.
+const MAX_DEPTH: usize = 16;
+
+/// Content of a plain text CV: a paragraph per line, with blank lines kept as
+/// spacing. Lines starting with a bullet (`•`, `-`, `*`, …) become list items.
+pub(crate) fn text_blocks(text: &str) -> Vec<Block> {
+  let mut blocks = Vec::new();
+  let mut blank_before = false;
+  for line in lines(text) {
+    let trimmed = line.trim();
+    if trimmed.is_empty() {
+      blank_before = true;
+      continue;
+    }
+    let space_before = if blank_before && !blocks.is_empty() {
+      8.0
+    } else {
+      0.0
+    };
+    blank_before = false;
+
+    let indent = (line.len() - line.trim_start().len()) as f64 * 3.0;
+    let mut chars = trimmed.chars();
+    let bullet = chars
+      .next()
+      .filter(|c| TEXT_BULLETS.contains(c) && chars.next() == Some(' '));
+    let paragraph = match bullet {
+      Some(bullet) => Paragraph {
+        runs: vec![plain_run(trimmed[bullet.len_utf8()..].trim_start())],
+        marker: Some(plain_run(if bullet == '-' || bullet == '*' {
+          BULLETS[0]
+        } else {
+          &trimmed[..bullet.len_utf8()]
+        })),
+        indent_left: indent + LIST_INDENT,
+        first_line_indent: -14.0,
+        ..Paragraph::default()
+      },
+      None => Paragraph {
+        runs: vec![plain_run(trimmed)],
+        indent_left: indent,
+        ..Paragraph::default()
+      },
+    };
+    blocks.push(Block::Paragraph(Paragraph {
+      space_before,
+      space_after: 2.0,
+      ..paragraph
+    }));
+  }
+  blocks
+}
+
+/// Content of a Markdown CV (CommonMark with GitHub tables and strikethrough):
+/// headings, paragraphs, bullet and numbered lists, block quotes, code blocks,
+/// tables, rules, and bold, italic, strikethrough and link text. Images and
+/// raw HTML are left out.
+pub(crate) fn markdown_blocks(text: &str) -> Vec<Block> {
+  let lines = lines(text);
+  let mut parser = MarkdownParser::default();
+  parser.parse(&lines, 0.0, 0);
+  parser.blocks
+}
+
+/// Lines of `text`, without line endings, tabs expanded to four spaces.
+fn lines(text: &str) -> Vec<String> {
+  text
+    .lines()
+    .map(|line| line.replace('\t', "    "))
+    .collect()
+}
+
+fn plain_run(text: &str) -> Run {
+  Run {
+    text: text.to_string(),
+    style: TextStyle::default(),
+  }
+}
+
+/// A list being read: the column of its items' text and its item number.
+struct OpenList {
+  /// Indentation of the item markers
+  marker_indent: usize,
+  /// Indentation of the item text; deeper lines continue the item
+  content_indent: usize,
+  ordered: bool,
+  number: u64,
+}
+
+#[derive(Default)]
+struct MarkdownParser {
+  blocks: Vec<Block>,
+}
+
+/// A line starting a list item: marker indent, content indent, number (for
+/// ordered lists) and text.
+struct ListItem<'l> {
+  marker_indent: usize,
+  content_indent: usize,
+  number: Option<u64>,
+  text: &'l str,
+}
+
+impl MarkdownParser {
+  /// Parse `lines` into blocks, indented by `indent` points.
+  fn parse(&mut self, lines: &[String], indent: f64, depth: usize) {
+    let mut paragraph: Vec<&str> = Vec::new();
+    // The paragraph being read, with its list marker if it starts an item
+    let mut template = Paragraph::default();
+    let mut lists: Vec<OpenList> = Vec::new();
+    let mut i = 0;
+
+    while i < lines.len() {
+      let line = lines[i].as_str();
+      let leading = line.len() - line.trim_start().len();
+      let trimmed = line.trim();
+
+      if trimmed.is_empty() {
+        self.paragraph(&mut paragraph, &mut template, indent, &lists);
+        i += 1;
+        continue;
+      }
+
+      // Lines less indented than the open items' text close them, unless they
+      // continue a paragraph; list items close the deeper lists.
+      match list_item(line) {
+        Some(item) => {
+          self.paragraph(&mut paragraph, &mut template, indent, &lists);
+          while lists
+            .last()
+            .is_some_and(|list| item.marker_indent < list.marker_indent)
+          {
+            lists.pop();
+          }
+        }
+        None if paragraph.is_empty() => {
+          self.paragraph(&mut paragraph, &mut template, indent, &lists);
+          while lists
+            .last()
+            .is_some_and(|list| leading < list.content_indent)
+          {
+            lists.pop();
+          }
+        }
+        None => {}
+      }
+      let list_indent = indent + lists.len() as f64 * LIST_INDENT;
+
+      // Code fence
+      if leading < 4 && (trimmed.starts_with("```") || trimmed.starts_with("~~~")) {
+        self.paragraph(&mut paragraph, &mut template, indent, &lists);
+        let fence = &trimmed[..3];
+        let mut code = Vec::new();
+        i += 1;
+        while i < lines.len() && !lines[i].trim_start().starts_with(fence) {
+          let code_line = lines[i].as_str();
+          let code_leading = code_line.len() - code_line.trim_start().len();
+          code.push(&code_line[leading.min(code_leading)..]);
+          i += 1;
+        }
+        i += 1;
+        let text = code.join("\n");
+        self.blocks.push(Block::Paragraph(Paragraph {
+          runs: vec![Run {
+            text,
+            style: TextStyle {
+              size: 9.5,
+              color: CODE_COLOR,
+              ..TextStyle::default()
+            },
+          }],
+          indent_left: list_indent + QUOTE_INDENT,
+          space_before: 2.0,
+          space_after: 6.0,
+          ..Paragraph::default()
+        }));
+        continue;
+      }
+
+      // Setext heading underline
+      if !paragraph.is_empty()
+        && leading < 4
+        && (trimmed.bytes().all(|b| b == b'=') || trimmed.bytes().all(|b| b == b'-'))
+      {
+        let level = if trimmed.starts_with('=') { 1 } else { 2 };
+        let text = paragraph.join(" ");
+        paragraph.clear();
+        template = Paragraph::default();
+        self.heading(level, &text, list_indent);
+        i += 1;
+        continue;
+      }
+
+      if leading < 4 && is_rule(trimmed) {
+        self.paragraph(&mut paragraph, &mut template, indent, &lists);
+        lists.clear();
+        self.blocks.push(rule(indent));
+        i += 1;
+        continue;
+      }
+
+      if let Some((level, text)) = atx_heading(line) {
+        self.paragraph(&mut paragraph, &mut template, indent, &lists);
+        lists.clear();
+        self.heading(level, text, indent);
+        i += 1;
+        continue;
+      }
+
+      if leading < 4 && trimmed.starts_with('>') {
+        self.paragraph(&mut paragraph, &mut template, indent, &lists);
+        let mut quoted = Vec::new();
+        while i < lines.len() && lines[i].trim_start().starts_with('>') {
+          let line = &lines[i].trim_start()[1..];
+          quoted.push(line.strip_prefix(' ').unwrap_or(line).to_string());
+          i += 1;
+        }
+        if depth < MAX_DEPTH {
+          let start = self.blocks.len();
+          self.parse(&quoted, list_indent + QUOTE_INDENT, depth + 1);
+          for block in &mut self.blocks[start..] {
+            if let Block::Paragraph(p) = block {
+              for run in &mut p.runs {
+                run.style.italic = true;
+                run.style.color = QUOTE_COLOR;
+              }
+            }
+          }
+        }
+        continue;
+      }
+
+      if let Some(item) = list_item(line) {
+        self.paragraph(&mut paragraph, &mut template, indent, &lists);
+        let same_level = lists
+          .last()
+          .is_some_and(|list| item.marker_indent < list.content_indent);
+        if same_level && lists.last().unwrap().ordered == item.number.is_some() {
+          let list = lists.last_mut().unwrap();
+          list.number += 1;
+          list.content_indent = item.content_indent;
+        } else {
+          if same_level {
+            lists.pop();
+          }
+          lists.push(OpenList {
+            marker_indent: item.marker_indent,
+            content_indent: item.content_indent,
+            ordered: item.number.is_some(),
+            number: item.number.unwrap_or(1),
+          });
+        }
+        let list = lists.last().unwrap();
+        let marker = if list.ordered {
+          format!("{}.", list.number)
+        } else {
+          BULLETS[(lists.len() - 1) % BULLETS.len()].to_string()
+        };
+        template = Paragraph {
+          marker: Some(plain_run(&marker)),
+          first_line_indent: -14.0,
+          ..Paragraph::default()
+        };
+        if !item.text.trim().is_empty() {
+          paragraph.push(item.text);
+        }
+        i += 1;
+        continue;
+      }
+
+      if paragraph.is_empty() && i + 1 < lines.len() && trimmed.contains('|') {
+        if let Some(alignments) = table_delimiter(&lines[i + 1]) {
+          let mut rows = vec![table_cells(trimmed)];
+          i += 2;
+          while i < lines.len() && lines[i].contains('|') && !lines[i].trim().is_empty() {
+            rows.push(table_cells(lines[i].trim()));
+            i += 1;
+          }
+          self.blocks.push(table(&rows, &alignments, list_indent));
+          continue;
+        }
+      }
+
+      paragraph.push(line);
+      i += 1;
+    }
+    self.paragraph(&mut paragraph, &mut template, indent, &lists);
+  }
+
+  /// End the paragraph being read, if any.
+  fn paragraph(
+    &mut self,
+    lines: &mut Vec<&str>,
+    template: &mut Paragraph,
+    indent: f64,
+    lists: &[OpenList],
+  ) {
+    let template = std::mem::take(template);
+    if lines.is_empty() && template.marker.is_none() {
+      return;
+    }
+    let mut text = String::new();
+    for (i, line) in lines.iter().enumerate() {
+      let hard_break = line.ends_with("  ") || line.ends_with('\\');
+      let line = line.trim();
+      text.push_str(line.strip_suffix('\\').unwrap_or(line));
+      if i + 1 < lines.len() {
+        text.push(if hard_break { '\n' } else { ' ' });
+      }
+    }
+    lines.clear();
+    let mut runs = inline_runs(&text, &TextStyle::default());
+    if runs.is_empty() {
+      runs.push(plain_run(""));
+    }
+    let in_list = template.marker.is_some();
+    self.blocks.push(Block::Paragraph(Paragraph {
+      runs,
+      indent_left: indent + lists.len() as f64 * LIST_INDENT,
+      space_after: if in_list { 2.0 } else { 6.0 },
+      ..template
+    }));
+  }
+
+  fn heading(&mut self, level: usize, text: &str, indent: f64) {
+    let style = TextStyle {
+      bold: true,
+      size: HEADING_SIZES[level - 1],
+      ..TextStyle::default()
+    };
+    let runs = inline_runs(text, &style);
+    if runs.is_empty() {
+      return;
+    }
+    self.blocks.push(Block::Paragraph(Paragraph {
+      runs,
+      indent_left: indent,
+      space_before: HEADING_SPACE_BEFORE[level - 1],
+      space_after: 6.0,
+      keep_with_next: true,
+      outline_level: Some(level - 1),
+      ..Paragraph::default()
+    }));
+  }
+}
+
+/// Level and text of an ATX heading (`## Experience`).
+fn atx_heading(line: &str) -> Option<(usize, &str)> {
+  let trimmed = line.trim_start();
+  if line.len() - trimmed.len() >= 4 {
+    return None;
+  }
+  let level = trimmed.bytes().take_while(|b| *b == b'#').count();
+  let rest = &trimmed[level..];
+  if !(1..=6).contains(&level) || !(rest.is_empty() || rest.starts_with(' ')) {
+    return None;
+  }
+  // Closing sequence of `#`
+  let text = rest.trim();
+  let without_closing = text.trim_end_matches('#');
+  let text = if without_closing.is_empty() || without_closing.ends_with(' ') {
+    without_closing.trim_end()
+  } else {
+    text
+  };
+  Some((level, text))
+}
+
+/// `---`, `***` or `___`, possibly spaced.
+fn is_rule(trimmed: &str) -> bool {
+  let Some(first) = trimmed
+    .chars()
+    .next()
+    .filter(|c| matches!(c, '-' | '*' | '_'))
+  else {
+    return false;
+  };
+  trimmed.chars().all(|c| c == first || c == ' ') && trimmed.matches(first).count() >= 3
+}
+
+fn rule(indent: f64) -> Block {
+  Block::Paragraph(Paragraph {
+    runs: vec![Run {
+      text: String::new(),
+      style: TextStyle {
+        size: 4.0,
+        ..TextStyle::default()
+      },
+    }],
+    indent_left: indent,
+    space_before: 4.0,
+    space_after: 8.0,
+    border_bottom: Some(RULE_COLOR),
+    ..Paragraph::default()
+  })
+}
+
+/// A list item line: `- text`, `* text`, `+ text`, `1. text` or `1) text`.
+fn list_item(line: &str) -> Option<ListItem<'_>> {
+  let trimmed = line.trim_start();
+  let marker_indent = line.len() - trimmed.len();
+  let digits = trimmed.bytes().take_while(u8::is_ascii_digit).count();
+  let (number, marker_len) = if digits > 0 {
+    if digits > 9 || !matches!(trimmed.as_bytes().get(digits), Some(b'.' | b')')) {
+      return None;
+    }
+    (trimmed[..digits].parse().ok(), digits + 1)
+  } else if matches!(trimmed.as_bytes().first(), Some(b'-' | b'*' | b'+')) {
+    (None, 1)
+  } else {
+    return None;
+  };
+  let rest = &trimmed[marker_len..];
+  if !(rest.is_empty() || rest.starts_with(' ')) {
+    return None;
+  }
+  let text = rest.trim_start();
+  Some(ListItem {
+    marker_indent,
+    content_indent: marker_indent + marker_len + (rest.len() - text.len()).clamp(1, 4),
+    number,
+    text,
+  })
+}
+
+/// Column alignments of a table delimiter row (`| :-- | :-: |`).
+fn table_delimiter(line: &str) -> Option<Vec<Align>> {
+  let line = line.trim();
+  if !line.contains('-') {
+    return None;
+  }
+  table_cells(line)
+    .iter()
+    .map(|cell| {
+      let dashes = cell.trim_matches(':');
+      if dashes.is_empty() || !dashes.bytes().all(|b| b == b'-') {
+        return None;
+      }
+      Some(match (cell.starts_with(':'), cell.ends_with(':')) {
+        (true, true) => Align::Center,
+        (false, true) => Align::Right,
+        _ => Align::Left,
+      })
+    })
+    .collect()
+}
+
+/// Cells of a table row, split on unescaped `|`.
+fn table_cells(line: &str) -> Vec<String> {
+  let line = line.strip_prefix('|').unwrap_or(line);
+  let line = match line.strip_suffix('|') {
+    Some(rest) if !rest.ends_with('\\') => rest,
+    _ => line,
+  };
+  let mut cells = vec![String::new()];
+  let mut chars = line.chars().peekable();
+  while let Some(c) = chars.next() {
+    match c {
+      '\\' if chars.peek() == Some(&'|') => {
+        cells.last_mut().unwrap().push('|');
+        chars.next();
+      }
+      '|' => cells.push(String::new()),
+      c => cells.last_mut().unwrap().push(c),
+    }
+  }
+  cells.iter().map(|cell| cell.trim().to_string()).collect()
+}
+
+fn table(rows: &[Vec<String>], alignments: &[Align], indent: f64) -> Block {
+  let rows = rows
+    .iter()
+    .enumerate()
+    .map(|(r, row)| {
+      (0..alignments.len())
+        .map(|c| {
+          let style = TextStyle {
+            bold: r == 0,
+            ..TextStyle::default()
+          };
+          let mut runs = inline_runs(row.get(c).map_or("", String::as_str), &style);
+          if runs.is_empty() {
+            runs.push(plain_run(""));
+          }
+          TableCell {
+            blocks: vec![Block::Paragraph(Paragraph {
+              runs,
+              align: alignments[c],
+              ..Paragraph::default()
+            })],
+            span: 1,
+            background: None,
+          }
+        })
+        .collect()
+    })
+    .collect();
+  Block::Table(Table {
+    columns: Vec::new(),
+    rows,
+    borders: Some(RULE_COLOR),
+    indent_left: indent,
+  })
+}
+
+/// Inline formatting of a piece of text.
+#[derive(Clone, Copy, Default, PartialEq)]
+struct Marks {
+  bold: bool,
+  italic: bool,
+  strike: bool,
+  link: bool,
+  code: bool,
+}
+
+enum Inline {
+  Text(String, Marks),
+  /// A run of `*`, `_` or `~` that may open or close emphasis
+  Delimiter {
+    c: char,
+    count: usize,
+    can_open: bool,
+    can_close: bool,
+    marks: Marks,
+  },
+}
+
+/// Runs of a paragraph's Markdown text, in `style`: emphasis, strong emphasis,
+/// strikethrough, code spans, links and autolinks, and backslash escapes.
+/// Images are replaced by nothing and inline HTML tags dropped.
+fn inline_runs(text: &str, style: &TextStyle) -> Vec<Run> {
+  let items = inline_items(text, Marks::default());
+  let items = resolve_emphasis(items);
+
+  let mut runs: Vec<Run> = Vec::new();
+  for (text, marks) in items {
+    let mut style = style.clone();
+    style.bold |= marks.bold;
+    style.italic |= marks.italic;
+    style.strike |= marks.strike;
+    if marks.link {
+      style.underline = true;
+      style.color = LINK_COLOR;
+    }
+    if marks.code {
+      style.color = CODE_COLOR;
+    }
+    match runs.last_mut() {
+      Some(last) if last.style == style => last.text.push_str(&text),
+      _ => runs.push(Run { text, style }),
+    }
+  }
+  trim_runs(&mut runs);
+  runs
+}
+
+fn inline_items(text: &str, marks: Marks) -> Vec<Inline> {
+  let mut items = Vec::new();
+  let mut plain = String::new();
+  let chars: Vec<char> = text.chars().collect();
+  let mut i = 0;
+
+  let flush = |plain: &mut String, items: &mut Vec<Inline>| {
+    if !plain.is_empty() {
+      items.push(Inline::Text(std::mem::take(plain), marks));
+    }
+  };
+
+  while i < chars.len() {
+    let c = chars[i];
+    match c {
+      '\\' if chars.get(i + 1).is_some_and(|c| c.is_ascii_punctuation()) => {
+        plain.push(chars[i + 1]);
+        i += 2;
+      }
+      '`' => {
+        let ticks = chars[i..].iter().take_while(|c| **c == '`').count();
+        let close = (i + ticks..chars.len()).find(|&j| {
+          chars[j..].iter().take_while(|c| **c == '`').count() == ticks
+            && (j == 0 || chars[j - 1] != '`')
+        });
+        match close {
+          Some(end) => {
+            flush(&mut plain, &mut items);
+            let code: String = chars[i + ticks..end].iter().collect();
+            let code = match code.strip_prefix(' ').and_then(|c| c.strip_suffix(' ')) {
+              Some(inner) if !inner.trim().is_empty() => inner.to_string(),
+              _ => code,
+            };
+            items.push(Inline::Text(
+              code,
+              Marks {
+                code: true,
+                ..marks
+              },
+            ));
+            i = end + ticks;
+          }
+          None => {
+            plain.extend(&chars[i..i + ticks]);
+            i += ticks;
+          }
+        }
+      }
+      '*' | '_' | '~' => {
+        let count = chars[i..].iter().take_while(|x| **x == c).count();
+        if c == '~' && count != 2 {
+          plain.extend(&chars[i..i + count]);
+          i += count;
+          continue;
+        }
+        let before = i.checked_sub(1).map(|j| chars[j]);
+        let after = chars.get(i + count).copied();
+        let space_or_end = |c: Option<char>| c.is_none_or(char::is_whitespace);
+        let punctuation = |c: Option<char>| c.is_some_and(|c| c.is_ascii_punctuation());
+        // Left- and right-flanking delimiter runs, as in CommonMark
+        let left = !space_or_end(after)
+          && (!punctuation(after) || space_or_end(before) || punctuation(before));
+        let right = !space_or_end(before)
+          && (!punctuation(before) || space_or_end(after) || punctuation(after));
+        let (can_open, can_close) = if c == '_' {
+          (
+            left && (!right || punctuation(before)),
+            right && (!left || punctuation(after)),
+          )
+        } else {
+          (left, right)
+        };
+        flush(&mut plain, &mut items);
+        items.push(Inline::Delimiter {
+          c,
+          count,
+          can_open,
+          can_close,
+          marks,
+        });
+        i += count;
+      }
+      '!' if chars.get(i + 1) == Some(&'[') => match link_end(&chars, i + 1) {
+        // Images are not fetched.
+        Some((_, _, end)) => i = end,
+        None => {
+          plain.push(c);
+          i += 1;
+        }
+      },
+      '[' => match link_end(&chars, i) {
+        Some((label_end, _, end)) => {
+          flush(&mut plain, &mut items);
+          let label: String = chars[i + 1..label_end].iter().collect();
+          items.extend(inline_items(
+            &label,
+            Marks {
+              link: true,
+              ..marks
+            },
+          ));
+          i = end;
+        }
+        None => {
+          plain.push(c);
+          i += 1;
+        }
+      },
+      '<' => {
+        let end = chars[i..].iter().position(|c| *c == '>').map(|p| i + p);
+        let inner: Option<String> = end.map(|end| chars[i + 1..end].iter().collect());
+        match (end, inner) {
+          (Some(end), Some(inner)) if is_autolink(&inner) => {
+            flush(&mut plain, &mut items);
+            let text = inner.strip_prefix("mailto:").unwrap_or(&inner).to_string();
+            items.push(Inline::Text(
+              text,
+              Marks {
+                link: true,
+                ..marks
+              },
+            ));
+            i = end + 1;
+          }
+          (Some(end), Some(inner)) if is_html_tag(&inner) => i = end + 1,
+          _ => {
+            plain.push(c);
+            i += 1;
+          }
+        }
+      }
+      c => {
+        plain.push(c);
+        i += 1;
+      }
+    }
+  }
+  flush(&mut plain, &mut items);
+  items
+}
+
+/// End of the label, start of the destination and end of a link starting with
+/// `[` at `start`: `[label](destination "title")`.
+fn link_end(chars: &[char], start: usize) -> Option<(usize, usize, usize)> {
+  let mut depth = 0;
+  let mut label_end = None;
+  let mut i = start;
+  while i < chars.len() {
+    match chars[i] {
+      '\\' => i += 1,
+      '[' => depth += 1,
+      ']' => {
+        depth -= 1;
+        if depth == 0 {
+          label_end = Some(i);
+          break;
+        }
+      }
+      _ => {}
+    }
+    i += 1;
+  }
+  let label_end = label_end?;
+  if chars.get(label_end + 1) != Some(&'(') {
+    return None;
+  }
+  let destination = label_end + 2;
+  let close = chars[destination..].iter().position(|c| *c == ')')?;
+  Some((label_end, destination, destination + close + 1))
+}
+
+fn is_autolink(inner: &str) -> bool {
+  let scheme = inner.split(':').next().unwrap_or("");
+  let uri = inner.contains(':')
+    && (2..=32).contains(&scheme.len())
+    && scheme
+      .chars()
+      .all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '.' | '-'));
+  let email = inner.split_once('@').is_some_and(|(local, domain)| {
+    !local.is_empty() && domain.contains('.') && !domain.contains('@')
+  });
+  (uri || email) && !inner.contains([' ', '<'])
+}
+
+fn is_html_tag(inner: &str) -> bool {
+  let name = inner.strip_prefix('/').unwrap_or(inner);
+  name.chars().next().is_some_and(|c| c.is_ascii_alphabetic())
+    && name
+      .chars()
+      .take_while(|c| !c.is_whitespace() && *c != '/')
+      .all(|c| c.is_ascii_alphanumeric() || c == '-')
+}
+
+/// Match emphasis delimiters, innermost first, and return the text with its
+/// marks; unmatched delimiters are kept as text.
+fn resolve_emphasis(mut items: Vec<Inline>) -> Vec<(String, Marks)> {
+  let mut closer = 0;
+  while closer < items.len() {
+    let Inline::Delimiter {
+      c,
+      count: close_count,
+      can_close: true,
+      ..
+    } = items[closer]
+    else {
+      closer += 1;
+      continue;
+    };
+    let opener = (0..closer).rev().find(|&j| {
+      matches!(items[j], Inline::Delimiter { c: oc, can_open: true, count, .. } if oc == c && count > 0)
+    });
+    let Some(opener) = opener else {
+      closer += 1;
+      continue;
+    };
+    let Inline::Delimiter {
+      count: open_count, ..
+    } = items[opener]
+    else {
+      unreachable!()
+    };
+    let used = if c == '~' || (open_count >= 2 && close_count >= 2) {
+      2
+    } else {
+      1
+    };
+    for item in &mut items[opener + 1..closer] {
+      if let Inline::Text(_, marks) = item {
+        match (c, used) {
+          ('~', _) => marks.strike = true,
+          (_, 2) => marks.bold = true,
+          _ => marks.italic = true,
+        }
+      }
+    }
+    // Delimiters between the pair can no longer match.
+    for item in &mut items[opener + 1..closer] {
+      if let Inline::Delimiter {
+        can_open,
+        can_close,
+        ..
+      } = item
+      {
+        *can_open = false;
+        *can_close = false;
+      }
+    }
+    for index in [opener, closer] {
+      if let Inline::Delimiter { count, .. } = &mut items[index] {
+        *count -= used;
+      }
+    }
+    if let Inline::Delimiter { count: 0, .. } = items[closer] {
+      closer += 1;
+    }
+  }
+
+  items
+    .into_iter()
+    .filter_map(|item| match item {
+      Inline::Text(text, marks) => Some((text, marks)),
+      Inline::Delimiter {
+        c, count, marks, ..
+      } => (count > 0).then(|| (c.to_string().repeat(count), marks)),
+    })
+    .collect()
+}
@@ -0,0 +1,330 @@
+use std::fmt::Write as FmtWrite;
+
+use roxmltree::{Document, Node};
+
+/// Advance width of every glyph of the text layer font, in thousandths of the font size.
+const GLYPH_WIDTH: f64 = 500.0;
+
+/// A recognized word, in the pixel space of the OCRed image (origin at the top left).
+struct OcrWord {
+  text: String,
+  bbox: [f64; 4],
+  /// Baseline at the left edge of the word, when the OCR engine reports it
+  baseline: Option<f64>,
+  /// Font size in pixels (line height)
+  size: f64,
+  /// Last word of its line
+  line_end: bool,
+}
+
+/// Words recognized on an image, from hOCR or ALTO XML.
+pub(crate) struct OcrLayout {
+  /// Size of the OCRed image, when the OCR output declares it
+  page_size: Option<(f64, f64)>,
+  words: Vec<OcrWord>,
+}
+
+impl OcrLayout {
+  /// Parse hOCR (Tesseract's HTML output) or ALTO XML; the format is detected from
+  /// the root element.
+  pub fn parse(xml: &str) -> Result<OcrLayout, String> {
+    let doc = Document::parse_with_options(
+      xml,
+      roxmltree::ParsingOptions {
+        allow_dtd: true,
+        ..Default::default()
+      },
+    )
+    .map_err(|e| e.to_string())?;
+    let root = doc.root_element();
+    match root.tag_name().name() {
+      "alto" => Ok(parse_alto(root)),
+      "html" => Ok(parse_hocr(root)),
+      other => Err(format!(
+        "expected hOCR (<html>) or ALTO (<alto>), found <{other}>"
+      )),
+    }
+  }
+
+  /// Content stream operators drawing the words as invisible text (render mode 3)
+  /// over a page of `width` × `height` points showing the OCRed image.
+  ///
+  /// `image_size` is the size of the image the OCR ran on, used when the OCR output
+  /// does not declare it. `font` is the resource name of a Type0 font with
+  /// Identity-H encoding whose codes are UTF-16 code units.
+  pub fn content(&self, width: f64, height: f64, image_size: (f64, f64), font: &str) -> String {
+    let (image_w, image_h) = self.page_size.unwrap_or(image_size);
+    if image_w <= 0.0 || image_h <= 0.0 {
+      return String::new();
+    }
+    let (sx, sy) = (width / image_w, height / image_h);
+
+    let mut out = String::from("BT\n3 Tr\n");
+    for word in &self.words {
+      let size = word.size * sy;
+      let chars = word.text.chars().count();
+      if size <= 0.0 || chars == 0 {
+        continue;
+      }
+      // Stretch the glyphs so the word covers its box on the image.
+      let natural = chars as f64 * GLYPH_WIDTH / 1000.0 * size;
+      let scaling = ((word.bbox[2] - word.bbox[0]) * sx / natural * 100.0).max(1.0);
+      let baseline = word.baseline.unwrap_or(word.bbox[3]);
+      let text = if word.line_end {
+        word.text.clone()
+      } else {
+        format!("{} ", word.text)
+      };
+      let _ = writeln!(
+        out,
+        "/{font} {size:.2} Tf {scaling:.2} Tz 1 0 0 1 {:.2} {:.2} Tm <{}> Tj",
+        word.bbox[0] * sx,
+        height - baseline * sy,
+        utf16_hex(&text)
+      );
+    }
+    out.push_str("ET\n");
+    out
+  }
+}
+
+/// Text as hex UTF-16BE code units; characters outside the BMP become U+FFFD.
+fn utf16_hex(text: &str) -> String {
+  text
+    .chars()
+    .map(|c| {
+      let unit = if (c as u32) <= 0xFFFF {
+        c as u32
+      } else {
+        0xFFFD
+      };
+      format!("{unit:04X}")
+    })
+    .collect()
+}
+
+/// ToUnicode CMap of the text layer font: every two-byte code is its own UTF-16 unit.
+pub(crate) fn identity_to_unicode_cmap() -> String {
+  let mut ranges = String::new();
+  let high_bytes: Vec<u32> = (0..=0xFF).filter(|h| !(0xD8..=0xDF).contains(h)).collect();
+  // At most 100 entries per bfrange block.
+  for block in high_bytes.chunks(100) {
+    let _ = writeln!(ranges, "{} beginbfrange", block.len());
+    for h in block {
+      let _ = writeln!(ranges, "<{h:02X}00> <{h:02X}FF> <{h:02X}00>");
+    }
+    ranges.push_str("endbfrange\n");
+  }
+  format!(
+    "/CIDInit /ProcSet findresource begin\n12 dict begin\nbegincmap\n\
+     /CIDSystemInfo << /Registry (Adobe) /Ordering (UCS) /Supplement 0 >> def\n\
+     /CMapName /Adobe-Identity-UCS def\n/CMapType 2 def\n\
+     1 begincodespacerange\n<0000> <FFFF>\nendcodespacerange\n{ranges}\
+     endcmap\nCMapName currentdict /CMap defineresource pop\nend\nend\n"
+  )
+}
+
+/// TrueType program of the text layer font: one empty glyph, 500 units wide, with
+/// only the tables a PDF font program needs (as Tesseract's GlyphLessFont).
+pub(crate) fn glyphless_font() -> Vec<u8> {
+  fn u32s(values: &[u32]) -> Vec<u8> {
+    values.iter().flat_map(|v| v.to_be_bytes()).collect()
+  }
+  fn u16s(values: &[u16]) -> Vec<u8> {
+    values.iter().flat_map(|v| v.to_be_bytes()).collect()
+  }
+  const VERSION: u32 = 0x0001_0000;
+  // Version, revision, checksum adjustment (set below), magic number; flags, units
+  // per em, creation and modification dates, bounding box, style, smallest size,
+  // direction, short `loca` offsets, glyph format
+  let head = [
+    u32s(&[VERSION, VERSION, 0, 0x5F0F_3CF5]),
+    u16s(&[
+      0x000B, 1000, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 500, 1000, 0, 8, 2, 0, 0,
+    ]),
+  ]
+  .concat();
+  // Ascender, descender, line gap, widest advance, side bearings, extent, caret
+  // slope and offset, reserved, metric format, number of advances
+  let hhea = [
+    u32s(&[VERSION]),
+    u16s(&[1000, 0, 0, 500, 0, 0, 500, 1, 0, 0, 0, 0, 0, 0, 0, 1]),
+  ]
+  .concat();
+  // One glyph with no points nor contours, two zones, no instructions
+  let maxp = [
+    u32s(&[VERSION]),
+    u16s(&[1, 0, 0, 0, 0, 2, 0, 0, 0, 0, 0, 0, 0, 0]),
+  ]
+  .concat();
+  // Sorted by tag; `glyf` is empty, as is the glyph.
+  let tables: [(&[u8; 4], Vec<u8>); 6] = [
+    (b"glyf", Vec::new()),
+    (b"head", head),
+    (b"hhea", hhea),
+    (b"hmtx", u16s(&[500, 0])),
+    (b"loca", u16s(&[0, 0])),
+    (b"maxp", maxp),
+  ];
+
+  let checksum = |data: &[u8]| {
+    data.chunks(4).fold(0u32, |sum, chunk| {
+      let mut word = [0; 4];
+      word[..chunk.len()].copy_from_slice(chunk);
+      sum.wrapping_add(u32::from_be_bytes(word))
+    })
+  };
+  let mut font = [u32s(&[VERSION]), u16s(&[tables.len() as u16, 64, 2, 32])].concat();
+  let mut offset = 12 + 16 * tables.len();
+  let mut head_offset = 0;
+  for (tag, data) in &tables {
+    if *tag == b"head" {
+      head_offset = offset;
+    }
+    font.extend_from_slice(*tag);
+    font.extend(u32s(&[checksum(data), offset as u32, data.len() as u32]));
+    offset += data.len().next_multiple_of(4);
+  }
+  for (_, data) in &tables {
+    font.extend(data);
+    font.resize(font.len().next_multiple_of(4), 0);
+  }
+  let adjustment = 0xB1B0_AFBA_u32.wrapping_sub(checksum(&font));
+  font[head_offset + 8..head_offset + 12].copy_from_slice(&adjustment.to_be_bytes());
+  font
+}
+
+/// Whether a node's `class` attribute contains `class`.
+fn has_class(node: &Node, class: &str) -> bool {
+  node
+    .attribute("class")
+    .is_some_and(|c| c.split_whitespace().any(|c| c == class))
+}
+
+/// Values of an hOCR `title` property (`bbox 0 0 10 10; x_size 12`).
+fn hocr_property(node: &Node, name: &str) -> Option<Vec<f64>> {
+  node.attribute("title")?.split(';').find_map(|prop| {
+    let mut parts = prop.split_whitespace();
+    (parts.next()? == name).then(|| parts.filter_map(|v| v.parse().ok()).collect())
+  })
+}
+
+fn hocr_bbox(node: &Node) -> Option<[f64; 4]> {
+  match hocr_property(node, "bbox")?[..] {
+    [x0, y0, x1, y1] => Some([x0, y0, x1, y1]),
+    _ => None,
+  }
+}
+
+fn parse_hocr(root: Node) -> OcrLayout {
+  let page = root.descendants().find(|n| has_class(n, "ocr_page"));
+  let page_size = page
+    .as_ref()
+    .and_then(hocr_bbox)
+    .map(|b| (b[2] - b[0], b[3] - b[1]));
+
+  let mut words = Vec::new();
+  let scope = page.unwrap_or(root);
+  for word_node in scope.descendants().filter(|n| has_class(n, "ocrx_word")) {
+    let Some(bbox) = hocr_bbox(&word_node) else {
+      continue;
+    };
+    let text: String = word_node
+      .descendants()
+      .filter(|n| n.is_text())
+      .filter_map(|n| n.text())
+      .collect::<String>()
+      .trim()
+      .to_string();
+    if text.is_empty() {
+      continue;
+    }
+
+    // Lines carry the baseline (`baseline slope offset`, relative to the bottom left
+    // corner of the line) and the line height (`x_size`).
+    let line = word_node.ancestors().find(is_hocr_line);
+    let line_bbox = line.as_ref().and_then(hocr_bbox);
+    let baseline = line.as_ref().zip(line_bbox).and_then(|(line, lb)| {
+      match hocr_property(line, "baseline")?[..] {
+        [slope, offset] => Some(lb[3] + slope * (bbox[0] - lb[0]) + offset),
+        _ => None,
+      }
+    });
+    let size = line
+      .as_ref()
+      .and_then(|l| hocr_property(l, "x_size"))
+      .and_then(|v| v.first().copied())
+      .or(line_bbox.map(|b| b[3] - b[1]))
+      .unwrap_or(bbox[3] - bbox[1]);
+
+    let line_end = line
+      .as_ref()
+      .is_some_and(|l| l.descendants().rfind(|n| has_class(n, "ocrx_word")) == Some(word_node));
+    words.push(OcrWord {
+      text,
+      bbox,
+      baseline,
+      size,
+      line_end,
+    });
+  }
+
+  OcrLayout { page_size, words }
+}
+
+fn is_hocr_line(node: &Node) -> bool {
+  [
+    "ocr_line",
+    "ocrx_line",
+    "ocr_header",
+    "ocr_caption",
+    "ocr_textfloat",
+  ]
+  .iter()
+  .any(|class| has_class(node, class))
+}
+
+fn parse_alto(root: Node) -> OcrLayout {
+  let number = |node: &Node, name: &str| node.attribute(name).and_then(|v| v.parse::<f64>().ok());
+  let page = root.descendants().find(|n| n.tag_name().name() == "Page");
+  let page_size = page
+    .as_ref()
+    .and_then(|p| Some((number(p, "WIDTH")?, number(p, "HEIGHT")?)));
+
+  let mut words = Vec::new();
+  let lines = root
+    .descendants()
+    .filter(|n| n.tag_name().name() == "TextLine");
+  for line in lines {
+    let strings: Vec<Node> = line
+      .children()
+      .filter(|n| n.tag_name().name() == "String")
+      .collect();
+    // BASELINE is a vertical position (ALTO 2/3) or a polyline (ALTO 4, ignored).
+    let baseline = number(&line, "BASELINE");
+    let line_height = number(&line, "HEIGHT");
+    for (i, string) in strings.iter().enumerate() {
+      let (Some(x), Some(y), Some(w), Some(h)) = (
+        number(string, "HPOS"),
+        number(string, "VPOS"),
+        number(string, "WIDTH"),
+        number(string, "HEIGHT"),
+      ) else {
+        continue;
+      };
+      let text = string.attribute("CONTENT").unwrap_or("").trim().to_string();
+      if text.is_empty() {
+        continue;
+      }
+      words.push(OcrWord {
+        text,
+        bbox: [x, y, x + w, y + h],
+        baseline,
+        size: line_height.unwrap_or(h),
+        line_end: i + 1 == strings.len(),
+      });
+    }
+  }
+
+  OcrLayout { page_size, words }
+}
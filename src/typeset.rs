@@ -0,0 +1,1386 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::Write as FmtWrite;
+use std::io::Cursor;
+
+use image::{ColorType, DynamicImage, GenericImageView, ImageFormat, ImageReader};
+use lopdf::{text_string, Object};
+use unicode_normalization::UnicodeNormalization;
+
+use crate::embedded_fonts::{FontFamily, FontStyle};
+use crate::outline::PdfOutlineItem;
+use crate::pdf_writer::PdfWriter;
+use crate::utils::calculate_target_size;
+
+/// An RGB color, each component from 0 to 1.
+pub(crate) type Rgb = [f64; 3];
+
+pub(crate) const BLACK: Rgb = [0.0, 0.0, 0.0];
+
+/// Horizontal and vertical padding of table cells, in points.
+const CELL_PADDING_X: f64 = 5.4;
+const CELL_PADDING_Y: f64 = 2.0;
+
+/// Longest side of embedded images, in pixels.
+const MAX_IMAGE_SIDE: u32 = 2000;
+
+/// Tolerance for rounding errors when fitting content on a page.
+const EPSILON: f64 = 0.01;
+
+/// Character formatting of a run of text.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct TextStyle {
+  pub bold: bool,
+  pub italic: bool,
+  pub underline: bool,
+  pub strike: bool,
+  /// Font size in points
+  pub size: f64,
+  pub color: Rgb,
+}
+
+impl Default for TextStyle {
+  fn default() -> Self {
+    TextStyle {
+      bold: false,
+      italic: false,
+      underline: false,
+      strike: false,
+      size: 11.0,
+      color: BLACK,
+    }
+  }
+}
+
+impl TextStyle {
+  fn font(&self) -> FontStyle {
+    FontStyle {
+      bold: self.bold,
+      italic: self.italic,
+    }
+  }
+}
+
+/// Text with one formatting; `\n` is a line break.
+#[derive(Clone, Debug)]
+pub(crate) struct Run {
+  pub text: String,
+  pub style: TextStyle,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub(crate) enum Align {
+  #[default]
+  Left,
+  Center,
+  Right,
+  Justify,
+}
+
+/// A paragraph; lengths are in points.
+///
+/// An empty run still counts for the height of an empty paragraph.
+#[derive(Clone, Debug)]
+pub(crate) struct Paragraph {
+  pub runs: Vec<Run>,
+  pub align: Align,
+  pub indent_left: f64,
+  pub indent_right: f64,
+  /// Indent of the first line from `indent_left`; negative for a hanging indent
+  pub first_line_indent: f64,
+  /// List item marker (bullet or number), set at the start of the first line
+  pub marker: Option<Run>,
+  pub space_before: f64,
+  pub space_after: f64,
+  /// Line height as a multiple of single spacing
+  pub line_spacing: f64,
+  /// Keep on the same page as the next paragraph (headings)
+  pub keep_with_next: bool,
+  /// Rule drawn under the paragraph
+  pub border_bottom: Option<Rgb>,
+  /// Outline level of a heading (0 for the first level), bookmarked in the PDF
+  pub outline_level: Option<usize>,
+}
+
+impl Default for Paragraph {
+  fn default() -> Self {
+    Paragraph {
+      runs: Vec::new(),
+      align: Align::Left,
+      indent_left: 0.0,
+      indent_right: 0.0,
+      first_line_indent: 0.0,
+      marker: None,
+      space_before: 0.0,
+      space_after: 0.0,
+      line_spacing: 1.0,
+      keep_with_next: false,
+      border_bottom: None,
+      outline_level: None,
+    }
+  }
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct Table {
+  /// Relative column widths; empty for equal columns
+  pub columns: Vec<f64>,
+  pub rows: Vec<Vec<TableCell>>,
+  /// Color of the cell borders; `None` for a table without borders
+  pub borders: Option<Rgb>,
+  pub indent_left: f64,
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct TableCell {
+  pub blocks: Vec<Block>,
+  /// Number of columns the cell spans
+  pub span: usize,
+  pub background: Option<Rgb>,
+}
+
+/// An image (any format the `image` crate reads), scaled down to fit the width.
+#[derive(Clone, Debug)]
+pub(crate) struct Picture {
+  pub data: Vec<u8>,
+  /// Size in points; the pixel size at 96 dpi when absent
+  pub width: Option<f64>,
+  pub height: Option<f64>,
+  pub align: Align,
+}
+
+#[derive(Clone, Debug)]
+pub(crate) enum Block {
+  Paragraph(Paragraph),
+  Table(Table),
+  Picture(Picture),
+  PageBreak,
+}
+
+/// Page size and margins, in points.
+#[derive(Clone, Debug)]
+pub(crate) struct PageSetup {
+  pub width: f64,
+  pub height: f64,
+  pub margin_top: f64,
+  pub margin_right: f64,
+  pub margin_bottom: f64,
+  pub margin_left: f64,
+}
+
+impl Default for PageSetup {
+  /// A4 portrait with 2 cm margins.
+  fn default() -> Self {
+    PageSetup {
+      width: 595.28,
+      height: 841.89,
+      margin_top: 56.69,
+      margin_right: 56.69,
+      margin_bottom: 56.69,
+      margin_left: 56.69,
+    }
+  }
+}
+
+impl PageSetup {
+  fn content_width(&self) -> f64 {
+    (self.width - self.margin_left - self.margin_right).max(72.0)
+  }
+
+  fn content_height(&self) -> f64 {
+    (self.height - self.margin_top - self.margin_bottom).max(72.0)
+  }
+}
+
+/// Drop the spaces at the start and end of a paragraph's text, and empty runs.
+pub(crate) fn trim_runs(runs: &mut Vec<Run>) {
+  while let Some(first) = runs.first_mut() {
+    let trimmed = first.text.trim_start_matches([' ', '\t']);
+    if trimmed.is_empty() {
+      runs.remove(0);
+    } else {
+      first.text = trimmed.to_string();
+      break;
+    }
+  }
+  while let Some(last) = runs.last_mut() {
+    let trimmed = last.text.trim_end_matches([' ', '\t']);
+    if trimmed.is_empty() {
+      runs.pop();
+    } else {
+      last.text.truncate(trimmed.len());
+      break;
+    }
+  }
+  runs.retain(|run| !run.text.is_empty());
+}
+
+/// Plain text of `blocks`: a line per paragraph, with its list marker.
+///
+/// The cells of a table row are separated by tabs when they hold a single line;
+/// otherwise the cells are written one after the other. Pictures are left out.
+pub(crate) fn plain_text(blocks: &[Block]) -> String {
+  let mut lines = Vec::new();
+  text_lines(blocks, &mut lines);
+  lines.join("\n")
+}
+
+fn text_lines(blocks: &[Block], lines: &mut Vec<String>) {
+  for block in blocks {
+    match block {
+      Block::Paragraph(p) => {
+        let mut line = String::new();
+        if let Some(marker) = &p.marker {
+          line.push_str(&marker.text);
+          line.push(' ');
+        }
+        for run in &p.runs {
+          line.push_str(&run.text);
+        }
+        lines.push(line);
+      }
+      Block::Table(table) => {
+        for row in &table.rows {
+          let cells: Vec<Vec<String>> = row
+            .iter()
+            .map(|cell| {
+              let mut cell_lines = Vec::new();
+              text_lines(&cell.blocks, &mut cell_lines);
+              cell_lines.retain(|line| !line.trim().is_empty());
+              cell_lines
+            })
+            .collect();
+          if cells.iter().all(|cell| cell.len() <= 1) {
+            let row_line: Vec<&str> = cells
+              .iter()
+              .map(|cell| cell.first().map(String::as_str).unwrap_or(""))
+              .collect();
+            lines.push(row_line.join("\t"));
+          } else {
+            lines.extend(cells.into_iter().flatten());
+          }
+        }
+        lines.push(String::new());
+      }
+      Block::Picture(_) => {}
+      Block::PageBreak => lines.push(String::new()),
+    }
+  }
+}
+
+/// Lay out `blocks` on pages and write them as a PDF embedding `fonts`, with a
+/// bookmark to every heading (paragraph with an outline level) outside tables.
+pub(crate) fn render_pdf(blocks: &[Block], page: &PageSetup, fonts: &FontFamily) -> Vec<u8> {
+  let mut layout = Layout {
+    fonts,
+    images: Vec::new(),
+    max_image_height: page.content_height(),
+  };
+  let items = layout.blocks(blocks, page.content_width());
+  let mut pager = Pager {
+    page,
+    pages: vec![Vec::new()],
+    y: 0.0,
+    bookmarks: Vec::new(),
+  };
+  pager.place(items);
+  let outline = outline_items(&pager.bookmarks);
+  write_pdf(&pager.pages, &layout.images, fonts, page, &outline)
+}
+
+/// A heading placed on a page: outline level, title and 1-based page number.
+type Bookmark = (usize, String, u32);
+
+/// Outline tree of the bookmarks: every item holds the following ones of a
+/// deeper level.
+fn outline_items(bookmarks: &[Bookmark]) -> Vec<PdfOutlineItem> {
+  // Items that may still get children, with their level, outermost first
+  let mut open: Vec<(usize, PdfOutlineItem)> = Vec::new();
+  let mut items = Vec::new();
+  let close = |open: &mut Vec<(usize, PdfOutlineItem)>, items: &mut Vec<_>, level: usize| {
+    while let Some((_, item)) = open.pop_if(|(last, _)| *last >= level) {
+      match open.last_mut() {
+        Some((_, parent)) => parent.children.push(item),
+        None => items.push(item),
+      }
+    }
+  };
+  for (level, title, page_number) in bookmarks {
+    close(&mut open, &mut items, *level);
+    open.push((
+      *level,
+      PdfOutlineItem {
+        title: title.clone(),
+        page_number: Some(*page_number),
+        children: Vec::new(),
+      },
+    ));
+  }
+  close(&mut open, &mut items, 0);
+  items
+}
+
+/// Something painted on a page; y grows downwards from the top of the enclosing box.
+#[derive(Clone, Debug)]
+enum Draw {
+  Text {
+    x: f64,
+    baseline: f64,
+    font: FontStyle,
+    size: f64,
+    color: Rgb,
+    text: String,
+  },
+  Image {
+    x: f64,
+    y: f64,
+    width: f64,
+    height: f64,
+    image: usize,
+  },
+  Rect {
+    x: f64,
+    y: f64,
+    width: f64,
+    height: f64,
+    color: Rgb,
+  },
+  Line {
+    from: (f64, f64),
+    to: (f64, f64),
+    width: f64,
+    color: Rgb,
+  },
+}
+
+impl Draw {
+  fn translate(mut self, dx: f64, dy: f64) -> Draw {
+    match &mut self {
+      Draw::Text { x, baseline, .. } => {
+        *x += dx;
+        *baseline += dy;
+      }
+      Draw::Image { x, y, .. } | Draw::Rect { x, y, .. } => {
+        *x += dx;
+        *y += dy;
+      }
+      Draw::Line { from, to, .. } => {
+        *from = (from.0 + dx, from.1 + dy);
+        *to = (to.0 + dx, to.1 + dy);
+      }
+    }
+    self
+  }
+}
+
+/// A box that is never split across pages (a line of text, an image).
+#[derive(Clone, Debug)]
+struct LineBox {
+  height: f64,
+  draws: Vec<Draw>,
+  keep_with_next: bool,
+  /// Outline level and title of the heading starting with this line
+  bookmark: Option<(usize, String)>,
+}
+
+/// Vertical flow of content, placed on pages by `Pager`.
+#[derive(Clone, Debug)]
+enum Item {
+  Line(LineBox),
+  /// Vertical space, dropped at the top of a page
+  Space(f64),
+  PageBreak,
+  Row(Row),
+}
+
+impl Item {
+  fn height(&self) -> f64 {
+    match self {
+      Item::Line(line) => line.height,
+      Item::Space(height) => *height,
+      Item::PageBreak => 0.0,
+      Item::Row(row) => row.height(),
+    }
+  }
+}
+
+/// A table row: cells are split across pages line by line.
+#[derive(Clone, Debug)]
+struct Row {
+  cells: Vec<Cell>,
+  borders: Option<Rgb>,
+}
+
+#[derive(Clone, Debug)]
+struct Cell {
+  x: f64,
+  width: f64,
+  items: Vec<Item>,
+  background: Option<Rgb>,
+}
+
+impl Row {
+  fn height(&self) -> f64 {
+    let content = self
+      .cells
+      .iter()
+      .map(|cell| cell.items.iter().map(Item::height).sum::<f64>())
+      .fold(0.0, f64::max);
+    content + 2.0 * CELL_PADDING_Y
+  }
+
+  /// Height of the first line of every cell, to keep a heading with the row.
+  fn first_line_height(&self) -> f64 {
+    let content = self
+      .cells
+      .iter()
+      .filter_map(|cell| {
+        cell
+          .items
+          .iter()
+          .find(|item| !matches!(item, Item::Space(_)))
+      })
+      .map(Item::height)
+      .fold(0.0, f64::max);
+    content + 2.0 * CELL_PADDING_Y
+  }
+
+  /// Split the row so that the first part fits in `height`; with `force`, at least
+  /// one item of every cell goes to the first part.
+  fn split(self, height: f64, force: bool) -> (Row, Row) {
+    let limit = height - 2.0 * CELL_PADDING_Y;
+    let mut head = Vec::new();
+    let mut tail = Vec::new();
+    for cell in self.cells {
+      let mut used = 0.0;
+      let mut taken = 0;
+      for item in &cell.items {
+        let h = item.height();
+        let first = !cell.items[..taken]
+          .iter()
+          .any(|i| !matches!(i, Item::Space(_)));
+        if used + h > limit + EPSILON && !(force && first) {
+          break;
+        }
+        used += h;
+        taken += 1;
+      }
+      let mut items = cell.items;
+      let rest: Vec<Item> = items
+        .split_off(taken)
+        .into_iter()
+        .skip_while(|item| matches!(item, Item::Space(_)))
+        .collect();
+      tail.push(Cell {
+        x: cell.x,
+        width: cell.width,
+        items: rest,
+        background: cell.background,
+      });
+      head.push(Cell {
+        x: cell.x,
+        width: cell.width,
+        items,
+        background: cell.background,
+      });
+    }
+    (
+      Row {
+        cells: head,
+        borders: self.borders,
+      },
+      Row {
+        cells: tail,
+        borders: self.borders,
+      },
+    )
+  }
+
+  fn is_empty(&self) -> bool {
+    self
+      .cells
+      .iter()
+      .all(|cell| cell.items.iter().all(|item| matches!(item, Item::Space(_))))
+  }
+}
+
+/// An image XObject.
+struct PdfImage {
+  data: Vec<u8>,
+  width: u32,
+  height: u32,
+  color_space: &'static str,
+  /// JPEG data is embedded as is (DCTDecode); other images as raw samples
+  jpeg: bool,
+}
+
+struct Layout<'f, 'a> {
+  fonts: &'f FontFamily<'a>,
+  images: Vec<PdfImage>,
+  max_image_height: f64,
+}
+
+/// A word or space of a paragraph, made of pieces of one or more runs.
+enum Token<'r> {
+  Word(Vec<(String, &'r TextStyle)>),
+  Space(&'r TextStyle),
+  Break,
+}
+
+/// A line of a paragraph before alignment.
+#[derive(Default)]
+struct TextLine<'r> {
+  tokens: Vec<Token<'r>>,
+  width: f64,
+  /// Ends with a line break or the end of the paragraph (not justified)
+  last: bool,
+}
+
+impl<'f, 'a> Layout<'f, 'a> {
+  fn blocks(&mut self, blocks: &[Block], width: f64) -> Vec<Item> {
+    let mut items = Vec::new();
+    for block in blocks {
+      match block {
+        Block::Paragraph(paragraph) => items.extend(self.paragraph(paragraph, width)),
+        Block::Table(table) => items.extend(self.table(table, width)),
+        Block::Picture(picture) => items.extend(self.picture(picture, width)),
+        Block::PageBreak => items.push(Item::PageBreak),
+      }
+    }
+    items
+  }
+
+  fn text_width(&self, text: &str, style: &TextStyle) -> f64 {
+    self.fonts.get(style.font()).width(text, style.size)
+  }
+
+  fn word_width(&self, pieces: &[(String, &TextStyle)]) -> f64 {
+    pieces
+      .iter()
+      .map(|(text, style)| self.text_width(text, style))
+      .sum()
+  }
+
+  fn paragraph(&mut self, p: &Paragraph, width: f64) -> Vec<Item> {
+    let mut items = Vec::new();
+    if p.space_before > 0.0 {
+      items.push(Item::Space(p.space_before));
+    }
+
+    // Where the first line starts, after the list marker
+    let marker_x = p.indent_left + p.first_line_indent;
+    let first_x = match &p.marker {
+      Some(marker) => {
+        let end = marker_x
+          + self.text_width(&marker.text, &marker.style)
+          + self.text_width(" ", &marker.style);
+        if p.first_line_indent < 0.0 {
+          end.max(p.indent_left)
+        } else {
+          end
+        }
+      }
+      None => marker_x,
+    };
+    let right = width - p.indent_right;
+    let line_width = |first: bool| {
+      let x = if first { first_x } else { p.indent_left };
+      (right - x).max(1.0)
+    };
+
+    let lines = self.break_lines(&tokenize(&p.runs), line_width);
+
+    let font = self.fonts.get(FontStyle::default());
+    let (ascent, descent) = (font.ascent(), font.descent());
+    let paragraph_size = p.runs.iter().map(|run| run.style.size).fold(0.0, f64::max);
+    let paragraph_size = if paragraph_size > 0.0 {
+      paragraph_size
+    } else {
+      TextStyle::default().size
+    };
+
+    let line_count = lines.len();
+    for (i, line) in lines.into_iter().enumerate() {
+      let first = i == 0;
+      let mut size = line
+        .tokens
+        .iter()
+        .flat_map(|token| match token {
+          Token::Word(pieces) => pieces.iter().map(|(_, s)| s.size).collect(),
+          Token::Space(style) => vec![style.size],
+          Token::Break => Vec::new(),
+        })
+        .fold(0.0, f64::max);
+      if size == 0.0 {
+        size = paragraph_size;
+      }
+      if let (true, Some(marker)) = (first, &p.marker) {
+        size = size.max(marker.style.size);
+      }
+      let baseline = size * ascent;
+      let height = size * (ascent + descent) * p.line_spacing;
+
+      let x0 = if first { first_x } else { p.indent_left };
+      let free = (line_width(first) - line.width).max(0.0);
+      let spaces = line
+        .tokens
+        .iter()
+        .filter(|t| matches!(t, Token::Space(_)))
+        .count();
+      let (offset, stretch) = match p.align {
+        Align::Left => (0.0, 0.0),
+        Align::Center => (free / 2.0, 0.0),
+        Align::Right => (free, 0.0),
+        Align::Justify if line.last || spaces == 0 => (0.0, 0.0),
+        Align::Justify => (0.0, free / spaces as f64),
+      };
+
+      let mut draws = Vec::new();
+      if let (true, Some(marker)) = (first, &p.marker) {
+        draws.push(Draw::Text {
+          x: marker_x,
+          baseline,
+          font: marker.style.font(),
+          size: marker.style.size,
+          color: marker.style.color,
+          text: marker.text.clone(),
+        });
+      }
+      self.line_draws(&line, x0 + offset, baseline, stretch, &mut draws);
+      let last_line = i + 1 == line_count;
+      let bookmark = p
+        .outline_level
+        .filter(|_| first)
+        .map(|level| (level, paragraph_title(&p.runs)))
+        .filter(|(_, title)| !title.is_empty());
+      items.push(Item::Line(LineBox {
+        height,
+        draws,
+        keep_with_next: p.keep_with_next && last_line,
+        bookmark,
+      }));
+    }
+
+    if let Some(color) = p.border_bottom {
+      items.push(Item::Line(LineBox {
+        height: 4.0,
+        draws: vec![Draw::Line {
+          from: (p.indent_left, 2.0),
+          to: (right, 2.0),
+          width: 0.75,
+          color,
+        }],
+        keep_with_next: p.keep_with_next,
+        bookmark: None,
+      }));
+    }
+    if p.space_after > 0.0 {
+      items.push(Item::Space(p.space_after));
+    }
+    items
+  }
+
+  /// Fill lines greedily; words wider than a line are cut.
+  fn break_lines<'r>(
+    &self,
+    tokens: &[Token<'r>],
+    line_width: impl Fn(bool) -> f64,
+  ) -> Vec<TextLine<'r>> {
+    let mut lines: Vec<TextLine> = Vec::new();
+    let mut line = TextLine::default();
+    let mut spaces: Vec<&TextStyle> = Vec::new();
+
+    for token in tokens {
+      match token {
+        Token::Break => {
+          line.last = true;
+          lines.push(std::mem::take(&mut line));
+          spaces.clear();
+        }
+        Token::Space(style) => {
+          if !line.tokens.is_empty() {
+            spaces.push(style);
+          }
+        }
+        Token::Word(pieces) => {
+          let word_width = self.word_width(pieces);
+          let space_width: f64 = spaces.iter().map(|s| self.text_width(" ", s)).sum();
+          let available = line_width(lines.is_empty());
+          if !line.tokens.is_empty() && line.width + space_width + word_width > available + EPSILON
+          {
+            lines.push(std::mem::take(&mut line));
+          } else {
+            for style in &spaces {
+              line.tokens.push(Token::Space(style));
+            }
+            line.width += space_width;
+          }
+          spaces.clear();
+
+          if line.tokens.is_empty() && word_width > available + EPSILON {
+            // Cut the word into pieces that fit the line.
+            let mut chunk: Vec<(String, &TextStyle)> = Vec::new();
+            let mut chunk_width = 0.0;
+            for (text, style) in pieces {
+              for c in text.chars() {
+                let w = self.text_width(c.encode_utf8(&mut [0; 4]), style);
+                if chunk_width + w > line_width(lines.is_empty()) && chunk_width > 0.0 {
+                  lines.push(TextLine {
+                    tokens: vec![Token::Word(std::mem::take(&mut chunk))],
+                    width: chunk_width,
+                    last: false,
+                  });
+                  chunk_width = 0.0;
+                }
+                match chunk.last_mut() {
+                  Some((last, last_style)) if *last_style == *style => last.push(c),
+                  _ => chunk.push((c.to_string(), *style)),
+                }
+                chunk_width += w;
+              }
+            }
+            line.tokens.push(Token::Word(chunk));
+            line.width = chunk_width;
+          } else {
+            line.tokens.push(Token::Word(pieces.clone()));
+            line.width += word_width;
+          }
+        }
+      }
+    }
+    if !line.tokens.is_empty() || lines.is_empty() {
+      lines.push(line);
+    }
+    if let Some(last) = lines.last_mut() {
+      last.last = true;
+    }
+    lines
+  }
+
+  /// Text draws of a line; consecutive pieces of the same style are drawn together
+  /// unless spaces are stretched.
+  fn line_draws(
+    &self,
+    line: &TextLine,
+    x0: f64,
+    baseline: f64,
+    stretch: f64,
+    draws: &mut Vec<Draw>,
+  ) {
+    let mut pending: Option<(f64, String, &TextStyle)> = None;
+    let mut x = x0;
+    let flush = |pending: &mut Option<(f64, String, &TextStyle)>, draws: &mut Vec<Draw>| {
+      if let Some((start, text, style)) = pending.take() {
+        self.text_draws(start, baseline, text, style, draws);
+      }
+    };
+
+    for token in &line.tokens {
+      let pieces: Vec<(&str, &TextStyle)> = match token {
+        Token::Word(pieces) => pieces.iter().map(|(t, s)| (t.as_str(), *s)).collect(),
+        Token::Space(style) => vec![(" ", *style)],
+        Token::Break => Vec::new(),
+      };
+      for (text, style) in pieces {
+        match &mut pending {
+          Some((_, pending_text, pending_style)) if *pending_style == style => {
+            pending_text.push_str(text)
+          }
+          _ => {
+            flush(&mut pending, draws);
+            pending = Some((x, text.to_string(), style));
+          }
+        }
+        x += self.text_width(text, style);
+      }
+      if let (Token::Space(_), true) = (token, stretch > 0.0) {
+        flush(&mut pending, draws);
+        x += stretch;
+      }
+    }
+    flush(&mut pending, draws);
+  }
+
+  fn text_draws(
+    &self,
+    x: f64,
+    baseline: f64,
+    text: String,
+    style: &TextStyle,
+    draws: &mut Vec<Draw>,
+  ) {
+    let trimmed = text.trim_end();
+    let width = self.text_width(trimmed, style);
+    let mut line_at = |y: f64| {
+      draws.push(Draw::Line {
+        from: (x, y),
+        to: (x + width, y),
+        width: style.size * 0.06,
+        color: style.color,
+      })
+    };
+    if style.underline && width > 0.0 {
+      line_at(baseline + style.size * 0.12);
+    }
+    if style.strike && width > 0.0 {
+      line_at(baseline - style.size * 0.28);
+    }
+    if trimmed.is_empty() {
+      return;
+    }
+    draws.push(Draw::Text {
+      x,
+      baseline,
+      font: style.font(),
+      size: style.size,
+      color: style.color,
+      text: trimmed.to_string(),
+    });
+  }
+
+  fn table(&mut self, table: &Table, width: f64) -> Vec<Item> {
+    let total = (width - table.indent_left).max(1.0);
+    let column_count = table
+      .rows
+      .iter()
+      .map(|row| row.iter().map(|cell| cell.span.max(1)).sum::<usize>())
+      .max()
+      .unwrap_or(0);
+    if column_count == 0 {
+      return Vec::new();
+    }
+    let relative: Vec<f64> =
+      if table.columns.len() >= column_count && table.columns.iter().all(|w| *w > 0.0) {
+        table.columns.clone()
+      } else {
+        vec![1.0; column_count]
+      };
+    let sum: f64 = relative.iter().sum();
+    let columns: Vec<f64> = relative.iter().map(|w| w / sum * total).collect();
+
+    let mut items = Vec::new();
+    for row in &table.rows {
+      let mut cells = Vec::new();
+      let mut x = table.indent_left;
+      let mut column = 0;
+      for cell in row {
+        let span = cell.span.max(1);
+        let cell_width: f64 = columns.iter().skip(column).take(span).sum();
+        column += span;
+        let inner = (cell_width - 2.0 * CELL_PADDING_X).max(1.0);
+        let content = self
+          .blocks(&cell.blocks, inner)
+          .into_iter()
+          .filter(|item| !matches!(item, Item::PageBreak))
+          .collect();
+        cells.push(Cell {
+          x,
+          width: cell_width,
+          items: content,
+          background: cell.background,
+        });
+        x += cell_width;
+      }
+      items.push(Item::Row(Row {
+        cells,
+        borders: table.borders,
+      }));
+    }
+    items
+  }
+
+  fn picture(&mut self, picture: &Picture, width: f64) -> Option<Item> {
+    let (image, pixel_width, pixel_height) = self.add_image(&picture.data)?;
+    let aspect = pixel_height as f64 / pixel_width as f64;
+    let (mut w, mut h) = match (picture.width, picture.height) {
+      (Some(w), Some(h)) => (w, h),
+      (Some(w), None) => (w, w * aspect),
+      (None, Some(h)) => (h / aspect, h),
+      // 96 dpi
+      (None, None) => (pixel_width as f64 * 0.75, pixel_height as f64 * 0.75),
+    };
+    let scale = (width / w).min(self.max_image_height / h).min(1.0);
+    w *= scale;
+    h *= scale;
+    let x = match picture.align {
+      Align::Center => (width - w) / 2.0,
+      Align::Right => width - w,
+      Align::Left | Align::Justify => 0.0,
+    };
+    Some(Item::Line(LineBox {
+      height: h,
+      draws: vec![Draw::Image {
+        x,
+        y: 0.0,
+        width: w,
+        height: h,
+        image,
+      }],
+      keep_with_next: false,
+      bookmark: None,
+    }))
+  }
+
+  /// Decode an image for embedding; returns its index and pixel size.
+  fn add_image(&mut self, data: &[u8]) -> Option<(usize, u32, u32)> {
+    let reader = ImageReader::new(Cursor::new(data))
+      .with_guessed_format()
+      .ok()?;
+    let format = reader.format();
+    let img = reader.decode().ok()?;
+    let (w, h) = img.dimensions();
+    if w == 0 || h == 0 {
+      return None;
+    }
+
+    let passthrough = format == Some(ImageFormat::Jpeg)
+      && matches!(img.color(), ColorType::Rgb8 | ColorType::L8)
+      && w.max(h) <= MAX_IMAGE_SIDE;
+    let image = if passthrough {
+      PdfImage {
+        data: data.to_vec(),
+        width: w,
+        height: h,
+        color_space: if img.color() == ColorType::L8 {
+          "DeviceGray"
+        } else {
+          "DeviceRGB"
+        },
+        jpeg: true,
+      }
+    } else {
+      let (tw, th) = calculate_target_size(w, h, MAX_IMAGE_SIDE);
+      let img = if (tw, th) == (w, h) {
+        img
+      } else {
+        img.resize_exact(tw, th, image::imageops::FilterType::Lanczos3)
+      };
+      PdfImage {
+        data: flatten_alpha(&img),
+        width: tw,
+        height: th,
+        color_space: "DeviceRGB",
+        jpeg: false,
+      }
+    };
+    self.images.push(image);
+    Some((self.images.len() - 1, w, h))
+  }
+}
+
+/// RGB samples of an image, transparent areas composited over white.
+fn flatten_alpha(img: &DynamicImage) -> Vec<u8> {
+  let rgba = img.to_rgba8();
+  let mut samples = Vec::with_capacity(rgba.len() / 4 * 3);
+  for pixel in rgba.pixels() {
+    let alpha = pixel[3] as u32;
+    for channel in &pixel.0[..3] {
+      samples.push(((*channel as u32 * alpha + 255 * (255 - alpha)) / 255) as u8);
+    }
+  }
+  samples
+}
+
+/// Text of a heading for its bookmark, on one line.
+fn paragraph_title(runs: &[Run]) -> String {
+  let text: String = runs.iter().map(|run| run.text.as_str()).collect();
+  text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Split runs into words, spaces and line breaks.
+fn tokenize(runs: &[Run]) -> Vec<Token<'_>> {
+  let mut tokens: Vec<Token> = Vec::new();
+  for run in runs {
+    let text: String = run.text.nfc().collect();
+    for c in text.chars() {
+      match c {
+        '\n' => tokens.push(Token::Break),
+        ' ' | '\t' => tokens.push(Token::Space(&run.style)),
+        c if c.is_control() => {}
+        c => match tokens.last_mut() {
+          Some(Token::Word(pieces)) => match pieces.last_mut() {
+            Some((text, style)) if std::ptr::eq(*style, &run.style) => text.push(c),
+            _ => pieces.push((c.to_string(), &run.style)),
+          },
+          _ => tokens.push(Token::Word(vec![(c.to_string(), &run.style)])),
+        },
+      }
+    }
+  }
+  tokens
+}
+
+/// Places items on pages, top to bottom.
+struct Pager<'p> {
+  page: &'p PageSetup,
+  /// Draws of every page, in page coordinates (from the top left corner)
+  pages: Vec<Vec<Draw>>,
+  /// Position in the content area of the current page
+  y: f64,
+  /// Headings placed so far
+  bookmarks: Vec<Bookmark>,
+}
+
+impl<'p> Pager<'p> {
+  fn new_page(&mut self) {
+    self.pages.push(Vec::new());
+    self.y = 0.0;
+  }
+
+  fn available(&self) -> f64 {
+    self.page.content_height() - self.y
+  }
+
+  fn draw(&mut self, draws: Vec<Draw>, dx: f64, dy: f64) {
+    let (left, top) = (
+      self.page.margin_left + dx,
+      self.page.margin_top + self.y + dy,
+    );
+    let page = self.pages.last_mut().expect("the pager always has a page");
+    page.extend(draws.into_iter().map(|d| d.translate(left, top)));
+  }
+
+  fn place(&mut self, items: Vec<Item>) {
+    // Space needed by lines kept with the next line
+    let keep: Vec<f64> = (0..items.len())
+      .map(|i| match &items[i] {
+        Item::Line(line) if line.keep_with_next => line.height + following_height(&items[i + 1..]),
+        item => item.height(),
+      })
+      .collect();
+
+    for (item, needed) in items.into_iter().zip(keep) {
+      match item {
+        Item::Space(height) => {
+          if self.y > 0.0 {
+            self.y += height;
+          }
+        }
+        Item::PageBreak => {
+          if self.y > 0.0 {
+            self.new_page();
+          }
+        }
+        Item::Line(line) => {
+          if self.y > 0.0
+            && (line.height > self.available() + EPSILON
+              || (needed > self.available() + EPSILON && needed <= self.page.content_height()))
+          {
+            self.new_page();
+          }
+          if let Some((level, title)) = line.bookmark {
+            self.bookmarks.push((level, title, self.pages.len() as u32));
+          }
+          self.draw(line.draws, 0.0, 0.0);
+          self.y += line.height;
+        }
+        Item::Row(row) => self.place_row(row),
+      }
+    }
+  }
+
+  fn place_row(&mut self, mut row: Row) {
+    loop {
+      let height = row.height();
+      if height <= self.available() + EPSILON {
+        let draws = row_draws(row, height);
+        self.draw(draws, 0.0, 0.0);
+        self.y += height;
+        return;
+      }
+      let at_top = self.y <= 0.0;
+      if !at_top
+        && (height <= self.page.content_height()
+          || row.first_line_height() > self.available() + EPSILON)
+      {
+        // Start the row on the next page, or split it there.
+        self.new_page();
+        continue;
+      }
+      let (head, tail) = row.split(self.available(), at_top);
+      let available = self.available();
+      let draws = row_draws(head, available);
+      self.draw(draws, 0.0, 0.0);
+      self.new_page();
+      if tail.is_empty() {
+        return;
+      }
+      row = tail;
+    }
+  }
+}
+
+/// Height needed after a kept line: spaces, then the next line (or the first line
+/// of the next row).
+fn following_height(items: &[Item]) -> f64 {
+  let mut height = 0.0;
+  for item in items {
+    match item {
+      Item::Space(h) => height += h,
+      Item::Line(line) => return height + line.height,
+      Item::Row(row) => return height + row.first_line_height(),
+      Item::PageBreak => return 0.0,
+    }
+  }
+  height
+}
+
+/// Draws of a row `height` high, relative to its top left corner.
+fn row_draws(row: Row, height: f64) -> Vec<Draw> {
+  let mut draws = Vec::new();
+  for cell in row.cells {
+    if let Some(color) = cell.background {
+      draws.push(Draw::Rect {
+        x: cell.x,
+        y: 0.0,
+        width: cell.width,
+        height,
+        color,
+      });
+    }
+    let mut y = CELL_PADDING_Y;
+    let x = cell.x + CELL_PADDING_X;
+    for item in cell.items {
+      let h = item.height();
+      match item {
+        Item::Line(line) => draws.extend(line.draws.into_iter().map(|d| d.translate(x, y))),
+        Item::Row(nested) => {
+          draws.extend(row_draws(nested, h).into_iter().map(|d| d.translate(x, y)))
+        }
+        Item::Space(_) | Item::PageBreak => {}
+      }
+      y += h;
+    }
+    if let Some(color) = row.borders {
+      let (x0, x1) = (cell.x, cell.x + cell.width);
+      for (from, to) in [
+        ((x0, 0.0), (x1, 0.0)),
+        ((x1, 0.0), (x1, height)),
+        ((x1, height), (x0, height)),
+        ((x0, height), (x0, 0.0)),
+      ] {
+        draws.push(Draw::Line {
+          from,
+          to,
+          width: 0.5,
+          color,
+        });
+      }
+    }
+  }
+  draws
+}
+
+fn font_resource(style: FontStyle) -> &'static str {
+  match (style.bold, style.italic) {
+    (false, false) => "F1",
+    (true, false) => "F2",
+    (false, true) => "F3",
+    (true, true) => "F4",
+  }
+}
+
+fn write_pdf(
+  pages: &[Vec<Draw>],
+  images: &[PdfImage],
+  fonts: &FontFamily,
+  setup: &PageSetup,
+  outline: &[PdfOutlineItem],
+) -> Vec<u8> {
+  let mut pdf = PdfWriter::new();
+  let catalog = pdf.reserve();
+  let pages_id = pdf.reserve();
+  let image_ids: Vec<u32> = images.iter().map(|_| pdf.reserve()).collect();
+  let mut font_ids: BTreeMap<FontStyle, u32> = BTreeMap::new();
+  let mut page_ids = Vec::new();
+
+  for draws in pages {
+    let mut content = String::new();
+    let mut page_fonts = BTreeSet::new();
+    let mut page_images = BTreeSet::new();
+    // PDF y axis goes up from the bottom of the page.
+    let flip = |y: f64| setup.height - y;
+    for draw in draws {
+      match draw {
+        Draw::Text {
+          x,
+          baseline,
+          font,
+          size,
+          color,
+          text,
+        } => {
+          page_fonts.insert(*font);
+          let _ = writeln!(
+            content,
+            "BT /{} {size:.2} Tf {} rg 1 0 0 1 {x:.2} {:.2} Tm <{}> Tj ET",
+            font_resource(*font),
+            color_operands(color),
+            flip(*baseline),
+            fonts.get(*font).encode(text)
+          );
+        }
+        Draw::Image {
+          x,
+          y,
+          width,
+          height,
+          image,
+        } => {
+          page_images.insert(*image);
+          let _ = writeln!(
+            content,
+            "q {width:.2} 0 0 {height:.2} {x:.2} {:.2} cm /Im{image} Do Q",
+            flip(y + height)
+          );
+        }
+        Draw::Rect {
+          x,
+          y,
+          width,
+          height,
+          color,
+        } => {
+          let _ = writeln!(
+            content,
+            "{} rg {x:.2} {:.2} {width:.2} {height:.2} re f",
+            color_operands(color),
+            flip(y + height)
+          );
+        }
+        Draw::Line {
+          from,
+          to,
+          width,
+          color,
+        } => {
+          let _ = writeln!(
+            content,
+            "{} RG {width:.2} w {:.2} {:.2} m {:.2} {:.2} l S",
+            color_operands(color),
+            from.0,
+            flip(from.1),
+            to.0,
+            flip(to.1)
+          );
+        }
+      }
+    }
+
+    let mut resources = String::new();
+    if !page_fonts.is_empty() {
+      resources.push_str("/Font <<");
+      for font in &page_fonts {
+        let id = *font_ids.entry(*font).or_insert_with(|| pdf.reserve());
+        let _ = write!(resources, " /{} {id} 0 R", font_resource(*font));
+      }
+      resources.push_str(" >> ");
+    }
+    if !page_images.is_empty() {
+      resources.push_str("/XObject <<");
+      for image in &page_images {
+        let _ = write!(resources, " /Im{image} {} 0 R", image_ids[*image]);
+      }
+      resources.push_str(" >> ");
+    }
+
+    let page_id = pdf.reserve();
+    let content_id = pdf.reserve();
+    pdf.object(
+      page_id,
+      &format!(
+        "<< /Type /Page /Parent {pages_id} 0 R /MediaBox [0 0 {:.2} {:.2}] /Resources << {resources}>> /Contents {content_id} 0 R >>",
+        setup.width, setup.height
+      ),
+    );
+    pdf.compressed_stream(content_id, "", content.as_bytes());
+    page_ids.push(page_id);
+  }
+
+  let kids: Vec<String> = page_ids.iter().map(|id| format!("{id} 0 R")).collect();
+  pdf.object(
+    pages_id,
+    &format!(
+      "<< /Type /Pages /Kids [{}] /Count {} >>",
+      kids.join(" "),
+      page_ids.len()
+    ),
+  );
+  for (style, id) in font_ids {
+    fonts.get(style).write(&mut pdf, id);
+  }
+  for (image, id) in images.iter().zip(image_ids) {
+    let dict = format!(
+      "/Type /XObject /Subtype /Image /Width {} /Height {} /ColorSpace /{} /BitsPerComponent 8",
+      image.width, image.height, image.color_space
+    );
+    if image.jpeg {
+      pdf.stream(id, &format!("{dict} /Filter /DCTDecode"), &image.data);
+    } else {
+      pdf.compressed_stream(id, &dict, &image.data);
+    }
+  }
+  let mut outlines = String::new();
+  if !outline.is_empty() {
+    let outlines_id = pdf.reserve();
+    let (first, last, count) =
+      write_outline_items(&mut pdf, outline, outlines_id, &page_ids, setup.height);
+    pdf.object(
+      outlines_id,
+      &format!("<< /Type /Outlines /First {first} 0 R /Last {last} 0 R /Count {count} >>"),
+    );
+    outlines = format!(" /Outlines {outlines_id} 0 R /PageMode /UseOutlines");
+  }
+  pdf.object(
+    catalog,
+    &format!("<< /Type /Catalog /Pages {pages_id} 0 R{outlines} >>"),
+  );
+  pdf.finish(catalog)
+}
+
+/// Write outline items under `parent`, as `write_outline` does: every item points
+/// to the top of its page. Returns the first and last ids and the item count.
+fn write_outline_items(
+  pdf: &mut PdfWriter,
+  items: &[PdfOutlineItem],
+  parent: u32,
+  page_ids: &[u32],
+  top: f64,
+) -> (u32, u32, usize) {
+  let ids: Vec<u32> = items.iter().map(|_| pdf.reserve()).collect();
+  let mut count = 0;
+  for (i, item) in items.iter().enumerate() {
+    let title = match text_string(&item.title) {
+      Object::String(bytes, _) => bytes.iter().map(|b| format!("{b:02X}")).collect(),
+      _ => String::new(),
+    };
+    let mut dict = format!("/Title <{title}> /Parent {parent} 0 R");
+    if i > 0 {
+      let _ = write!(dict, " /Prev {} 0 R", ids[i - 1]);
+    }
+    if let Some(next) = ids.get(i + 1) {
+      let _ = write!(dict, " /Next {next} 0 R");
+    }
+    let page = item
+      .page_number
+      .and_then(|n| page_ids.get((n as usize).checked_sub(1)?));
+    if let Some(page) = page {
+      let _ = write!(dict, " /Dest [{page} 0 R /XYZ 0 {top:.2} null]");
+    }
+    if !item.children.is_empty() {
+      let (first, last, descendants) =
+        write_outline_items(pdf, &item.children, ids[i], page_ids, top);
+      let _ = write!(
+        dict,
+        " /First {first} 0 R /Last {last} 0 R /Count {descendants}"
+      );
+      count += descendants;
+    }
+    pdf.object(ids[i], &format!("<< {dict} >>"));
+    count += 1;
+  }
+  (ids[0], ids[ids.len() - 1], count)
+}
+
+fn color_operands(color: &Rgb) -> String {
+  format!("{:.3} {:.3} {:.3}", color[0], color[1], color[2])
+}
@@ -1,6 +1,8 @@
 use image::ImageError;
 use napi::{Error, Status};
 
+use crate::typeset::Rgb;
+
 /// Map image crate errors to NAPI errors
 pub fn map_image_error(err: ImageError) -> Error {
   Error::new(
@@ -30,6 +32,98 @@ pub fn is_pdf_mime(mime: &str) -> bool {
   mime == "application/pdf" || mime == "application/x-pdf"
 }
 
+pub fn is_docx_mime(mime: &str) -> bool {
+  mime == "application/vnd.openxmlformats-officedocument.wordprocessingml.document"
+}
+
+pub fn is_doc_mime(mime: &str) -> bool {
+  mime == "application/msword"
+}
+
+pub fn is_odt_mime(mime: &str) -> bool {
+  mime == "application/vnd.oasis.opendocument.text"
+}
+
+pub fn is_rtf_mime(mime: &str) -> bool {
+  mime == "application/rtf" || mime == "text/rtf" || mime == "application/x-rtf"
+}
+
+pub fn is_html_mime(mime: &str) -> bool {
+  mime == "text/html" || mime == "application/xhtml+xml"
+}
+
+pub fn is_plain_text_mime(mime: &str) -> bool {
+  mime == "text/plain"
+}
+
+pub fn is_markdown_mime(mime: &str) -> bool {
+  mime == "text/markdown" || mime == "text/x-markdown"
+}
+
 pub fn is_supported_image_mime(mime: &str) -> bool {
   mime == "image/png" || mime == "image/jpeg" || mime == "image/jpg" || mime == "image/pjpeg"
 }
+
+/// Hex color (`1F4E79`, `#1F4E79`), as DOCX and OpenDocument write them.
+pub(crate) fn hex_color(value: &str) -> Option<Rgb> {
+  let hex = value.strip_prefix('#').unwrap_or(value);
+  if hex.len() != 6 {
+    return None;
+  }
+  let channel = |i: usize| {
+    u8::from_str_radix(hex.get(i..i + 2)?, 16)
+      .ok()
+      .map(|c| c as f64 / 255.0)
+  };
+  Some([channel(0)?, channel(2)?, channel(4)?])
+}
+
+/// List item number `n` in a numbering format: Word's `lowerLetter`, `upperRoman`,
+/// … or the `a`, `A`, `i` and `I` of OpenDocument and HTML; decimal otherwise.
+pub(crate) fn format_number(n: u32, format: &str) -> String {
+  match format {
+    "lowerLetter" | "a" => letters(n).to_lowercase(),
+    "upperLetter" | "A" => letters(n),
+    "lowerRoman" | "i" => roman(n).to_lowercase(),
+    "upperRoman" | "I" => roman(n),
+    "decimalZero" => format!("{n:02}"),
+    // OpenDocument's empty `num-format` shows no number.
+    "" => String::new(),
+    _ => n.to_string(),
+  }
+}
+
+/// A, B, …, Z, AA, BB, … as lists number items with letters.
+fn letters(n: u32) -> String {
+  if n == 0 {
+    return String::new();
+  }
+  let letter = (b'A' + ((n - 1) % 26) as u8) as char;
+  letter.to_string().repeat(((n - 1) / 26 + 1) as usize)
+}
+
+fn roman(mut n: u32) -> String {
+  const NUMERALS: [(u32, &str); 13] = [
+    (1000, "M"),
+    (900, "CM"),
+    (500, "D"),
+    (400, "CD"),
+    (100, "C"),
+    (90, "XC"),
+    (50, "L"),
+    (40, "XL"),
+    (10, "X"),
+    (9, "IX"),
+    (5, "V"),
+    (4, "IV"),
+    (1, "I"),
+  ];
+  let mut out = String::new();
+  for (value, numeral) in NUMERALS {
+    while n >= value {
+      out.push_str(numeral);
+      n -= value;
+    }
+  }
+  out
+}